@@ -0,0 +1,129 @@
+/*
+ * C bindings for the hidpp crate.
+ *
+ * All functions block the calling thread until the underlying HID++
+ * operation completes, or return HIDPP_TIMEOUT once the timeout of the
+ * channel elapses (HIDPP_DEFAULT_TIMEOUT_MS unless changed using
+ * hidpp_channel_set_timeout). Strings are written to caller-provided buffers
+ * as null-terminated UTF-8.
+ */
+
+#ifndef HIDPP_H
+#define HIDPP_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum hidpp_status {
+    HIDPP_OK = 0,
+    HIDPP_INVALID_ARGUMENT = -1,
+    HIDPP_UNSUPPORTED = -2,
+    HIDPP_PROTOCOL = -3,
+    HIDPP_BUFFER_TOO_SMALL = -4,
+    HIDPP_NOT_FOUND = -5,
+    HIDPP_OFFLINE = -6,
+    HIDPP_TIMEOUT = -7,
+} hidpp_status;
+
+#define HIDPP_DEFAULT_TIMEOUT_MS 2000
+
+/* Returns the amount of written/read bytes or a negative value on error. */
+typedef intptr_t (*hidpp_write_fn)(void *user_data, const uint8_t *buf, size_t len);
+typedef intptr_t (*hidpp_read_fn)(void *user_data, uint8_t *buf, size_t len);
+
+/*
+ * The callbacks and metadata of a raw HID channel, e.g. wrapping a hidraw
+ * file descriptor or a Windows HID handle.
+ *
+ * Callbacks may be called from a background thread and concurrently with
+ * each other. `read` should return 0 after a short timeout without a report
+ * so that the channel can be closed in time.
+ *
+ * `supports_short` and `supports_long` are 1, 0 or -1 if unknown, in which
+ * case `get_report_descriptor` is used to determine them.
+ */
+typedef struct hidpp_raw_channel {
+    void *user_data;
+    uint16_t vendor_id;
+    uint16_t product_id;
+    int8_t supports_short;
+    int8_t supports_long;
+    hidpp_write_fn write;
+    hidpp_read_fn read;
+    hidpp_read_fn get_report_descriptor;
+} hidpp_raw_channel;
+
+typedef struct hidpp_channel hidpp_channel;
+typedef struct hidpp_device hidpp_device;
+
+typedef struct hidpp_feature_info {
+    uint8_t index;
+    uint16_t id;
+    uint8_t version;
+    bool obsolete;
+    bool hidden;
+    bool engineering;
+    bool manufacturing_deactivatable;
+    bool compliance_deactivatable;
+    bool known;
+} hidpp_feature_info;
+
+typedef struct hidpp_battery_info {
+    uint8_t percentage;
+    uint8_t level;  /* 1 critical, 2 low, 4 good, 8 full */
+    uint8_t status; /* 0 discharging, 1 charging, 2 charging slowly, 3 full, 4 error */
+} hidpp_battery_info;
+
+typedef struct hidpp_smartshift_mode {
+    uint8_t wheel_mode; /* 1 freespin, 2 ratchet, 0 keeps the current mode when setting */
+    uint8_t auto_disengage;
+    uint8_t auto_disengage_default;
+} hidpp_smartshift_mode;
+
+typedef struct hidpp_wheel_mode {
+    bool diverted;
+    bool high_resolution;
+    bool inverted;
+} hidpp_wheel_mode;
+
+/*
+ * Returns NULL if the channel does not support HID++, an error occurred or
+ * opening it took longer than HIDPP_DEFAULT_TIMEOUT_MS.
+ */
+hidpp_channel *hidpp_channel_open(const hidpp_raw_channel *raw);
+void hidpp_channel_free(hidpp_channel *channel);
+
+/*
+ * Sets how long calls on the channel and its devices wait for a response
+ * before returning HIDPP_TIMEOUT. 0 lets calls wait indefinitely.
+ */
+hidpp_status hidpp_channel_set_timeout(const hidpp_channel *channel, uint32_t timeout_ms);
+
+/* Use device index 0xff for directly connected devices. */
+hidpp_status hidpp_device_open(const hidpp_channel *channel, uint8_t device_index, hidpp_device **out);
+void hidpp_device_free(hidpp_device *device);
+hidpp_status hidpp_device_protocol_version(const hidpp_device *device, uint8_t *protocol_num, uint8_t *target_sw);
+
+size_t hidpp_device_feature_count(const hidpp_device *device);
+hidpp_status hidpp_device_feature_at(const hidpp_device *device, size_t position, hidpp_feature_info *out);
+
+hidpp_status hidpp_device_ping(const hidpp_device *device, uint8_t payload, uint8_t *out);
+hidpp_status hidpp_device_get_name(const hidpp_device *device, char *buf, size_t len);
+hidpp_status hidpp_device_get_friendly_name(const hidpp_device *device, char *buf, size_t len);
+hidpp_status hidpp_device_get_serial_number(const hidpp_device *device, char *buf, size_t len);
+hidpp_status hidpp_device_get_battery(const hidpp_device *device, hidpp_battery_info *out);
+hidpp_status hidpp_device_get_smartshift(const hidpp_device *device, hidpp_smartshift_mode *out);
+hidpp_status hidpp_device_set_smartshift(const hidpp_device *device, const hidpp_smartshift_mode *mode);
+hidpp_status hidpp_device_get_wheel_mode(const hidpp_device *device, hidpp_wheel_mode *out);
+hidpp_status hidpp_device_set_wheel_mode(const hidpp_device *device, const hidpp_wheel_mode *mode);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* HIDPP_H */
@@ -0,0 +1,194 @@
+//! Implements HID++ channels backed by caller-provided I/O callbacks.
+
+use std::{
+    error::Error,
+    ffi::c_void,
+    io,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+};
+
+use hidpp::{
+    async_trait,
+    channel::{HidppChannel, RawHidChannel},
+};
+
+use crate::{HIDPP_DEFAULT_TIMEOUT_MS, HidppStatus, block_on};
+
+/// Writes a raw report.
+///
+/// Returns the amount of written bytes or a negative value on error.
+pub type HidppWriteFn =
+    unsafe extern "C" fn(user_data: *mut c_void, buf: *const u8, len: usize) -> isize;
+
+/// Reads a raw report into a buffer, blocking until one is available.
+///
+/// The callback should return `0` after a short timeout (e.g. 100ms) without
+/// a report so that the channel can be closed in time.
+///
+/// Returns the amount of read bytes or a negative value on error.
+pub type HidppReadFn =
+    unsafe extern "C" fn(user_data: *mut c_void, buf: *mut u8, len: usize) -> isize;
+
+/// Represents the callbacks and metadata of a raw HID channel provided by the
+/// caller, for example wrapping a hidraw file descriptor or a Windows HID
+/// handle.
+///
+/// All callbacks may be called from a background thread and concurrently with
+/// each other, so they have to be thread-safe.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct HidppRawChannel {
+    /// An arbitrary pointer passed to every callback.
+    pub user_data: *mut c_void,
+
+    pub vendor_id: u16,
+    pub product_id: u16,
+
+    /// Whether the channel supports short HID++ reports: `1` if it does, `0`
+    /// if it does not and `-1` if unknown, in which case the report
+    /// descriptor is read.
+    pub supports_short: i8,
+
+    /// Whether the channel supports long HID++ reports, see
+    /// [`Self::supports_short`].
+    pub supports_long: i8,
+
+    pub write: Option<HidppWriteFn>,
+    pub read: Option<HidppReadFn>,
+
+    /// Reads the raw report descriptor, using the same semantics as
+    /// [`Self::read`].
+    ///
+    /// May be null if both [`Self::supports_short`] and
+    /// [`Self::supports_long`] are known.
+    pub get_report_descriptor: Option<HidppReadFn>,
+}
+
+// SAFETY: The caller guarantees that the callbacks and user data can be used
+// from any thread, as documented on `HidppRawChannel`.
+unsafe impl Send for HidppRawChannel {
+}
+unsafe impl Sync for HidppRawChannel {
+}
+
+/// Converts the return value of a callback into a result.
+fn callback_result(result: isize) -> Result<usize, Box<dyn Error + Sync + Send>> {
+    usize::try_from(result).map_err(|_| io::Error::other("the callback returned an error").into())
+}
+
+#[async_trait]
+impl RawHidChannel for HidppRawChannel {
+    fn vendor_id(&self) -> u16 {
+        self.vendor_id
+    }
+
+    fn product_id(&self) -> u16 {
+        self.product_id
+    }
+
+    async fn write_report(&self, src: &[u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let write = self.write.ok_or("no write callback was provided")?;
+        callback_result(unsafe { write(self.user_data, src.as_ptr(), src.len()) })
+    }
+
+    async fn read_report(&self, buf: &mut [u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let read = self.read.ok_or("no read callback was provided")?;
+        callback_result(unsafe { read(self.user_data, buf.as_mut_ptr(), buf.len()) })
+    }
+
+    fn supports_short_long_hidpp(&self) -> Option<(bool, bool)> {
+        if self.supports_short < 0 || self.supports_long < 0 {
+            return None;
+        }
+
+        Some((self.supports_short != 0, self.supports_long != 0))
+    }
+
+    async fn get_report_descriptor(
+        &self,
+        buf: &mut [u8],
+    ) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let read = self
+            .get_report_descriptor
+            .ok_or("no report descriptor callback was provided")?;
+        callback_result(unsafe { read(self.user_data, buf.as_mut_ptr(), buf.len()) })
+    }
+}
+
+/// An opaque handle to a HID++ channel.
+pub struct HidppChannelHandle {
+    pub(crate) channel: Arc<HidppChannel>,
+
+    /// The timeout of blocking calls in milliseconds, shared with all devices
+    /// opened on the channel.
+    pub(crate) timeout_ms: Arc<AtomicU32>,
+}
+
+/// Opens a HID++ channel using a set of raw channel callbacks.
+///
+/// Returns null if the raw channel does not support HID++, an error occurred
+/// or opening it took longer than [`HIDPP_DEFAULT_TIMEOUT_MS`]. The returned
+/// handle has to be freed using [`hidpp_channel_free`] after all devices opened
+/// on it were freed.
+///
+/// # Safety
+///
+/// `raw` must point to a valid [`HidppRawChannel`], whose callbacks must stay
+/// callable until the channel is freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_channel_open(
+    raw: *const HidppRawChannel,
+) -> *mut HidppChannelHandle {
+    let Some(raw) = (unsafe { raw.as_ref() }) else {
+        return std::ptr::null_mut();
+    };
+
+    match block_on(
+        HIDPP_DEFAULT_TIMEOUT_MS,
+        HidppChannel::from_raw_channel(*raw),
+    ) {
+        Ok(Ok(channel)) => Box::into_raw(Box::new(HidppChannelHandle {
+            channel: Arc::new(channel),
+            timeout_ms: Arc::new(AtomicU32::new(HIDPP_DEFAULT_TIMEOUT_MS)),
+        })),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Sets how long blocking calls on the channel and on all devices opened on
+/// it wait for a response, in milliseconds.
+///
+/// Calls running into the timeout return [`HidppStatus::Timeout`]. A timeout
+/// of `0` lets calls wait indefinitely.
+///
+/// # Safety
+///
+/// `channel` must be a valid handle returned by [`hidpp_channel_open`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_channel_set_timeout(
+    channel: *const HidppChannelHandle,
+    timeout_ms: u32,
+) -> HidppStatus {
+    let Some(channel) = (unsafe { channel.as_ref() }) else {
+        return HidppStatus::InvalidArgument;
+    };
+
+    channel.timeout_ms.store(timeout_ms, Ordering::Relaxed);
+    HidppStatus::Ok
+}
+
+/// Frees a HID++ channel, stopping its read thread.
+///
+/// # Safety
+///
+/// `channel` must be null or a handle returned by [`hidpp_channel_open`]
+/// that was not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_channel_free(channel: *mut HidppChannelHandle) {
+    if !channel.is_null() {
+        drop(unsafe { Box::from_raw(channel) });
+    }
+}
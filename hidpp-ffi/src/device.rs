@@ -0,0 +1,222 @@
+//! Implements device initialization and feature enumeration.
+
+use std::sync::{
+    Arc,
+    atomic::{AtomicU32, Ordering},
+};
+
+use hidpp::{
+    device::{Device, DeviceError},
+    feature::feature_set::FeatureInformation,
+    protocol::ProtocolVersion,
+};
+
+use crate::{HidppStatus, block_on, channel::HidppChannelHandle};
+
+/// An opaque handle to an initialized HID++2.0 device.
+pub struct HidppDeviceHandle {
+    pub(crate) device: Device,
+    pub(crate) features: Vec<FeatureInformation>,
+
+    /// The timeout of the channel the device was opened on.
+    pub(crate) timeout_ms: Arc<AtomicU32>,
+}
+
+impl HidppDeviceHandle {
+    /// Blocks until a call to the device completes or the timeout of its
+    /// channel elapses.
+    pub(crate) fn block_on<T, E>(
+        &self,
+        future: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, HidppStatus> {
+        block_on(self.timeout_ms.load(Ordering::Relaxed), future)?
+            .map_err(|_| HidppStatus::Protocol)
+    }
+}
+
+/// Contains information about a single feature of a device.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct HidppFeatureInfo {
+    /// The index the feature is addressed with.
+    pub index: u8,
+
+    /// The protocol ID of the feature.
+    pub id: u16,
+
+    /// The latest supported version of the feature.
+    pub version: u8,
+
+    pub obsolete: bool,
+    pub hidden: bool,
+    pub engineering: bool,
+    pub manufacturing_deactivatable: bool,
+    pub compliance_deactivatable: bool,
+
+    /// Whether the feature is known to the underlying `hidpp` crate.
+    pub known: bool,
+}
+
+/// Opens a HID++2.0 device and enumerates its features.
+///
+/// The device index is `0xff` for devices connected directly to the host and
+/// the receiver slot for devices paired to a receiver.
+///
+/// On success, writes the device handle to `out`. The handle has to be freed
+/// using [`hidpp_device_free`].
+///
+/// Returns [`HidppStatus::NotFound`] if there is no device at the given
+/// index, [`HidppStatus::Unsupported`] if it does not support HID++2.0 and
+/// [`HidppStatus::Timeout`] if it did not respond within the timeout of the
+/// channel.
+///
+/// # Safety
+///
+/// `channel` must be a valid handle returned by
+/// [`crate::hidpp_channel_open`] and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_open(
+    channel: *const HidppChannelHandle,
+    device_index: u8,
+    out: *mut *mut HidppDeviceHandle,
+) -> HidppStatus {
+    let Some(channel) = (unsafe { channel.as_ref() }) else {
+        return HidppStatus::InvalidArgument;
+    };
+    if out.is_null() {
+        return HidppStatus::InvalidArgument;
+    }
+
+    let timeout_ms = channel.timeout_ms.load(Ordering::Relaxed);
+    let result = block_on(timeout_ms, async {
+        let mut device = Device::new(Arc::clone(&channel.channel), device_index).await?;
+        let features = device
+            .enumerate_features()
+            .await
+            .map_err(|_| HidppStatus::Protocol)?
+            .unwrap_or_default();
+
+        Ok::<_, HidppStatus>(HidppDeviceHandle {
+            device,
+            features,
+            timeout_ms: Arc::clone(&channel.timeout_ms),
+        })
+    });
+
+    match result.and_then(|x| x) {
+        Ok(handle) => {
+            unsafe { *out = Box::into_raw(Box::new(handle)) };
+            HidppStatus::Ok
+        },
+        Err(status) => status,
+    }
+}
+
+impl From<DeviceError> for HidppStatus {
+    fn from(err: DeviceError) -> Self {
+        match err {
+            DeviceError::DeviceNotFound => Self::NotFound,
+            DeviceError::DeviceOffline => Self::Offline,
+            DeviceError::UnsupportedProtocolVersion => Self::Unsupported,
+            _ => Self::Protocol,
+        }
+    }
+}
+
+/// Frees a device handle.
+///
+/// # Safety
+///
+/// `device` must be null or a handle returned by [`hidpp_device_open`] that
+/// was not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_free(device: *mut HidppDeviceHandle) {
+    if !device.is_null() {
+        drop(unsafe { Box::from_raw(device) });
+    }
+}
+
+/// Retrieves the HID++ protocol version of a device.
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `protocol_num` and `target_sw`
+/// must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_protocol_version(
+    device: *const HidppDeviceHandle,
+    protocol_num: *mut u8,
+    target_sw: *mut u8,
+) -> HidppStatus {
+    let Some(device) = (unsafe { device.as_ref() }) else {
+        return HidppStatus::InvalidArgument;
+    };
+    if protocol_num.is_null() || target_sw.is_null() {
+        return HidppStatus::InvalidArgument;
+    }
+
+    let ProtocolVersion::V20 {
+        protocol_num: num,
+        target_sw: sw,
+    } = device.device.protocol_version
+    else {
+        return HidppStatus::Unsupported;
+    };
+
+    unsafe {
+        *protocol_num = num;
+        *target_sw = sw;
+    }
+
+    HidppStatus::Ok
+}
+
+/// Returns the amount of features of a device, excluding the root feature.
+///
+/// # Safety
+///
+/// `device` must be a valid device handle.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_feature_count(device: *const HidppDeviceHandle) -> usize {
+    unsafe { device.as_ref() }.map_or(0, |device| device.features.len())
+}
+
+/// Retrieves information about the feature at a position, starting at `0`.
+///
+/// The position `n` refers to the feature with the index `n + 1`.
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_feature_at(
+    device: *const HidppDeviceHandle,
+    position: usize,
+    out: *mut HidppFeatureInfo,
+) -> HidppStatus {
+    let Some(device) = (unsafe { device.as_ref() }) else {
+        return HidppStatus::InvalidArgument;
+    };
+    let Some(info) = device.features.get(position) else {
+        return HidppStatus::InvalidArgument;
+    };
+    if out.is_null() {
+        return HidppStatus::InvalidArgument;
+    }
+
+    unsafe {
+        *out = HidppFeatureInfo {
+            index: (position + 1) as u8,
+            id: info.id,
+            version: info.version,
+            obsolete: info.typ.obsolete,
+            hidden: info.typ.hidden,
+            engineering: info.typ.engineering,
+            manufacturing_deactivatable: info.typ.manufacturing_deactivatable,
+            compliance_deactivatable: info.typ.compliance_deactivatable,
+            known: hidpp::feature::registry::lookup(info.id).is_some(),
+        }
+    };
+
+    HidppStatus::Ok
+}
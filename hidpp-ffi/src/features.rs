@@ -0,0 +1,385 @@
+//! Implements a curated set of feature calls.
+
+use std::sync::Arc;
+
+use hidpp::feature::{
+    Feature,
+    device_friendly_name::DeviceFriendlyNameFeature,
+    device_information::DeviceInformationFeature,
+    device_type_and_name::DeviceTypeAndNameFeature,
+    hires_wheel::{HiResWheelFeature, WheelEventTarget, WheelResolution},
+    smartshift::{SmartShiftFeature, WheelMode},
+    unified_battery::UnifiedBatteryFeature,
+};
+
+use crate::{HidppStatus, device::HidppDeviceHandle, write_c_string};
+
+/// Contains the battery state of a device as reported by the unified battery
+/// feature (`0x1004`).
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct HidppBatteryInfo {
+    /// The charge in percent, or `0` if the device does not report it.
+    pub percentage: u8,
+
+    /// The approximate charge level: `1` (critical), `2` (low), `4` (good) or
+    /// `8` (full).
+    pub level: u8,
+
+    /// The charging status: `0` (discharging), `1` (charging), `2` (charging
+    /// slowly), `3` (full) or `4` (error).
+    pub status: u8,
+}
+
+/// Contains the state of the SmartShift feature (`0x2110`).
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct HidppSmartShiftMode {
+    /// The wheel mode: `1` (freespin) or `2` (ratchet).
+    pub wheel_mode: u8,
+
+    /// The amount of quarter-turns per second it takes for the wheel to
+    /// automatically disengage, or `0xff` to never disengage.
+    pub auto_disengage: u8,
+
+    /// The default value of [`Self::auto_disengage`].
+    pub auto_disengage_default: u8,
+}
+
+/// Contains the mode of the hi-res wheel feature (`0x2121`).
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct HidppWheelMode {
+    /// Whether wheel reports are diverted to HID++ instead of native HID.
+    pub diverted: bool,
+
+    /// Whether the high-resolution mode is enabled.
+    pub high_resolution: bool,
+
+    /// Whether the scrolling direction is inverted.
+    pub inverted: bool,
+}
+
+/// Resolves a device handle and a feature implementation of it.
+///
+/// # Safety
+///
+/// `device` must be null or a valid device handle.
+unsafe fn feature<'a, F: Feature>(
+    device: *const HidppDeviceHandle,
+) -> Result<(&'a HidppDeviceHandle, Arc<F>), HidppStatus> {
+    let device = unsafe { device.as_ref() }.ok_or(HidppStatus::InvalidArgument)?;
+    let feature = device
+        .device
+        .get_feature::<F>()
+        .ok_or(HidppStatus::Unsupported)?;
+
+    Ok((device, feature))
+}
+
+/// Runs a call against a feature, writing its result to `out`.
+///
+/// # Safety
+///
+/// `device` must be null or a valid device handle and `out` must be null or
+/// valid for writes.
+unsafe fn call<F: Feature, T, R, E>(
+    device: *const HidppDeviceHandle,
+    out: *mut T,
+    f: impl AsyncFnOnce(Arc<F>) -> Result<R, E>,
+    map: impl FnOnce(R) -> T,
+) -> HidppStatus {
+    if out.is_null() {
+        return HidppStatus::InvalidArgument;
+    }
+    let (device, feature) = match unsafe { feature::<F>(device) } {
+        Ok(feature) => feature,
+        Err(status) => return status,
+    };
+
+    match device.block_on(f(feature)) {
+        Ok(result) => {
+            unsafe { *out = map(result) };
+            HidppStatus::Ok
+        },
+        Err(status) => status,
+    }
+}
+
+/// Runs a call against a feature returning a string, writing it to a
+/// caller-provided buffer as a null-terminated C string.
+///
+/// # Safety
+///
+/// `device` must be null or a valid device handle and `buf` must be null or
+/// valid for writes of `len` bytes.
+unsafe fn call_string<F: Feature, E>(
+    device: *const HidppDeviceHandle,
+    buf: *mut u8,
+    len: usize,
+    f: impl AsyncFnOnce(Arc<F>) -> Result<String, E>,
+) -> HidppStatus {
+    let (device, feature) = match unsafe { feature::<F>(device) } {
+        Ok(feature) => feature,
+        Err(status) => return status,
+    };
+
+    match device.block_on(f(feature)) {
+        Ok(value) => unsafe { write_c_string(&value, buf, len) },
+        Err(status) => status,
+    }
+}
+
+/// Pings a device, writing the echoed payload to `out`.
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_ping(
+    device: *const HidppDeviceHandle,
+    payload: u8,
+    out: *mut u8,
+) -> HidppStatus {
+    unsafe {
+        call(
+            device,
+            out,
+            async |root: Arc<hidpp::feature::root::RootFeature>| root.ping(payload).await,
+            |echo| echo,
+        )
+    }
+}
+
+/// Retrieves the name of a device using the device type and name feature
+/// (`0x0005`).
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `buf` must be valid for writes
+/// of `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_get_name(
+    device: *const HidppDeviceHandle,
+    buf: *mut u8,
+    len: usize,
+) -> HidppStatus {
+    unsafe {
+        call_string(
+            device,
+            buf,
+            len,
+            async |feature: Arc<DeviceTypeAndNameFeature>| feature.get_whole_device_name().await,
+        )
+    }
+}
+
+/// Retrieves the user-defined name of a device using the device friendly name
+/// feature (`0x0007`).
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `buf` must be valid for writes
+/// of `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_get_friendly_name(
+    device: *const HidppDeviceHandle,
+    buf: *mut u8,
+    len: usize,
+) -> HidppStatus {
+    unsafe {
+        call_string(
+            device,
+            buf,
+            len,
+            async |feature: Arc<DeviceFriendlyNameFeature>| feature.get_whole_friendly_name().await,
+        )
+    }
+}
+
+/// Retrieves the serial number of a device using the device information
+/// feature (`0x0003`).
+///
+/// Returns [`HidppStatus::Unsupported`] if the device does not report a
+/// serial number.
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `buf` must be valid for writes
+/// of `len` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_get_serial_number(
+    device: *const HidppDeviceHandle,
+    buf: *mut u8,
+    len: usize,
+) -> HidppStatus {
+    let (device, feature) = match unsafe { feature::<DeviceInformationFeature>(device) } {
+        Ok(feature) => feature,
+        Err(status) => return status,
+    };
+
+    let result = device.block_on(async {
+        if !feature
+            .get_versioned_device_info()
+            .await?
+            .supports_serial_number()
+        {
+            return Ok(None);
+        }
+
+        feature.get_serial_number().await.map(Some)
+    });
+
+    match result {
+        Ok(Some(serial)) => unsafe { write_c_string(&serial, buf, len) },
+        Ok(None) => HidppStatus::Unsupported,
+        Err(status) => status,
+    }
+}
+
+/// Retrieves the battery state of a device using the unified battery feature
+/// (`0x1004`).
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_get_battery(
+    device: *const HidppDeviceHandle,
+    out: *mut HidppBatteryInfo,
+) -> HidppStatus {
+    unsafe {
+        call(
+            device,
+            out,
+            async |feature: Arc<UnifiedBatteryFeature>| feature.get_battery_info().await,
+            |info| HidppBatteryInfo {
+                percentage: info.charging_percentage,
+                level: info.level.into(),
+                status: info.status.into(),
+            },
+        )
+    }
+}
+
+/// Retrieves the SmartShift state of a device.
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_get_smartshift(
+    device: *const HidppDeviceHandle,
+    out: *mut HidppSmartShiftMode,
+) -> HidppStatus {
+    unsafe {
+        call(
+            device,
+            out,
+            async |feature: Arc<SmartShiftFeature>| feature.get_ratchet_control_mode().await,
+            |mode| HidppSmartShiftMode {
+                wheel_mode: mode.wheel_mode.into(),
+                auto_disengage: mode.auto_disengage,
+                auto_disengage_default: mode.auto_disengage_default,
+            },
+        )
+    }
+}
+
+/// Sets the SmartShift state of a device.
+///
+/// Every field set to `0` keeps its current value.
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `mode` must point to a valid
+/// [`HidppSmartShiftMode`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_set_smartshift(
+    device: *const HidppDeviceHandle,
+    mode: *const HidppSmartShiftMode,
+) -> HidppStatus {
+    let Some(mode) = (unsafe { mode.as_ref() }) else {
+        return HidppStatus::InvalidArgument;
+    };
+    let wheel_mode = match mode.wheel_mode {
+        0 => None,
+        value => match WheelMode::try_from(value) {
+            Ok(wheel_mode) => Some(wheel_mode),
+            Err(_) => return HidppStatus::InvalidArgument,
+        },
+    };
+    let (device, feature) = match unsafe { feature::<SmartShiftFeature>(device) } {
+        Ok(feature) => feature,
+        Err(status) => return status,
+    };
+
+    match device.block_on(feature.set_ratchet_control_mode(
+        wheel_mode,
+        Some(mode.auto_disengage),
+        Some(mode.auto_disengage_default),
+    )) {
+        Ok(()) => HidppStatus::Ok,
+        Err(status) => status,
+    }
+}
+
+/// Retrieves the hi-res wheel mode of a device.
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `out` must be valid for writes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_get_wheel_mode(
+    device: *const HidppDeviceHandle,
+    out: *mut HidppWheelMode,
+) -> HidppStatus {
+    unsafe {
+        call(
+            device,
+            out,
+            async |feature: Arc<HiResWheelFeature>| feature.get_wheel_mode().await,
+            |mode| HidppWheelMode {
+                diverted: mode.target == WheelEventTarget::Diverted,
+                high_resolution: mode.resolution == WheelResolution::High,
+                inverted: mode.inverted,
+            },
+        )
+    }
+}
+
+/// Sets the hi-res wheel mode of a device.
+///
+/// # Safety
+///
+/// `device` must be a valid device handle and `mode` must point to a valid
+/// [`HidppWheelMode`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn hidpp_device_set_wheel_mode(
+    device: *const HidppDeviceHandle,
+    mode: *const HidppWheelMode,
+) -> HidppStatus {
+    let Some(mode) = (unsafe { mode.as_ref() }) else {
+        return HidppStatus::InvalidArgument;
+    };
+    let (device, feature) = match unsafe { feature::<HiResWheelFeature>(device) } {
+        Ok(feature) => feature,
+        Err(status) => return status,
+    };
+
+    let target = if mode.diverted {
+        WheelEventTarget::Diverted
+    } else {
+        WheelEventTarget::Native
+    };
+    let resolution = if mode.high_resolution {
+        WheelResolution::High
+    } else {
+        WheelResolution::Low
+    };
+
+    match device.block_on(feature.set_wheel_mode(target, resolution, mode.inverted)) {
+        Ok(_) => HidppStatus::Ok,
+        Err(status) => status,
+    }
+}
@@ -0,0 +1,97 @@
+//! C bindings for the `hidpp` crate.
+//!
+//! The bindings expose channel creation from a set of caller-provided I/O
+//! callbacks, device initialization, feature enumeration and a curated set of
+//! feature calls. The matching C declarations can be found in
+//! `include/hidpp.h`.
+//!
+//! All functions block the calling thread until the underlying HID++
+//! operation completes, or until the timeout of the channel elapses, see
+//! [`hidpp_channel_set_timeout`]. Functions reporting success or failure
+//! return a [`HidppStatus`].
+
+mod channel;
+mod device;
+mod features;
+
+use std::time::Duration;
+
+pub use channel::*;
+pub use device::*;
+pub use features::*;
+use futures::{FutureExt, select};
+use futures_timer::Delay;
+
+/// The time blocking functions wait for a device unless another timeout is
+/// set using [`hidpp_channel_set_timeout`].
+pub const HIDPP_DEFAULT_TIMEOUT_MS: u32 = 2000;
+
+/// Represents the result of a fallible operation.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum HidppStatus {
+    /// The operation succeeded.
+    Ok = 0,
+
+    /// A passed pointer was null or an argument was out of range.
+    InvalidArgument = -1,
+
+    /// The device does not support the feature required by the operation.
+    Unsupported = -2,
+
+    /// The communication with the device failed or the device returned an
+    /// error.
+    Protocol = -3,
+
+    /// A caller-provided buffer is too small to hold the result.
+    BufferTooSmall = -4,
+
+    /// There is no device at the requested index.
+    NotFound = -5,
+
+    /// A device is paired to the requested index, but currently not
+    /// connected.
+    Offline = -6,
+
+    /// The device did not respond in time.
+    Timeout = -7,
+}
+
+/// Blocks the calling thread until a future completes, giving up after
+/// `timeout_ms` milliseconds unless it is `0`.
+fn block_on<T>(timeout_ms: u32, future: impl Future<Output = T>) -> Result<T, HidppStatus> {
+    if timeout_ms == 0 {
+        return Ok(futures::executor::block_on(future));
+    }
+
+    futures::executor::block_on(async {
+        select! {
+            result = future.fuse() => Ok(result),
+            _ = Delay::new(Duration::from_millis(timeout_ms.into())).fuse() => {
+                Err(HidppStatus::Timeout)
+            },
+        }
+    })
+}
+
+/// Copies a string into a caller-provided buffer as a null-terminated C
+/// string.
+///
+/// # Safety
+///
+/// `buf` must be valid for writes of `len` bytes.
+unsafe fn write_c_string(value: &str, buf: *mut u8, len: usize) -> HidppStatus {
+    if buf.is_null() {
+        return HidppStatus::InvalidArgument;
+    }
+    if value.len() + 1 > len {
+        return HidppStatus::BufferTooSmall;
+    }
+
+    unsafe {
+        std::ptr::copy_nonoverlapping(value.as_ptr(), buf, value.len());
+        *buf.add(value.len()) = 0;
+    }
+
+    HidppStatus::Ok
+}
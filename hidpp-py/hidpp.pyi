@@ -0,0 +1,51 @@
+from typing import Any, AsyncIterator, Literal, Optional
+
+class HidppError(Exception): ...
+class UnsupportedFeatureError(HidppError): ...
+
+class Feature:
+    index: int
+    id: int
+    name: Optional[str]
+    version: int
+
+class Battery:
+    percentage: Optional[int]
+    level: Literal["critical", "low", "good", "full", "unknown"]
+    status: Literal["discharging", "charging", "charging_slow", "full", "error", "unknown"]
+
+class SmartShift:
+    mode: Literal["freespin", "ratchet", "unknown"]
+    auto_disengage: int
+    auto_disengage_default: int
+
+class Event:
+    kind: str
+    data: Any
+
+class EventStream(AsyncIterator[Event]):
+    def __aiter__(self) -> EventStream: ...
+    async def __anext__(self) -> Event: ...
+
+class Device:
+    name: str
+    vendor_id: int
+    product_id: int
+    slot: Optional[int]
+    wpid: Optional[int]
+
+    def features(self) -> list[Feature]: ...
+    async def ping(self, payload: int = 0) -> int: ...
+    async def battery(self) -> Battery: ...
+    async def get_dpi(self, sensor: int = 0) -> int: ...
+    async def get_dpi_list(self, sensor: int = 0) -> list[int]: ...
+    async def set_dpi(self, dpi: int, sensor: int = 0) -> int: ...
+    async def get_smartshift(self) -> SmartShift: ...
+    async def set_smartshift(
+        self,
+        mode: Optional[Literal["freespin", "ratchet"]] = None,
+        auto_disengage: Optional[int] = None,
+    ) -> None: ...
+    def events(self) -> EventStream: ...
+
+async def enumerate() -> list[Device]: ...
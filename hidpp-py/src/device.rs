@@ -0,0 +1,416 @@
+//! Implements the `Device` class and the values returned by its methods.
+
+use std::sync::Arc;
+
+use hidpp::{
+    channel::HidppChannel,
+    device::{self, DeviceError},
+    feature::{
+        Feature,
+        adjustable_dpi::{AdjustableDpiFeature, DpiListEntry},
+        device_type_and_name::DeviceTypeAndNameFeature,
+        feature_set::FeatureInformation,
+        registry,
+        smartshift::{SmartShiftFeature, WheelMode},
+        unified_battery::{BatteryLevel, BatteryStatus, UnifiedBatteryFeature},
+    },
+    receiver::{self, ReceiverDevice},
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use pyo3_async_runtimes::tokio::future_into_py;
+
+use crate::{UnsupportedFeatureError, events::EventStream, to_py_err};
+
+/// The device index used to address a device that is directly connected to
+/// the host.
+const DIRECT_DEVICE_INDEX: u8 = 0xff;
+
+/// A HID++2.0 device, either paired to a receiver or connected directly to
+/// the host.
+#[pyclass(frozen, module = "hidpp")]
+pub struct Device {
+    device: device::Device,
+    features: Vec<FeatureInformation>,
+
+    /// The name of the device.
+    #[pyo3(get)]
+    name: String,
+
+    /// The vendor ID of the HID device the device is connected through.
+    #[pyo3(get)]
+    vendor_id: u16,
+
+    /// The product ID of the HID device the device is connected through.
+    #[pyo3(get)]
+    product_id: u16,
+
+    /// The receiver slot of the device, or `None` if it is connected directly.
+    #[pyo3(get)]
+    slot: Option<u8>,
+
+    /// The wireless product ID of the device, or `None` if it is connected
+    /// directly.
+    #[pyo3(get)]
+    wpid: Option<u16>,
+}
+
+/// Discovers all online HID++2.0 devices.
+pub async fn discover() -> PyResult<Vec<Device>> {
+    let channels = hidpp::async_hid::enumerate_hidpp()
+        .await
+        .map_err(to_py_err)?;
+
+    let mut devices = Vec::new();
+    for channel in channels.into_iter().map(Arc::new) {
+        let Some(receiver) = receiver::detect(Arc::clone(&channel)) else {
+            if let Some(device) = Device::init(&channel, DIRECT_DEVICE_INDEX, None).await? {
+                devices.push(device);
+            }
+            continue;
+        };
+
+        let mut paired_devices = receiver.collect_paired_devices().await.map_err(to_py_err)?;
+        paired_devices.sort_by_key(|x| x.slot);
+
+        for paired in paired_devices.into_iter().filter(|x| x.online) {
+            let name = receiver.get_device_name(&paired).await.map_err(to_py_err)?;
+
+            if let Some(mut device) = Device::init(&channel, paired.slot, Some(paired.wpid)).await?
+            {
+                device.name = name;
+                devices.push(device);
+            }
+        }
+    }
+
+    Ok(devices)
+}
+
+impl Device {
+    /// Initializes a device and enumerates its features.
+    ///
+    /// Returns `Ok(None)` if there is no HID++2.0 device at the given index.
+    async fn init(
+        channel: &Arc<HidppChannel>,
+        device_index: u8,
+        wpid: Option<u16>,
+    ) -> PyResult<Option<Self>> {
+        let mut device = match device::Device::new(Arc::clone(channel), device_index).await {
+            Ok(device) => device,
+            Err(
+                DeviceError::DeviceNotFound
+                | DeviceError::DeviceOffline
+                | DeviceError::Unknown(_)
+                | DeviceError::UnsupportedProtocolVersion,
+            ) => {
+                return Ok(None);
+            },
+            Err(err) => return Err(to_py_err(err)),
+        };
+        let features = device
+            .enumerate_features()
+            .await
+            .map_err(to_py_err)?
+            .unwrap_or_default();
+
+        let name = match device.get_feature::<DeviceTypeAndNameFeature>() {
+            Some(feature) => feature.get_whole_device_name().await.map_err(to_py_err)?,
+            None => "Unknown device".to_string(),
+        };
+
+        Ok(Some(Self {
+            device,
+            features,
+            name,
+            vendor_id: channel.vendor_id,
+            product_id: channel.product_id,
+            slot: wpid.map(|_| device_index),
+            wpid,
+        }))
+    }
+
+    /// Retrieves a feature implementation, raising
+    /// [`UnsupportedFeatureError`] if the device does not support it.
+    fn feature<F: Feature>(&self) -> PyResult<Arc<F>> {
+        self.device.get_feature::<F>().ok_or_else(|| {
+            UnsupportedFeatureError::new_err(format!(
+                "{} does not support the required feature",
+                self.name
+            ))
+        })
+    }
+}
+
+#[pymethods]
+impl Device {
+    /// Lists all features of the device.
+    fn features(&self) -> Vec<FeatureInfo> {
+        self.features
+            .iter()
+            .enumerate()
+            .map(|(i, info)| FeatureInfo {
+                index: i as u8 + 1,
+                id: info.id,
+                name: registry::lookup(info.id).map(|x| x.name.to_string()),
+                version: info.version,
+            })
+            .collect()
+    }
+
+    /// Pings the device, returning the echoed payload.
+    #[pyo3(signature = (payload = 0))]
+    fn ping<'py>(&self, py: Python<'py>, payload: u8) -> PyResult<Bound<'py, PyAny>> {
+        let root = self.device.root();
+        future_into_py(
+            py,
+            async move { root.ping(payload).await.map_err(to_py_err) },
+        )
+    }
+
+    /// Retrieves the battery state of the device.
+    fn battery<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let feature = self.feature::<UnifiedBatteryFeature>()?;
+        future_into_py(py, async move {
+            let capabilities = feature
+                .get_battery_capabilities()
+                .await
+                .map_err(to_py_err)?;
+            let info = feature.get_battery_info().await.map_err(to_py_err)?;
+
+            Ok(Battery {
+                percentage: capabilities.percentage.then_some(info.charging_percentage),
+                level: battery_level_name(info.level),
+                status: battery_status_name(info.status),
+            })
+        })
+    }
+
+    /// Retrieves the current DPI of a sensor.
+    #[pyo3(signature = (sensor = 0))]
+    fn get_dpi<'py>(&self, py: Python<'py>, sensor: u8) -> PyResult<Bound<'py, PyAny>> {
+        let feature = self.feature::<AdjustableDpiFeature>()?;
+        future_into_py(py, async move {
+            Ok(feature.get_sensor_dpi(sensor).await.map_err(to_py_err)?.dpi)
+        })
+    }
+
+    /// Lists all DPI values supported by a sensor.
+    #[pyo3(signature = (sensor = 0))]
+    fn get_dpi_list<'py>(&self, py: Python<'py>, sensor: u8) -> PyResult<Bound<'py, PyAny>> {
+        let feature = self.feature::<AdjustableDpiFeature>()?;
+        future_into_py(py, async move {
+            let list = feature
+                .get_sensor_dpi_list(sensor)
+                .await
+                .map_err(to_py_err)?;
+
+            let mut values = Vec::new();
+            for entry in list.entries {
+                match entry {
+                    DpiListEntry::Value(value) => values.push(value),
+                    DpiListEntry::Range {
+                        min,
+                        max,
+                        step,
+                    } => values.extend((min..=max).step_by(step.max(1) as usize)),
+                    _ => {},
+                }
+            }
+
+            Ok(values)
+        })
+    }
+
+    /// Sets the DPI of a sensor, returning the DPI actually set by the device.
+    ///
+    /// Raises `ValueError` if the sensor does not support the DPI.
+    #[pyo3(signature = (dpi, sensor = 0))]
+    fn set_dpi<'py>(&self, py: Python<'py>, dpi: u16, sensor: u8) -> PyResult<Bound<'py, PyAny>> {
+        let feature = self.feature::<AdjustableDpiFeature>()?;
+        future_into_py(py, async move {
+            let list = feature
+                .get_sensor_dpi_list(sensor)
+                .await
+                .map_err(to_py_err)?;
+            if !list.contains(dpi) {
+                return Err(PyValueError::new_err(format!(
+                    "the sensor does not support {} DPI",
+                    dpi
+                )));
+            }
+
+            feature.set_sensor_dpi(sensor, dpi).await.map_err(to_py_err)
+        })
+    }
+
+    /// Retrieves the SmartShift state of the scroll wheel.
+    fn get_smartshift<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let feature = self.feature::<SmartShiftFeature>()?;
+        future_into_py(py, async move {
+            let mode = feature
+                .get_ratchet_control_mode()
+                .await
+                .map_err(to_py_err)?;
+
+            Ok(SmartShift {
+                mode: match mode.wheel_mode {
+                    WheelMode::Freespin => "freespin",
+                    WheelMode::Ratchet => "ratchet",
+                    _ => "unknown",
+                }
+                .to_string(),
+                auto_disengage: mode.auto_disengage,
+                auto_disengage_default: mode.auto_disengage_default,
+            })
+        })
+    }
+
+    /// Changes the SmartShift state of the scroll wheel.
+    ///
+    /// `mode` is either `"freespin"` or `"ratchet"`. Omitted values stay as
+    /// they are.
+    #[pyo3(signature = (mode = None, auto_disengage = None))]
+    fn set_smartshift<'py>(
+        &self,
+        py: Python<'py>,
+        mode: Option<&str>,
+        auto_disengage: Option<u8>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let feature = self.feature::<SmartShiftFeature>()?;
+        let mode = match mode {
+            None => None,
+            Some("freespin") => Some(WheelMode::Freespin),
+            Some("ratchet") => Some(WheelMode::Ratchet),
+            Some(other) => {
+                return Err(PyValueError::new_err(format!(
+                    "invalid wheel mode \"{}\", expected \"freespin\" or \"ratchet\"",
+                    other
+                )));
+            },
+        };
+
+        future_into_py(py, async move {
+            feature
+                .set_ratchet_control_mode(mode, auto_disengage, None)
+                .await
+                .map_err(to_py_err)
+        })
+    }
+
+    /// Creates an asynchronous iterator yielding all events emitted by the
+    /// device.
+    fn events(&self) -> EventStream {
+        EventStream::new(&self.device)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Device(name={:?}, vendor_id=0x{:04x}, product_id=0x{:04x})",
+            self.name, self.vendor_id, self.product_id
+        )
+    }
+}
+
+/// A single feature of a device.
+#[pyclass(frozen, get_all, name = "Feature", module = "hidpp")]
+pub struct FeatureInfo {
+    /// The index the feature is addressed with.
+    index: u8,
+
+    /// The protocol ID of the feature.
+    id: u16,
+
+    /// The name of the feature, or `None` if it is unknown.
+    name: Option<String>,
+
+    /// The latest supported version of the feature.
+    version: u8,
+}
+
+#[pymethods]
+impl FeatureInfo {
+    fn __repr__(&self) -> String {
+        format!(
+            "Feature(index={}, id=0x{:04x}, name={:?}, version={})",
+            self.index,
+            self.id,
+            self.name.as_deref().unwrap_or("Unknown"),
+            self.version
+        )
+    }
+}
+
+/// The battery state of a device.
+#[pyclass(frozen, get_all, module = "hidpp")]
+pub struct Battery {
+    /// The charge in percent, or `None` if the device only reports levels.
+    percentage: Option<u8>,
+
+    /// The approximate charge level: `"critical"`, `"low"`, `"good"` or
+    /// `"full"`.
+    level: String,
+
+    /// The charging status: `"discharging"`, `"charging"`,
+    /// `"charging_slow"`, `"full"` or `"error"`.
+    status: String,
+}
+
+#[pymethods]
+impl Battery {
+    fn __repr__(&self) -> String {
+        format!(
+            "Battery(percentage={}, level={:?}, status={:?})",
+            self.percentage
+                .map_or_else(|| "None".to_string(), |x| x.to_string()),
+            self.level,
+            self.status
+        )
+    }
+}
+
+/// The SmartShift state of a scroll wheel.
+#[pyclass(frozen, get_all, module = "hidpp")]
+pub struct SmartShift {
+    /// The wheel mode: `"freespin"` or `"ratchet"`.
+    mode: String,
+
+    /// The amount of quarter-turns per second it takes for the wheel to
+    /// automatically disengage, or `255` to never disengage.
+    auto_disengage: u8,
+
+    /// The default value of `auto_disengage`.
+    auto_disengage_default: u8,
+}
+
+#[pymethods]
+impl SmartShift {
+    fn __repr__(&self) -> String {
+        format!(
+            "SmartShift(mode={:?}, auto_disengage={}, auto_disengage_default={})",
+            self.mode, self.auto_disengage, self.auto_disengage_default
+        )
+    }
+}
+
+fn battery_level_name(level: BatteryLevel) -> String {
+    match level {
+        BatteryLevel::Critical => "critical",
+        BatteryLevel::Low => "low",
+        BatteryLevel::Good => "good",
+        BatteryLevel::Full => "full",
+        _ => "unknown",
+    }
+    .to_string()
+}
+
+fn battery_status_name(status: BatteryStatus) -> String {
+    match status {
+        BatteryStatus::Discharging => "discharging",
+        BatteryStatus::Charging => "charging",
+        BatteryStatus::ChargingSlow => "charging_slow",
+        BatteryStatus::Full => "full",
+        BatteryStatus::Error => "error",
+        _ => "unknown",
+    }
+    .to_string()
+}
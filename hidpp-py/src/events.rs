@@ -0,0 +1,146 @@
+//! Implements the asynchronous event stream of a device.
+
+use std::fmt::Debug;
+
+use hidpp::{
+    device::Device,
+    feature::{
+        EmittingFeature,
+        Feature,
+        battery_voltage::BatteryVoltageFeature,
+        hires_wheel::HiResWheelFeature,
+        thumbwheel::ThumbwheelFeature,
+        unified_battery::UnifiedBatteryFeature,
+        wireless_device_status::WirelessDeviceStatusFeature,
+    },
+};
+use pyo3::{
+    exceptions::PyStopAsyncIteration,
+    prelude::*,
+    types::{PyDict, PyList},
+};
+use serde::Serialize;
+
+/// An event emitted by a device.
+#[pyclass(frozen, module = "hidpp")]
+pub struct Event {
+    /// The kind of the event: `"battery"`, `"battery_voltage"`,
+    /// `"wireless_status"`, `"hires_wheel"` or `"thumbwheel"`.
+    #[pyo3(get)]
+    kind: String,
+
+    data: serde_json::Value,
+}
+
+#[pymethods]
+impl Event {
+    /// The data of the event as a dictionary.
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_to_py(py, &self.data)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Event(kind={:?}, data={})", self.kind, self.data)
+    }
+}
+
+/// An asynchronous iterator yielding all events emitted by a device.
+#[pyclass(frozen, module = "hidpp")]
+pub struct EventStream {
+    rx: async_channel::Receiver<Event>,
+}
+
+impl EventStream {
+    /// Starts forwarding the events of all supported emitting features of a
+    /// device to a new stream.
+    pub fn new(device: &Device) -> Self {
+        let (tx, rx) = async_channel::unbounded();
+
+        forward::<UnifiedBatteryFeature, _>(device, "battery", &tx);
+        forward::<BatteryVoltageFeature, _>(device, "battery_voltage", &tx);
+        forward::<WirelessDeviceStatusFeature, _>(device, "wireless_status", &tx);
+        forward::<HiResWheelFeature, _>(device, "hires_wheel", &tx);
+        forward::<ThumbwheelFeature, _>(device, "thumbwheel", &tx);
+
+        Self {
+            rx,
+        }
+    }
+}
+
+#[pymethods]
+impl EventStream {
+    fn __aiter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __anext__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let rx = self.rx.clone();
+        pyo3_async_runtimes::tokio::future_into_py(py, async move {
+            rx.recv()
+                .await
+                .map_err(|_| PyStopAsyncIteration::new_err(()))
+        })
+    }
+}
+
+/// Forwards all events of a specific feature of a device to a channel, if the
+/// device supports the feature.
+fn forward<F, T>(device: &Device, kind: &'static str, tx: &async_channel::Sender<Event>)
+where
+    F: Feature + EmittingFeature<T>,
+    T: Serialize + Debug + Send + 'static,
+{
+    let Some(feature) = device.get_feature::<F>() else {
+        return;
+    };
+
+    let rx = feature.listen();
+    let tx = tx.clone();
+    pyo3_async_runtimes::tokio::get_runtime().spawn(async move {
+        while let Ok(event) = rx.recv().await {
+            let event = Event {
+                kind: kind.to_string(),
+                data: serde_json::to_value(&event).unwrap_or_default(),
+            };
+
+            if tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Converts a JSON value into the equivalent Python object.
+fn json_to_py<'py>(py: Python<'py>, value: &serde_json::Value) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match value {
+        serde_json::Value::Null => py.None().into_bound(py),
+        serde_json::Value::Bool(value) => value.into_pyobject(py)?.to_owned().into_any(),
+        serde_json::Value::Number(value) => match (value.as_i64(), value.as_u64()) {
+            (Some(value), _) => value.into_pyobject(py)?.into_any(),
+            (None, Some(value)) => value.into_pyobject(py)?.into_any(),
+            (None, None) => value
+                .as_f64()
+                .unwrap_or_default()
+                .into_pyobject(py)?
+                .into_any(),
+        },
+        serde_json::Value::String(value) => value.into_pyobject(py)?.into_any(),
+        serde_json::Value::Array(values) => PyList::new(
+            py,
+            values
+                .iter()
+                .map(|x| json_to_py(py, x))
+                .collect::<PyResult<Vec<_>>>()?,
+        )?
+        .into_any(),
+        serde_json::Value::Object(values) => {
+            let dict = PyDict::new(py);
+            for (key, value) in values {
+                dict.set_item(key, json_to_py(py, value)?)?;
+            }
+            dict.into_any()
+        },
+    })
+}
@@ -0,0 +1,61 @@
+//! Python bindings for the `hidpp` crate.
+//!
+//! The bindings expose a high-level API to enumerate devices, read their
+//! battery state, manage their DPI and SmartShift settings and receive their
+//! events. All methods communicating with a device are coroutines.
+//!
+//! The extension module is built using [maturin](https://www.maturin.rs):
+//!
+//! ```sh
+//! maturin develop -m hidpp-py/Cargo.toml
+//! ```
+
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+
+mod device;
+mod events;
+
+create_exception!(
+    hidpp,
+    HidppError,
+    PyException,
+    "Raised when the communication with a device fails."
+);
+
+create_exception!(
+    hidpp,
+    UnsupportedFeatureError,
+    HidppError,
+    "Raised when a device does not support the feature required by an operation."
+);
+
+/// Converts an error returned by the `hidpp` crate into a [`HidppError`].
+fn to_py_err(err: impl ToString) -> PyErr {
+    HidppError::new_err(err.to_string())
+}
+
+/// Discovers all online HID++2.0 devices, including devices paired to a
+/// receiver and devices connected directly to the host.
+#[pyfunction]
+fn enumerate(py: Python<'_>) -> PyResult<Bound<'_, PyAny>> {
+    pyo3_async_runtimes::tokio::future_into_py(py, device::discover())
+}
+
+#[pymodule]
+#[pyo3(name = "hidpp")]
+fn hidpp_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(enumerate, m)?)?;
+    m.add_class::<device::Device>()?;
+    m.add_class::<device::FeatureInfo>()?;
+    m.add_class::<device::Battery>()?;
+    m.add_class::<device::SmartShift>()?;
+    m.add_class::<events::Event>()?;
+    m.add_class::<events::EventStream>()?;
+    m.add("HidppError", m.py().get_type::<HidppError>())?;
+    m.add(
+        "UnsupportedFeatureError",
+        m.py().get_type::<UnsupportedFeatureError>(),
+    )?;
+
+    Ok(())
+}
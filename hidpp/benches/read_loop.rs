@@ -0,0 +1,142 @@
+//! Measures the throughput and heap allocations of the read loop of
+//! [`HidppChannel`] when dispatching notifications to a listener.
+//!
+//! Run with `cargo bench -p hidpp --bench read_loop`. Exits with an error if
+//! the steady state allocates more than the transport itself does.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    error::Error,
+    process::ExitCode,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Instant,
+};
+
+use async_trait::async_trait;
+use hidpp::channel::{HidppChannel, RawHidChannel};
+
+/// The amount of notifications processed before measuring.
+const WARMUP: usize = 10_000;
+
+/// The total amount of notifications served by the transport.
+const TOTAL: usize = 1_010_000;
+
+/// The allocations per message performed by the transport itself, as
+/// [`async_trait`] boxes the future returned by
+/// [`RawHidChannel::read_report`].
+const TRANSPORT_ALLOCATIONS: f64 = 1.0;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// A transport serving [`TOTAL`] battery notifications and nothing else
+/// afterwards.
+struct NotificationChannel {
+    served: AtomicUsize,
+}
+
+#[async_trait]
+impl RawHidChannel for NotificationChannel {
+    fn vendor_id(&self) -> u16 {
+        0x046d
+    }
+
+    fn product_id(&self) -> u16 {
+        0xc548
+    }
+
+    async fn write_report(&self, src: &[u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        Ok(src.len())
+    }
+
+    async fn read_report(&self, buf: &mut [u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        if self.served.fetch_add(1, Ordering::Relaxed) >= TOTAL {
+            futures::future::pending::<()>().await;
+        }
+
+        let report = [0x10, 0x01, 0x08, 0x00, 0x50, 0x00, 0x00];
+        buf[..report.len()].copy_from_slice(&report);
+        Ok(report.len())
+    }
+
+    fn supports_short_long_hidpp(&self) -> Option<(bool, bool)> {
+        Some((true, true))
+    }
+
+    async fn get_report_descriptor(
+        &self,
+        _: &mut [u8],
+    ) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        Err("the report descriptor is not needed".into())
+    }
+}
+
+fn wait_for(received: &AtomicUsize, count: usize) {
+    while received.load(Ordering::Acquire) < count {
+        thread::yield_now();
+    }
+}
+
+fn main() -> ExitCode {
+    let chan = Arc::new(
+        futures::executor::block_on(HidppChannel::from_raw_channel(NotificationChannel {
+            served: AtomicUsize::new(0),
+        }))
+        .unwrap(),
+    );
+
+    let received = Arc::new(AtomicUsize::new(0));
+    let _listener = chan.subscribe({
+        let received = Arc::clone(&received);
+        move |_, _| {
+            received.fetch_add(1, Ordering::Release);
+        }
+    });
+
+    wait_for(&received, WARMUP);
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+
+    wait_for(&received, TOTAL);
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+
+    let messages = (TOTAL - WARMUP) as f64;
+    let per_message = allocations as f64 / messages;
+
+    println!(
+        "read_loop: {:.0} ns/message, {per_message:.3} allocations/message",
+        elapsed.as_nanos() as f64 / messages,
+    );
+
+    if per_message > TRANSPORT_ALLOCATIONS + 0.01 {
+        eprintln!("the read loop allocates in the steady state");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,273 @@
+//! Implements [`RawHidChannel`] using the [`async-hid`](https://crates.io/crates/async-hid)
+//! crate.
+//!
+//! This module is only available with the `async-hid` feature.
+
+use std::{collections::HashSet, error::Error, pin::pin};
+#[cfg(target_os = "linux")]
+use std::{fs::File, io::Read};
+
+#[cfg(any(target_os = "linux", target_os = "windows"))]
+use ::async_hid::DeviceId;
+use ::async_hid::{
+    AsyncHidRead,
+    AsyncHidWrite,
+    Device,
+    DeviceInfo,
+    DeviceReader,
+    DeviceWriter,
+    HidBackend,
+    HidError,
+};
+use async_trait::async_trait;
+use futures::{
+    StreamExt,
+    future::{self, Either},
+    lock::Mutex,
+};
+use thiserror::Error;
+
+use crate::channel::{
+    self,
+    ChannelError,
+    HidppChannel,
+    LONG_REPORT_LENGTH,
+    RawHidChannel,
+    SHORT_REPORT_ID,
+};
+
+/// Represents an opened `async-hid` device.
+pub struct AsyncHidChannel {
+    reader: Mutex<DeviceReader>,
+    writer: Mutex<DeviceWriter>,
+    info: DeviceInfo,
+
+    /// The short HID++ collection of the same physical device, if it is a
+    /// separate HID device, as is the case on Windows.
+    short: Option<(Mutex<DeviceReader>, Mutex<DeviceWriter>)>,
+}
+
+impl AsyncHidChannel {
+    /// Opens a HID device for reading and writing.
+    pub async fn open(device: Device) -> Result<Self, HidError> {
+        let (reader, writer) = device.open().await?;
+
+        Ok(Self {
+            reader: Mutex::new(reader),
+            writer: Mutex::new(writer),
+            info: device.to_device_info(),
+            short: None,
+        })
+    }
+
+    /// Opens the long and the short HID++ collection of a device, which are
+    /// separate HID devices on Windows, as a single channel.
+    ///
+    /// Short reports are sent through the short collection and reports are
+    /// read from both.
+    pub async fn open_collections(long: Device, short: Device) -> Result<Self, HidError> {
+        let (short_reader, short_writer) = short.open().await?;
+
+        Ok(Self {
+            short: Some((Mutex::new(short_reader), Mutex::new(short_writer))),
+            ..Self::open(long).await?
+        })
+    }
+
+    /// Provides the information about the device the channel was opened on.
+    pub fn info(&self) -> &DeviceInfo {
+        &self.info
+    }
+}
+
+#[async_trait]
+impl RawHidChannel for AsyncHidChannel {
+    fn vendor_id(&self) -> u16 {
+        self.info.vendor_id
+    }
+
+    fn product_id(&self) -> u16 {
+        self.info.product_id
+    }
+
+    async fn write_report(&self, src: &[u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let writer = match &self.short {
+            Some((_, writer)) if src.first() == Some(&SHORT_REPORT_ID) => writer,
+            _ => &self.writer,
+        };
+
+        writer.lock().await.write_output_report(src).await?;
+        Ok(src.len())
+    }
+
+    async fn read_report(&self, buf: &mut [u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let mut guard = self.reader.lock().await;
+        let Some((short_reader, _)) = &self.short else {
+            return Ok(guard.read_input_report(buf).await?);
+        };
+
+        // The backend keeps a pending read across cancellations, so the read
+        // losing the race does not drop a report.
+        let mut short_guard = short_reader.lock().await;
+        let mut short_buf = [0u8; LONG_REPORT_LENGTH];
+        let read = {
+            let long_read = pin!(guard.read_input_report(buf));
+            let short_read = pin!(short_guard.read_input_report(&mut short_buf));
+            match future::select(long_read, short_read).await {
+                Either::Left((len, _)) => Either::Left(len),
+                Either::Right((len, _)) => Either::Right(len),
+            }
+        };
+
+        match read {
+            Either::Left(len) => Ok(len?),
+            Either::Right(len) => {
+                let len = len?.min(buf.len());
+                buf[..len].copy_from_slice(&short_buf[..len]);
+                Ok(len)
+            },
+        }
+    }
+
+    fn supports_short_long_hidpp(&self) -> Option<(bool, bool)> {
+        if self.short.is_some() {
+            return Some((true, true));
+        }
+
+        // Report descriptors are only available on Linux, so other platforms
+        // rely on the usage of the top-level collection instead.
+        if cfg!(target_os = "linux") {
+            return None;
+        }
+
+        let guess = channel::guess_short_long_hidpp(
+            self.info.vendor_id,
+            self.info.usage_page,
+            self.info.usage_id,
+        );
+        Some(match guess {
+            // On macOS, a device covers all of its top-level collections, so
+            // a device with one HID++ collection also has the other one.
+            Some(_) if cfg!(target_os = "macos") => (true, true),
+            Some(guess) => guess,
+            None => (false, false),
+        })
+    }
+
+    async fn get_report_descriptor(
+        &self,
+        buf: &mut [u8],
+    ) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        #[cfg(target_os = "linux")]
+        {
+            let DeviceId::DevPath(ref path) = self.info.id else {
+                return Err("unsupported HID device ID".into());
+            };
+
+            let descriptor_path = path.join("device/report_descriptor");
+            let mut file = File::open(descriptor_path)?;
+            Ok(file.read(buf)?)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let _ = buf;
+            Err("report descriptors are currently only supported on Linux".into())
+        }
+    }
+}
+
+/// Represents an error that occurred while enumerating HID++ channels.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum AsyncHidError {
+    /// Indicates that the HID devices could not be enumerated or opened.
+    #[error("the HID backend returned an error: {0}")]
+    Hid(#[from] HidError),
+
+    /// Indicates that a HID++ channel could not be initialized on a device
+    /// supporting HID++.
+    #[error("could not initialize the HID++ channel")]
+    Channel(#[from] ChannelError),
+}
+
+/// Tries to find all [`HidppChannel`]s on the local machine.
+pub async fn enumerate_hidpp() -> Result<Vec<HidppChannel>, AsyncHidError> {
+    let hid = HidBackend::default();
+    let mut devices: Vec<Device> = hid.enumerate().await?.collect::<Vec<Device>>().await;
+    let mut seen = HashSet::new();
+    devices.retain(|x| seen.insert(x.id.clone()));
+
+    let mut channels = Vec::new();
+    for (dev, short) in group_collections(devices) {
+        let raw = match short {
+            Some(short) => AsyncHidChannel::open_collections(dev, short).await?,
+            None => AsyncHidChannel::open(dev).await?,
+        };
+
+        let channel = match HidppChannel::from_raw_channel(raw).await {
+            Ok(channel) => channel,
+            Err(ChannelError::HidppNotSupported) => continue,
+            Err(other) => return Err(other.into()),
+        };
+        channels.push(channel);
+    }
+
+    Ok(channels)
+}
+
+/// Pairs the long HID++ collection of every device with its short HID++
+/// collection, if both are separate HID devices.
+///
+/// On Windows, every top-level collection is enumerated as a separate device,
+/// which would otherwise result in two channels per physical device.
+fn group_collections(devices: Vec<Device>) -> Vec<(Device, Option<Device>)> {
+    let (mut shorts, devices): (Vec<_>, Vec<_>) = devices.into_iter().partition(|dev| {
+        physical_device(dev).is_some()
+            && channel::guess_short_long_hidpp(dev.vendor_id, dev.usage_page, dev.usage_id)
+                == Some((true, false))
+    });
+
+    let mut grouped = Vec::with_capacity(devices.len() + shorts.len());
+    for dev in devices {
+        let short = physical_device(&dev)
+            .filter(|_| {
+                channel::guess_short_long_hidpp(dev.vendor_id, dev.usage_page, dev.usage_id)
+                    == Some((false, true))
+            })
+            .and_then(|path| {
+                shorts
+                    .iter()
+                    .position(|short| physical_device(short).as_ref() == Some(&path))
+            })
+            .map(|index| shorts.swap_remove(index));
+        grouped.push((dev, short));
+    }
+
+    // Keep short collections without a matching long collection on their own.
+    grouped.extend(shorts.into_iter().map(|short| (short, None)));
+    grouped
+}
+
+/// Identifies the physical device a HID device belongs to, if its top-level
+/// collections are enumerated as separate devices.
+///
+/// Windows device interface paths look like
+/// `\\?\hid#vid_046d&pid_c52b&mi_02&col01#7&2b3a4c5d&0&0000#{...}`, where
+/// the `&colNN` part and the last part of the instance ID differ between the
+/// collections of the same device.
+#[cfg(target_os = "windows")]
+fn physical_device(device: &Device) -> Option<String> {
+    let DeviceId::UncPath(ref path) = device.id;
+    let path = path.to_string().to_lowercase();
+
+    let mut parts = path.split('#').skip(1);
+    let (hardware_id, _) = parts.next()?.split_once("&col")?;
+    let (instance_id, _) = parts.next()?.rsplit_once('&')?;
+    Some(format!("{hardware_id}#{instance_id}"))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn physical_device(_: &Device) -> Option<String> {
+    None
+}
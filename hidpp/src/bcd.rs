@@ -1,19 +1,84 @@
-use crate::nibble::U4;
+//! Conversion between integers and packed binary-coded decimal (BCD) values.
+//!
+//! Packed BCD stores one decimal digit in every nibble, so `0x42` represents
+//! the number `42`. HID++ devices use this encoding for firmware versions,
+//! for example.
+//!
+//! ```
+//! use hidpp::bcd;
+//!
+//! assert_eq!(bcd::encode_packed_u16(1234), Ok(0x1234));
+//! assert_eq!(bcd::decode_packed_u16(0x1234), Ok(1234));
+//! assert!(bcd::decode_packed_u8(0x1a).is_err());
+//!
+//! for value in 0..=9999 {
+//!     let encoded = bcd::encode_packed_u16(value).unwrap();
+//!     assert_eq!(bcd::decode_packed_u16(encoded), Ok(value));
+//! }
+//! ```
 
-pub fn convert_packed_u8(bcd: u8) -> Result<u8, ()> {
+use thiserror::Error;
+
+use crate::nibble::{self, U4};
+
+/// Represents an error that may occur when converting from or to packed BCD.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Error)]
+#[non_exhaustive]
+pub enum BcdError {
+    /// Indicates that a value to decode contains a nibble greater than `9`,
+    /// which is not a decimal digit.
+    ///
+    /// Values passed to [`decode_packed_u8`] are widened to [`u16`], so the
+    /// invalid byte is held in the lower eight bits.
+    #[error("{0:#06x} is not a valid packed BCD value")]
+    InvalidDigit(u16),
+
+    /// Indicates that a value has more decimal digits than fit into the
+    /// target type when encoded.
+    ///
+    /// Values passed to [`encode_packed_u8`] are widened to [`u16`].
+    #[error("{0} has too many digits to be encoded as packed BCD")]
+    OutOfRange(u16),
+}
+
+/// Decodes a packed BCD byte holding two decimal digits.
+pub fn decode_packed_u8(bcd: u8) -> Result<u8, BcdError> {
     let digit_0 = U4::from_hi(bcd).to_lo();
     let digit_1 = U4::from_lo(bcd).to_lo();
 
     if digit_0 > 9 || digit_1 > 9 {
-        Err(())
+        Err(BcdError::InvalidDigit(bcd as u16))
     } else {
         Ok(digit_0 * 10 + digit_1)
     }
 }
 
-pub fn convert_packed_u16(bcd: u16) -> Result<u16, ()> {
-    let digits_0 = convert_packed_u8((bcd >> 8) as u8)? as u16;
-    let digits_1 = convert_packed_u8((bcd & 0xff) as u8)? as u16;
+/// Decodes a packed BCD value holding four decimal digits.
+pub fn decode_packed_u16(bcd: u16) -> Result<u16, BcdError> {
+    let [hi, lo] = bcd.to_be_bytes();
+    match (decode_packed_u8(hi), decode_packed_u8(lo)) {
+        (Ok(hi), Ok(lo)) => Ok(hi as u16 * 100 + lo as u16),
+        _ => Err(BcdError::InvalidDigit(bcd)),
+    }
+}
+
+/// Encodes a value of at most two decimal digits as a packed BCD byte.
+pub fn encode_packed_u8(value: u8) -> Result<u8, BcdError> {
+    if value > 99 {
+        return Err(BcdError::OutOfRange(value as u16));
+    }
+
+    Ok(nibble::combine(U4::new(value / 10), U4::new(value % 10)))
+}
+
+/// Encodes a value of at most four decimal digits as a packed BCD value.
+pub fn encode_packed_u16(value: u16) -> Result<u16, BcdError> {
+    if value > 9999 {
+        return Err(BcdError::OutOfRange(value));
+    }
+
+    let hi = encode_packed_u8((value / 100) as u8)?;
+    let lo = encode_packed_u8((value % 100) as u8)?;
 
-    Ok(digits_0 * 100 + digits_1)
+    Ok(u16::from_be_bytes([hi, lo]))
 }
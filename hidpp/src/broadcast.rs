@@ -0,0 +1,100 @@
+//! Applies the same operation to multiple devices concurrently.
+//!
+//! This is meant for actions affecting every device of a user, like turning
+//! on the Fn lock of all keyboards. The operation runs for all devices at the
+//! same time and a failure on one device does not affect the others.
+//!
+//! ```ignore
+//! use hidpp::{broadcast, feature::smartshift::SmartShiftFeature};
+//!
+//! let results = broadcast::broadcast_channel(channel, async |device| {
+//!     match device.get_feature::<SmartShiftFeature>() {
+//!         Some(feature) => feature.get_ratchet_control_mode().await.map(Some),
+//!         None => Ok(None),
+//!     }
+//! })
+//! .await;
+//!
+//! for result in results {
+//!     println!("{}: {:?}", result.device_index, result.result);
+//! }
+//! ```
+
+use std::sync::Arc;
+
+use futures::future;
+
+use crate::{channel::HidppChannel, device::Device};
+
+/// The device indices probed by [`discover_devices`]: the six slots of a
+/// receiver and the index of a directly connected device.
+pub const DEVICE_INDICES: [u8; 7] = [1, 2, 3, 4, 5, 6, 0xff];
+
+/// Represents the result of an operation on a single device.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct BroadcastResult<T> {
+    /// The index of the device the operation ran on.
+    pub device_index: u8,
+
+    /// The result of the operation.
+    pub result: T,
+}
+
+/// Runs an operation on all given devices concurrently.
+///
+/// Returns the results in the order of the devices.
+pub async fn broadcast<'a, F, T>(
+    devices: impl IntoIterator<Item = &'a Device>,
+    op: F,
+) -> Vec<BroadcastResult<T>>
+where
+    F: AsyncFn(&'a Device) -> T,
+{
+    let op = &op;
+
+    future::join_all(devices.into_iter().map(|device| async move {
+        BroadcastResult {
+            device_index: device.device_index,
+            result: op(device).await,
+        }
+    }))
+    .await
+}
+
+/// Initializes all online HID++2.0 devices of a channel and enumerates their
+/// features.
+///
+/// Devices that are offline, only support HID++1.0 or fail to initialize are
+/// skipped.
+pub async fn discover_devices(chan: Arc<HidppChannel>) -> Vec<Device> {
+    let devices = future::join_all(DEVICE_INDICES.into_iter().map(|device_index| {
+        let chan = Arc::clone(&chan);
+
+        async move {
+            let mut device = Device::new(chan, device_index).await.ok()?;
+            device.enumerate_features().await.ok()?;
+            Some(device)
+        }
+    }))
+    .await;
+
+    devices.into_iter().flatten().collect()
+}
+
+/// Runs an operation on all online devices of a channel concurrently, as
+/// found by [`discover_devices`].
+///
+/// Returns the results in the order of the device indices.
+pub async fn broadcast_channel<F, T>(chan: Arc<HidppChannel>, op: F) -> Vec<BroadcastResult<T>>
+where F: AsyncFn(&Device) -> T {
+    let devices = discover_devices(chan).await;
+
+    future::join_all(devices.iter().map(|device| async {
+        BroadcastResult {
+            device_index: device.device_index,
+            result: op(device).await,
+        }
+    }))
+    .await
+}
@@ -0,0 +1,107 @@
+//! Decodes presses and releases of diverted controls.
+//!
+//! Devices report diverted controls via the `ReprogControls` / `0x1b04`
+//! feature. Instead of individual presses and releases, they report the set
+//! of diverted controls held down after every change, which a
+//! [`ButtonEventDecoder`] turns into [`ButtonEvent`]s.
+
+use std::{hash::Hash, time::Instant};
+
+/// Represents a press or release of a diverted control.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ButtonEvent {
+    /// The CID of the control.
+    pub control: u16,
+
+    /// Whether the control was pressed or released.
+    pub state: ButtonState,
+
+    /// The time the notification reporting the change was received at.
+    pub timestamp: Instant,
+}
+
+/// Represents whether a [`ButtonEvent`] is a press or a release.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum ButtonState {
+    Pressed,
+    Released,
+}
+
+/// Decodes [`ButtonEvent`]s from the notifications of diverted controls.
+///
+/// The device does not report individual presses and releases, but the set of
+/// diverted controls held down after every change. The decoder remembers the
+/// previous set and reports the controls that were added as pressed and the
+/// ones that disappeared as released.
+///
+/// ```
+/// use std::time::Instant;
+///
+/// use hidpp::buttons::{ButtonEventDecoder, ButtonState};
+///
+/// let mut decoder = ButtonEventDecoder::new();
+/// let now = Instant::now();
+///
+/// let events = decoder.decode(&[0x0053], now);
+/// assert_eq!(events[0].control, 0x0053);
+/// assert_eq!(events[0].state, ButtonState::Pressed);
+///
+/// let events = decoder.decode(&[0x0053, 0x0056], now);
+/// assert_eq!(events.len(), 1);
+/// assert_eq!(events[0].control, 0x0056);
+///
+/// let events = decoder.decode(&[], now);
+/// assert!(events.iter().all(|x| x.state == ButtonState::Released));
+/// assert_eq!(events.len(), 2);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ButtonEventDecoder {
+    /// The CIDs of the controls held down after the last notification.
+    pressed: Vec<u16>,
+}
+
+impl ButtonEventDecoder {
+    /// Constructs a decoder assuming that no control is held down.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes a notification containing the CIDs of all currently held down
+    /// controls.
+    ///
+    /// Releases are returned before presses, both in the order the device
+    /// reported the controls.
+    pub fn decode(&mut self, pressed: &[u16], timestamp: Instant) -> Vec<ButtonEvent> {
+        let released = self
+            .pressed
+            .iter()
+            .filter(|cid| !pressed.contains(cid))
+            .map(|&control| ButtonEvent {
+                control,
+                state: ButtonState::Released,
+                timestamp,
+            });
+
+        let newly_pressed = pressed
+            .iter()
+            .filter(|cid| !self.pressed.contains(cid))
+            .map(|&control| ButtonEvent {
+                control,
+                state: ButtonState::Pressed,
+                timestamp,
+            });
+
+        let events = released.chain(newly_pressed).collect();
+        self.pressed = pressed.to_vec();
+
+        events
+    }
+
+    /// Provides the CIDs of the controls currently held down.
+    pub fn pressed(&self) -> &[u16] {
+        &self.pressed
+    }
+}
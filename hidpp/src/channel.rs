@@ -3,16 +3,19 @@
 //! This includes mapping incoming messages to previously sent requests.
 
 use std::{
-    collections::{HashMap, VecDeque},
+    collections::VecDeque,
     error::Error,
     sync::{
         Arc,
         Mutex,
-        atomic::{AtomicBool, AtomicU8, Ordering},
+        Weak,
+        atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering},
+        mpsc,
     },
     thread::{self, JoinHandle},
 };
 
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
 use futures::{FutureExt, channel::oneshot, select};
 use hidreport::{Field, Report, ReportDescriptor, Usage, UsageId, UsagePage};
@@ -30,6 +33,9 @@ const MAX_REPORT_DESCRIPTOR_LENGTH: usize = 4096;
 /// As we only care about HID++ reports, this equals to [`LONG_REPORT_LENGTH`].
 const MAX_REPORT_LENGTH: usize = LONG_REPORT_LENGTH;
 
+/// The USB vendor ID of Logitech.
+pub const LOGITECH_VENDOR_ID: u16 = 0x046d;
+
 /// The ID of the HID report that is used to transmit short HID++ messages.
 pub const SHORT_REPORT_ID: u8 = 0x10;
 
@@ -101,6 +107,28 @@ pub trait RawHidChannel: Sync + Send + 'static {
     ) -> Result<usize, Box<dyn Error + Sync + Send>>;
 }
 
+/// Guesses whether a HID top-level collection carries short or long HID++
+/// messages based on its usage, without reading the report descriptor.
+///
+/// This is meant for [`RawHidChannel`] implementations on platforms that
+/// don't expose report descriptors but split devices into one channel per
+/// top-level collection, like Windows. Logitech devices declare a
+/// vendor-defined collection for each of the two message lengths.
+///
+/// Returns `Some((supports_short, supports_long))` for the HID++ collections
+/// of Logitech devices and `None` for every other collection.
+pub fn guess_short_long_hidpp(vendor_id: u16, usage_page: u16, usage: u16) -> Option<(bool, bool)> {
+    if vendor_id != LOGITECH_VENDOR_ID {
+        return None;
+    }
+
+    match (usage_page, usage) {
+        (SHORT_REPORT_USAGE_PAGE, SHORT_REPORT_USAGE) => Some((true, false)),
+        (LONG_REPORT_USAGE_PAGE, LONG_REPORT_USAGE) => Some((false, true)),
+        _ => None,
+    }
+}
+
 /// Checks whether a raw channel supports short or long HID++ messages.
 async fn supports_short_long_hidpp(
     chan: &impl RawHidChannel,
@@ -211,7 +239,17 @@ impl HidppMessage {
     }
 }
 
-type MessageListener = Box<dyn Fn(HidppMessage, bool) + Send>;
+/// The amount of incoming messages that may wait for the listeners to be
+/// called before further messages are dropped instead of dispatched.
+const DISPATCH_QUEUE_LENGTH: usize = 256;
+
+type MessageListener = Arc<dyn Fn(HidppMessage, bool) + Send + Sync>;
+
+/// The registered message listeners together with their handles.
+///
+/// The list is never modified in place. Instead, a modified copy replaces it
+/// as a whole, so the read thread can dispatch messages without taking a lock.
+type MessageListeners = ArcSwap<Vec<(u32, MessageListener)>>;
 
 /// Represents a HID communication channel supporting HID++.
 pub struct HidppChannel {
@@ -241,7 +279,11 @@ pub struct HidppChannel {
 
     /// Registered listeners that will receive notifications about incoming
     /// messages.
-    message_listeners: Arc<Mutex<HashMap<u32, MessageListener>>>,
+    message_listeners: Arc<MessageListeners>,
+
+    /// The amount of incoming messages that were not dispatched to the
+    /// listeners because the dispatch queue was full.
+    dropped_messages: Arc<AtomicUsize>,
 
     /// The sender signaling the read thread to stop.
     read_thread_close: Option<oneshot::Sender<()>>,
@@ -249,6 +291,10 @@ pub struct HidppChannel {
     /// The handle to the read thread. Should be joined after signaling
     /// [`Self::read_thread_close`].
     read_thread_hdl: Option<JoinHandle<()>>,
+
+    /// The handle to the thread calling the [`Self::message_listeners`]. It
+    /// stops on its own once the read thread stopped.
+    dispatch_thread_hdl: Option<JoinHandle<()>>,
 }
 
 impl Drop for HidppChannel {
@@ -264,6 +310,15 @@ impl Drop for HidppChannel {
         if let Some(read_thread_hdl) = self.read_thread_hdl.take() {
             read_thread_hdl.join().unwrap();
         }
+
+        // The channel may be dropped by a listener, in which case the dispatch
+        // thread can't wait for itself and just stops after the listener returns.
+        if let Some(dispatch_thread_hdl) = self.dispatch_thread_hdl.take()
+            && dispatch_thread_hdl.thread().id() != thread::current().id()
+        {
+            // A panicking listener already reported its panic on the dispatch thread.
+            let _ = dispatch_thread_hdl.join();
+        }
     }
 }
 
@@ -292,14 +347,31 @@ impl HidppChannel {
 
         let raw_channel_rc = Arc::new(raw);
         let pending_messages_rc = Arc::new(Mutex::new(VecDeque::<PendingMessage>::new()));
-        let message_listeners_rc = Arc::new(Mutex::new(HashMap::<u32, MessageListener>::new()));
+        let message_listeners_rc = Arc::new(MessageListeners::default());
+        let dropped_messages_rc = Arc::new(AtomicUsize::new(0));
 
         let (close_sender, mut close_receiver) = oneshot::channel::<()>();
+        let (dispatch_sender, dispatch_receiver) =
+            mpsc::sync_channel::<(HidppMessage, bool)>(DISPATCH_QUEUE_LENGTH);
+
+        // Listeners run on their own thread, so slow listeners can't delay
+        // matching responses to pending requests.
+        let dispatch_thread_hdl = thread::spawn({
+            let message_listeners = Arc::clone(&message_listeners_rc);
+
+            move || {
+                for (msg, matched) in dispatch_receiver {
+                    for (_, listener) in message_listeners.load().iter() {
+                        listener(msg, matched);
+                    }
+                }
+            }
+        });
 
         let read_thread_hdl = thread::spawn({
             let raw_channel = Arc::clone(&raw_channel_rc);
             let pending_messages = Arc::clone(&pending_messages_rc);
-            let message_listeners = Arc::clone(&message_listeners_rc);
+            let dropped_messages = Arc::clone(&dropped_messages_rc);
 
             move || {
                 futures::executor::block_on(async {
@@ -322,6 +394,11 @@ impl HidppChannel {
                         };
 
                         let mut msgs = pending_messages.lock().unwrap();
+
+                        // Requests whose caller stopped waiting, e.g. because of a
+                        // timeout, must not swallow responses meant for newer ones.
+                        msgs.retain(|elem| !elem.sender.is_canceled());
+
                         let mut matched = false;
                         if let Some(pos) =
                             msgs.iter().position(|elem| (elem.response_predicate)(&msg))
@@ -331,8 +408,18 @@ impl HidppChannel {
                             matched = true;
                         }
 
-                        for listener in message_listeners.lock().unwrap().values() {
-                            listener(msg, matched);
+                        drop(msgs);
+
+                        // Waiting for the listeners to catch up would stop responses from
+                        // being matched, which deadlocks listeners waiting for one, as well
+                        // as listeners dropping the channel, which joins this thread.
+                        // Sending only fails otherwise if the dispatch thread panicked
+                        // because of a listener, in which case there is nobody left to
+                        // notify.
+                        if let Err(mpsc::TrySendError::Full(_)) =
+                            dispatch_sender.try_send((msg, matched))
+                        {
+                            dropped_messages.fetch_add(1, Ordering::Relaxed);
                         }
                     }
                 });
@@ -349,8 +436,10 @@ impl HidppChannel {
             software_id: AtomicU8::new(0x01),
             pending_messages: pending_messages_rc,
             message_listeners: message_listeners_rc,
+            dropped_messages: dropped_messages_rc,
             read_thread_close: Some(close_sender),
             read_thread_hdl: Some(read_thread_hdl),
+            dispatch_thread_hdl: Some(dispatch_thread_hdl),
         })
     }
 
@@ -385,10 +474,11 @@ impl HidppChannel {
             U4::from_lo(
                 self.software_id
                     .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |old| {
-                        Some(if old & 0x0f == 0x0f {
+                        let next = U4::from_lo(old) + U4::new(1);
+                        Some(if next == U4::MIN {
                             0x01
                         } else {
-                            old.wrapping_add(1)
+                            next.to_lo()
                         })
                     })
                     .unwrap(),
@@ -410,7 +500,10 @@ impl HidppChannel {
     ///
     /// If no response is expected/required, use [`Self::send_and_forget`].
     ///
-    /// The future resolves to `Ok(None)` if no response was received.
+    /// The future only resolves once a matching response was received, so
+    /// callers have to apply a timeout themselves if the device may not answer.
+    /// It resolves to [`ChannelError::NoResponse`] if the request was discarded
+    /// without a response.
     pub async fn send(
         &self,
         msg: HidppMessage,
@@ -455,30 +548,104 @@ impl HidppChannel {
 
     /// Registers a listener that will be called for every incoming message.
     ///
+    /// Listeners are called on a dedicated thread, in the order the messages
+    /// arrived. Blocking in a listener delays other listeners, but not the
+    /// responses to requests. Listeners may even wait for responses
+    /// themselves. If so many messages queue up that the listeners can't catch
+    /// up, further messages are dropped, see [`Self::dropped_message_count`].
+    ///
     /// Returns a handle that can be used to remove the listener using a call to
     /// [`Self::remove_msg_listener`].
-    pub fn add_msg_listener(&self, listener: impl Fn(HidppMessage, bool) + Send + 'static) -> u32 {
-        let mut listeners = self.message_listeners.lock().unwrap();
-
+    ///
+    /// Listeners may register and remove listeners themselves. Such changes
+    /// take effect starting with the next incoming message.
+    pub fn add_msg_listener(
+        &self,
+        listener: impl Fn(HidppMessage, bool) + Send + Sync + 'static,
+    ) -> u32 {
+        let listener: MessageListener = Arc::new(listener);
         let mut rng = rand::rng();
-        let mut hdl = rng.random::<u32>();
-        while listeners.contains_key(&hdl) {
+        let mut hdl = 0;
+
+        self.message_listeners.rcu(|listeners| {
             hdl = rng.random::<u32>();
-        }
+            while listeners.iter().any(|&(x, _)| x == hdl) {
+                hdl = rng.random::<u32>();
+            }
+
+            let mut listeners = Vec::clone(listeners);
+            listeners.push((hdl, Arc::clone(&listener)));
+            listeners
+        });
 
-        listeners.insert(hdl, Box::new(listener));
         hdl
     }
 
+    /// Registers a listener that will be called for every incoming message,
+    /// like [`Self::add_msg_listener`].
+    ///
+    /// The listener is removed as soon as the returned guard is dropped.
+    pub fn subscribe(
+        self: &Arc<Self>,
+        listener: impl Fn(HidppMessage, bool) + Send + Sync + 'static,
+    ) -> ListenerGuard {
+        ListenerGuard {
+            chan: Arc::downgrade(self),
+            hdl: self.add_msg_listener(listener),
+        }
+    }
+
+    /// Returns the amount of incoming messages that were not passed to the
+    /// listeners because too many messages were still waiting to be
+    /// dispatched.
+    ///
+    /// Responses to requests are delivered regardless.
+    pub fn dropped_message_count(&self) -> usize {
+        self.dropped_messages.load(Ordering::Relaxed)
+    }
+
     /// Removes a previously registered message listener.
     ///
     /// Returns whether a listener was found using the given handle.
+    ///
+    /// A message that is currently being dispatched may still reach the
+    /// listener after this returns.
     pub fn remove_msg_listener(&self, hdl: u32) -> bool {
-        self.message_listeners
-            .lock()
-            .unwrap()
-            .remove(&hdl)
-            .is_some()
+        let mut found = false;
+
+        self.message_listeners.rcu(|listeners| {
+            let mut listeners = Vec::clone(listeners);
+            let len = listeners.len();
+            listeners.retain(|&(x, _)| x != hdl);
+            found = listeners.len() != len;
+            listeners
+        });
+
+        found
+    }
+}
+
+/// Removes a message listener registered via [`HidppChannel::subscribe`] when
+/// dropped.
+#[must_use = "the listener is removed as soon as the guard is dropped"]
+pub struct ListenerGuard {
+    chan: Weak<HidppChannel>,
+    hdl: u32,
+}
+
+impl ListenerGuard {
+    /// The handle of the listener, as also returned by
+    /// [`HidppChannel::add_msg_listener`].
+    pub fn handle(&self) -> u32 {
+        self.hdl
+    }
+}
+
+impl Drop for ListenerGuard {
+    fn drop(&mut self) {
+        if let Some(chan) = self.chan.upgrade() {
+            chan.remove_msg_listener(self.hdl);
+        }
     }
 }
 
@@ -508,4 +675,15 @@ pub enum ChannelError {
     /// Indicates that no response was received following a request.
     #[error("the device did not respond to the request")]
     NoResponse,
+
+    /// Indicates that the HID++ channel was already dropped by its owner.
+    #[error("the HID++ channel is no longer available")]
+    ChannelGone,
+}
+
+/// Upgrades a weak reference to a HID++ channel, as held by features.
+///
+/// Returns [`ChannelError::ChannelGone`] if the channel was dropped.
+pub(crate) fn upgrade(chan: &Weak<HidppChannel>) -> Result<Arc<HidppChannel>, ChannelError> {
+    chan.upgrade().ok_or(ChannelError::ChannelGone)
 }
@@ -1,7 +1,9 @@
 //! Implements peripheral devices connected to HID++ channels.
 
-use std::{any::TypeId, collections::HashMap, sync::Arc};
+use std::{any::TypeId, collections::HashMap, fmt, sync::Arc, time::Duration};
 
+use futures::{FutureExt, Stream, select, stream};
+use futures_timer::Delay;
 use thiserror::Error;
 
 use crate::{
@@ -10,15 +12,27 @@ use crate::{
         self,
         CreatableFeature,
         Feature,
+        FeatureType,
+        device_friendly_name::DeviceFriendlyNameFeature,
+        device_information::DeviceInformationFeature,
+        device_type_and_name::DeviceTypeAndNameFeature,
         feature_set::{FeatureInformation, FeatureSetFeature},
         root::RootFeature,
     },
-    protocol::{self, ProtocolVersion, v20::Hidpp20Error},
+    protocol::{self, DeviceProbe, ProtocolVersion, v20::Hidpp20Error},
+    receiver::Receiver,
 };
 
+/// The time [`Device::is_online`] waits for a device to respond.
+pub const PRESENCE_TIMEOUT: Duration = Duration::from_millis(500);
+
 /// Represents a single HID++ device connected to a [`HidppChannel`].
 ///
 /// This is used only for peripheral devices and not receivers.
+///
+/// Features only hold weak references to the channel, so the device (or any
+/// other owner of the channel) has to be kept alive while features are used.
+/// Afterwards, feature functions fail with [`ChannelError::ChannelGone`].
 #[derive(Clone)]
 pub struct Device {
     /// The underlying HID++ channel.
@@ -41,21 +55,44 @@ impl Device {
     /// version it supports via [`protocol::determine_version`].
     ///
     /// Returns [`DeviceError::DeviceNotFound`] if there is no device with the
-    /// specified index connected to the channel.
+    /// specified index connected to the channel and
+    /// [`DeviceError::DeviceOffline`] if the device is paired, but currently
+    /// not connected.
     ///
     /// Returns [`DeviceError::UnsupportedProtocolVersion`] if the device only
     /// supports [`ProtocolVersion::V10`].
     pub async fn new(chan: Arc<HidppChannel>, device_index: u8) -> Result<Self, DeviceError> {
-        let protocol_version = protocol::determine_version(&chan, device_index).await?;
-
-        if protocol_version.is_none() {
-            return Err(DeviceError::DeviceNotFound);
-        }
-        let version = protocol_version.unwrap();
+        Self::new_with_retries(chan, device_index, 0, Duration::ZERO).await
+    }
 
-        if version == ProtocolVersion::V10 {
-            return Err(DeviceError::UnsupportedProtocolVersion);
-        }
+    /// Tries to initialize a device on a HID++ channel like [`Self::new`], but
+    /// pings the device up to `retries` more times, waiting `delay` in
+    /// between, as long as it is offline.
+    ///
+    /// This is useful for devices that just announced their connection, as
+    /// some of them take a moment to wake up before they respond.
+    pub async fn new_with_retries(
+        chan: Arc<HidppChannel>,
+        device_index: u8,
+        retries: u32,
+        delay: Duration,
+    ) -> Result<Self, DeviceError> {
+        let mut attempt = 0;
+        let version = loop {
+            match protocol::determine_version(&chan, device_index).await? {
+                DeviceProbe::Found(ProtocolVersion::V10) => {
+                    return Err(DeviceError::UnsupportedProtocolVersion);
+                },
+                DeviceProbe::Found(version) => break version,
+                DeviceProbe::Offline if attempt < retries => {
+                    attempt += 1;
+                    Delay::new(delay).await;
+                },
+                DeviceProbe::Offline => return Err(DeviceError::DeviceOffline),
+                DeviceProbe::NotFound => return Err(DeviceError::DeviceNotFound),
+                DeviceProbe::Unknown(code) => return Err(DeviceError::Unknown(code)),
+            }
+        };
 
         let mut device = Self {
             chan,
@@ -71,6 +108,140 @@ impl Device {
         Ok(device)
     }
 
+    /// Checks whether the device is currently reachable by pinging it.
+    ///
+    /// The device counts as offline if it does not respond within
+    /// [`PRESENCE_TIMEOUT`] or the ping fails.
+    pub async fn is_online(&self) -> bool {
+        select! {
+            probe = protocol::determine_version(&self.chan, self.device_index).fuse() => {
+                matches!(probe, Ok(DeviceProbe::Found(_)))
+            },
+            _ = Delay::new(PRESENCE_TIMEOUT).fuse() => false,
+        }
+    }
+
+    /// Watches whether the device is reachable by calling [`Self::is_online`]
+    /// every `interval`.
+    ///
+    /// The returned stream yields the current state first and then every
+    /// transition between online and offline. This is meant for devices whose
+    /// receivers don't reliably announce connection changes.
+    pub fn watch_presence(&self, interval: Duration) -> impl Stream<Item = bool> + Send + use<> {
+        stream::unfold((self.clone(), None), move |(device, last)| async move {
+            let mut first = last.is_none();
+            loop {
+                if !first {
+                    Delay::new(interval).await;
+                }
+                first = false;
+
+                let online = device.is_online().await;
+                if last != Some(online) {
+                    return Some((online, (device, Some(online))));
+                }
+            }
+        })
+    }
+
+    /// Resolves the best human-readable name for the device.
+    ///
+    /// The name is taken from the first source providing a non-empty name, in
+    /// this order:
+    ///
+    /// 1. the friendly name set by the user ([`DeviceFriendlyNameFeature`])
+    /// 2. the marketing name ([`DeviceTypeAndNameFeature`])
+    /// 3. the codename stored by the receiver the device is paired to, if one
+    ///    is given
+    ///
+    /// Sources that fail to respond are skipped. Returns [`None`] if no source
+    /// provided a name.
+    pub async fn resolve_name(&self, receiver: Option<&Receiver>) -> Option<ResolvedName> {
+        if let Some(feature) = self.get_feature::<DeviceFriendlyNameFeature>()
+            && let Ok(name) = feature.get_whole_friendly_name().await
+            && !name.is_empty()
+        {
+            return Some(ResolvedName {
+                name,
+                source: NameSource::FriendlyName,
+            });
+        }
+
+        if let Some(feature) = self.get_feature::<DeviceTypeAndNameFeature>()
+            && let Ok(name) = feature.name().await
+            && !name.is_empty()
+        {
+            return Some(ResolvedName {
+                name,
+                source: NameSource::DeviceName,
+            });
+        }
+
+        if let Some(receiver) = receiver
+            && let Ok(name) = receiver.get_device_codename(self.device_index).await
+            && !name.is_empty()
+        {
+            return Some(ResolvedName {
+                name,
+                source: NameSource::ReceiverCodename,
+            });
+        }
+
+        None
+    }
+
+    /// Derives an identifier for the device that stays the same across
+    /// reconnects, receivers and hosts, suitable as the key for caches and
+    /// configuration files.
+    ///
+    /// The identifier is taken from the first available of:
+    ///
+    /// 1. the serial number of the device, via [`DeviceInformationFeature`]
+    /// 2. the unit ID of the device, via [`DeviceInformationFeature`]
+    /// 3. the unit ID stored by the receiver the device is paired to, if one is
+    ///    given
+    /// 4. the unique ID of the receiver combined with the pairing slot of the
+    ///    device, if a receiver is given
+    ///
+    /// Sources that fail to respond are skipped and unit IDs consisting of
+    /// zeros only are ignored. Returns [`None`] if no source provided an
+    /// identifier.
+    pub async fn identity(&self, receiver: Option<&Receiver>) -> Option<DeviceIdentity> {
+        if let Some(feature) = self.get_feature::<DeviceInformationFeature>()
+            && let Ok(info) = feature.get_versioned_device_info().await
+        {
+            if info.supports_serial_number()
+                && let Ok(serial) = feature.get_serial_number().await
+                && !serial.is_empty()
+            {
+                return Some(DeviceIdentity::Serial(serial));
+            }
+
+            if let Some(unit_id) = info.unit_id()
+                && unit_id != [0; 4]
+            {
+                return Some(DeviceIdentity::UnitId(unit_id));
+            }
+        }
+
+        if let Some(receiver) = receiver {
+            if let Ok(unit_id) = receiver.get_device_unit_id(self.device_index).await
+                && unit_id != [0; 4]
+            {
+                return Some(DeviceIdentity::UnitId(unit_id));
+            }
+
+            if let Ok(receiver) = receiver.get_unique_id().await {
+                return Some(DeviceIdentity::Pairing {
+                    receiver,
+                    slot: self.device_index,
+                });
+            }
+        }
+
+        None
+    }
+
     /// A convenience wrapper around [`Self::get_feature`] to obtain the root
     /// feature.
     pub fn root(&self) -> Arc<RootFeature> {
@@ -128,10 +299,23 @@ impl Device {
     ///
     /// Returns a vector containing all feature IDs supported by the device.
     ///
-    /// Returns `Ok(None)` if the [`FeatureSetFeatureV0`] feature, which is
+    /// Returns `Ok(None)` if the [`FeatureSetFeature`] feature, which is
     /// required for feature enumeration, is not supported by the device.
     pub async fn enumerate_features(
         &mut self,
+    ) -> Result<Option<Vec<FeatureInformation>>, Hidpp20Error> {
+        self.enumerate_features_with(&EnumerationOptions::default())
+            .await
+    }
+
+    /// Works like [`Self::enumerate_features`], but allows skipping features
+    /// based on their [`FeatureType`].
+    ///
+    /// Skipped features are treated as if the device did not support them, so
+    /// they are neither instantiated nor contained in the returned vector.
+    pub async fn enumerate_features_with(
+        &mut self,
+        options: &EnumerationOptions,
     ) -> Result<Option<Vec<FeatureInformation>>, Hidpp20Error> {
         let Some(feature_set_info) = self.root().get_feature(FeatureSetFeature::ID).await? else {
             return Ok(None);
@@ -143,11 +327,10 @@ impl Device {
         let mut features = Vec::with_capacity(count as usize);
         for i in 1..=count {
             let info = feature_set_feature.get_feature(i).await?;
-            features.push(info);
-
-            if i == feature_set_info.index {
+            if options.skips(info.typ) {
                 continue;
             }
+            features.push(info);
 
             let Some(impls) = feature::registry::lookup_version(info.id, info.version) else {
                 continue;
@@ -165,6 +348,94 @@ impl Device {
     }
 }
 
+/// Configures which features [`Device::enumerate_features_with`] skips.
+///
+/// By default, no features are skipped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct EnumerationOptions {
+    /// Whether to skip features flagged as [`FeatureType::hidden`], which are
+    /// not meant to be used by end user configuration software.
+    pub skip_hidden: bool,
+
+    /// Whether to skip features flagged as [`FeatureType::engineering`], which
+    /// are used for internal testing and manufacturing.
+    pub skip_engineering: bool,
+}
+
+impl EnumerationOptions {
+    /// Checks whether a feature of the given type is skipped.
+    fn skips(&self, typ: FeatureType) -> bool {
+        (self.skip_hidden && typ.hidden) || (self.skip_engineering && typ.engineering)
+    }
+}
+
+/// Represents a name resolved by [`Device::resolve_name`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ResolvedName {
+    pub name: String,
+
+    /// The source the name was taken from.
+    pub source: NameSource,
+}
+
+/// Represents a source of the name of a device.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum NameSource {
+    /// The friendly name set by the user, via [`DeviceFriendlyNameFeature`].
+    FriendlyName,
+
+    /// The marketing name of the device, via [`DeviceTypeAndNameFeature`].
+    DeviceName,
+
+    /// The codename stored by the receiver the device is paired to.
+    ReceiverCodename,
+}
+
+/// Represents a stable identifier of a device, as derived by
+/// [`Device::identity`].
+///
+/// The [`Display`](fmt::Display) implementation yields a compact string
+/// usable as a key, like `serial:1234ABCD`, `unit:0a1b2c3d` or
+/// `pairing:5C1A2B3C:2`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum DeviceIdentity {
+    /// The serial number of the device.
+    Serial(String),
+
+    /// The unit ID of the device.
+    UnitId([u8; 4]),
+
+    /// The pairing slot of the device on a receiver, identified by its unique
+    /// ID.
+    ///
+    /// This only stays the same as long as the device is not re-paired.
+    Pairing {
+        receiver: String,
+        slot: u8,
+    },
+}
+
+impl fmt::Display for DeviceIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Serial(serial) => write!(f, "serial:{serial}"),
+            Self::UnitId([a, b, c, d]) => write!(f, "unit:{a:02x}{b:02x}{c:02x}{d:02x}"),
+            Self::Pairing {
+                receiver,
+                slot,
+            } => write!(f, "pairing:{receiver}:{slot}"),
+        }
+    }
+}
+
 /// Represents a device-specific error.
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -177,6 +448,16 @@ pub enum DeviceError {
     #[error("there is no device with the specified device index")]
     DeviceNotFound,
 
+    /// Indicates that a device is paired to the specified device index, but
+    /// is currently not connected.
+    #[error("the device with the specified device index is offline")]
+    DeviceOffline,
+
+    /// Indicates that the specified device index responded with an unexpected
+    /// HID++1.0 error code.
+    #[error("the device index responded with the unexpected error code {0:#04x}")]
+    Unknown(u8),
+
     /// Indicates that the addressed device does only support HID++1.0.
     #[error("the device does not support HID++2.0 or newer")]
     UnsupportedProtocolVersion,
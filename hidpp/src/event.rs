@@ -1,31 +1,88 @@
-use std::sync::Mutex;
+//! Provides the types used to deliver events emitted by features and
+//! receivers.
+
+use std::{sync::Mutex, time::Instant};
+
+use crate::channel::HidppMessage;
+
+/// Wraps an event together with the time it was received and the raw message
+/// it was parsed from.
+///
+/// Envelopes allow measuring latencies and ordering events across different
+/// features and receivers.
+#[derive(Clone, Debug)]
+pub struct Envelope<T> {
+    /// The event itself.
+    pub event: T,
+
+    /// The monotonic time the message carrying the event was processed at.
+    pub received_at: Instant,
+
+    /// The raw message the event was parsed from.
+    pub raw: HidppMessage,
+}
 
 /// A simple event emitter sending a single event to multiple MPSC channels.
 #[derive(Debug)]
-pub struct EventEmitter<T: Clone> {
-    senders: Mutex<Vec<async_channel::Sender<T>>>,
+pub(crate) struct EventEmitter<T: Clone> {
+    senders: Mutex<Senders<T>>,
+}
+
+/// The senders of an [`EventEmitter`], kept behind a single lock so emitting
+/// an event only has to lock once.
+#[derive(Debug)]
+struct Senders<T> {
+    events: Vec<async_channel::Sender<T>>,
+    envelopes: Vec<async_channel::Sender<Envelope<T>>>,
 }
 
 impl<T: Clone> EventEmitter<T> {
     pub fn new() -> Self {
         Self {
-            senders: Mutex::new(Vec::new()),
+            senders: Mutex::new(Senders {
+                events: Vec::new(),
+                envelopes: Vec::new(),
+            }),
         }
     }
 
     /// Creates a new receiver and adds the corresponding sender to the sender
     /// list.
     pub fn create_receiver(&self) -> async_channel::Receiver<T> {
-        let mut senders = self.senders.lock().unwrap();
         let (tx, rx) = async_channel::unbounded();
-        senders.push(tx);
+        self.senders.lock().unwrap().events.push(tx);
+        rx
+    }
+
+    /// Creates a new receiver for events wrapped in an [`Envelope`] and adds
+    /// the corresponding sender to the sender list.
+    pub fn create_envelope_receiver(&self) -> async_channel::Receiver<Envelope<T>> {
+        let (tx, rx) = async_channel::unbounded();
+        self.senders.lock().unwrap().envelopes.push(tx);
         rx
     }
 
-    /// Emits an event to all senders. Senders whose receivers were dropped are
-    /// removed from the list.
-    pub fn emit(&self, event: T) {
+    /// Emits an event parsed from a raw message to all senders. Senders whose
+    /// receivers were dropped are removed from the list.
+    pub fn emit(&self, event: T, raw: HidppMessage) {
         let mut senders = self.senders.lock().unwrap();
-        senders.retain(|sender| sender.send_blocking(event.clone()).is_ok());
+        if senders.events.is_empty() && senders.envelopes.is_empty() {
+            return;
+        }
+
+        let received_at = Instant::now();
+        senders.envelopes.retain(|sender| {
+            sender
+                .send_blocking(Envelope {
+                    event: event.clone(),
+                    received_at,
+                    raw,
+                })
+                .is_ok()
+        });
+
+        senders
+            .events
+            .retain(|sender| sender.send_blocking(event.clone()).is_ok());
     }
 }
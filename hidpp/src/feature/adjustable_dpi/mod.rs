@@ -0,0 +1,412 @@
+//! Implements the `AdjustableDpi` feature (ID `0x2201`) that allows changing
+//! the DPI of the sensors of a mouse.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use thiserror::Error;
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `AdjustableDpi` / `0x2201` feature.
+pub struct AdjustableDpiFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for AdjustableDpiFeature {
+    const ID: u16 = 0x2201;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for AdjustableDpiFeature {
+}
+
+impl AdjustableDpiFeature {
+    /// Retrieves the amount of sensors whose DPI can be adjusted.
+    pub async fn get_sensor_count(&self) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Retrieves the DPI values supported by a sensor.
+    pub async fn get_sensor_dpi_list(&self, sensor: u8) -> Result<SensorDpiList, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [sensor, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        let values = payload[1..]
+            .chunks_exact(2)
+            .map(|x| u16::from_be_bytes([x[0], x[1]]))
+            .take_while(|&x| x != 0)
+            .collect::<Vec<u16>>();
+
+        let mut entries = Vec::new();
+        let mut i = 0;
+        while i < values.len() {
+            // A value with the three highest bits set marks a step between
+            // the previous and the next value.
+            if values[i] >> 13 == 0b111 {
+                let (Some(DpiListEntry::Value(min)), Some(&max)) =
+                    (entries.pop(), values.get(i + 1))
+                else {
+                    return Err(
+                        Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+                    );
+                };
+
+                entries.push(DpiListEntry::Range {
+                    min,
+                    max,
+                    step: values[i] & 0x1fff,
+                });
+                i += 2;
+                continue;
+            }
+
+            entries.push(DpiListEntry::Value(values[i]));
+            i += 1;
+        }
+
+        Ok(SensorDpiList {
+            entries,
+        })
+    }
+
+    /// Retrieves the current and default DPI of a sensor.
+    ///
+    /// The default DPI was added in feature version 2 and will be `0` for all
+    /// older versions.
+    pub async fn get_sensor_dpi(&self, sensor: u8) -> Result<SensorDpi, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(2),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [sensor, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(SensorDpi {
+            dpi: u16::from_be_bytes([payload[1], payload[2]]),
+            default_dpi: u16::from_be_bytes([payload[3], payload[4]]),
+        })
+    }
+
+    /// Sets the DPI of a sensor.
+    ///
+    /// Returns the DPI actually set by the device.
+    pub async fn set_sensor_dpi(&self, sensor: u8, dpi: u16) -> Result<u16, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let dpi_bytes = dpi.to_be_bytes();
+
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(3),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [sensor, dpi_bytes[0], dpi_bytes[1]],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(u16::from_be_bytes([payload[1], payload[2]]))
+    }
+
+    /// Determines which step of a preset a sensor is currently set to.
+    ///
+    /// Returns `None` if the current DPI matches none of the steps.
+    pub async fn read_dpi_preset_step(
+        &self,
+        sensor: u8,
+        preset: &DpiPreset,
+    ) -> Result<Option<usize>, Hidpp20Error> {
+        let dpi = self.get_sensor_dpi(sensor).await?.dpi;
+        Ok(preset.position(dpi))
+    }
+
+    /// Validates a preset against the values supported by a sensor and sets
+    /// the sensor to one of its steps, or to its default step if no index is
+    /// given.
+    ///
+    /// Returns the DPI actually set by the device.
+    pub async fn apply_dpi_preset(
+        &self,
+        sensor: u8,
+        preset: &DpiPreset,
+        index: Option<usize>,
+    ) -> Result<u16, DpiPresetError> {
+        preset.validate_for(&self.get_sensor_dpi_list(sensor).await?)?;
+
+        let index = index.unwrap_or(preset.default_index);
+        let step = preset
+            .steps
+            .get(index)
+            .ok_or(DpiPresetError::IndexOutOfRange {
+                index,
+                len: preset.steps.len(),
+            })?;
+
+        Ok(self.set_sensor_dpi(sensor, step.dpi).await?)
+    }
+}
+
+/// Represents the DPI values supported by a sensor as reported by
+/// [`AdjustableDpiFeature::get_sensor_dpi_list`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct SensorDpiList {
+    pub entries: Vec<DpiListEntry>,
+}
+
+impl SensorDpiList {
+    /// Checks whether a DPI value is supported by the sensor.
+    pub fn contains(&self, dpi: u16) -> bool {
+        self.entries.iter().any(|entry| match *entry {
+            DpiListEntry::Value(value) => value == dpi,
+            DpiListEntry::Range {
+                min,
+                max,
+                step,
+            } => (min..=max).contains(&dpi) && (step == 0 || (dpi - min).is_multiple_of(step)),
+        })
+    }
+}
+
+/// Represents a single entry of a [`SensorDpiList`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum DpiListEntry {
+    /// A single supported DPI value.
+    Value(u16),
+
+    /// A range of supported DPI values from `min` to `max` (inclusive) in
+    /// increments of `step`.
+    Range {
+        min: u16,
+        max: u16,
+        step: u16,
+    },
+}
+
+/// Represents the DPI of a sensor as reported by
+/// [`AdjustableDpiFeature::get_sensor_dpi`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct SensorDpi {
+    /// The current DPI.
+    pub dpi: u16,
+
+    /// The default DPI, or `0` for feature versions below 2.
+    pub default_dpi: u16,
+}
+
+/// Represents a list of DPI steps a sensor can be switched between, like the
+/// ones cycled through by a DPI button.
+///
+/// The same model is used for DPI settings stored on the host and on the
+/// device, so it can be validated against a [`SensorDpiList`] before it is
+/// applied.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DpiPreset {
+    /// The steps of the preset, in the order they are cycled through.
+    pub steps: Vec<DpiStep>,
+
+    /// The index of the step the sensor is set to by default.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub default_index: usize,
+}
+
+/// Represents a single step of a [`DpiPreset`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct DpiStep {
+    /// An optional name describing the step, like `"precision"`.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub name: Option<String>,
+
+    /// The DPI of the step.
+    pub dpi: u16,
+}
+
+impl DpiStep {
+    /// Constructs an unnamed step.
+    pub fn new(dpi: u16) -> Self {
+        Self {
+            name: None,
+            dpi,
+        }
+    }
+
+    /// Constructs a named step.
+    pub fn named(name: impl Into<String>, dpi: u16) -> Self {
+        Self {
+            name: Some(name.into()),
+            dpi,
+        }
+    }
+}
+
+impl DpiPreset {
+    /// Constructs a preset, failing if it has no steps or the default index
+    /// points to none of them.
+    pub fn new(steps: Vec<DpiStep>, default_index: usize) -> Result<Self, DpiPresetError> {
+        let preset = Self {
+            steps,
+            default_index,
+        };
+        preset.validate()?;
+
+        Ok(preset)
+    }
+
+    /// Checks whether the preset has steps and the default index points to
+    /// one of them.
+    pub fn validate(&self) -> Result<(), DpiPresetError> {
+        if self.steps.is_empty() {
+            return Err(DpiPresetError::Empty);
+        }
+
+        if self.default_index >= self.steps.len() {
+            return Err(DpiPresetError::IndexOutOfRange {
+                index: self.default_index,
+                len: self.steps.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether the preset is valid and all of its steps are supported
+    /// by a sensor.
+    pub fn validate_for(&self, list: &SensorDpiList) -> Result<(), DpiPresetError> {
+        self.validate()?;
+
+        if let Some((index, step)) = self
+            .steps
+            .iter()
+            .enumerate()
+            .find(|(_, step)| !list.contains(step.dpi))
+        {
+            return Err(DpiPresetError::UnsupportedDpi {
+                index,
+                dpi: step.dpi,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Provides the step the sensor is set to by default.
+    pub fn default_step(&self) -> Option<&DpiStep> {
+        self.steps.get(self.default_index)
+    }
+
+    /// Finds the index of the first step with the given DPI.
+    pub fn position(&self, dpi: u16) -> Option<usize> {
+        self.steps.iter().position(|step| step.dpi == dpi)
+    }
+}
+
+/// Represents an error that may occur when validating or applying a
+/// [`DpiPreset`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DpiPresetError {
+    /// Indicates that the preset has no steps.
+    #[error("the DPI preset has no steps")]
+    Empty,
+
+    /// Indicates that a step index points to no step of the preset.
+    #[error("the DPI preset has no step {index}, it only has {len}")]
+    IndexOutOfRange {
+        index: usize,
+        len: usize,
+    },
+
+    /// Indicates that a step uses a DPI value the sensor does not support.
+    #[error("the sensor does not support {dpi} DPI used by step {index}")]
+    UnsupportedDpi {
+        index: usize,
+        dpi: u16,
+    },
+
+    /// Indicates that the device returned an error.
+    #[error("the device returned an error")]
+    Hidpp(#[from] Hidpp20Error),
+}
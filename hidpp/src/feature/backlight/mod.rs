@@ -0,0 +1,182 @@
+//! Implements the `Backlight3` feature (ID `0x1983`) that allows controlling
+//! the brightness of a keyboard backlight.
+//!
+//! The older `Backlight1` (`0x1981`) and `Backlight2` (`0x1982`) features are
+//! not implemented.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `Backlight3` / `0x1983` feature.
+///
+/// Only the two lowest bits of the backlight state are known, so its other bits
+/// are ignored.
+pub struct Backlight3Feature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<BacklightEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for Backlight3Feature {
+    const ID: u16 = 0x1983;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::backlight_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for Backlight3Feature {
+}
+
+impl EmittingFeature<BacklightEvent> for Backlight3Feature {
+    fn listen(&self) -> async_channel::Receiver<BacklightEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<BacklightEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl Backlight3Feature {
+    /// Retrieves the current state of the backlight.
+    pub async fn get_backlight_info(&self) -> Result<BacklightInfo, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(BacklightInfo::from([payload[0], payload[1], payload[2]]))
+    }
+
+    /// Sets the brightness level of the backlight, which has to be lower than
+    /// [`BacklightInfo::level_count`].
+    ///
+    /// Setting a level disables the automatic brightness adjustment of
+    /// devices with an ambient light sensor.
+    ///
+    /// Returns the level actually set by the device.
+    pub async fn set_brightness(&self, level: u8) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [level, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+}
+
+/// Represents the state of the backlight.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct BacklightInfo {
+    /// Whether the backlight is currently turned on.
+    pub enabled: bool,
+
+    /// Whether the brightness is adjusted automatically based on the ambient
+    /// light.
+    pub automatic: bool,
+
+    /// The amount of brightness levels, which are indexed starting at `0`.
+    pub level_count: u8,
+
+    /// The current brightness level.
+    pub level: u8,
+}
+
+impl From<[u8; 3]> for BacklightInfo {
+    fn from(value: [u8; 3]) -> Self {
+        Self {
+            enabled: value[0] & (1 << 0) != 0,
+            automatic: value[0] & (1 << 1) != 0,
+            level_count: value[1],
+            level: value[2],
+        }
+    }
+}
+
+/// Represents an event emitted by the [`Backlight3Feature`] feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum BacklightEvent {
+    /// Is emitted whenever the state of the backlight changes, like when the
+    /// brightness is changed using the keys of the keyboard.
+    ///
+    /// This event is always enabled.
+    InfoUpdate(BacklightInfo),
+}
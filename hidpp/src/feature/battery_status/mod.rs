@@ -0,0 +1,265 @@
+//! Implements the `BatteryStatus` feature (ID `0x1000`) that provides
+//! information about the battery of older devices not supporting
+//! [`UnifiedBatteryFeature`](super::unified_battery::UnifiedBatteryFeature).
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{
+        CreatableFeature,
+        EmittingFeature,
+        Feature,
+        unified_battery::{BatteryLevel, BatteryStatus},
+    },
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `BatteryStatus` / `0x1000` feature.
+pub struct BatteryStatusFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<BatteryStatusEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for BatteryStatusFeature {
+    const ID: u16 = 0x1000;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::battery_status_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for BatteryStatusFeature {
+}
+
+impl EmittingFeature<BatteryStatusEvent> for BatteryStatusFeature {
+    fn listen(&self) -> async_channel::Receiver<BatteryStatusEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<BatteryStatusEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl BatteryStatusFeature {
+    /// Retrieves the current battery level and charging status.
+    pub async fn get_battery_level_status(&self) -> Result<BatteryLevelStatus, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        BatteryLevelStatus::try_from([payload[0], payload[1], payload[2]])
+            .map_err(|_| Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID))
+    }
+
+    /// Retrieves the capabilities of the battery.
+    pub async fn get_battery_capability(&self) -> Result<BatteryStatusCapability, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(BatteryStatusCapability {
+            level_count: payload[0],
+            osd_disabled: payload[1] & (1 << 0) != 0,
+            mileage: payload[1] & (1 << 1) != 0,
+            rechargeable: payload[1] & (1 << 2) != 0,
+            nominal_battery_life: u16::from_be_bytes([payload[2], payload[3]]),
+            critical_level: payload[4],
+        })
+    }
+}
+
+/// Represents the capabilities of the battery as reported by
+/// [`BatteryStatusFeature::get_battery_capability`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct BatteryStatusCapability {
+    /// The amount of distinct levels the battery reports, like `4` for a
+    /// device reporting 10%, 30%, 50% and 90%.
+    pub level_count: u8,
+
+    /// Whether the device asks the host not to show its own on-screen
+    /// notifications about the battery.
+    pub osd_disabled: bool,
+
+    /// Whether the device reports its level in percent, as opposed to only
+    /// [`Self::level_count`] distinct levels.
+    pub mileage: bool,
+
+    /// Whether the battery is rechargeable.
+    pub rechargeable: bool,
+
+    /// The nominal battery life in hours, or `0` if it is unknown.
+    pub nominal_battery_life: u16,
+
+    /// The discharge level in percent at and below which the battery is
+    /// critical.
+    pub critical_level: u8,
+}
+
+/// Represents the battery level and charging status as reported by
+/// [`BatteryStatusFeature::get_battery_level_status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct BatteryLevelStatus {
+    /// The current discharge level of the battery in percent, or `0` if it is
+    /// unknown, like while charging.
+    pub discharge_level: u8,
+
+    /// The discharge level in percent the battery will report next, or `0`
+    /// if it is unknown.
+    pub next_discharge_level: u8,
+
+    /// The charging status of the battery.
+    pub status: BatteryChargeStatus,
+}
+
+impl BatteryLevelStatus {
+    /// Maps the discharge level to the [`BatteryLevel`] reported by
+    /// [`UnifiedBatteryFeature`](super::unified_battery::UnifiedBatteryFeature),
+    /// treating the critical level of the battery as the upper bound of
+    /// [`BatteryLevel::Critical`].
+    ///
+    /// Returns [`None`] if the discharge level is unknown.
+    pub fn level(&self, capability: &BatteryStatusCapability) -> Option<BatteryLevel> {
+        match self.discharge_level {
+            0 => None,
+            level if level <= capability.critical_level => Some(BatteryLevel::Critical),
+            level => Some(BatteryLevel::from_percentage(level)),
+        }
+    }
+
+    /// Maps the charging status to the [`BatteryStatus`] reported by
+    /// [`UnifiedBatteryFeature`](super::unified_battery::UnifiedBatteryFeature).
+    pub fn battery_status(&self) -> BatteryStatus {
+        match self.status {
+            BatteryChargeStatus::Discharging => BatteryStatus::Discharging,
+            BatteryChargeStatus::Recharging | BatteryChargeStatus::AlmostFull => {
+                BatteryStatus::Charging
+            },
+            BatteryChargeStatus::SlowRecharge => BatteryStatus::ChargingSlow,
+            BatteryChargeStatus::Full => BatteryStatus::Full,
+            BatteryChargeStatus::InvalidBattery
+            | BatteryChargeStatus::ThermalError
+            | BatteryChargeStatus::OtherChargingError => BatteryStatus::Error,
+        }
+    }
+}
+
+impl TryFrom<[u8; 3]> for BatteryLevelStatus {
+    type Error = ();
+
+    fn try_from(value: [u8; 3]) -> Result<Self, Self::Error> {
+        Ok(Self {
+            discharge_level: value[0],
+            next_discharge_level: value[1],
+            status: BatteryChargeStatus::try_from(value[2]).map_err(|_| ())?,
+        })
+    }
+}
+
+/// Represents the charging status as reported in
+/// [`BatteryLevelStatus::status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum BatteryChargeStatus {
+    Discharging = 0,
+    Recharging = 1,
+    AlmostFull = 2,
+    Full = 3,
+    SlowRecharge = 4,
+    InvalidBattery = 5,
+    ThermalError = 6,
+    OtherChargingError = 7,
+}
+
+/// Represents an event emitted by the [`BatteryStatusFeature`] feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum BatteryStatusEvent {
+    /// Is emitted whenever the battery level or charging status changes.
+    ///
+    /// This event is always enabled.
+    LevelStatusBroadcast(BatteryLevelStatus),
+}
@@ -0,0 +1,199 @@
+//! Implements the `BatteryVoltage` feature (ID `0x1001`) that provides
+//! information about the battery voltage and charging state of the device.
+
+use std::sync::{Arc, Weak};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `BatteryVoltage` / `0x1001` feature.
+///
+/// This feature is mostly supported by devices that predate the
+/// `UnifiedBattery` feature.
+pub struct BatteryVoltageFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<BatteryVoltageEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for BatteryVoltageFeature {
+    const ID: u16 = 0x1001;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::battery_voltage_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for BatteryVoltageFeature {
+}
+
+impl EmittingFeature<BatteryVoltageEvent> for BatteryVoltageFeature {
+    fn listen(&self) -> async_channel::Receiver<BatteryVoltageEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<BatteryVoltageEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl BatteryVoltageFeature {
+    /// Retrieves the current battery voltage and charging state.
+    pub async fn get_battery_voltage(&self) -> Result<BatteryVoltageInfo, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(BatteryVoltageInfo::from(
+            <[u8; 3]>::try_from(&payload[..3]).unwrap(),
+        ))
+    }
+}
+
+/// Represents the battery voltage and charging state as reported by
+/// [`BatteryVoltageFeature::get_battery_voltage`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct BatteryVoltageInfo {
+    /// The current battery voltage in millivolts.
+    pub voltage: u16,
+
+    /// Whether an external power source is connected and the battery is
+    /// charging.
+    pub charging: bool,
+
+    /// The status of the charging process.
+    ///
+    /// This is only set if [`Self::charging`] is `true` and the device
+    /// reported a known status.
+    pub charging_status: Option<BatteryVoltageChargingStatus>,
+
+    /// The type of the charging process.
+    ///
+    /// This is only meaningful if [`Self::charging`] is `true`.
+    pub charging_type: BatteryVoltageChargingType,
+
+    /// Whether the device reports the battery level to be critical.
+    pub critical: bool,
+}
+
+impl From<[u8; 3]> for BatteryVoltageInfo {
+    fn from(value: [u8; 3]) -> Self {
+        let flags = value[2];
+        let charging = flags & (1 << 7) != 0;
+
+        Self {
+            voltage: u16::from_be_bytes([value[0], value[1]]),
+            charging,
+            charging_status: if charging {
+                BatteryVoltageChargingStatus::try_from(flags & 0x07).ok()
+            } else {
+                None
+            },
+            charging_type: if flags & (1 << 3) != 0 {
+                BatteryVoltageChargingType::Fast
+            } else if flags & (1 << 4) != 0 {
+                BatteryVoltageChargingType::Slow
+            } else {
+                BatteryVoltageChargingType::Standard
+            },
+            critical: flags & (1 << 5) != 0,
+        }
+    }
+}
+
+/// Represents the status of the charging process as reported in
+/// [`BatteryVoltageInfo::charging_status`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum BatteryVoltageChargingStatus {
+    Charging = 0,
+    Full = 1,
+    NotCharging = 2,
+    Error = 7,
+}
+
+/// Represents the type of the charging process as reported in
+/// [`BatteryVoltageInfo::charging_type`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum BatteryVoltageChargingType {
+    Standard,
+    Fast,
+    Slow,
+}
+
+/// Represents an event emitted by the [`BatteryVoltageFeature`] feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum BatteryVoltageEvent {
+    /// Is emitted whenever the battery voltage or charging state changes.
+    ///
+    /// This event is always enabled.
+    InfoUpdate(BatteryVoltageInfo),
+}
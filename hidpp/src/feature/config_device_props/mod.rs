@@ -0,0 +1,187 @@
+//! Implements the `ConfigDeviceProps` feature (ID `0x1806`) that provides
+//! access to properties stored on the device during manufacturing, like the
+//! extended model ID or names used for Bluetooth advertising.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// The ID of the property containing the extended model ID of the device.
+pub const PROPERTY_EXTENDED_MODEL_ID: u8 = 0x01;
+
+/// The ID of the property containing the keyboard layout of the device.
+pub const PROPERTY_KEYBOARD_LAYOUT: u8 = 0x02;
+
+/// Implements the `ConfigDeviceProps` / `0x1806` feature.
+///
+/// Only the lowest bit of the property information, telling whether the
+/// property is present, is known. Property contents are handled as raw bytes,
+/// as their formats are not known.
+pub struct ConfigDevicePropsFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for ConfigDevicePropsFeature {
+    const ID: u16 = 0x1806;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for ConfigDevicePropsFeature {
+}
+
+impl ConfigDevicePropsFeature {
+    /// Retrieves information about a property.
+    pub async fn get_property_info(&self, property: u8) -> Result<PropertyInfo, Hidpp20Error> {
+        let response = self.request(0, &[property]).await?;
+        let payload = response.extend_payload();
+
+        Ok(PropertyInfo {
+            present: payload[0] & (1 << 0) != 0,
+            size: u16::from_be_bytes([payload[1], payload[2]]),
+        })
+    }
+
+    /// Selects the property subsequent calls of [`Self::read_property`] and
+    /// [`Self::write_property`] access, starting at the given byte offsets.
+    pub async fn select_property(
+        &self,
+        property: u8,
+        read_offset: u16,
+        write_offset: u16,
+    ) -> Result<(), Hidpp20Error> {
+        let [read_hi, read_lo] = read_offset.to_be_bytes();
+        let [write_hi, write_lo] = write_offset.to_be_bytes();
+        self.request(1, &[property, read_hi, read_lo, write_hi, write_lo])
+            .await?;
+
+        Ok(())
+    }
+
+    /// Reads the next chunk of the selected property and advances the read
+    /// offset.
+    ///
+    /// This function will always retrieve 16 bytes, filling up the rest with
+    /// zeroes if the property ends earlier.\
+    /// A convenience wrapper reading a whole property is provided as
+    /// [`Self::read_whole_property`].
+    pub async fn read_property(&self) -> Result<[u8; 16], Hidpp20Error> {
+        let response = self.request(2, &[]).await?;
+
+        Ok(response.extend_payload())
+    }
+
+    /// Writes the next chunk of the selected property and advances the write
+    /// offset.
+    ///
+    /// A convenience wrapper writing a whole property is provided as
+    /// [`Self::write_whole_property`].
+    pub async fn write_property(&self, chunk: [u8; 16]) -> Result<(), Hidpp20Error> {
+        self.request(3, &chunk).await?;
+
+        Ok(())
+    }
+
+    /// Deletes a property, so it is no longer present.
+    pub async fn delete_property(&self, property: u8) -> Result<(), Hidpp20Error> {
+        self.request(4, &[property]).await?;
+
+        Ok(())
+    }
+
+    /// Reads the whole content of a property by first calling
+    /// [`Self::get_property_info`] and [`Self::select_property`] once and
+    /// then repeatedly calling [`Self::read_property`] until all bytes were
+    /// received.
+    pub async fn read_whole_property(&self, property: u8) -> Result<Vec<u8>, Hidpp20Error> {
+        let size = self.get_property_info(property).await?.size as usize;
+        self.select_property(property, 0, 0).await?;
+
+        let mut data = Vec::with_capacity(size);
+        while data.len() < size {
+            let chunk = self.read_property().await?;
+            data.extend_from_slice(&chunk[..(size - data.len()).min(chunk.len())]);
+        }
+
+        Ok(data)
+    }
+
+    /// Writes the whole content of a property by first calling
+    /// [`Self::select_property`] once and then repeatedly calling
+    /// [`Self::write_property`] until all bytes were sent.
+    ///
+    /// The last chunk is padded with zeroes.
+    pub async fn write_whole_property(
+        &self,
+        property: u8,
+        data: &[u8],
+    ) -> Result<(), Hidpp20Error> {
+        self.select_property(property, 0, 0).await?;
+
+        for chunk in data.chunks(16) {
+            let mut padded = [0; 16];
+            padded[..chunk.len()].copy_from_slice(chunk);
+            self.write_property(padded).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sends a long request to a function of the feature, padding the payload
+    /// with zeros.
+    async fn request(&self, function_id: u8, payload: &[u8]) -> Result<v20::Message, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+
+        let mut data = [0; 16];
+        data[..payload.len()].copy_from_slice(payload);
+
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Long(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(function_id),
+                    software_id: chan.get_sw_id(),
+                },
+                data,
+            ),
+        )
+        .await
+    }
+}
+
+/// Represents information about a property as reported by
+/// [`ConfigDevicePropsFeature::get_property_info`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct PropertyInfo {
+    /// Whether the property is present on the device.
+    pub present: bool,
+
+    /// The size of the property in bytes.
+    pub size: u16,
+}
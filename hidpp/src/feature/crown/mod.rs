@@ -0,0 +1,251 @@
+//! Implements the `Crown` feature (ID `0x4600`) that provides access to the
+//! dial of the Craft keyboard.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `Crown` / `0x4600` feature.
+///
+/// The crown reports three different states while it is held down. Their
+/// differences are unknown, so all of them are reported as
+/// [`CrownPressState::Held`].
+pub struct CrownFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<CrownEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for CrownFeature {
+    const ID: u16 = 0x4600;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::crown_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for CrownFeature {
+}
+
+impl EmittingFeature<CrownEvent> for CrownFeature {
+    fn listen(&self) -> async_channel::Receiver<CrownEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<CrownEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl CrownFeature {
+    /// Retrieves how the crown reports its events and whether it ratchets.
+    pub async fn get_crown_mode(&self) -> Result<CrownMode, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(CrownMode {
+            reporting: CrownReportingMode::try_from(payload[0]).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
+            ratchet: CrownRatchetMode::try_from(payload[1]).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
+        })
+    }
+
+    /// Sets how the crown reports its events and whether it ratchets.
+    ///
+    /// All values are optional and will stay as they are if provided with
+    /// [`None`]. The crown has to be diverted using
+    /// [`CrownReportingMode::Diverted`] for [`Self::listen`] to report any
+    /// rotations.
+    pub async fn set_crown_mode(
+        &self,
+        reporting: Option<CrownReportingMode>,
+        ratchet: Option<CrownRatchetMode>,
+    ) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(2),
+                    software_id: chan.get_sw_id(),
+                },
+                [
+                    reporting.map_or(0, u8::from),
+                    ratchet.map_or(0, u8::from),
+                    0x00,
+                ],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents the mode of the crown as reported by
+/// [`CrownFeature::get_crown_mode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct CrownMode {
+    /// How the crown reports its events.
+    pub reporting: CrownReportingMode,
+
+    /// Whether the crown rotates smoothly or in detents.
+    pub ratchet: CrownRatchetMode,
+}
+
+/// Represents how the crown reports its events.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum CrownReportingMode {
+    /// Rotations are reported to the native HID channel, usually as
+    /// scrolling.
+    Native = 1,
+
+    /// Rotations, touches and presses are reported via HID++.
+    Diverted = 2,
+}
+
+/// Represents whether the crown rotates smoothly or in detents.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum CrownRatchetMode {
+    Smooth = 1,
+    Ratchet = 2,
+}
+
+/// Represents an event emitted by the [`CrownFeature`] feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum CrownEvent {
+    /// Is emitted whenever the diverted crown is rotated, touched or pressed.
+    Update(CrownUpdate),
+}
+
+/// Represents the data of the [`CrownEvent::Update`] event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct CrownUpdate {
+    /// The rotation since the last update in increments of the crown's
+    /// sensor, where positive values are clockwise.
+    pub rotation: i8,
+
+    /// The amount of detents passed since the last update, where positive
+    /// values are clockwise.
+    ///
+    /// This is only non-zero in [`CrownRatchetMode::Ratchet`].
+    pub ratchets: i8,
+
+    /// Whether the crown was tapped without being rotated.
+    pub tapped: bool,
+
+    /// The state of a press of the crown.
+    pub press: CrownPressState,
+}
+
+/// Represents the state of a press of the crown as reported in
+/// [`CrownUpdate::press`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum CrownPressState {
+    /// The crown is not pressed.
+    Idle,
+
+    /// The crown started to be pressed.
+    Started,
+
+    /// The crown is held down.
+    Held,
+
+    /// The crown was released.
+    Ended,
+}
+
+impl From<u8> for CrownPressState {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Self::Started,
+            2..=4 => Self::Held,
+            5 => Self::Ended,
+            _ => Self::Idle,
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Implements the `CursorBallistic` feature (ID `0x6030`) that allows
+//! selecting the ballistics curve used to accelerate the cursor of a touch
+//! device.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `CursorBallistic` / `0x6030` feature.
+pub struct CursorBallisticFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for CursorBallisticFeature {
+    const ID: u16 = 0x6030;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for CursorBallisticFeature {
+}
+
+impl CursorBallisticFeature {
+    /// Retrieves the amount of ballistics curves the device offers and the
+    /// default one.
+    pub async fn get_ballistic_info(&self) -> Result<BallisticInfo, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(BallisticInfo {
+            curve_count: payload[0],
+            default_curve: payload[1],
+        })
+    }
+
+    /// Retrieves the index of the ballistics curve currently in use.
+    pub async fn get_ballistic(&self) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Selects the ballistics curve to use, which has to be lower than
+    /// [`BallisticInfo::curve_count`].
+    ///
+    /// Returns the index of the curve actually selected by the device.
+    pub async fn set_ballistic(&self, curve: u8) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(2),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [curve, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+}
+
+/// Represents the ballistics curves offered by a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct BallisticInfo {
+    /// The amount of ballistics curves, which are indexed starting at `0`.
+    pub curve_count: u8,
+
+    /// The index of the curve used by default.
+    pub default_curve: u8,
+}
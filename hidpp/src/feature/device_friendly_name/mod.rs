@@ -1,20 +1,20 @@
 //! Implements the `DeviceFriendlyName` feature (ID `0x0007`) that provides
 //! functionality to set and retrieve a custom device name.
 
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 use crate::{
-    channel::HidppChannel,
+    channel::{self, HidppChannel},
     feature::{CreatableFeature, Feature},
     nibble::U4,
-    protocol::v20::{self, Hidpp20Error},
+    protocol::v20::{self, CallContext, Hidpp20Error},
 };
 
 /// Implements the `DeviceFriendlyName` / `0x0007` feature.
 #[derive(Clone)]
 pub struct DeviceFriendlyNameFeature {
     /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
+    chan: Weak<HidppChannel>,
 
     /// The index of the device to implement the feature for.
     device_index: u8,
@@ -29,7 +29,7 @@ impl CreatableFeature for DeviceFriendlyNameFeature {
 
     fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
         Self {
-            chan,
+            chan: Arc::downgrade(&chan),
             device_index,
             feature_index,
         }
@@ -42,17 +42,20 @@ impl Feature for DeviceFriendlyNameFeature {
 impl DeviceFriendlyNameFeature {
     /// Retrieves the length data of the friendly device name feature.
     pub async fn get_friendly_name_length(&self) -> Result<DeviceFriendlyNameLength, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(0),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
@@ -75,17 +78,20 @@ impl DeviceFriendlyNameFeature {
     /// A convenience wrapper implementing this functionality is provided as
     /// [`Self::get_whole_friendly_name`].
     pub async fn get_friendly_name(&self, index: u8) -> Result<[u8; 15], Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(1),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [index, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [index, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         Ok(response.extend_payload()[1..].try_into().unwrap())
@@ -101,7 +107,17 @@ impl DeviceFriendlyNameFeature {
         let mut len = 0;
         while len < count as usize {
             let part = self.get_friendly_name(len as u8).await?;
-            string.push_str(str::from_utf8(&part).map_err(|_| Hidpp20Error::UnsupportedResponse)?);
+            string.push_str(str::from_utf8(&part).map_err(|_| {
+                Hidpp20Error::UnsupportedResponse {
+                    context: CallContext {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        feature_id: Some(Self::ID),
+                        function_id: U4::new(1),
+                    },
+                    payload: part.to_vec(),
+                }
+            })?);
             len = string.len();
         }
 
@@ -119,17 +135,20 @@ impl DeviceFriendlyNameFeature {
     /// A convenience wrapper implementing this functionality is provided as
     /// [`Self::get_whole_default_friendly_name`].
     pub async fn get_default_friendly_name(&self, index: u8) -> Result<[u8; 15], Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(2),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [index, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(2),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [index, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         Ok(response.extend_payload()[1..].try_into().unwrap())
@@ -145,7 +164,17 @@ impl DeviceFriendlyNameFeature {
         let mut len = 0;
         while len < count as usize {
             let part = self.get_default_friendly_name(len as u8).await?;
-            string.push_str(str::from_utf8(&part).map_err(|_| Hidpp20Error::UnsupportedResponse)?);
+            string.push_str(str::from_utf8(&part).map_err(|_| {
+                Hidpp20Error::UnsupportedResponse {
+                    context: CallContext {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        feature_id: Some(Self::ID),
+                        function_id: U4::new(2),
+                    },
+                    payload: part.to_vec(),
+                }
+            })?);
             len = string.len();
         }
 
@@ -164,21 +193,24 @@ impl DeviceFriendlyNameFeature {
     /// A convenience wrapper setting the whole friendly device name at once is
     /// provided as [`Self::set_whole_device_name`].
     pub async fn set_friendly_name(&self, index: u8, chunk: [u8; 15]) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
         let mut data = [0u8; 16];
         data[0] = index;
         data[1..].copy_from_slice(&chunk);
 
-        let response = self
-            .chan
-            .send_v20(v20::Message::Long(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(3),
-                    software_id: self.chan.get_sw_id(),
-                },
-                data,
-            ))
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Long(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(3),
+                        software_id: chan.get_sw_id(),
+                    },
+                    data,
+                ),
+            )
             .await?;
 
         Ok(response.extend_payload()[0])
@@ -219,17 +251,20 @@ impl DeviceFriendlyNameFeature {
     ///
     /// Returns the total length of the name after resetting it,
     pub async fn reset_friendly_name(&self) -> Result<u8, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(4),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(4),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         Ok(response.extend_payload()[0])
@@ -1,29 +1,50 @@
 //! Implements the `DeviceInformation` feature (ID `0x0003`) that provides some
 //! general information about the device.
 
-use std::sync::Arc;
+use std::{
+    cmp::Ordering,
+    fmt,
+    str::FromStr,
+    sync::{Arc, OnceLock, Weak},
+};
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use thiserror::Error;
 
 use crate::{
     bcd,
-    channel::HidppChannel,
-    feature::{CreatableFeature, Feature},
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature, root::RootFeature},
     nibble::U4,
-    protocol::v20::{self, Hidpp20Error},
+    protocol::v20::{self, CallContext, Hidpp20Error},
 };
 
+/// The version of the feature that added [`DeviceInformation::unit_id`],
+/// [`DeviceInformation::transport`] and [`DeviceInformation::model_id`].
+const MODEL_VERSION: u8 = 1;
+
+/// The version of the feature that added
+/// [`DeviceInformation::extended_model_id`].
+const EXTENDED_MODEL_VERSION: u8 = 2;
+
+/// The version of the feature that added [`DeviceInformation::capabilities`]
+/// and [`DeviceInformationFeature::get_serial_number`].
+const SERIAL_NUMBER_VERSION: u8 = 4;
+
 /// Implements the `DeviceInformation` / `0x0003` feature.
 #[derive(Clone)]
 pub struct DeviceInformationFeature {
     /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
+    chan: Weak<HidppChannel>,
 
     /// The index of the device to implement the feature for.
     device_index: u8,
 
     /// The index of the feature in the feature table.
     feature_index: u8,
+
+    /// The version of the feature, once retrieved via [`Self::get_version`].
+    version: OnceLock<u8>,
 }
 
 impl CreatableFeature for DeviceInformationFeature {
@@ -32,9 +53,10 @@ impl CreatableFeature for DeviceInformationFeature {
 
     fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
         Self {
-            chan,
+            chan: Arc::downgrade(&chan),
             device_index,
             feature_index,
+            version: OnceLock::new(),
         }
     }
 }
@@ -43,19 +65,46 @@ impl Feature for DeviceInformationFeature {
 }
 
 impl DeviceInformationFeature {
+    /// Provides the version of the feature supported by the device if it was
+    /// already retrieved via [`Self::get_version`].
+    pub fn version(&self) -> Option<u8> {
+        self.version.get().copied()
+    }
+
+    /// Retrieves the version of the feature supported by the device via the
+    /// root feature.
+    ///
+    /// The version is only requested once and cached afterwards.
+    pub async fn get_version(&self) -> Result<u8, Hidpp20Error> {
+        if let Some(&version) = self.version.get() {
+            return Ok(version);
+        }
+
+        let chan = channel::upgrade(&self.chan)?;
+        let version = RootFeature::new(chan, self.device_index, 0)
+            .get_feature(Self::ID)
+            .await?
+            .map_or(0, |info| info.version);
+
+        Ok(*self.version.get_or_init(|| version))
+    }
+
     /// Retrieves general information about the device and its capabilities.
     pub async fn get_device_info(&self) -> Result<DeviceInformation, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(0),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
@@ -74,6 +123,18 @@ impl DeviceInformationFeature {
         })
     }
 
+    /// Retrieves general information about the device like
+    /// [`Self::get_device_info`], together with the version of the feature,
+    /// so only fields meaningful for that version are exposed.
+    pub async fn get_versioned_device_info(
+        &self,
+    ) -> Result<VersionedDeviceInformation, Hidpp20Error> {
+        Ok(VersionedDeviceInformation {
+            version: self.get_version().await?,
+            info: self.get_device_info().await?,
+        })
+    }
+
     /// Retrieves information about the firmware of a specific entity,
     /// identified by its index bound by the value in
     /// [`DeviceInformation::entity_count`].
@@ -81,61 +142,114 @@ impl DeviceInformationFeature {
         &self,
         entity_index: u8,
     ) -> Result<DeviceEntityFirmwareInfo, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(1),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [entity_index, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [entity_index, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
 
         Ok(DeviceEntityFirmwareInfo {
-            entity_type: DeviceEntityType::try_from(payload[0])
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-            firmware_prefix: String::from_utf8(payload[1..=3].to_vec())
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-            firmware_number: bcd::convert_packed_u8(payload[4])
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-            revision: bcd::convert_packed_u8(payload[5])
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-            build: bcd::convert_packed_u16(u16::from_be_bytes(payload[6..=7].try_into().unwrap()))
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
+            entity_type: DeviceEntityType::try_from(payload[0]).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
+            firmware_prefix: String::from_utf8(payload[1..=3].to_vec()).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
+            firmware_number: bcd::decode_packed_u8(payload[4]).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
+            revision: bcd::decode_packed_u8(payload[5]).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
+            build: bcd::decode_packed_u16(u16::from_be_bytes(payload[6..=7].try_into().unwrap()))
+                .map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
             active: payload[8] & 1 != 0,
             transport_pid: u16::from_be_bytes(payload[9..=10].try_into().unwrap()),
             extra_version: payload[11..=15].try_into().unwrap(),
         })
     }
 
+    /// Retrieves the firmware information of all entities of the device, in
+    /// the order of their entity index.
+    ///
+    /// Exactly one of the entities is marked as
+    /// [`DeviceEntityFirmwareInfo::active`], which can be found using
+    /// [`active_fw_info`].
+    pub async fn get_all_fw_info(&self) -> Result<Vec<DeviceEntityFirmwareInfo>, Hidpp20Error> {
+        let entity_count = self.get_device_info().await?.entity_count;
+
+        let mut entities = Vec::with_capacity(entity_count as usize);
+        for entity_index in 0..entity_count {
+            entities.push(self.get_fw_info(entity_index).await?);
+        }
+
+        Ok(entities)
+    }
+
     /// Retrieves the serial number of the device.
     ///
-    /// This function was added in feature version 4 and will likely result in
-    /// an [`v20::ErrorType::InvalidFunctionId`] error for older versions,
-    /// so [`DeviceInformationCapabilities::serial_number`] should be
-    /// verified before calling.
+    /// This function was added in feature version 4, so
+    /// [`Hidpp20Error::UnsupportedVersion`] is returned without contacting the
+    /// device for older versions. Devices of newer versions still have to flag
+    /// the function in their capabilities, see
+    /// [`VersionedDeviceInformation::supports_serial_number`].
     pub async fn get_serial_number(&self) -> Result<String, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(2),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        self.require_version(2, SERIAL_NUMBER_VERSION).await?;
+
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(2),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
 
-        String::from_utf8(payload[..12].to_vec()).map_err(|_| Hidpp20Error::UnsupportedResponse)
+        String::from_utf8(payload[..12].to_vec())
+            .map_err(|_| Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID))
+    }
+
+    /// Fails with [`Hidpp20Error::UnsupportedVersion`] if the device supports
+    /// an older version of the feature than the one a function was added in.
+    async fn require_version(&self, function_id: u8, required: u8) -> Result<(), Hidpp20Error> {
+        let version = self.get_version().await?;
+        if version >= required {
+            return Ok(());
+        }
+
+        Err(Hidpp20Error::UnsupportedVersion {
+            context: CallContext {
+                device_index: self.device_index,
+                feature_index: self.feature_index,
+                feature_id: Some(Self::ID),
+                function_id: U4::from_lo(function_id),
+            },
+            version,
+            required,
+        })
     }
 }
 
@@ -190,6 +304,64 @@ pub struct DeviceInformation {
     pub capabilities: DeviceInformationCapabilities,
 }
 
+/// Represents the information reported by
+/// [`DeviceInformationFeature::get_device_info`] together with the version of
+/// the feature, exposing only the fields that are meaningful for it.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub struct VersionedDeviceInformation {
+    /// The version of the feature supported by the device.
+    pub version: u8,
+
+    /// The raw information, including fields that are always zero for the
+    /// feature version.
+    pub info: DeviceInformation,
+}
+
+impl VersionedDeviceInformation {
+    /// Provides [`DeviceInformation::entity_count`].
+    pub fn entity_count(&self) -> u8 {
+        self.info.entity_count
+    }
+
+    /// Provides [`DeviceInformation::unit_id`] if the feature version supports
+    /// it.
+    pub fn unit_id(&self) -> Option<[u8; 4]> {
+        (self.version >= MODEL_VERSION).then_some(self.info.unit_id)
+    }
+
+    /// Provides [`DeviceInformation::transport`] if the feature version
+    /// supports it.
+    pub fn transport(&self) -> Option<DeviceTransport> {
+        (self.version >= MODEL_VERSION).then_some(self.info.transport)
+    }
+
+    /// Provides [`DeviceInformation::model_id`] if the feature version
+    /// supports it.
+    pub fn model_id(&self) -> Option<[u16; 3]> {
+        (self.version >= MODEL_VERSION).then_some(self.info.model_id)
+    }
+
+    /// Provides [`DeviceInformation::extended_model_id`] if the feature
+    /// version supports it.
+    pub fn extended_model_id(&self) -> Option<u8> {
+        (self.version >= EXTENDED_MODEL_VERSION).then_some(self.info.extended_model_id)
+    }
+
+    /// Provides [`DeviceInformation::capabilities`] if the feature version
+    /// supports them.
+    pub fn capabilities(&self) -> Option<DeviceInformationCapabilities> {
+        (self.version >= SERIAL_NUMBER_VERSION).then_some(self.info.capabilities)
+    }
+
+    /// Checks whether [`DeviceInformationFeature::get_serial_number`] can be
+    /// called.
+    pub fn supports_serial_number(&self) -> bool {
+        self.capabilities()
+            .is_some_and(|capabilities| capabilities.serial_number)
+    }
+}
+
 /// Represents the bitfield stating which transport protocols a device supports.
 ///
 /// One given device can only support up to three transport protocols at a time.
@@ -234,12 +406,19 @@ pub struct DeviceInformationCapabilities {
     /// This field was added in feature version 4 and will always be `false` for
     /// older versions.
     pub serial_number: bool,
+
+    /// The remaining capability bits. None of them is documented publicly, so
+    /// they are passed through without interpretation.
+    ///
+    /// The bit of [`Self::serial_number`] is always cleared.
+    pub other: u8,
 }
 
 impl From<u8> for DeviceInformationCapabilities {
     fn from(value: u8) -> Self {
         Self {
             serial_number: value & 1 != 0,
+            other: value & !1,
         }
     }
 }
@@ -289,6 +468,140 @@ pub struct DeviceEntityFirmwareInfo {
     pub extra_version: [u8; 5],
 }
 
+/// Finds the active entity in the result of
+/// [`DeviceInformationFeature::get_all_fw_info`].
+pub fn active_fw_info(entities: &[DeviceEntityFirmwareInfo]) -> Option<&DeviceEntityFirmwareInfo> {
+    entities.iter().find(|entity| entity.active)
+}
+
+impl DeviceEntityFirmwareInfo {
+    /// Provides the version of the firmware.
+    pub fn version(&self) -> Version {
+        Version {
+            prefix: self.firmware_prefix.clone(),
+            number: self.firmware_number,
+            revision: self.revision,
+            build: self.build,
+        }
+    }
+
+    /// Checks whether the firmware is newer than another one.
+    ///
+    /// Returns `None` if the firmwares have different prefixes, which means
+    /// that they are different firmwares that can't be compared.
+    pub fn is_newer_than(&self, other: &DeviceEntityFirmwareInfo) -> Option<bool> {
+        self.version().is_newer_than(&other.version())
+    }
+}
+
+/// Represents a firmware version, consisting of a prefix naming the firmware
+/// and a number, revision and build.
+///
+/// Versions are formatted and parsed like `RBM 14.01.B0012`. Only versions
+/// with the same prefix are comparable, so [`PartialOrd`] returns `None` for
+/// different prefixes.
+///
+/// ```
+/// use hidpp::feature::device_information::Version;
+///
+/// let installed: Version = "RBM 14.01.B0012".parse().unwrap();
+/// let image: Version = "RBM 14.02.B0003".parse().unwrap();
+///
+/// assert!(image > installed);
+/// assert_eq!(image.to_string(), "RBM 14.02.B0003");
+/// assert_eq!(
+///     image.is_newer_than(&"BL1 14.02.B0003".parse().unwrap()),
+///     None
+/// );
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct Version {
+    /// The 3-letter prefix of the firmware name.
+    pub prefix: String,
+
+    pub number: u8,
+    pub revision: u8,
+    pub build: u16,
+}
+
+impl Version {
+    /// Constructs a version from its parts.
+    pub fn new(prefix: impl Into<String>, number: u8, revision: u8, build: u16) -> Self {
+        Self {
+            prefix: prefix.into(),
+            number,
+            revision,
+            build,
+        }
+    }
+
+    /// Checks whether this version is newer than another one.
+    ///
+    /// Returns `None` if the versions have different prefixes.
+    pub fn is_newer_than(&self, other: &Version) -> Option<bool> {
+        self.partial_cmp(other)
+            .map(|ordering| ordering == Ordering::Greater)
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        if self.prefix != other.prefix {
+            return None;
+        }
+
+        Some((self.number, self.revision, self.build).cmp(&(
+            other.number,
+            other.revision,
+            other.build,
+        )))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:02}.{:02}.B{:04}",
+            self.prefix, self.number, self.revision, self.build
+        )
+    }
+}
+
+impl FromStr for Version {
+    type Err = VersionParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || VersionParseError(s.to_string());
+
+        let (prefix, rest) = s.trim().split_once(' ').ok_or_else(err)?;
+        let mut parts = rest.split('.');
+        let (Some(number), Some(revision), Some(build), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(err());
+        };
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            number: number.parse().map_err(|_| err())?,
+            revision: revision.parse().map_err(|_| err())?,
+            build: build
+                .strip_prefix('B')
+                .unwrap_or(build)
+                .parse()
+                .map_err(|_| err())?,
+        })
+    }
+}
+
+/// Represents the error returned when parsing an invalid [`Version`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Error)]
+#[error("\"{0}\" is not a valid firmware version")]
+pub struct VersionParseError(pub String);
+
 /// Represents the type of a device entity.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, IntoPrimitive, TryFromPrimitive)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
@@ -1,28 +1,31 @@
 //! Implements the `DeviceTypeAndName` feature (ID `0x0005`) that provides some
 //! information about the marketing type and name of a device.
 
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock, Weak};
 
-use num_enum::{IntoPrimitive, TryFromPrimitive};
+use num_enum::{FromPrimitive, IntoPrimitive};
 
 use crate::{
-    channel::HidppChannel,
+    channel::{self, HidppChannel},
     feature::{CreatableFeature, Feature},
     nibble::U4,
-    protocol::v20::{self, Hidpp20Error},
+    protocol::v20::{self, CallContext, Hidpp20Error},
 };
 
 /// Implements the `DeviceTypeAndName` / `0x0005` feature.
 #[derive(Clone)]
 pub struct DeviceTypeAndNameFeature {
     /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
+    chan: Weak<HidppChannel>,
 
     /// The index of the device to implement the feature for.
     device_index: u8,
 
     /// The index of the feature in the feature table.
     feature_index: u8,
+
+    /// The marketing name of the device, once retrieved via [`Self::name`].
+    name: OnceLock<String>,
 }
 
 impl CreatableFeature for DeviceTypeAndNameFeature {
@@ -31,9 +34,10 @@ impl CreatableFeature for DeviceTypeAndNameFeature {
 
     fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
         Self {
-            chan,
+            chan: Arc::downgrade(&chan),
             device_index,
             feature_index,
+            name: OnceLock::new(),
         }
     }
 }
@@ -44,17 +48,20 @@ impl Feature for DeviceTypeAndNameFeature {
 impl DeviceTypeAndNameFeature {
     /// Retrieves the amount of characters in the marketing name of the device.
     pub async fn get_device_name_count(&self) -> Result<u8, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(0),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         Ok(response.extend_payload()[0])
@@ -64,25 +71,31 @@ impl DeviceTypeAndNameFeature {
     /// starting at a specific index (inclusive).
     ///
     /// Depending on the device and channel capabilities, this function will
-    /// return at most 3 or 16 characters of the device name.
+    /// return at most 3 or 16 characters of the device name. Long messages are
+    /// used if the channel supports them, so names arrive in chunks of 16
+    /// characters where possible.
     ///
     /// Use this function in conjunction with [`Self::get_device_name_count`] to
     /// retrieve the whole device name.\
     /// A convenience wrapper implementing this functionality is provided as
     /// [`Self::get_whole_device_name`].
     pub async fn get_device_name(&self, index: u8) -> Result<Vec<u8>, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(1),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [index, 0x00, 0x00],
-            ))
-            .await?;
+        let chan = channel::upgrade(&self.chan)?;
+        let header = v20::MessageHeader {
+            device_index: self.device_index,
+            feature_index: self.feature_index,
+            function_id: U4::from_lo(1),
+            software_id: chan.get_sw_id(),
+        };
+
+        let request = if chan.supports_long {
+            let mut payload = [0; 16];
+            payload[0] = index;
+            v20::Message::Long(header, payload)
+        } else {
+            v20::Message::Short(header, [index, 0x00, 0x00])
+        };
+        let response = chan.send_v20_feature(Self::ID, request).await?;
 
         match response {
             v20::Message::Long(_, payload) => Ok(payload.to_vec()),
@@ -99,38 +112,68 @@ impl DeviceTypeAndNameFeature {
 
         let mut len = 0;
         while len < count as usize {
-            let part = self.get_device_name(len as u8).await?;
-            string.push_str(str::from_utf8(&part).map_err(|_| Hidpp20Error::UnsupportedResponse)?);
+            let mut part = self.get_device_name(len as u8).await?;
+            part.truncate(count as usize - len);
+            string.push_str(str::from_utf8(&part).map_err(|_| {
+                Hidpp20Error::UnsupportedResponse {
+                    context: CallContext {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        feature_id: Some(Self::ID),
+                        function_id: U4::new(1),
+                    },
+                    payload: part.to_vec(),
+                }
+            })?);
             len = string.len();
         }
 
         Ok(string.trim_end_matches(char::from(0)).to_string())
     }
 
+    /// Provides the marketing name of the device.
+    ///
+    /// The name is retrieved via [`Self::get_whole_device_name`] once and
+    /// cached afterwards, as it does not change.
+    pub async fn name(&self) -> Result<String, Hidpp20Error> {
+        if let Some(name) = self.name.get() {
+            return Ok(name.clone());
+        }
+
+        let name = self.get_whole_device_name().await?;
+
+        Ok(self.name.get_or_init(|| name).clone())
+    }
+
     /// Retrieves the marketing type of the device.
+    ///
+    /// Types not known to this implementation are reported as
+    /// [`DeviceType::Other`].
     pub async fn get_device_type(&self) -> Result<DeviceType, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(2),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(2),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
-        DeviceType::try_from(response.extend_payload()[0])
-            .map_err(|_| Hidpp20Error::UnsupportedResponse)
+        Ok(DeviceType::from(response.extend_payload()[0]))
     }
 }
 
 /// Represents the type of a HID++2.0 device as returned by the
 /// [`DeviceTypeAndNameFeature`] feature.
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, IntoPrimitive, TryFromPrimitive)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, IntoPrimitive, FromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[repr(u8)]
 pub enum DeviceType {
@@ -154,4 +197,8 @@ pub enum DeviceType {
     ProgrammableController = 17,
     CarSimPedals = 18,
     Adapter = 19,
+
+    /// A type newer devices report that is not known to this implementation.
+    #[num_enum(catch_all)]
+    Other(u8),
 }
@@ -0,0 +1,372 @@
+//! Implements the `Dfu` feature (ID `0x00d0`) that allows updating the
+//! firmware of a device.
+//!
+//! The feature is only available while the device runs its bootloader, which
+//! can be entered via the [`dfu_control`](super::dfu_control) features.
+//!
+//! Firmware files consist of 16-byte blocks, which are sent to the device one
+//! after another. The first block starts the update and contains the entity
+//! the firmware is meant for, while all following blocks contain the firmware
+//! itself. [`DfuFeature::write_firmware`] implements the whole update.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use futures::{FutureExt, select};
+use futures_timer::Delay;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use thiserror::Error;
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// The size of a single block of a firmware file.
+pub const DFU_BLOCK_SIZE: usize = 16;
+
+/// The time to wait for the status of a block the device is still processing.
+const STATUS_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The number of status notifications to wait for while the device is still
+/// processing a block.
+const MAX_STATUS_NOTIFICATIONS: usize = 10;
+
+/// Implements the `Dfu` / `0x00d0` feature.
+///
+/// The meaning of the most significant bit of the status byte is unknown, so it
+/// is ignored. Apart from the entity in the first block, the contents of
+/// firmware files are not known, so they are sent to the device without being
+/// verified.
+pub struct DfuFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<DfuEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for DfuFeature {
+    const ID: u16 = 0x00d0;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::dfu_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for DfuFeature {
+}
+
+impl EmittingFeature<DfuEvent> for DfuFeature {
+    fn listen(&self) -> async_channel::Receiver<DfuEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<DfuEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl DfuFeature {
+    /// Starts an update by sending the first block of a firmware file.
+    pub async fn start(
+        &self,
+        block: [u8; DFU_BLOCK_SIZE],
+    ) -> Result<DfuPacketStatus, Hidpp20Error> {
+        self.send_block(4, block).await
+    }
+
+    /// Sends a block of a firmware file after the update was started.
+    ///
+    /// Blocks are sent using a sliding window of four functions, so `window`
+    /// has to be incremented modulo 4 for every block, starting at 1 for the
+    /// block following the one passed to [`Self::start`].
+    pub async fn send_data(
+        &self,
+        window: u8,
+        block: [u8; DFU_BLOCK_SIZE],
+    ) -> Result<DfuPacketStatus, Hidpp20Error> {
+        self.send_block(window % 4, block).await
+    }
+
+    /// Writes a whole firmware file to the device.
+    ///
+    /// `progress` is called after every block that was accepted by the device.
+    ///
+    /// Returns the status of the last block, which tells whether the device
+    /// has to be restarted to run the new firmware.
+    pub async fn write_firmware(
+        &self,
+        firmware: &DfuFirmware,
+        mut progress: impl FnMut(DfuProgress),
+    ) -> Result<DfuStatus, DfuError> {
+        let events = self.listen();
+        let total = firmware.blocks().len();
+
+        let mut function_id = 4;
+        let mut status = DfuStatus::DfuSuccess;
+        for (i, block) in firmware.blocks().iter().enumerate() {
+            // Notifications of earlier blocks must not be mistaken for the
+            // status of this one.
+            while events.try_recv().is_ok() {}
+
+            let mut response = self.send_block(function_id, *block).await?;
+            let mut notifications = 0;
+            while response.status == DfuStatus::WaitForEvent
+                && notifications < MAX_STATUS_NOTIFICATIONS
+            {
+                let DfuEvent::Status(notified) = select! {
+                    event = events.recv().fuse() => event.map_err(|_| DfuError::Timeout)?,
+                    _ = Delay::new(STATUS_TIMEOUT).fuse() => return Err(DfuError::Timeout),
+                };
+
+                response = notified;
+                notifications += 1;
+            }
+
+            if !response.status.is_success() {
+                return Err(DfuError::Status {
+                    status: response.status,
+                    offset: i * DFU_BLOCK_SIZE,
+                });
+            }
+
+            status = response.status;
+            function_id = (function_id + 1) % 4;
+            progress(DfuProgress {
+                blocks_written: i + 1,
+                blocks_total: total,
+            });
+        }
+
+        Ok(status)
+    }
+
+    /// Sends a block to a function of the feature and parses the status of
+    /// the response.
+    async fn send_block(
+        &self,
+        function_id: u8,
+        block: [u8; DFU_BLOCK_SIZE],
+    ) -> Result<DfuPacketStatus, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Long(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(function_id),
+                        software_id: chan.get_sw_id(),
+                    },
+                    block,
+                ),
+            )
+            .await?;
+
+        DfuPacketStatus::parse(&response.extend_payload())
+            .ok_or_else(|| Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID))
+    }
+}
+
+/// Represents a firmware file split into the blocks sent to the device.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DfuFirmware {
+    blocks: Vec<[u8; DFU_BLOCK_SIZE]>,
+}
+
+impl DfuFirmware {
+    /// Splits the content of a firmware file into blocks.
+    ///
+    /// Fails if the file is empty or its size is not a multiple of
+    /// [`DFU_BLOCK_SIZE`].
+    pub fn parse(data: &[u8]) -> Result<Self, DfuError> {
+        if data.is_empty() || !data.len().is_multiple_of(DFU_BLOCK_SIZE) {
+            return Err(DfuError::MalformedFirmware(data.len()));
+        }
+
+        Ok(Self {
+            blocks: data
+                .chunks_exact(DFU_BLOCK_SIZE)
+                .map(|block| block.try_into().unwrap())
+                .collect(),
+        })
+    }
+
+    /// Returns the entity of the device the firmware is meant for, like the
+    /// main application or the radio.
+    pub fn entity(&self) -> u8 {
+        self.blocks[0][0]
+    }
+
+    /// Returns the blocks of the firmware, starting with the one passed to
+    /// [`DfuFeature::start`].
+    pub fn blocks(&self) -> &[[u8; DFU_BLOCK_SIZE]] {
+        &self.blocks
+    }
+}
+
+/// Represents the status of a block sent to the device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct DfuPacketStatus {
+    /// The number of blocks the device received so far.
+    pub packet_count: u32,
+
+    /// The status of the block.
+    pub status: DfuStatus,
+}
+
+impl DfuPacketStatus {
+    /// Parses the status as contained in responses and notifications.
+    pub(crate) fn parse(payload: &[u8]) -> Option<Self> {
+        Some(Self {
+            packet_count: u32::from_be_bytes(payload[..4].try_into().unwrap()),
+            status: DfuStatus::try_from(payload[4] & 0x7f).ok()?,
+        })
+    }
+}
+
+/// Represents a status reported by the device for a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum DfuStatus {
+    PacketSuccess = 0x01,
+    DfuSuccess = 0x02,
+
+    /// The device is still processing the block and reports its status via
+    /// [`DfuEvent::Status`].
+    WaitForEvent = 0x03,
+
+    GenericError = 0x04,
+    EntityRestartRequired = 0x05,
+    SystemRestartRequired = 0x06,
+    UnknownError = 0x10,
+    BadVoltage = 0x11,
+    UnsupportedFirmware = 0x12,
+    UnsupportedEncryptionMode = 0x13,
+    BadMagicString = 0x14,
+    EraseFailure = 0x15,
+    DfuNotStarted = 0x16,
+    BadSequenceNumber = 0x17,
+    UnsupportedCommand = 0x18,
+    CommandInProgress = 0x19,
+    AddressOutOfRange = 0x1a,
+    UnalignedAddress = 0x1b,
+    BadSize = 0x1c,
+    MissingProgramData = 0x1d,
+    MissingCheckData = 0x1e,
+    ProgramFailure = 0x1f,
+    VerifyFailure = 0x20,
+    BadFirmware = 0x21,
+    FirmwareCheckFailure = 0x22,
+    BlockedCommand = 0x23,
+}
+
+impl DfuStatus {
+    /// Returns whether the block was accepted by the device.
+    pub fn is_success(&self) -> bool {
+        matches!(
+            self,
+            Self::PacketSuccess
+                | Self::DfuSuccess
+                | Self::EntityRestartRequired
+                | Self::SystemRestartRequired
+        )
+    }
+}
+
+/// Represents the progress of [`DfuFeature::write_firmware`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct DfuProgress {
+    /// The number of blocks accepted by the device so far.
+    pub blocks_written: usize,
+
+    /// The number of blocks of the firmware.
+    pub blocks_total: usize,
+}
+
+/// Represents an event emitted by the [`DfuFeature`] feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum DfuEvent {
+    /// Is emitted once the device finished processing a block it reported
+    /// [`DfuStatus::WaitForEvent`] for.
+    Status(DfuPacketStatus),
+}
+
+/// Represents an error that may occur when updating the firmware of a device.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum DfuError {
+    /// Indicates that the size of a firmware file is not a positive multiple
+    /// of [`DFU_BLOCK_SIZE`].
+    #[error("the firmware has {0} bytes, which is not a positive multiple of 16")]
+    MalformedFirmware(usize),
+
+    /// Indicates that the device rejected a block of the firmware.
+    #[error("the device rejected the block at offset {offset:#06x} with status {status:?}")]
+    Status {
+        status: DfuStatus,
+        offset: usize,
+    },
+
+    /// Indicates that the device did not report the status of a block in
+    /// time.
+    #[error("the device did not report the status of a block in time")]
+    Timeout,
+
+    /// Indicates that the device returned an error.
+    #[error("the device returned an error")]
+    Hidpp(#[from] Hidpp20Error),
+}
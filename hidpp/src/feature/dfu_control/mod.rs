@@ -0,0 +1,238 @@
+//! Implements the `DfuControl` family of features (IDs `0x00c0` to `0x00c3`)
+//! that allow restarting a device into its bootloader, where its firmware can
+//! be updated via [`DfuFeature`](super::dfu::DfuFeature).
+//!
+//! All members of the family share the [`DfuControlStatus`] type and the same
+//! functions. They differ in which devices implement them:
+//!
+//! - [`DfuControlLegacyFeature`] (`0x00c0`) is implemented by old devices.
+//! - [`DfuControlUnsignedFeature`] (`0x00c1`) is implemented by devices
+//!   accepting unsigned firmware.
+//! - [`DfuControlSignedFeature`] (`0x00c2`) is implemented by devices only
+//!   accepting signed firmware.
+//! - [`DfuControlBoltFeature`] (`0x00c3`) is implemented by Bolt receivers and
+//!   the devices paired with them.
+//!
+//! The values of the parameter passed to the bootloader are not known, so it is
+//! passed through unchanged.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// The magic bytes that have to accompany the request to enter DFU mode, so it
+/// is not sent by accident.
+const DFU_MAGIC: &[u8; 3] = b"DFU";
+
+/// Represents the DFU status of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct DfuControlStatus {
+    /// Whether the device enters DFU mode on its next restart.
+    pub enter_dfu: bool,
+
+    /// The parameter passed to the bootloader when entering DFU mode.
+    pub param: u8,
+
+    /// Whether the user has to perform an action, like pressing a key
+    /// combination, before the device can enter DFU mode.
+    pub user_action_required: bool,
+}
+
+/// Contains the implementation shared by all features of the family.
+struct DfuControl {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The ID of the member of the family, used in error contexts.
+    feature_id: u16,
+}
+
+impl DfuControl {
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8, feature_id: u16) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            feature_id,
+        }
+    }
+
+    async fn get_dfu_status(&self) -> Result<DfuControlStatus, Hidpp20Error> {
+        let response = self.request(0, &[]).await?;
+        let payload = response.extend_payload();
+
+        Ok(DfuControlStatus {
+            enter_dfu: payload[0] & 1 != 0,
+            param: payload[1],
+            user_action_required: payload[2] & 1 != 0,
+        })
+    }
+
+    async fn enter_dfu(&self, param: u8) -> Result<(), Hidpp20Error> {
+        let mut payload = [0x01, param, 0x00, 0x00, 0x00, 0x00, 0x00];
+        payload[4..].copy_from_slice(DFU_MAGIC);
+
+        self.request(1, &payload).await?;
+
+        Ok(())
+    }
+
+    /// Sends a long request to a function of the feature, padding the payload
+    /// with zeros.
+    async fn request(&self, function_id: u8, payload: &[u8]) -> Result<v20::Message, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+
+        let mut data = [0; 16];
+        data[..payload.len()].copy_from_slice(payload);
+
+        chan.send_v20_feature(
+            self.feature_id,
+            v20::Message::Long(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(function_id),
+                    software_id: chan.get_sw_id(),
+                },
+                data,
+            ),
+        )
+        .await
+    }
+}
+
+/// Implements the `DfuControlLegacy` / `0x00c0` feature.
+pub struct DfuControlLegacyFeature(DfuControl);
+
+impl CreatableFeature for DfuControlLegacyFeature {
+    const ID: u16 = 0x00c0;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(DfuControl::new(chan, device_index, feature_index, Self::ID))
+    }
+}
+
+impl Feature for DfuControlLegacyFeature {
+}
+
+impl DfuControlLegacyFeature {
+    /// Retrieves the DFU status of the device.
+    pub async fn get_dfu_status(&self) -> Result<DfuControlStatus, Hidpp20Error> {
+        self.0.get_dfu_status().await
+    }
+
+    /// Makes the device restart into its bootloader, passing it `param`.
+    ///
+    /// The device disconnects afterwards, so the request may fail even though
+    /// the device entered DFU mode.
+    pub async fn enter_dfu(&self, param: u8) -> Result<(), Hidpp20Error> {
+        self.0.enter_dfu(param).await
+    }
+}
+
+/// Implements the `DfuControlUnsigned` / `0x00c1` feature.
+pub struct DfuControlUnsignedFeature(DfuControl);
+
+impl CreatableFeature for DfuControlUnsignedFeature {
+    const ID: u16 = 0x00c1;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(DfuControl::new(chan, device_index, feature_index, Self::ID))
+    }
+}
+
+impl Feature for DfuControlUnsignedFeature {
+}
+
+impl DfuControlUnsignedFeature {
+    /// Retrieves the DFU status of the device.
+    pub async fn get_dfu_status(&self) -> Result<DfuControlStatus, Hidpp20Error> {
+        self.0.get_dfu_status().await
+    }
+
+    /// Makes the device restart into its bootloader, passing it `param`.
+    ///
+    /// The device disconnects afterwards, so the request may fail even though
+    /// the device entered DFU mode.
+    pub async fn enter_dfu(&self, param: u8) -> Result<(), Hidpp20Error> {
+        self.0.enter_dfu(param).await
+    }
+}
+
+/// Implements the `DfuControlSigned` / `0x00c2` feature.
+pub struct DfuControlSignedFeature(DfuControl);
+
+impl CreatableFeature for DfuControlSignedFeature {
+    const ID: u16 = 0x00c2;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(DfuControl::new(chan, device_index, feature_index, Self::ID))
+    }
+}
+
+impl Feature for DfuControlSignedFeature {
+}
+
+impl DfuControlSignedFeature {
+    /// Retrieves the DFU status of the device.
+    pub async fn get_dfu_status(&self) -> Result<DfuControlStatus, Hidpp20Error> {
+        self.0.get_dfu_status().await
+    }
+
+    /// Makes the device restart into its bootloader, passing it `param`.
+    ///
+    /// The device disconnects afterwards, so the request may fail even though
+    /// the device entered DFU mode.
+    pub async fn enter_dfu(&self, param: u8) -> Result<(), Hidpp20Error> {
+        self.0.enter_dfu(param).await
+    }
+}
+
+/// Implements the `DfuControlBolt` / `0x00c3` feature.
+pub struct DfuControlBoltFeature(DfuControl);
+
+impl CreatableFeature for DfuControlBoltFeature {
+    const ID: u16 = 0x00c3;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(DfuControl::new(chan, device_index, feature_index, Self::ID))
+    }
+}
+
+impl Feature for DfuControlBoltFeature {
+}
+
+impl DfuControlBoltFeature {
+    /// Retrieves the DFU status of the device.
+    pub async fn get_dfu_status(&self) -> Result<DfuControlStatus, Hidpp20Error> {
+        self.0.get_dfu_status().await
+    }
+
+    /// Makes the device restart into its bootloader, passing it `param`.
+    ///
+    /// The device disconnects afterwards, so the request may fail even though
+    /// the device entered DFU mode.
+    pub async fn enter_dfu(&self, param: u8) -> Result<(), Hidpp20Error> {
+        self.0.enter_dfu(param).await
+    }
+}
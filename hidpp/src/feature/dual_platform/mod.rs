@@ -0,0 +1,154 @@
+//! Implements the `DualPlatform` feature (ID `0x4530`) that allows switching
+//! the key layout of older keyboards between two platforms.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `DualPlatform` / `0x4530` feature.
+pub struct DualPlatformFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<DualPlatformEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for DualPlatformFeature {
+    const ID: u16 = 0x4530;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::dual_platform_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for DualPlatformFeature {
+}
+
+impl EmittingFeature<DualPlatformEvent> for DualPlatformFeature {
+    fn listen(&self) -> async_channel::Receiver<DualPlatformEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<DualPlatformEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl DualPlatformFeature {
+    /// Retrieves the platform the keyboard is currently set to.
+    pub async fn get_platform(&self) -> Result<DualPlatform, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        DualPlatform::try_from(response.extend_payload()[0])
+            .map_err(|_| Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID))
+    }
+
+    /// Sets the platform of the keyboard.
+    pub async fn set_platform(&self, platform: DualPlatform) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(2),
+                    software_id: chan.get_sw_id(),
+                },
+                [platform.into(), 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents a platform a keyboard implementing [`DualPlatformFeature`] can
+/// be set to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum DualPlatform {
+    /// iOS and macOS.
+    Apple = 0,
+
+    /// Android and Windows.
+    AndroidWindows = 1,
+}
+
+/// Represents an event emitted by the [`DualPlatformFeature`] feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum DualPlatformEvent {
+    /// Is emitted whenever the platform is changed on the keyboard, like by
+    /// using a key combination.
+    ///
+    /// This event is always enabled.
+    PlatformChange(DualPlatform),
+}
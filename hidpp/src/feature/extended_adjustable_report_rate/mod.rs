@@ -0,0 +1,264 @@
+//! Implements the `ExtendedAdjustableReportRate` feature (ID `0x8061`) that
+//! allows changing the report rate of newer gaming devices, up to 8 kHz.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `ExtendedAdjustableReportRate` / `0x8061` feature.
+///
+/// Only the wired and gaming wireless connection types are known. Report rates
+/// listed by the device that are not known are ignored.
+pub struct ExtendedAdjustableReportRateFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<ReportRateEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for ExtendedAdjustableReportRateFeature {
+    const ID: u16 = 0x8061;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) =
+                        parse::extended_adjustable_report_rate_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for ExtendedAdjustableReportRateFeature {
+}
+
+impl EmittingFeature<ReportRateEvent> for ExtendedAdjustableReportRateFeature {
+    fn listen(&self) -> async_channel::Receiver<ReportRateEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<ReportRateEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl ExtendedAdjustableReportRateFeature {
+    /// Retrieves the connection types the device supports.
+    pub async fn get_device_capabilities(&self) -> Result<ReportRateCapabilities, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let flags = response.extend_payload()[0];
+
+        Ok(ReportRateCapabilities {
+            wired: flags & (1 << 0) != 0,
+            gaming_wireless: flags & (1 << 1) != 0,
+        })
+    }
+
+    /// Retrieves the report rates the device supports while using the given
+    /// connection type, from the lowest to the highest rate.
+    pub async fn get_report_rate_list(
+        &self,
+        connection: ReportRateConnection,
+    ) -> Result<Vec<ReportRate>, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [connection.into(), 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+        let flags = u16::from_be_bytes([payload[0], payload[1]]);
+
+        Ok((0..16)
+            .filter(|bit| flags & (1 << bit) != 0)
+            .filter_map(|bit| ReportRate::try_from(bit as u8).ok())
+            .collect())
+    }
+
+    /// Retrieves the current report rate.
+    pub async fn get_report_rate(&self) -> Result<ReportRate, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(2),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        ReportRate::try_from(response.extend_payload()[0])
+            .map_err(|_| Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID))
+    }
+
+    /// Sets the report rate.
+    ///
+    /// The rate has to be supported for the current connection type, see
+    /// [`Self::get_report_rate_list`].
+    pub async fn set_report_rate(&self, rate: ReportRate) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(3),
+                    software_id: chan.get_sw_id(),
+                },
+                [rate.into(), 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents the connection types a device implementing
+/// [`ExtendedAdjustableReportRateFeature`] supports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ReportRateCapabilities {
+    /// Whether the device can be connected via USB.
+    pub wired: bool,
+
+    /// Whether the device can be connected via a gaming wireless receiver,
+    /// like Lightspeed.
+    pub gaming_wireless: bool,
+}
+
+/// Represents a connection type the report rates of a device can differ for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum ReportRateConnection {
+    Wired = 0,
+    GamingWireless = 1,
+}
+
+/// Represents a report rate of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum ReportRate {
+    /// A report every 8 ms.
+    Hz125 = 0,
+
+    /// A report every 4 ms.
+    Hz250 = 1,
+
+    /// A report every 2 ms.
+    Hz500 = 2,
+
+    /// A report every millisecond.
+    Hz1000 = 3,
+
+    /// A report every 500 µs.
+    Hz2000 = 4,
+
+    /// A report every 250 µs.
+    Hz4000 = 5,
+
+    /// A report every 125 µs.
+    Hz8000 = 6,
+}
+
+impl ReportRate {
+    /// Returns the number of reports per second.
+    pub fn frequency(&self) -> u16 {
+        125 << u8::from(*self)
+    }
+}
+
+/// Represents an event emitted by the [`ExtendedAdjustableReportRateFeature`]
+/// feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum ReportRateEvent {
+    /// Is emitted whenever the report rate changes, like after switching the
+    /// connection type or the onboard profile.
+    ///
+    /// This event is always enabled.
+    RateChange(ReportRate),
+}
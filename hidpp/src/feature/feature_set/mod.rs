@@ -1,10 +1,10 @@
 //! Implements the `FeatureSet` feature (ID `0x0001`) that allows enumerating
 //! all the features supported by a device.
 
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 use crate::{
-    channel::HidppChannel,
+    channel::{self, HidppChannel},
     feature::{CreatableFeature, Feature, FeatureType},
     nibble::U4,
     protocol::v20::{self, Hidpp20Error},
@@ -17,10 +17,15 @@ use crate::{
 /// supported features (excluding the root feature). Then call
 /// [`Self::get_feature`] for every `i in 1..=count` (1-based, as accessing the
 /// root feature is not allowed).
+///
+/// This implementation supports all versions of the feature. Devices
+/// supporting newer versions also provide [`FeatureSetFeatureV1`] and
+/// [`FeatureSetFeatureV2`], which guarantee that the fields added in these
+/// versions are meaningful.
 #[derive(Clone)]
 pub struct FeatureSetFeature {
     /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
+    chan: Weak<HidppChannel>,
 
     /// The index of the device to implement the feature for.
     device_index: u8,
@@ -35,7 +40,7 @@ impl CreatableFeature for FeatureSetFeature {
 
     fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
         Self {
-            chan,
+            chan: Arc::downgrade(&chan),
             device_index,
             feature_index,
         }
@@ -49,17 +54,20 @@ impl FeatureSetFeature {
     /// Retrieves the amount of features supported by the device, not including
     /// the root feature.
     pub async fn count(&self) -> Result<u8, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(0),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         Ok(response.extend_payload()[0])
@@ -70,17 +78,20 @@ impl FeatureSetFeature {
     ///
     /// Feature index `0` for the root feature is not allowed.
     pub async fn get_feature(&self, index: u8) -> Result<FeatureInformation, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(1),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [index, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [index, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
@@ -116,3 +127,78 @@ pub struct FeatureInformation {
     /// versions.
     pub version: u8,
 }
+
+/// Implements version 1 of the `FeatureSet` / `0x0001` feature, which added
+/// [`FeatureInformation::version`].
+#[derive(Clone)]
+pub struct FeatureSetFeatureV1(FeatureSetFeature);
+
+impl CreatableFeature for FeatureSetFeatureV1 {
+    const ID: u16 = FeatureSetFeature::ID;
+    const STARTING_VERSION: u8 = 1;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(FeatureSetFeature::new(chan, device_index, feature_index))
+    }
+}
+
+impl Feature for FeatureSetFeatureV1 {
+}
+
+impl FeatureSetFeatureV1 {
+    /// Retrieves the amount of features supported by the device, not including
+    /// the root feature.
+    pub async fn count(&self) -> Result<u8, Hidpp20Error> {
+        self.0.count().await
+    }
+
+    /// Retrieves the information about a specific feature based on its index in
+    /// the feature table.
+    ///
+    /// [`FeatureInformation::version`] is always reported by this version of
+    /// the feature, so a value of `0` denotes the first version of the
+    /// feature.
+    ///
+    /// Feature index `0` for the root feature is not allowed.
+    pub async fn get_feature(&self, index: u8) -> Result<FeatureInformation, Hidpp20Error> {
+        self.0.get_feature(index).await
+    }
+}
+
+/// Implements version 2 of the `FeatureSet` / `0x0001` feature, which added
+/// the [`FeatureType::manufacturing_deactivatable`] and
+/// [`FeatureType::compliance_deactivatable`] type bits.
+#[derive(Clone)]
+pub struct FeatureSetFeatureV2(FeatureSetFeature);
+
+impl CreatableFeature for FeatureSetFeatureV2 {
+    const ID: u16 = FeatureSetFeature::ID;
+    const STARTING_VERSION: u8 = 2;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(FeatureSetFeature::new(chan, device_index, feature_index))
+    }
+}
+
+impl Feature for FeatureSetFeatureV2 {
+}
+
+impl FeatureSetFeatureV2 {
+    /// Retrieves the amount of features supported by the device, not including
+    /// the root feature.
+    pub async fn count(&self) -> Result<u8, Hidpp20Error> {
+        self.0.count().await
+    }
+
+    /// Retrieves the information about a specific feature based on its index in
+    /// the feature table.
+    ///
+    /// Both [`FeatureInformation::version`] and all bits of
+    /// [`FeatureInformation::typ`] are reported by this version of the
+    /// feature.
+    ///
+    /// Feature index `0` for the root feature is not allowed.
+    pub async fn get_feature(&self, index: u8) -> Result<FeatureInformation, Hidpp20Error> {
+        self.0.get_feature(index).await
+    }
+}
@@ -0,0 +1,192 @@
+//! Implements the `FirmwareProperties` feature (ID `0x1f1f`) that provides
+//! extended properties of the firmware entities of a device, which update
+//! tooling can use to verify that a firmware image is compatible before
+//! flashing it.
+//!
+//! Entities are identified by the same index as used by the
+//! [`device_information`](super::device_information) feature.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `FirmwareProperties` / `0x1f1f` feature.
+///
+/// The identifiers of properties are not known, so their contents are returned
+/// as raw bytes.
+pub struct FirmwarePropertiesFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for FirmwarePropertiesFeature {
+    const ID: u16 = 0x1f1f;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for FirmwarePropertiesFeature {
+}
+
+impl FirmwarePropertiesFeature {
+    /// Retrieves the number of properties an entity provides.
+    ///
+    /// Properties are identified by their index bound by this value.
+    pub async fn get_property_count(&self, entity_index: u8) -> Result<u8, Hidpp20Error> {
+        let response = self.request(0, &[entity_index]).await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Retrieves information about a property of an entity.
+    pub async fn get_property_info(
+        &self,
+        entity_index: u8,
+        property_index: u8,
+    ) -> Result<FirmwarePropertyInfo, Hidpp20Error> {
+        let response = self.request(1, &[entity_index, property_index]).await?;
+        let payload = response.extend_payload();
+
+        Ok(FirmwarePropertyInfo {
+            id: u16::from_be_bytes([payload[0], payload[1]]),
+            size: u16::from_be_bytes([payload[2], payload[3]]),
+        })
+    }
+
+    /// Reads a chunk of a property of an entity, starting at the given byte
+    /// offset.
+    ///
+    /// This function will always retrieve 16 bytes, filling up the rest with
+    /// zeroes if the property ends earlier.\
+    /// A convenience wrapper reading a whole property is provided as
+    /// [`Self::read_whole_property`].
+    pub async fn read_property(
+        &self,
+        entity_index: u8,
+        property_index: u8,
+        offset: u16,
+    ) -> Result<[u8; 16], Hidpp20Error> {
+        let [offset_hi, offset_lo] = offset.to_be_bytes();
+        let response = self
+            .request(2, &[entity_index, property_index, offset_hi, offset_lo])
+            .await?;
+
+        Ok(response.extend_payload())
+    }
+
+    /// Reads the whole content of a property of an entity by first calling
+    /// [`Self::get_property_info`] once and then repeatedly calling
+    /// [`Self::read_property`] until all bytes were received.
+    pub async fn read_whole_property(
+        &self,
+        entity_index: u8,
+        property_index: u8,
+    ) -> Result<FirmwareProperty, Hidpp20Error> {
+        let info = self.get_property_info(entity_index, property_index).await?;
+        let size = info.size as usize;
+
+        let mut data = Vec::with_capacity(size);
+        while data.len() < size {
+            let chunk = self
+                .read_property(entity_index, property_index, data.len() as u16)
+                .await?;
+            data.extend_from_slice(&chunk[..(size - data.len()).min(chunk.len())]);
+        }
+
+        Ok(FirmwareProperty {
+            id: info.id,
+            data,
+        })
+    }
+
+    /// Reads all properties of an entity, in the order of their property
+    /// index.
+    pub async fn read_all_properties(
+        &self,
+        entity_index: u8,
+    ) -> Result<Vec<FirmwareProperty>, Hidpp20Error> {
+        let count = self.get_property_count(entity_index).await?;
+
+        let mut properties = Vec::with_capacity(count as usize);
+        for property_index in 0..count {
+            properties.push(
+                self.read_whole_property(entity_index, property_index)
+                    .await?,
+            );
+        }
+
+        Ok(properties)
+    }
+
+    /// Sends a long request to a function of the feature, padding the payload
+    /// with zeros.
+    async fn request(&self, function_id: u8, payload: &[u8]) -> Result<v20::Message, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+
+        let mut data = [0; 16];
+        data[..payload.len()].copy_from_slice(payload);
+
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Long(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(function_id),
+                    software_id: chan.get_sw_id(),
+                },
+                data,
+            ),
+        )
+        .await
+    }
+}
+
+/// Represents information about a firmware property as reported by
+/// [`FirmwarePropertiesFeature::get_property_info`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct FirmwarePropertyInfo {
+    /// The identifier of the property, describing how its content is to be
+    /// interpreted.
+    pub id: u16,
+
+    /// The size of the property in bytes.
+    pub size: u16,
+}
+
+/// Represents a firmware property as read by
+/// [`FirmwarePropertiesFeature::read_whole_property`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct FirmwareProperty {
+    /// The identifier of the property, describing how its content is to be
+    /// interpreted.
+    pub id: u16,
+
+    /// The raw content of the property.
+    pub data: Vec<u8>,
+}
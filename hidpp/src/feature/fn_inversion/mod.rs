@@ -0,0 +1,345 @@
+//! Implements the `FnInversion` family of features (IDs `0x40a0`, `0x40a2`
+//! and `0x40a3`) that allow swapping the primary and secondary functions of
+//! the F-keys of a keyboard.
+//!
+//! All three features share the [`FnInversionState`] and [`FnInversionEvent`]
+//! types. They differ in what they report:
+//!
+//! - [`FnInversionFeature`] (`0x40a0`) only reports whether the keys are
+//!   inverted.
+//! - [`FnInversionWithDefaultStateFeature`] (`0x40a2`) additionally reports the
+//!   state the keyboard starts in.
+//! - [`FnInversionForMultiHostDevicesFeature`] (`0x40a3`) keeps a separate
+//!   state for every host the keyboard is paired with.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// The host index referring to the host the keyboard is currently connected
+/// to, as accepted by [`FnInversionForMultiHostDevicesFeature`].
+pub const CURRENT_HOST: u8 = 0xff;
+
+/// Represents the member of the feature family, which determines the layout
+/// of its messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FnInversionVariant {
+    /// `FnInversion` / `0x40a0`.
+    Basic,
+
+    /// `FnInversionWithDefaultState` / `0x40a2`.
+    WithDefaultState,
+
+    /// `FnInversionForMultiHostDevices` / `0x40a3`.
+    MultiHost,
+}
+
+impl FnInversionVariant {
+    /// Parses the state as reported by the getter, the setter and the
+    /// notification of the feature.
+    pub fn parse_state(self, payload: &[u8]) -> FnInversionState {
+        match self {
+            Self::Basic => FnInversionState {
+                host: None,
+                inverted: payload[0] & 1 != 0,
+                default_inverted: None,
+            },
+            Self::WithDefaultState => FnInversionState {
+                host: None,
+                inverted: payload[0] & 1 != 0,
+                default_inverted: Some(payload[1] & 1 != 0),
+            },
+            Self::MultiHost => FnInversionState {
+                host: Some(payload[0]),
+                inverted: payload[1] & 1 != 0,
+                default_inverted: Some(payload[2] & 1 != 0),
+            },
+        }
+    }
+}
+
+/// Represents whether the F-keys are inverted, meaning that they perform
+/// their secondary function unless Fn is held down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct FnInversionState {
+    /// The host the state belongs to.
+    ///
+    /// This is only reported by [`FnInversionForMultiHostDevicesFeature`].
+    pub host: Option<u8>,
+
+    /// Whether the F-keys are currently inverted.
+    pub inverted: bool,
+
+    /// Whether the F-keys are inverted when the keyboard starts.
+    ///
+    /// This is not reported by [`FnInversionFeature`].
+    pub default_inverted: Option<bool>,
+}
+
+/// Represents an event emitted by all features of the family.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum FnInversionEvent {
+    /// Is emitted whenever the state is changed on the keyboard, like by
+    /// pressing Fn + Esc.
+    ///
+    /// This event is always enabled.
+    StateChange(FnInversionState),
+}
+
+/// Contains the implementation shared by all features of the family.
+struct FnInversion {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The ID of the implemented member of the family.
+    feature_id: u16,
+
+    /// The member of the family that is implemented.
+    variant: FnInversionVariant,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<FnInversionEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl FnInversion {
+    fn new(
+        chan: Arc<HidppChannel>,
+        device_index: u8,
+        feature_index: u8,
+        feature_id: u16,
+        variant: FnInversionVariant,
+    ) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::fn_inversion_event(variant, function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            feature_id,
+            variant,
+            emitter,
+            _listener: listener,
+        }
+    }
+
+    async fn get(&self, host: u8) -> Result<FnInversionState, Hidpp20Error> {
+        self.request(0, [host, 0x00, 0x00]).await
+    }
+
+    async fn set(&self, host: u8, inverted: bool) -> Result<FnInversionState, Hidpp20Error> {
+        let payload = match self.variant {
+            FnInversionVariant::MultiHost => [host, inverted as u8, 0x00],
+            _ => [inverted as u8, 0x00, 0x00],
+        };
+
+        self.request(1, payload).await
+    }
+
+    async fn request(
+        &self,
+        function_id: u8,
+        payload: [u8; 3],
+    ) -> Result<FnInversionState, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                self.feature_id,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(function_id),
+                        software_id: chan.get_sw_id(),
+                    },
+                    payload,
+                ),
+            )
+            .await?;
+
+        Ok(self.variant.parse_state(&response.extend_payload()))
+    }
+}
+
+/// Implements the `FnInversion` / `0x40a0` feature.
+pub struct FnInversionFeature(FnInversion);
+
+impl CreatableFeature for FnInversionFeature {
+    const ID: u16 = 0x40a0;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(FnInversion::new(
+            chan,
+            device_index,
+            feature_index,
+            Self::ID,
+            FnInversionVariant::Basic,
+        ))
+    }
+}
+
+impl Feature for FnInversionFeature {
+}
+
+impl EmittingFeature<FnInversionEvent> for FnInversionFeature {
+    fn listen(&self) -> async_channel::Receiver<FnInversionEvent> {
+        self.0.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<FnInversionEvent>> {
+        self.0.emitter.create_envelope_receiver()
+    }
+}
+
+impl FnInversionFeature {
+    /// Retrieves whether the F-keys are inverted.
+    pub async fn get_fn_inversion(&self) -> Result<FnInversionState, Hidpp20Error> {
+        self.0.get(0x00).await
+    }
+
+    /// Sets whether the F-keys are inverted.
+    ///
+    /// Returns the state reported by the keyboard afterwards.
+    pub async fn set_fn_inversion(&self, inverted: bool) -> Result<FnInversionState, Hidpp20Error> {
+        self.0.set(0x00, inverted).await
+    }
+}
+
+/// Implements the `FnInversionWithDefaultState` / `0x40a2` feature.
+pub struct FnInversionWithDefaultStateFeature(FnInversion);
+
+impl CreatableFeature for FnInversionWithDefaultStateFeature {
+    const ID: u16 = 0x40a2;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(FnInversion::new(
+            chan,
+            device_index,
+            feature_index,
+            Self::ID,
+            FnInversionVariant::WithDefaultState,
+        ))
+    }
+}
+
+impl Feature for FnInversionWithDefaultStateFeature {
+}
+
+impl EmittingFeature<FnInversionEvent> for FnInversionWithDefaultStateFeature {
+    fn listen(&self) -> async_channel::Receiver<FnInversionEvent> {
+        self.0.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<FnInversionEvent>> {
+        self.0.emitter.create_envelope_receiver()
+    }
+}
+
+impl FnInversionWithDefaultStateFeature {
+    /// Retrieves whether the F-keys are inverted and whether they are by
+    /// default.
+    pub async fn get_fn_inversion(&self) -> Result<FnInversionState, Hidpp20Error> {
+        self.0.get(0x00).await
+    }
+
+    /// Sets whether the F-keys are inverted.
+    ///
+    /// Returns the state reported by the keyboard afterwards.
+    pub async fn set_fn_inversion(&self, inverted: bool) -> Result<FnInversionState, Hidpp20Error> {
+        self.0.set(0x00, inverted).await
+    }
+}
+
+/// Implements the `FnInversionForMultiHostDevices` / `0x40a3` feature.
+pub struct FnInversionForMultiHostDevicesFeature(FnInversion);
+
+impl CreatableFeature for FnInversionForMultiHostDevicesFeature {
+    const ID: u16 = 0x40a3;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(FnInversion::new(
+            chan,
+            device_index,
+            feature_index,
+            Self::ID,
+            FnInversionVariant::MultiHost,
+        ))
+    }
+}
+
+impl Feature for FnInversionForMultiHostDevicesFeature {
+}
+
+impl EmittingFeature<FnInversionEvent> for FnInversionForMultiHostDevicesFeature {
+    fn listen(&self) -> async_channel::Receiver<FnInversionEvent> {
+        self.0.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<FnInversionEvent>> {
+        self.0.emitter.create_envelope_receiver()
+    }
+}
+
+impl FnInversionForMultiHostDevicesFeature {
+    /// Retrieves whether the F-keys are inverted for a host, which can be
+    /// [`CURRENT_HOST`].
+    pub async fn get_fn_inversion(&self, host: u8) -> Result<FnInversionState, Hidpp20Error> {
+        self.0.get(host).await
+    }
+
+    /// Sets whether the F-keys are inverted for a host, which can be
+    /// [`CURRENT_HOST`].
+    ///
+    /// Returns the state reported by the keyboard afterwards.
+    pub async fn set_fn_inversion(
+        &self,
+        host: u8,
+        inverted: bool,
+    ) -> Result<FnInversionState, Hidpp20Error> {
+        self.0.set(host, inverted).await
+    }
+}
@@ -0,0 +1,312 @@
+//! Implements the `ForceFeedback` feature (ID `0x8123`) that allows playing
+//! force feedback effects on racing wheels.
+//!
+//! Effects are downloaded into slots of the device, which can then be played,
+//! stopped and destroyed independently.
+//!
+//! Periodic and condition effects need more parameters than fit into a long
+//! HID++ message and are not supported yet.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `ForceFeedback` / `0x8123` feature.
+pub struct ForceFeedbackFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for ForceFeedbackFeature {
+    const ID: u16 = 0x8123;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for ForceFeedbackFeature {
+}
+
+impl ForceFeedbackFeature {
+    /// Retrieves the number of effect slots of the device.
+    ///
+    /// One of the slots is usually occupied by the built-in autocenter spring
+    /// effect.
+    pub async fn get_slot_count(&self) -> Result<u8, Hidpp20Error> {
+        let response = self.request(0, &[]).await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Stops and destroys all effects, restoring the state after powering on
+    /// the device.
+    pub async fn reset_all(&self) -> Result<(), Hidpp20Error> {
+        self.request(1, &[]).await?;
+
+        Ok(())
+    }
+
+    /// Downloads an effect into a slot of the device and returns the slot.
+    ///
+    /// If `slot` is [`None`], a free slot is allocated by the device.
+    /// Otherwise, the effect in the given slot is replaced.
+    pub async fn download_effect(
+        &self,
+        effect: &ForceFeedbackEffect,
+        slot: Option<u8>,
+    ) -> Result<u8, Hidpp20Error> {
+        let mut payload = vec![slot.unwrap_or(0), effect.kind.effect_type()];
+        payload.extend(effect.length.to_be_bytes());
+        payload.extend(effect.delay.to_be_bytes());
+
+        match effect.kind {
+            EffectKind::Constant {
+                force,
+                envelope,
+            } => {
+                payload.extend(force.to_be_bytes());
+                payload.extend(envelope.to_bytes());
+            },
+            EffectKind::Ramp {
+                start,
+                end,
+                envelope,
+            } => {
+                payload.extend(start.to_be_bytes());
+                payload.extend(end.to_be_bytes());
+                payload.extend(envelope.to_bytes());
+            },
+        }
+
+        let response = self.request(2, &payload).await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Plays, stops or pauses the effect in a slot.
+    pub async fn set_effect_state(&self, slot: u8, state: EffectState) -> Result<(), Hidpp20Error> {
+        self.request(3, &[slot, state.into()]).await?;
+
+        Ok(())
+    }
+
+    /// Destroys the effect in a slot, freeing the slot.
+    pub async fn destroy_effect(&self, slot: u8) -> Result<(), Hidpp20Error> {
+        self.request(4, &[slot]).await?;
+
+        Ok(())
+    }
+
+    /// Retrieves the rotation range of the wheel in degrees.
+    pub async fn get_aperture(&self) -> Result<u16, Hidpp20Error> {
+        let response = self.request(5, &[]).await?;
+        let payload = response.extend_payload();
+
+        Ok(u16::from_be_bytes([payload[0], payload[1]]))
+    }
+
+    /// Sets the rotation range of the wheel in degrees.
+    pub async fn set_aperture(&self, degrees: u16) -> Result<(), Hidpp20Error> {
+        self.request(6, &degrees.to_be_bytes()).await?;
+
+        Ok(())
+    }
+
+    /// Retrieves the gains applied to all effects.
+    pub async fn get_global_gains(&self) -> Result<GlobalGains, Hidpp20Error> {
+        let response = self.request(7, &[]).await?;
+        let payload = response.extend_payload();
+
+        Ok(GlobalGains {
+            gain: u16::from_be_bytes([payload[0], payload[1]]),
+            boost: u16::from_be_bytes([payload[2], payload[3]]),
+        })
+    }
+
+    /// Sets the gains applied to all effects.
+    pub async fn set_global_gains(&self, gains: GlobalGains) -> Result<(), Hidpp20Error> {
+        let [gain_hi, gain_lo] = gains.gain.to_be_bytes();
+        let [boost_hi, boost_lo] = gains.boost.to_be_bytes();
+        self.request(8, &[gain_hi, gain_lo, boost_hi, boost_lo])
+            .await?;
+
+        Ok(())
+    }
+
+    /// Sends a long request to a function of the feature, padding the payload
+    /// with zeros.
+    async fn request(&self, function_id: u8, payload: &[u8]) -> Result<v20::Message, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+
+        let mut data = [0; 16];
+        data[..payload.len()].copy_from_slice(payload);
+
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Long(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(function_id),
+                    software_id: chan.get_sw_id(),
+                },
+                data,
+            ),
+        )
+        .await
+    }
+}
+
+/// Represents a force feedback effect that can be downloaded via
+/// [`ForceFeedbackFeature::download_effect`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ForceFeedbackEffect {
+    /// How long the effect plays in milliseconds.
+    pub length: u16,
+
+    /// How long to wait before playing the effect in milliseconds.
+    pub delay: u16,
+
+    /// The kind of the effect and its parameters.
+    pub kind: EffectKind,
+}
+
+impl ForceFeedbackEffect {
+    /// Creates a new effect from its individual parts.
+    pub fn new(length: u16, delay: u16, kind: EffectKind) -> Self {
+        Self {
+            length,
+            delay,
+            kind,
+        }
+    }
+}
+
+/// Represents the kind of a force feedback effect.
+///
+/// Forces are signed, where positive forces turn the wheel to the right.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "effect", rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum EffectKind {
+    /// Applies a constant force.
+    Constant {
+        force: i16,
+        envelope: EffectEnvelope,
+    },
+
+    /// Applies a force changing linearly from `start` to `end`.
+    Ramp {
+        start: i16,
+        end: i16,
+        envelope: EffectEnvelope,
+    },
+}
+
+impl EffectKind {
+    /// Returns the type ID of the effect used by the device.
+    fn effect_type(&self) -> u8 {
+        match self {
+            EffectKind::Constant {
+                ..
+            } => 0x00,
+            EffectKind::Ramp {
+                ..
+            } => 0x0a,
+        }
+    }
+}
+
+/// Represents the envelope of a force feedback effect, which fades the effect
+/// in at the start and out at the end.
+///
+/// Lengths are given in milliseconds and levels relative to the force of the
+/// effect, where `255` is the full force.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EffectEnvelope {
+    pub attack_level: u8,
+    pub attack_length: u16,
+    pub fade_level: u8,
+    pub fade_length: u16,
+}
+
+impl EffectEnvelope {
+    /// Converts the envelope to its byte representation used in effect
+    /// parameters.
+    fn to_bytes(self) -> [u8; 6] {
+        let [attack_hi, attack_lo] = self.attack_length.to_be_bytes();
+        let [fade_hi, fade_lo] = self.fade_length.to_be_bytes();
+
+        [
+            self.attack_level,
+            attack_hi,
+            attack_lo,
+            self.fade_level,
+            fade_hi,
+            fade_lo,
+        ]
+    }
+}
+
+/// Represents a state an effect can be set to via
+/// [`ForceFeedbackFeature::set_effect_state`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum EffectState {
+    Stopped = 1,
+    Playing = 2,
+    Paused = 3,
+}
+
+/// Represents the gains applied to all force feedback effects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct GlobalGains {
+    /// The gain, where `0xffff` applies effects at their full force.
+    pub gain: u16,
+
+    /// The boost applied on top of the gain.
+    pub boost: u16,
+}
+
+impl GlobalGains {
+    /// Creates new gains from their individual parts.
+    pub fn new(gain: u16, boost: u16) -> Self {
+        Self {
+            gain,
+            boost,
+        }
+    }
+}
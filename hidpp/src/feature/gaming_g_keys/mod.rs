@@ -0,0 +1,147 @@
+//! Implements the `GamingGKeys` feature (ID `0x8010`) that allows diverting
+//! the programmable G-keys of gaming devices to software.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `GamingGKeys` / `0x8010` feature.
+///
+/// Held keys are reported in a little-endian bit mask of four bytes. Whether
+/// devices with more than 32 G-keys use a different layout is unknown.
+pub struct GamingGKeysFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<GKeysEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for GamingGKeysFeature {
+    const ID: u16 = 0x8010;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::gaming_g_keys_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for GamingGKeysFeature {
+}
+
+impl EmittingFeature<GKeysEvent> for GamingGKeysFeature {
+    fn listen(&self) -> async_channel::Receiver<GKeysEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<GKeysEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl GamingGKeysFeature {
+    /// Retrieves the number of G-keys of the device.
+    pub async fn get_count(&self) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Enables or disables software control of the G-keys.
+    ///
+    /// While enabled, the G-keys no longer perform their onboard function and
+    /// [`GKeysEvent::KeysDown`] is emitted instead. The device falls back to
+    /// the onboard functions when it is reset or reconnected.
+    pub async fn set_software_control(&self, enabled: bool) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(2),
+                    software_id: chan.get_sw_id(),
+                },
+                [enabled as u8, 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents an event emitted by the [`GamingGKeysFeature`] feature.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum GKeysEvent {
+    /// Is emitted whenever the set of held down G-keys changes.
+    ///
+    /// Contains the numbers of all G-keys that are currently held down,
+    /// starting at 1 for G1, which is empty once all of them were released.
+    ///
+    /// This event is only emitted while software control is enabled via
+    /// [`GamingGKeysFeature::set_software_control`].
+    KeysDown(Vec<u8>),
+}
@@ -0,0 +1,152 @@
+//! Implements the `GamingMKeys` feature (ID `0x8020`) that allows controlling
+//! the LEDs of the mode keys of gaming devices and reports presses of them.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `GamingMKeys` / `0x8020` feature.
+///
+/// Only the first byte of the LED mask and of the notifications is known, so at
+/// most eight M-keys are supported.
+pub struct GamingMKeysFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<MKeysEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for GamingMKeysFeature {
+    const ID: u16 = 0x8020;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::gaming_m_keys_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for GamingMKeysFeature {
+}
+
+impl EmittingFeature<MKeysEvent> for GamingMKeysFeature {
+    fn listen(&self) -> async_channel::Receiver<MKeysEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<MKeysEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl GamingMKeysFeature {
+    /// Retrieves the number of M-keys of the device.
+    pub async fn get_count(&self) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Sets which M-key LEDs are lit, starting at 1 for M1.
+    ///
+    /// The LEDs of all other M-keys are turned off. Numbers beyond the eighth
+    /// M-key are ignored.
+    pub async fn set_leds(&self, lit: &[u8]) -> Result<(), Hidpp20Error> {
+        let leds = lit
+            .iter()
+            .filter(|&&key| (1..=8).contains(&key))
+            .fold(0u8, |leds, key| leds | (1 << (key - 1)));
+
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(1),
+                    software_id: chan.get_sw_id(),
+                },
+                [leds, 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents an event emitted by the [`GamingMKeysFeature`] feature.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum MKeysEvent {
+    /// Is emitted whenever the set of held down M-keys changes.
+    ///
+    /// Contains the numbers of all M-keys that are currently held down,
+    /// starting at 1 for M1, which is empty once all of them were released.
+    ///
+    /// This event is only emitted while software control of the G-keys is
+    /// enabled via
+    /// [`GamingGKeysFeature::set_software_control`](super::gaming_g_keys::GamingGKeysFeature::set_software_control).
+    KeysDown(Vec<u8>),
+}
@@ -0,0 +1,523 @@
+//! Implements the `Gestures2` feature (ID `0x6501`) that allows configuring
+//! the gestures of touch surfaces and gesture buttons.
+//!
+//! Besides the raw feature functions, this module provides
+//! [`GestureConfig`], a declarative description of the gesture state of a
+//! device. It can be read from a device, diffed against a desired state and
+//! applied, writing only the settings that actually differ, which matters as
+//! every write costs a round-trip to the device.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Weak},
+};
+
+use thiserror::Error;
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// The maximum size of a parameter value that can be written, as the request
+/// also carries the parameter index and a trailing `0xff`.
+pub const MAX_PARAM_SIZE: usize = 14;
+
+/// Implements the `Gestures2` / `0x6501` feature.
+///
+/// The device describes its gestures and parameters as a list of fields,
+/// which is retrieved using [`Self::get_gesture_table`]. The resulting
+/// [`GestureTable`] is needed to address gestures and parameters in all other
+/// functions.
+pub struct GesturesFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for GesturesFeature {
+    const ID: u16 = 0x6501;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for GesturesFeature {
+}
+
+impl GesturesFeature {
+    /// Retrieves all gestures and parameters supported by the device.
+    pub async fn get_gesture_table(&self) -> Result<GestureTable, Hidpp20Error> {
+        let mut table = GestureTable::default();
+        let mut enable_index = 0u16;
+        let mut divert_index = 0u16;
+        let mut param_index = 0u8;
+
+        let mut offset = 0u16;
+        loop {
+            let offset_bytes = offset.to_be_bytes();
+            let response = self.request(0, &[offset_bytes[0], offset_bytes[1]]).await?;
+
+            for field in response.extend_payload().chunks_exact(2) {
+                let (high, low) = (field[0], field[1]);
+
+                // A field with the high byte `0x01` terminates the list.
+                if high == 0x01 {
+                    return Ok(table);
+                }
+
+                if high & 0x80 != 0 {
+                    let gesture =
+                        GestureInfo::from_field(high, low, &mut enable_index, &mut divert_index);
+                    table.gestures.push(gesture);
+                } else if matches!(high & 0xf0, 0x20 | 0x30) {
+                    table.params.push(ParamInfo {
+                        id: low,
+                        size: high & 0x0f,
+                        index: param_index,
+                    });
+                    param_index = param_index.wrapping_add(1);
+                }
+            }
+
+            let Some(next) = offset.checked_add(8) else {
+                return Err(Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID));
+            };
+            offset = next;
+        }
+    }
+
+    /// Checks whether a gesture is enabled.
+    pub async fn is_gesture_enabled(&self, gesture: &GestureInfo) -> Result<bool, Hidpp20Error> {
+        let Some(index) = gesture.enable_index else {
+            return Ok(gesture.default_enabled);
+        };
+
+        let (offset, mask) = offset_mask(index);
+        let response = self.request(1, &[offset, 0x01, mask]).await?;
+
+        Ok(response.extend_payload()[0] & mask != 0)
+    }
+
+    /// Enables or disables a gesture.
+    ///
+    /// Does nothing if the gesture can't be enabled or disabled.
+    pub async fn set_gesture_enabled(
+        &self,
+        gesture: &GestureInfo,
+        enabled: bool,
+    ) -> Result<(), Hidpp20Error> {
+        let Some(index) = gesture.enable_index.filter(|_| gesture.can_be_enabled) else {
+            return Ok(());
+        };
+
+        let (offset, mask) = offset_mask(index);
+        self.request(2, &[
+            offset,
+            0x01,
+            mask,
+            if enabled {
+                mask
+            } else {
+                0x00
+            },
+        ])
+        .await?;
+
+        Ok(())
+    }
+
+    /// Checks whether a gesture is diverted to be reported via HID++.
+    pub async fn is_gesture_diverted(&self, gesture: &GestureInfo) -> Result<bool, Hidpp20Error> {
+        let Some(index) = gesture.divert_index else {
+            return Ok(false);
+        };
+
+        let (offset, mask) = offset_mask(index);
+        let response = self.request(3, &[offset, 0x01, mask]).await?;
+
+        Ok(response.extend_payload()[0] & mask != 0)
+    }
+
+    /// Sets whether a gesture is diverted to be reported via HID++.
+    ///
+    /// Does nothing if the gesture can't be diverted.
+    pub async fn set_gesture_diverted(
+        &self,
+        gesture: &GestureInfo,
+        diverted: bool,
+    ) -> Result<(), Hidpp20Error> {
+        let Some(index) = gesture.divert_index else {
+            return Ok(());
+        };
+
+        let (offset, mask) = offset_mask(index);
+        self.request(4, &[
+            offset,
+            0x01,
+            mask,
+            if diverted {
+                mask
+            } else {
+                0x00
+            },
+        ])
+        .await?;
+
+        Ok(())
+    }
+
+    /// Retrieves the current value of a parameter.
+    pub async fn get_param(&self, param: &ParamInfo) -> Result<Vec<u8>, Hidpp20Error> {
+        let response = self.request(7, &[param.index, 0xff]).await?;
+
+        Ok(response.extend_payload()[..param.size as usize].to_vec())
+    }
+
+    /// Sets the value of a parameter, which is truncated or padded with zeros
+    /// to the size of the parameter.
+    ///
+    /// Only the first [`MAX_PARAM_SIZE`] bytes of larger parameters are
+    /// written, as the request has no room for more.
+    pub async fn set_param(&self, param: &ParamInfo, value: &[u8]) -> Result<(), Hidpp20Error> {
+        let size = (param.size as usize).min(MAX_PARAM_SIZE);
+
+        let mut payload = vec![param.index];
+        payload.extend((0..size).map(|i| value.get(i).copied().unwrap_or(0)));
+        payload.push(0xff);
+        self.request(8, &payload).await?;
+
+        Ok(())
+    }
+
+    /// Reads the state of all gestures and parameters of a table.
+    pub async fn read_gesture_config(
+        &self,
+        table: &GestureTable,
+    ) -> Result<GestureConfig, Hidpp20Error> {
+        let mut config = GestureConfig::default();
+
+        for gesture in &table.gestures {
+            if gesture.enable_index.is_some() {
+                config
+                    .enabled
+                    .insert(gesture.id, self.is_gesture_enabled(gesture).await?);
+            }
+            if gesture.divert_index.is_some() {
+                config
+                    .diverted
+                    .insert(gesture.id, self.is_gesture_diverted(gesture).await?);
+            }
+        }
+
+        for param in &table.params {
+            config.params.insert(param.id, self.get_param(param).await?);
+        }
+
+        Ok(config)
+    }
+
+    /// Writes all settings of a config, which is usually the difference
+    /// returned by [`GestureConfig::diff`].
+    ///
+    /// All settings are checked against the table before anything is
+    /// written.
+    pub async fn apply_gesture_config(
+        &self,
+        table: &GestureTable,
+        config: &GestureConfig,
+    ) -> Result<(), GestureError> {
+        let mut enabled = Vec::new();
+        for (&id, &value) in &config.enabled {
+            match table.gesture(id) {
+                Some(gesture) if gesture.can_be_enabled && gesture.enable_index.is_some() => {
+                    enabled.push((gesture, value));
+                },
+                _ => return Err(GestureError::UnsupportedGesture(id)),
+            }
+        }
+
+        let mut diverted = Vec::new();
+        for (&id, &value) in &config.diverted {
+            match table.gesture(id) {
+                Some(gesture) if gesture.divert_index.is_some() => diverted.push((gesture, value)),
+                _ => return Err(GestureError::UnsupportedGesture(id)),
+            }
+        }
+
+        let mut params = Vec::new();
+        for (&id, value) in &config.params {
+            let param = table.param(id).ok_or(GestureError::UnknownParam(id))?;
+            if param.size as usize > MAX_PARAM_SIZE {
+                return Err(GestureError::UnsupportedParamSize {
+                    param: id,
+                    size: param.size,
+                });
+            }
+            if value.len() != param.size as usize {
+                return Err(GestureError::InvalidParamSize {
+                    param: id,
+                    expected: param.size,
+                    actual: value.len(),
+                });
+            }
+            params.push((param, value));
+        }
+
+        for (gesture, value) in enabled {
+            self.set_gesture_enabled(gesture, value).await?;
+        }
+        for (gesture, value) in diverted {
+            self.set_gesture_diverted(gesture, value).await?;
+        }
+        for (param, value) in params {
+            self.set_param(param, value).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the current state of the device, diffs it against the desired
+    /// config and writes only the differences.
+    ///
+    /// Returns the applied differences.
+    pub async fn sync_gesture_config(
+        &self,
+        table: &GestureTable,
+        desired: &GestureConfig,
+    ) -> Result<GestureConfig, GestureError> {
+        let diff = self.read_gesture_config(table).await?.diff(desired);
+        if !diff.is_empty() {
+            self.apply_gesture_config(table, &diff).await?;
+        }
+
+        Ok(diff)
+    }
+
+    /// Sends a long request to a function of the feature, padding the payload
+    /// with zeros.
+    async fn request(&self, function_id: u8, payload: &[u8]) -> Result<v20::Message, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+
+        let mut data = [0; 16];
+        data[..payload.len()].copy_from_slice(payload);
+
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Long(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(function_id),
+                    software_id: chan.get_sw_id(),
+                },
+                data,
+            ),
+        )
+        .await
+    }
+}
+
+/// Calculates the byte offset and bit mask of an index into the bit fields
+/// used for the enabled and diverted states.
+fn offset_mask(index: u16) -> (u8, u8) {
+    ((index >> 3) as u8, 1 << (index & 0x07))
+}
+
+/// Represents the gestures and parameters supported by a device as reported by
+/// [`GesturesFeature::get_gesture_table`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct GestureTable {
+    pub gestures: Vec<GestureInfo>,
+    pub params: Vec<ParamInfo>,
+}
+
+impl GestureTable {
+    /// Looks up a gesture by its ID.
+    pub fn gesture(&self, id: u8) -> Option<&GestureInfo> {
+        self.gestures.iter().find(|gesture| gesture.id == id)
+    }
+
+    /// Looks up a parameter by its ID.
+    pub fn param(&self, id: u8) -> Option<&ParamInfo> {
+        self.params.iter().find(|param| param.id == id)
+    }
+}
+
+/// Represents a gesture of a [`GestureTable`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct GestureInfo {
+    /// The ID of the gesture.
+    pub id: u8,
+
+    /// Whether the gesture can be enabled and disabled.
+    pub can_be_enabled: bool,
+
+    /// Whether the gesture can be diverted to be reported via HID++.
+    pub can_be_diverted: bool,
+
+    /// Whether the gesture should be shown in user interfaces.
+    pub show_in_ui: bool,
+
+    /// Whether software should enable the gesture by default.
+    pub desired_software_default: bool,
+
+    /// Whether the enabled state of the gesture is stored persistently.
+    pub persistent: bool,
+
+    /// Whether the gesture is enabled by default.
+    pub default_enabled: bool,
+
+    /// The index of the gesture in the bit field of enabled gestures.
+    enable_index: Option<u16>,
+
+    /// The index of the gesture in the bit field of diverted gestures.
+    divert_index: Option<u16>,
+}
+
+impl GestureInfo {
+    /// Parses a gesture field, assigning the next free bit field indices to
+    /// it.
+    fn from_field(high: u8, low: u8, enable_index: &mut u16, divert_index: &mut u16) -> Self {
+        let can_be_enabled = high & 0x01 != 0;
+        let can_be_diverted = high & 0x02 != 0;
+        let default_enabled = high & 0x20 != 0;
+
+        let next = |index: &mut u16, present: bool| {
+            present.then(|| {
+                let current = *index;
+                *index = index.wrapping_add(1);
+                current
+            })
+        };
+
+        Self {
+            id: low,
+            can_be_enabled,
+            can_be_diverted,
+            show_in_ui: high & 0x04 != 0,
+            desired_software_default: high & 0x08 != 0,
+            persistent: high & 0x10 != 0,
+            default_enabled,
+            enable_index: next(enable_index, can_be_enabled || default_enabled),
+            divert_index: next(divert_index, can_be_diverted),
+        }
+    }
+}
+
+/// Represents a gesture parameter of a [`GestureTable`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ParamInfo {
+    /// The ID of the parameter.
+    pub id: u8,
+
+    /// The size of the parameter value in bytes.
+    pub size: u8,
+
+    /// The index used to address the parameter.
+    index: u8,
+}
+
+/// Represents the gesture state of a device.
+///
+/// Gestures and parameters are identified by their IDs. Only the gestures and
+/// parameters contained in a config are considered when diffing or applying
+/// it, so a desired config only needs to list the settings it cares about.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[non_exhaustive]
+pub struct GestureConfig {
+    /// Whether gestures are enabled.
+    pub enabled: BTreeMap<u8, bool>,
+
+    /// Whether gestures are diverted to be reported via HID++.
+    pub diverted: BTreeMap<u8, bool>,
+
+    /// The raw values of parameters.
+    pub params: BTreeMap<u8, Vec<u8>>,
+}
+
+impl GestureConfig {
+    /// Computes the settings of a desired config that differ from this one.
+    pub fn diff(&self, desired: &GestureConfig) -> GestureConfig {
+        fn changed<V: PartialEq + Clone>(
+            current: &BTreeMap<u8, V>,
+            desired: &BTreeMap<u8, V>,
+        ) -> BTreeMap<u8, V> {
+            desired
+                .iter()
+                .filter(|(id, value)| current.get(id) != Some(value))
+                .map(|(&id, value)| (id, value.clone()))
+                .collect()
+        }
+
+        GestureConfig {
+            enabled: changed(&self.enabled, &desired.enabled),
+            diverted: changed(&self.diverted, &desired.diverted),
+            params: changed(&self.params, &desired.params),
+        }
+    }
+
+    /// Whether the config contains no settings.
+    pub fn is_empty(&self) -> bool {
+        self.enabled.is_empty() && self.diverted.is_empty() && self.params.is_empty()
+    }
+}
+
+/// Represents an error that may occur when applying a [`GestureConfig`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum GestureError {
+    /// Indicates that the device does not have a gesture with the ID or it
+    /// can't be enabled or diverted as requested.
+    #[error("the gesture {0:#04x} does not support the requested setting")]
+    UnsupportedGesture(u8),
+
+    /// Indicates that the device does not have a parameter with the ID.
+    #[error("there is no gesture parameter {0:#04x}")]
+    UnknownParam(u8),
+
+    /// Indicates that a parameter value does not have the size of the
+    /// parameter.
+    #[error("the gesture parameter {param:#04x} takes {expected} bytes, but {actual} were given")]
+    InvalidParamSize {
+        param: u8,
+        expected: u8,
+        actual: usize,
+    },
+
+    /// Indicates that a parameter is larger than [`MAX_PARAM_SIZE`] and can't
+    /// be written.
+    #[error(
+        "the gesture parameter {param:#04x} takes {size} bytes, but at most {MAX_PARAM_SIZE} can \
+         be written"
+    )]
+    UnsupportedParamSize {
+        param: u8,
+        size: u8,
+    },
+
+    /// Indicates that the device returned an error.
+    #[error("the device returned an error")]
+    Hidpp(#[from] Hidpp20Error),
+}
@@ -0,0 +1,92 @@
+//! Implements the `HighResolutionScrolling` feature (ID `0x2120`) that allows
+//! switching older mice to high-resolution scrolling.
+//!
+//! Newer mice implement
+//! [`HiResWheelFeature`](super::hires_wheel::HiResWheelFeature) instead.
+
+use std::sync::{Arc, Weak};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature, hires_wheel::WheelResolution},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `HighResolutionScrolling` / `0x2120` feature.
+pub struct HighResolutionScrollingFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for HighResolutionScrollingFeature {
+    const ID: u16 = 0x2120;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for HighResolutionScrollingFeature {
+}
+
+impl HighResolutionScrollingFeature {
+    /// Retrieves the resolution the wheel currently reports in.
+    pub async fn get_scrolling_mode(&self) -> Result<WheelResolution, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(WheelResolution::try_from(response.extend_payload()[0] & 1).unwrap())
+    }
+
+    /// Sets the resolution the wheel reports in.
+    ///
+    /// In [`WheelResolution::High`], the wheel reports multiple events per
+    /// ratchet, so the host has to support high-resolution scrolling to
+    /// avoid scrolling too fast.
+    pub async fn set_scrolling_mode(
+        &self,
+        resolution: WheelResolution,
+    ) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(1),
+                    software_id: chan.get_sw_id(),
+                },
+                [resolution.into(), 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,25 +1,30 @@
 //! Implements the `HiResWheel` feature (ID `0x2121`) that allows configuring
 //! and using high-resolution scrolling.
 
-use std::{hash::Hash, sync::Arc};
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::{
-    channel::HidppChannel,
-    event::EventEmitter,
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
     feature::{CreatableFeature, EmittingFeature, Feature},
     nibble::U4,
+    parse,
     protocol::v20::{self, Hidpp20Error},
 };
 
 /// Implements the `HiResWheel` / `0x2121` feature.
 ///
 /// The analytics part of the feature is not implemented here as its data
-/// structure lacks any documentation.
+/// structure lacks any documentation. A best-effort implementation is provided
+/// by [`HiResWheelFeatureV1`].
 pub struct HiResWheelFeature {
     /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
+    chan: Weak<HidppChannel>,
 
     /// The index of the device to implement the feature for.
     device_index: u8,
@@ -30,10 +35,9 @@ pub struct HiResWheelFeature {
     /// The emitter used to emit events.
     emitter: Arc<EventEmitter<HiResWheelEvent>>,
 
-    /// The handle assigned to the message listener registered via
-    /// [`HidppChannel::add_msg_listener`].
-    /// This is used to remove the listener when the feature is dropped.
-    msg_listener_hdl: u32,
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
 }
 
 impl CreatableFeature for HiResWheelFeature {
@@ -43,7 +47,7 @@ impl CreatableFeature for HiResWheelFeature {
     fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
         let emitter = Arc::new(EventEmitter::new());
 
-        let hdl = chan.add_msg_listener({
+        let listener = chan.subscribe({
             let emitter = Arc::clone(&emitter);
 
             move |raw, matched| {
@@ -51,52 +55,21 @@ impl CreatableFeature for HiResWheelFeature {
                     return;
                 }
 
-                let msg = v20::Message::from(raw);
-
-                let header = msg.header();
-                if header.device_index != device_index
-                    || header.feature_index != feature_index
-                    || header.software_id.to_lo() != 0
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::hires_wheel_event(function_id, &payload)
                 {
-                    return;
+                    emitter.emit(event, raw);
                 }
-
-                let payload = msg.extend_payload();
-
-                let event = match header.function_id.to_lo() {
-                    0 => {
-                        let Ok(resolution) =
-                            WheelResolution::try_from((payload[0] & (1 << 4)) >> 4)
-                        else {
-                            return;
-                        };
-
-                        HiResWheelEvent::WheelMovement(WheelMovementData {
-                            resolution,
-                            periods: U4::from_lo(payload[0]),
-                            delta_vertical: i16::from_be_bytes(payload[1..=2].try_into().unwrap()),
-                        })
-                    },
-                    1 => {
-                        let Ok(state) = WheelRatchetState::try_from(payload[0] & 1) else {
-                            return;
-                        };
-
-                        HiResWheelEvent::RatchetSwitch(state)
-                    },
-                    _ => return,
-                };
-
-                emitter.emit(event);
             }
         });
 
         Self {
-            chan,
+            chan: Arc::downgrade(&chan),
             device_index,
             feature_index,
             emitter,
-            msg_listener_hdl: hdl,
+            _listener: listener,
         }
     }
 }
@@ -108,34 +81,36 @@ impl EmittingFeature<HiResWheelEvent> for HiResWheelFeature {
     fn listen(&self) -> async_channel::Receiver<HiResWheelEvent> {
         self.emitter.create_receiver()
     }
-}
 
-impl Drop for HiResWheelFeature {
-    fn drop(&mut self) {
-        self.chan.remove_msg_listener(self.msg_listener_hdl);
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<HiResWheelEvent>> {
+        self.emitter.create_envelope_receiver()
     }
 }
 
 impl HiResWheelFeature {
     /// Retrieves the capabilities of the hi-res wheel and this feature.
     pub async fn get_wheel_capabilities(&self) -> Result<WheelCapabilities, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(0),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
 
         Ok(WheelCapabilities {
             multiplier: payload[0],
+            has_analytics: payload[1] & (1 << 4) != 0,
             has_invert: payload[1] & (1 << 3) != 0,
             has_switch: payload[1] & (1 << 2) != 0,
             ratches_per_rotation: payload[2],
@@ -145,28 +120,23 @@ impl HiResWheelFeature {
 
     /// Retrieves the current mode of the hi-res wheel.
     pub async fn get_wheel_mode(&self) -> Result<WheelMode, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(1),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
-        let payload = response.extend_payload();
-
-        Ok(WheelMode {
-            inverted: payload[0] & (1 << 2) != 0,
-            resolution: WheelResolution::try_from((payload[0] & (1 << 1)) >> 1)
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-            target: WheelEventTarget::try_from(payload[0] & 1)
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-        })
+        parse_wheel_mode(&response)
     }
 
     /// Sets the mode of the hi-res wheel.
@@ -174,66 +144,161 @@ impl HiResWheelFeature {
     /// Setting the bit to control analytics collection is not supported in this
     /// feature implementation as the analytics data structure is completely
     /// undocumented.\
-    /// If this is implemented in the future, a new implementation will do so to
-    /// not break this one.
+    /// [`HiResWheelFeatureV1::set_wheel_mode`] supports it on a best-effort
+    /// basis.
     pub async fn set_wheel_mode(
         &self,
         target: WheelEventTarget,
         resolution: WheelResolution,
         inverted: bool,
     ) -> Result<WheelMode, Hidpp20Error> {
-        let mut mode_byte = 0u8;
-        if inverted {
-            mode_byte |= 1 << 2;
-        }
-        mode_byte |= u8::from(resolution) << 1;
-        mode_byte |= u8::from(target);
-
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(2),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [mode_byte, 0x00, 0x00],
-            ))
-            .await?;
+        self.set_raw_wheel_mode(wheel_mode_byte(target, resolution, inverted))
+            .await
+    }
 
-        let payload = response.extend_payload();
+    /// Sets the mode of the hi-res wheel from its raw byte.
+    async fn set_raw_wheel_mode(&self, mode_byte: u8) -> Result<WheelMode, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(2),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [mode_byte, 0x00, 0x00],
+                ),
+            )
+            .await?;
 
-        Ok(WheelMode {
-            inverted: payload[0] & (1 << 2) != 0,
-            resolution: WheelResolution::try_from((payload[0] & (1 << 1)) >> 1)
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-            target: WheelEventTarget::try_from(payload[0] & 1)
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-        })
+        parse_wheel_mode(&response)
     }
 
     /// Retrieves the current state of the ratchet switch.
     pub async fn get_ratchet_switch_state(&self) -> Result<WheelRatchetState, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(3),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(3),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
 
-        WheelRatchetState::try_from(payload[0] & 1).map_err(|_| Hidpp20Error::UnsupportedResponse)
+        WheelRatchetState::try_from(payload[0] & 1)
+            .map_err(|_| Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID))
+    }
+}
+
+/// Implements version 1 of the `HiResWheel` / `0x2121` feature, which added
+/// the collection of analytics data.
+///
+/// The analytics data structure is undocumented, so it is only decoded on a
+/// best-effort basis, see [`WheelAnalyticsData`]. All other functions behave
+/// like the ones of [`HiResWheelFeature`].
+pub struct HiResWheelFeatureV1(HiResWheelFeature);
+
+impl CreatableFeature for HiResWheelFeatureV1 {
+    const ID: u16 = HiResWheelFeature::ID;
+    const STARTING_VERSION: u8 = 1;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(HiResWheelFeature::new(chan, device_index, feature_index))
+    }
+}
+
+impl Feature for HiResWheelFeatureV1 {
+}
+
+impl EmittingFeature<HiResWheelEvent> for HiResWheelFeatureV1 {
+    fn listen(&self) -> async_channel::Receiver<HiResWheelEvent> {
+        self.0.listen()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<HiResWheelEvent>> {
+        self.0.listen_envelopes()
     }
 }
 
+impl HiResWheelFeatureV1 {
+    /// Retrieves the capabilities of the hi-res wheel and this feature,
+    /// including [`WheelCapabilities::has_analytics`].
+    pub async fn get_wheel_capabilities(&self) -> Result<WheelCapabilities, Hidpp20Error> {
+        self.0.get_wheel_capabilities().await
+    }
+
+    /// Retrieves the current mode of the hi-res wheel, including
+    /// [`WheelMode::analytics`].
+    pub async fn get_wheel_mode(&self) -> Result<WheelMode, Hidpp20Error> {
+        self.0.get_wheel_mode().await
+    }
+
+    /// Sets the mode of the hi-res wheel, including whether analytics data is
+    /// collected and reported via [`HiResWheelEvent::Analytics`].
+    ///
+    /// Analytics collection should only be enabled if
+    /// [`WheelCapabilities::has_analytics`] is set.
+    pub async fn set_wheel_mode(
+        &self,
+        target: WheelEventTarget,
+        resolution: WheelResolution,
+        inverted: bool,
+        analytics: bool,
+    ) -> Result<WheelMode, Hidpp20Error> {
+        let mut mode_byte = wheel_mode_byte(target, resolution, inverted);
+        if analytics {
+            mode_byte |= 1 << 3;
+        }
+
+        self.0.set_raw_wheel_mode(mode_byte).await
+    }
+
+    /// Retrieves the current state of the ratchet switch.
+    pub async fn get_ratchet_switch_state(&self) -> Result<WheelRatchetState, Hidpp20Error> {
+        self.0.get_ratchet_switch_state().await
+    }
+}
+
+/// Builds the raw mode byte from its parts, leaving the analytics bit unset.
+fn wheel_mode_byte(target: WheelEventTarget, resolution: WheelResolution, inverted: bool) -> u8 {
+    let mut mode_byte = 0u8;
+    if inverted {
+        mode_byte |= 1 << 2;
+    }
+    mode_byte |= u8::from(resolution) << 1;
+    mode_byte |= u8::from(target);
+
+    mode_byte
+}
+
+/// Parses the wheel mode contained in a response.
+fn parse_wheel_mode(response: &v20::Message) -> Result<WheelMode, Hidpp20Error> {
+    let payload = response.extend_payload();
+
+    Ok(WheelMode {
+        analytics: payload[0] & (1 << 3) != 0,
+        inverted: payload[0] & (1 << 2) != 0,
+        resolution: WheelResolution::try_from((payload[0] & (1 << 1)) >> 1).map_err(|_| {
+            Hidpp20Error::unsupported_response(response).with_feature_id(HiResWheelFeature::ID)
+        })?,
+        target: WheelEventTarget::try_from(payload[0] & 1).map_err(|_| {
+            Hidpp20Error::unsupported_response(response).with_feature_id(HiResWheelFeature::ID)
+        })?,
+    })
+}
+
 /// Represents the capabilities of the hi-res wheel and this feature as reported
 /// by [`HiResWheelFeature::get_wheel_capabilities`].
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -245,6 +310,13 @@ pub struct WheelCapabilities {
     /// mode.
     pub multiplier: u8,
 
+    /// Whether the device supports collecting analytics data.
+    ///
+    /// This field was added in feature version 1 and will always be `false`
+    /// for older versions. Its bit is undocumented, so it is only decoded on a
+    /// best-effort basis.
+    pub has_analytics: bool,
+
     /// Whether the device supports inverting the scrolling direction when in
     /// native HID reporting mode.
     ///
@@ -268,6 +340,13 @@ pub struct WheelCapabilities {
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[non_exhaustive]
 pub struct WheelMode {
+    /// Whether analytics data is collected and reported via
+    /// [`HiResWheelEvent::Analytics`].
+    ///
+    /// This field was added in feature version 1 and will always be `false`
+    /// for older versions.
+    pub analytics: bool,
+
     /// Whether the scrolling direction is inverted.
     /// Only applies when in native HID mode.
     pub inverted: bool,
@@ -321,6 +400,37 @@ pub enum HiResWheelEvent {
     ///
     /// This event is always enabled.
     RatchetSwitch(WheelRatchetState),
+
+    /// Is emitted with analytics data if enabled via
+    /// [`HiResWheelFeatureV1::set_wheel_mode`].
+    Analytics(WheelAnalyticsData),
+}
+
+/// Represents the data of the [`HiResWheelEvent::Analytics`] event.
+///
+/// The structure of analytics data is undocumented, so the raw payload is
+/// provided alongside a best-effort decoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct WheelAnalyticsData {
+    /// The raw payload of the notification.
+    pub raw: [u8; 16],
+}
+
+impl WheelAnalyticsData {
+    /// Decodes the analytics data as wheel movement.
+    ///
+    /// This is a best-effort decoding assuming that analytics data uses the
+    /// layout of [`HiResWheelEvent::WheelMovement`], which devices observed so
+    /// far seem to do. Returns [`None`] if the payload does not match it.
+    pub fn movement(&self) -> Option<WheelMovementData> {
+        Some(WheelMovementData {
+            resolution: WheelResolution::try_from((self.raw[0] & (1 << 4)) >> 4).ok()?,
+            periods: U4::from_lo(self.raw[0]),
+            delta_vertical: i16::from_be_bytes([self.raw[1], self.raw[2]]),
+        })
+    }
 }
 
 /// Represents the data of the [`HiResWheelEvent::WheelMovement`] event.
@@ -338,3 +448,42 @@ pub struct WheelMovementData {
     /// values.
     pub delta_vertical: i16,
 }
+
+impl WheelMovementData {
+    /// Converts the vertical movement delta to ratchets, taking the
+    /// [`WheelCapabilities::multiplier`] into account if the movement was
+    /// reported in high resolution.
+    pub fn ratchets(&self, capabilities: &WheelCapabilities) -> f32 {
+        match self.resolution {
+            WheelResolution::High if capabilities.multiplier != 0 => {
+                self.delta_vertical as f32 / capabilities.multiplier as f32
+            },
+            _ => self.delta_vertical as f32,
+        }
+    }
+
+    /// Converts the vertical movement delta to the angle the wheel was rotated
+    /// by in degrees.
+    ///
+    /// Returns `0.0` if the device reports no ratchets per rotation.
+    pub fn degrees(&self, capabilities: &WheelCapabilities) -> f32 {
+        self.rotations(capabilities) * 360.0
+    }
+
+    /// Converts the vertical movement delta to the distance the surface of the
+    /// wheel moved in millimeters, based on the nominal wheel diameter.
+    ///
+    /// Returns `0.0` if the device reports no ratchets per rotation.
+    pub fn millimeters(&self, capabilities: &WheelCapabilities) -> f32 {
+        self.rotations(capabilities) * capabilities.wheel_diameter as f32 * std::f32::consts::PI
+    }
+
+    /// Converts the vertical movement delta to whole wheel rotations.
+    fn rotations(&self, capabilities: &WheelCapabilities) -> f32 {
+        if capabilities.ratches_per_rotation == 0 {
+            return 0.0;
+        }
+
+        self.ratchets(capabilities) / capabilities.ratches_per_rotation as f32
+    }
+}
@@ -0,0 +1,262 @@
+//! A lighting model shared by the `ColorLedEffects` (ID `0x8070`) and
+//! `RgbEffects` (ID `0x8071`) features.
+//!
+//! Both features configure lighting zones (called clusters by `RgbEffects`)
+//! by selecting one of the effects supported by the zone and passing it ten
+//! bytes of effect-specific parameters. The types in this module describe
+//! lighting independent of the feature a device implements and are encoded
+//! to the parameters of the respective feature by
+//! [`encode_color_led_effects`] and [`encode_rgb_effects`].
+
+use thiserror::Error;
+
+/// Represents a 24-bit RGB color.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Color {
+    /// Constructs a color from its components.
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+        }
+    }
+
+    /// Converts the color to its byte representation used in effect
+    /// parameters.
+    pub const fn to_bytes(self) -> [u8; 3] {
+        [self.red, self.green, self.blue]
+    }
+}
+
+/// Represents a lighting effect.
+///
+/// Periods are given in milliseconds and intensities in percent, where `0`
+/// lets the device choose its default intensity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "effect", rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum LedEffect {
+    /// Turns the zone off.
+    Off,
+
+    /// Shows a static color, optionally ramping up to it.
+    Static {
+        color: Color,
+        ramp: bool,
+    },
+
+    /// Pulses a color at the given speed.
+    Pulse {
+        color: Color,
+        speed: u8,
+    },
+
+    /// Cycles through all colors.
+    Cycle {
+        period: u16,
+        intensity: u8,
+    },
+
+    /// Fades a color in and out.
+    Breathe {
+        color: Color,
+        period: u16,
+        intensity: u8,
+    },
+
+    /// Shows ripples of a color whenever a key is pressed.
+    Ripple {
+        color: Color,
+        period: u16,
+    },
+}
+
+impl LedEffect {
+    /// The ID the features use to identify the effect in the effect lists of
+    /// zones.
+    pub fn id(&self) -> u16 {
+        match self {
+            Self::Off => 0x00,
+            Self::Static {
+                ..
+            } => 0x01,
+            Self::Pulse {
+                ..
+            } => 0x02,
+            Self::Cycle {
+                ..
+            } => 0x03,
+            Self::Breathe {
+                ..
+            } => 0x0a,
+            Self::Ripple {
+                ..
+            } => 0x0b,
+        }
+    }
+
+    /// Encodes the effect-specific parameters, which are laid out the same
+    /// way by both features.
+    pub fn params(&self) -> [u8; 10] {
+        let mut params = [0; 10];
+
+        match *self {
+            Self::Off => {},
+            Self::Static {
+                color,
+                ramp,
+            } => {
+                params[0..3].copy_from_slice(&color.to_bytes());
+                params[3] = if ramp {
+                    0x01
+                } else {
+                    0x02
+                };
+            },
+            Self::Pulse {
+                color,
+                speed,
+            } => {
+                params[0..3].copy_from_slice(&color.to_bytes());
+                params[3] = speed;
+            },
+            Self::Cycle {
+                period,
+                intensity,
+            } => {
+                params[5..7].copy_from_slice(&period.to_be_bytes());
+                params[7] = intensity;
+            },
+            Self::Breathe {
+                color,
+                period,
+                intensity,
+            } => {
+                params[0..3].copy_from_slice(&color.to_bytes());
+                params[3..5].copy_from_slice(&period.to_be_bytes());
+                params[6] = intensity;
+            },
+            Self::Ripple {
+                color,
+                period,
+            } => {
+                params[0..3].copy_from_slice(&color.to_bytes());
+                params[4..6].copy_from_slice(&period.to_be_bytes());
+            },
+        }
+
+        params
+    }
+}
+
+/// Represents a lighting zone of a device.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Zone {
+    /// The index of the zone or cluster.
+    pub index: u8,
+
+    /// The physical location of the zone as reported by the device.
+    pub location: u16,
+
+    /// The IDs of the effects supported by the zone, in the order of their
+    /// effect index.
+    pub effects: Vec<u16>,
+}
+
+impl Zone {
+    /// Constructs a zone from the information reported by the device.
+    pub fn new(index: u8, location: u16, effects: Vec<u16>) -> Self {
+        Self {
+            index,
+            location,
+            effects,
+        }
+    }
+
+    /// Finds the index the zone uses for an effect, if it supports it.
+    pub fn effect_index(&self, effect: &LedEffect) -> Option<u8> {
+        self.effects
+            .iter()
+            .position(|&id| id == effect.id())
+            .and_then(|index| u8::try_from(index).ok())
+    }
+
+    /// Whether the zone supports an effect.
+    pub fn supports(&self, effect: &LedEffect) -> bool {
+        self.effect_index(effect).is_some()
+    }
+}
+
+/// Represents an error that may occur when encoding a [`LedEffect`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Error)]
+#[non_exhaustive]
+pub enum LightingError {
+    /// Indicates that the zone does not support the effect.
+    #[error("zone {zone} does not support the effect {effect:#06x}")]
+    UnsupportedEffect {
+        zone: u8,
+        effect: u16,
+    },
+}
+
+/// Encodes an effect as the payload of the `setZoneEffect` function of the
+/// `ColorLedEffects` / `0x8070` feature.
+///
+/// The effect is stored persistently if `persist` is set.
+pub fn encode_color_led_effects(
+    zone: &Zone,
+    effect: &LedEffect,
+    persist: bool,
+) -> Result<[u8; 13], LightingError> {
+    encode_effect(zone, effect, persist as u8)
+}
+
+/// Encodes an effect as the payload of the `setRgbClusterEffect` function of
+/// the `RgbEffects` / `0x8071` feature, using the zone index as the cluster
+/// index.
+///
+/// The effect is stored persistently if `persist` is set.
+pub fn encode_rgb_effects(
+    zone: &Zone,
+    effect: &LedEffect,
+    persist: bool,
+) -> Result<[u8; 13], LightingError> {
+    encode_effect(zone, effect, persist as u8)
+}
+
+/// Encodes the zone index, effect index and parameters of an effect, followed
+/// by the given persistence byte.
+fn encode_effect(
+    zone: &Zone,
+    effect: &LedEffect,
+    persistence: u8,
+) -> Result<[u8; 13], LightingError> {
+    let effect_index = zone
+        .effect_index(effect)
+        .ok_or(LightingError::UnsupportedEffect {
+            zone: zone.index,
+            effect: effect.id(),
+        })?;
+
+    let mut payload = [0; 13];
+    payload[0] = zone.index;
+    payload[1] = effect_index;
+    payload[2..12].copy_from_slice(&effect.params());
+    payload[12] = persistence;
+
+    Ok(payload)
+}
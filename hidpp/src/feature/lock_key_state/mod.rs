@@ -0,0 +1,139 @@
+//! Implements the `LockKeyState` feature (ID `0x4220`) that reports the state
+//! of the lock keys of a keyboard.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `LockKeyState` / `0x4220` feature.
+///
+/// This allows rendering lock indicators for keyboards without LEDs for
+/// them.
+pub struct LockKeyStateFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<LockKeyStateEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for LockKeyStateFeature {
+    const ID: u16 = 0x4220;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::lock_key_state_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for LockKeyStateFeature {
+}
+
+impl EmittingFeature<LockKeyStateEvent> for LockKeyStateFeature {
+    fn listen(&self) -> async_channel::Receiver<LockKeyStateEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<LockKeyStateEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl LockKeyStateFeature {
+    /// Retrieves the current state of the lock keys.
+    pub async fn get_lock_key_state(&self) -> Result<LockKeyState, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(LockKeyState::from(response.extend_payload()[0]))
+    }
+}
+
+/// Represents which lock keys are currently active.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct LockKeyState {
+    pub num_lock: bool,
+    pub caps_lock: bool,
+    pub scroll_lock: bool,
+}
+
+impl From<u8> for LockKeyState {
+    fn from(value: u8) -> Self {
+        Self {
+            num_lock: value & (1 << 0) != 0,
+            caps_lock: value & (1 << 1) != 0,
+            scroll_lock: value & (1 << 2) != 0,
+        }
+    }
+}
+
+/// Represents an event emitted by the [`LockKeyStateFeature`] feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum LockKeyStateEvent {
+    /// Is emitted whenever a lock key is toggled.
+    ///
+    /// This event is always enabled.
+    StateChange(LockKeyState),
+}
@@ -1,20 +1,131 @@
 //! Specific device feature implementations.
+//!
+//! Feature implementations are grouped into cargo features, so consumers can
+//! compile only the ones they use. All groups are enabled by default:
+//!
+//! - `feat-battery`: [`battery_status`], [`battery_voltage`],
+//!   [`solar_keyboard_dashboard`] and [`unified_battery`]
+//! - `feat-config`: [`config_device_props`], [`remaining_pairings`] and
+//!   [`unique_random_id`]
+//! - `feat-controls`: [`gestures`], [`persistent_remappable_action`],
+//!   [`reprog_controls`] and [`swap_left_right_button`], as well as
+//!   [`crate::buttons`] and [`crate::remap`]
+//! - `feat-dfu`: [`dfu`], [`dfu_control`] and [`firmware_properties`]
+//! - `feat-dpi`: [`adjustable_dpi`], [`mouse_pointer`],
+//!   [`pointer_axes_orientation`], [`pointer_motion_scaling`],
+//!   [`sensor_angle_snapping`] and [`surface_tuning`]
+//! - `feat-gaming`: [`extended_adjustable_report_rate`], [`force_feedback`],
+//!   [`gaming_g_keys`] and [`gaming_m_keys`]
+//! - `feat-keyboard`: [`dual_platform`], [`fn_inversion`] and
+//!   [`lock_key_state`]
+//! - `feat-lighting`: [`backlight`], [`lighting`] and [`per_key_lighting`]
+//! - `feat-touchpad`: [`cursor_ballistic`], [`tap_enable`], [`touchpad_items`]
+//!   and [`touchpad_resolution_divider`]
+//! - `feat-wheel`: [`crown`], [`high_resolution_scrolling`], [`hires_wheel`],
+//!   [`smartshift`], [`thumbwheel`], [`vertical_scrolling`] and [`wheel_stats`]
+//! - `feat-wireless`: [`wireless_device_status`] and
+//!   [`wireless_signal_strength`]
+//!
+//! The features needed to initialize devices are always available. Features
+//! of disabled groups are still known to the [`registry`] by name, but are not
+//! instantiated.
 
 use std::{any::Any, sync::Arc};
 
-use crate::channel::HidppChannel;
-
+use crate::{channel::HidppChannel, event::Envelope};
+
+#[cfg(feature = "feat-dpi")]
+pub mod adjustable_dpi;
+#[cfg(feature = "feat-lighting")]
+pub mod backlight;
+#[cfg(feature = "feat-battery")]
+pub mod battery_status;
+#[cfg(feature = "feat-battery")]
+pub mod battery_voltage;
+#[cfg(feature = "feat-config")]
+pub mod config_device_props;
+#[cfg(feature = "feat-wheel")]
+pub mod crown;
+#[cfg(feature = "feat-touchpad")]
+pub mod cursor_ballistic;
 pub mod device_friendly_name;
 pub mod device_information;
 pub mod device_type_and_name;
+#[cfg(feature = "feat-dfu")]
+pub mod dfu;
+#[cfg(feature = "feat-dfu")]
+pub mod dfu_control;
+#[cfg(feature = "feat-keyboard")]
+pub mod dual_platform;
+#[cfg(feature = "feat-gaming")]
+pub mod extended_adjustable_report_rate;
 pub mod feature_set;
+#[cfg(feature = "feat-dfu")]
+pub mod firmware_properties;
+#[cfg(feature = "feat-keyboard")]
+pub mod fn_inversion;
+#[cfg(feature = "feat-gaming")]
+pub mod force_feedback;
+#[cfg(feature = "feat-gaming")]
+pub mod gaming_g_keys;
+#[cfg(feature = "feat-gaming")]
+pub mod gaming_m_keys;
+#[cfg(feature = "feat-controls")]
+pub mod gestures;
+#[cfg(feature = "feat-wheel")]
+pub mod high_resolution_scrolling;
+#[cfg(feature = "feat-wheel")]
 pub mod hires_wheel;
+#[cfg(feature = "feat-lighting")]
+pub mod lighting;
+#[cfg(feature = "feat-keyboard")]
+pub mod lock_key_state;
+#[cfg(feature = "feat-dpi")]
+pub mod mouse_pointer;
+#[cfg(feature = "feat-lighting")]
+pub mod per_key_lighting;
+#[cfg(feature = "feat-controls")]
+pub mod persistent_remappable_action;
+#[cfg(feature = "feat-dpi")]
+pub mod pointer_axes_orientation;
+#[cfg(feature = "feat-dpi")]
+pub mod pointer_motion_scaling;
 pub mod registry;
+#[cfg(feature = "feat-config")]
+pub mod remaining_pairings;
+#[cfg(feature = "feat-controls")]
+pub mod reprog_controls;
 pub mod root;
+#[cfg(feature = "feat-dpi")]
+pub mod sensor_angle_snapping;
+#[cfg(feature = "feat-wheel")]
 pub mod smartshift;
+#[cfg(feature = "feat-battery")]
+pub mod solar_keyboard_dashboard;
+#[cfg(feature = "feat-dpi")]
+pub mod surface_tuning;
+#[cfg(feature = "feat-controls")]
+pub mod swap_left_right_button;
+#[cfg(feature = "feat-touchpad")]
+pub mod tap_enable;
+#[cfg(feature = "feat-wheel")]
 pub mod thumbwheel;
+#[cfg(feature = "feat-touchpad")]
+pub mod touchpad_items;
+#[cfg(feature = "feat-touchpad")]
+pub mod touchpad_resolution_divider;
+#[cfg(feature = "feat-battery")]
 pub mod unified_battery;
+#[cfg(feature = "feat-config")]
+pub mod unique_random_id;
+#[cfg(feature = "feat-wheel")]
+pub mod vertical_scrolling;
+#[cfg(feature = "feat-wheel")]
+pub mod wheel_stats;
+#[cfg(feature = "feat-wireless")]
 pub mod wireless_device_status;
+#[cfg(feature = "feat-wireless")]
+pub mod wireless_signal_strength;
 
 /// Represents a concrete implementation of a HID++2.0 device feature.
 pub trait Feature: Any + Send + Sync {}
@@ -36,13 +147,17 @@ pub trait EmittingFeature<T>: Feature {
     /// Creates a receiver that is being notified whenever a new event of type
     /// `T` is emitted by the feature.
     fn listen(&self) -> async_channel::Receiver<T>;
+
+    /// Creates a receiver like [`Self::listen`], but wraps every event in an
+    /// [`Envelope`] carrying the time it was received and its raw message.
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<T>>;
 }
 
 /// A bitfield describing some properties of a feature.
 ///
 /// Documentation is taken from <https://drive.google.com/file/d/1ULmw9uJL8b8iwwUo5xjSS9F5Zvno-86y/view>.
 #[derive(Clone, Copy, Hash, Debug)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub struct FeatureType {
     /// An obsolete feature is a feature that has been replaced by a newer one,
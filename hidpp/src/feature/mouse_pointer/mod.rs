@@ -0,0 +1,110 @@
+//! Implements the `MousePointer` feature (ID `0x2200`) that describes the
+//! pointer of legacy mice predating
+//! [`AdjustableDpiFeature`](super::adjustable_dpi::AdjustableDpiFeature).
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `MousePointer` / `0x2200` feature.
+pub struct MousePointerFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for MousePointerFeature {
+    const ID: u16 = 0x2200;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for MousePointerFeature {
+}
+
+impl MousePointerFeature {
+    /// Retrieves the resolution of the sensor and how the host should treat
+    /// its movement.
+    pub async fn get_mouse_pointer_info(&self) -> Result<MousePointerInfo, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+        let flags = payload[2];
+
+        Ok(MousePointerInfo {
+            dpi: u16::from_be_bytes([payload[0], payload[1]]),
+            acceleration: PointerAcceleration::try_from(flags & 0x03).unwrap(),
+            suggest_os_ballistics: flags & (1 << 2) != 0,
+            suggest_vertical_orientation: flags & (1 << 3) != 0,
+        })
+    }
+}
+
+/// Represents the pointer of a mouse as reported by
+/// [`MousePointerFeature::get_mouse_pointer_info`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct MousePointerInfo {
+    /// The resolution of the sensor in DPI.
+    pub dpi: u16,
+
+    /// The acceleration the device suggests the host to apply.
+    pub acceleration: PointerAcceleration,
+
+    /// Whether the device suggests the host to use its own ballistics
+    /// instead of a custom curve.
+    pub suggest_os_ballistics: bool,
+
+    /// Whether the device suggests the host to use a vertical orientation,
+    /// like for vertical mice.
+    pub suggest_vertical_orientation: bool,
+}
+
+/// Represents a pointer acceleration suggested by a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum PointerAcceleration {
+    None = 0,
+    Low = 1,
+    Medium = 2,
+    High = 3,
+}
@@ -0,0 +1,176 @@
+//! Implements the `PerKeyLighting2` feature (ID `0x8081`) that allows setting
+//! the color of individual keys of keyboards.
+//!
+//! Colors are written to a frame buffer on the device first and only shown
+//! once the frame is committed via [`PerKeyLightingFeature::commit_frame`],
+//! so many keys can be updated at once without visible tearing.
+
+use std::sync::{Arc, Weak};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature, lighting::Color},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// The number of zones whose presence is reported by a single call of the
+/// `getInfo` function.
+const ZONES_PER_PAGE: u16 = 14 * 8;
+
+/// The number of zones [`PerKeyLightingFeature::set_zones`] sends in a
+/// single message.
+const INDIVIDUAL_ZONES_PER_MESSAGE: usize = 4;
+
+/// The number of colors [`PerKeyLightingFeature::set_consecutive_zones`]
+/// sends in a single message.
+const CONSECUTIVE_ZONES_PER_MESSAGE: usize = 5;
+
+/// Implements the `PerKeyLighting2` / `0x8081` feature.
+///
+/// The meaning of the first two bytes of the `getInfo` response is unknown, so
+/// they are skipped when reading the present zones.
+pub struct PerKeyLightingFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for PerKeyLightingFeature {
+    const ID: u16 = 0x8081;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for PerKeyLightingFeature {
+}
+
+impl PerKeyLightingFeature {
+    /// Retrieves the IDs of the zones present on the device, in ascending
+    /// order.
+    ///
+    /// Zone IDs usually correspond to the HID usage of the key the zone
+    /// belongs to.
+    pub async fn get_zones(&self) -> Result<Vec<u8>, Hidpp20Error> {
+        let mut zones = Vec::new();
+
+        for page in 0..=(u8::MAX as u16 / ZONES_PER_PAGE) {
+            let response = self.request(0, &[0x00, page as u8]).await?;
+            let payload = response.extend_payload();
+
+            for (i, byte) in payload[2..].iter().enumerate() {
+                for bit in 0..8 {
+                    let zone = page * ZONES_PER_PAGE + i as u16 * 8 + bit;
+                    if byte & (1 << bit) != 0 && zone <= u8::MAX as u16 {
+                        zones.push(zone as u8);
+                    }
+                }
+            }
+        }
+
+        Ok(zones)
+    }
+
+    /// Writes the colors of individual zones to the frame buffer.
+    ///
+    /// Up to four zones are sent per message, so the zones are split into as
+    /// many messages as needed.
+    pub async fn set_zones(&self, zones: &[(u8, Color)]) -> Result<(), Hidpp20Error> {
+        for chunk in zones.chunks(INDIVIDUAL_ZONES_PER_MESSAGE) {
+            let payload = chunk
+                .iter()
+                .flat_map(|(zone, color)| {
+                    let [red, green, blue] = color.to_bytes();
+                    [*zone, red, green, blue]
+                })
+                .collect::<Vec<_>>();
+
+            self.request(1, &payload).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the colors of consecutive zones to the frame buffer, starting at
+    /// the zone `first`.
+    ///
+    /// This is more efficient than [`Self::set_zones`] for updating whole
+    /// rows of keys, as up to five zones are sent per message.
+    pub async fn set_consecutive_zones(
+        &self,
+        first: u8,
+        colors: &[Color],
+    ) -> Result<(), Hidpp20Error> {
+        for (i, chunk) in colors.chunks(CONSECUTIVE_ZONES_PER_MESSAGE).enumerate() {
+            let Some(start) = u8::try_from(i * CONSECUTIVE_ZONES_PER_MESSAGE)
+                .ok()
+                .and_then(|offset| first.checked_add(offset))
+            else {
+                break;
+            };
+
+            let mut payload = vec![start];
+            payload.extend(chunk.iter().flat_map(|color| color.to_bytes()));
+
+            self.request(2, &payload).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a single color to all zones from `first` to `last`, inclusive,
+    /// to the frame buffer.
+    pub async fn set_zone_range(
+        &self,
+        first: u8,
+        last: u8,
+        color: Color,
+    ) -> Result<(), Hidpp20Error> {
+        let [red, green, blue] = color.to_bytes();
+        self.request(5, &[first, last, red, green, blue]).await?;
+
+        Ok(())
+    }
+
+    /// Shows the colors written to the frame buffer since the last commit.
+    pub async fn commit_frame(&self) -> Result<(), Hidpp20Error> {
+        self.request(7, &[]).await?;
+
+        Ok(())
+    }
+
+    /// Sends a long request to a function of the feature, padding the payload
+    /// with zeros.
+    async fn request(&self, function_id: u8, payload: &[u8]) -> Result<v20::Message, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+
+        let mut data = [0; 16];
+        data[..payload.len()].copy_from_slice(payload);
+
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Long(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(function_id),
+                    software_id: chan.get_sw_id(),
+                },
+                data,
+            ),
+        )
+        .await
+    }
+}
@@ -0,0 +1,247 @@
+//! Implements the `PersistentRemappableAction` feature (ID `0x1c00`) that
+//! allows assigning arbitrary actions to controls, stored on the device.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `PersistentRemappableAction` / `0x1c00` feature.
+///
+/// Controls are identified by the same control IDs (CIDs) used by the
+/// [`ReprogControlsFeature`](super::reprog_controls::ReprogControlsFeature).
+pub struct PersistentRemappableActionFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for PersistentRemappableActionFeature {
+    const ID: u16 = 0x1c00;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for PersistentRemappableActionFeature {
+}
+
+impl PersistentRemappableActionFeature {
+    /// Retrieves the amount of controls that can be remapped.
+    pub async fn get_control_count(&self) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Retrieves the CIDs of all controls that can be remapped.
+    pub async fn get_controls(&self) -> Result<Vec<u16>, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let count = self.get_control_count().await?;
+
+        let mut controls = Vec::with_capacity(count as usize);
+        while controls.len() < count as usize {
+            let response = chan
+                .send_v20_feature(
+                    Self::ID,
+                    v20::Message::Short(
+                        v20::MessageHeader {
+                            device_index: self.device_index,
+                            feature_index: self.feature_index,
+                            function_id: U4::from_lo(2),
+                            software_id: chan.get_sw_id(),
+                        },
+                        [controls.len() as u8, 0x00, 0x00],
+                    ),
+                )
+                .await?;
+
+            let remaining = count as usize - controls.len();
+            let before = controls.len();
+            controls.extend(
+                response
+                    .extend_payload()
+                    .chunks_exact(2)
+                    .map(|x| u16::from_be_bytes([x[0], x[1]]))
+                    .take(remaining),
+            );
+
+            if controls.len() == before {
+                return Err(Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID));
+            }
+        }
+
+        Ok(controls)
+    }
+
+    /// Retrieves the action currently assigned to a control.
+    pub async fn get_action(&self, cid: u16) -> Result<RemappableAction, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let cid_bytes = cid.to_be_bytes();
+
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(3),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [cid_bytes[0], cid_bytes[1], 0xff],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(RemappableAction {
+            kind: ActionKind::try_from(payload[0]).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
+            value: u16::from_be_bytes([payload[1], payload[2]]),
+            modifiers: payload[3],
+        })
+    }
+
+    /// Assigns an action to a control.
+    pub async fn set_action(&self, cid: u16, action: RemappableAction) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let cid_bytes = cid.to_be_bytes();
+        let value_bytes = action.value.to_be_bytes();
+
+        let mut payload = [0; 16];
+        payload[..7].copy_from_slice(&[
+            cid_bytes[0],
+            cid_bytes[1],
+            0xff,
+            action.kind.into(),
+            value_bytes[0],
+            value_bytes[1],
+            action.modifiers,
+        ]);
+
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Long(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(4),
+                    software_id: chan.get_sw_id(),
+                },
+                payload,
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Restores the default action of a control.
+    pub async fn reset_action(&self, cid: u16) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let cid_bytes = cid.to_be_bytes();
+
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(5),
+                    software_id: chan.get_sw_id(),
+                },
+                [cid_bytes[0], cid_bytes[1], 0xff],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents an action assigned to a control.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct RemappableAction {
+    pub kind: ActionKind,
+
+    /// The value of the action, depending on its kind:
+    ///
+    /// - [`ActionKind::Key`]: the keyboard page HID usage
+    /// - [`ActionKind::Mouse`]: a bit mask of mouse buttons, where bit `n`
+    ///   corresponds to button `n + 1`
+    /// - [`ActionKind::Consumer`]: the consumer page HID usage
+    pub value: u16,
+
+    /// The keyboard modifiers pressed along with a key, using the bit layout
+    /// of HID keyboard reports.
+    pub modifiers: u8,
+}
+
+impl RemappableAction {
+    /// Constructs an action.
+    pub fn new(kind: ActionKind, value: u16, modifiers: u8) -> Self {
+        Self {
+            kind,
+            value,
+            modifiers,
+        }
+    }
+}
+
+/// Represents the kind of a [`RemappableAction`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum ActionKind {
+    /// The control does nothing.
+    Empty = 0x00,
+    Key = 0x01,
+    Mouse = 0x02,
+    HorizontalDisplacement = 0x03,
+    VerticalDisplacement = 0x04,
+    VerticalScroll = 0x05,
+    HorizontalScroll = 0x06,
+    Consumer = 0x07,
+    Internal = 0x08,
+    Power = 0x09,
+}
@@ -0,0 +1,146 @@
+//! Implements the `PointerAxesOrientation` feature (ID `0x2006`) that allows
+//! inverting and rotating the axes of the pointer, like for left-handed use
+//! or vertical mice.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `PointerAxesOrientation` / `0x2006` feature.
+///
+/// Only the three lowest bits of the orientation are known. Setting an
+/// orientation clears all other bits.
+pub struct PointerAxesOrientationFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for PointerAxesOrientationFeature {
+    const ID: u16 = 0x2006;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for PointerAxesOrientationFeature {
+}
+
+impl PointerAxesOrientationFeature {
+    /// Retrieves the current orientation of the pointer axes.
+    pub async fn get_orientation(&self) -> Result<AxesOrientation, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(AxesOrientation::from(response.extend_payload()[0]))
+    }
+
+    /// Sets the orientation of the pointer axes.
+    pub async fn set_orientation(&self, orientation: AxesOrientation) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(1),
+                    software_id: chan.get_sw_id(),
+                },
+                [orientation.into(), 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents the orientation of the pointer axes.
+///
+/// The axes are swapped before they are inverted, so a clockwise rotation by
+/// 90 degrees is represented by swapping the axes and inverting the X axis.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct AxesOrientation {
+    /// Whether the X axis is inverted.
+    pub invert_x: bool,
+
+    /// Whether the Y axis is inverted.
+    pub invert_y: bool,
+
+    /// Whether the X and Y axes are swapped.
+    pub swap_axes: bool,
+}
+
+impl AxesOrientation {
+    /// Creates a new orientation from its individual parts.
+    pub fn new(invert_x: bool, invert_y: bool, swap_axes: bool) -> Self {
+        Self {
+            invert_x,
+            invert_y,
+            swap_axes,
+        }
+    }
+}
+
+impl From<u8> for AxesOrientation {
+    fn from(value: u8) -> Self {
+        Self {
+            invert_x: value & (1 << 0) != 0,
+            invert_y: value & (1 << 1) != 0,
+            swap_axes: value & (1 << 2) != 0,
+        }
+    }
+}
+
+impl From<AxesOrientation> for u8 {
+    fn from(value: AxesOrientation) -> Self {
+        let mut raw = 0;
+
+        if value.invert_x {
+            raw |= 1 << 0
+        }
+        if value.invert_y {
+            raw |= 1 << 1
+        }
+        if value.swap_axes {
+            raw |= 1 << 2
+        }
+
+        raw
+    }
+}
@@ -0,0 +1,93 @@
+//! Implements the `PointerMotionScaling` feature (ID `0x2205`) that allows
+//! scaling the pointer speed on the device, independent of the settings of
+//! the host.
+
+use std::sync::{Arc, Weak};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// The pointer speed that does not scale the pointer motion.
+pub const DEFAULT_POINTER_SPEED: u16 = 0x0100;
+
+/// Implements the `PointerMotionScaling` / `0x2205` feature.
+pub struct PointerMotionScalingFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for PointerMotionScalingFeature {
+    const ID: u16 = 0x2205;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for PointerMotionScalingFeature {
+}
+
+impl PointerMotionScalingFeature {
+    /// Retrieves the current pointer speed.
+    ///
+    /// The speed is a fixed-point number with 8 fractional bits, so
+    /// [`DEFAULT_POINTER_SPEED`] does not scale the pointer motion at all.
+    pub async fn get_pointer_speed(&self) -> Result<u16, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+        Ok(u16::from_be_bytes([payload[0], payload[1]]))
+    }
+
+    /// Sets the pointer speed.
+    ///
+    /// See [`Self::get_pointer_speed`] for the format of the speed.
+    pub async fn set_pointer_speed(&self, speed: u16) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let [hi, lo] = speed.to_be_bytes();
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(1),
+                    software_id: chan.get_sw_id(),
+                },
+                [hi, lo, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -1,27 +1,119 @@
 //! Maintains a registry of well-known HID++2.0 features and their default
 //! implementations.
 
-use std::{any::TypeId, collections::HashMap, sync::Arc};
+use std::{
+    any::TypeId,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+    ptr,
+    sync::Arc,
+};
 
 use lazy_static::lazy_static;
 
-use super::Feature;
-use crate::{
-    channel::HidppChannel,
-    feature::{
-        CreatableFeature,
-        device_friendly_name::DeviceFriendlyNameFeature,
-        device_information::DeviceInformationFeature,
-        device_type_and_name::DeviceTypeAndNameFeature,
-        feature_set::FeatureSetFeature,
-        hires_wheel::HiResWheelFeature,
-        root::RootFeature,
-        smartshift::SmartShiftFeature,
-        thumbwheel::ThumbwheelFeature,
-        unified_battery::UnifiedBatteryFeature,
-        wireless_device_status::WirelessDeviceStatusFeature,
-    },
+#[cfg(feature = "feat-dpi")]
+use super::adjustable_dpi::AdjustableDpiFeature;
+#[cfg(feature = "feat-lighting")]
+use super::backlight::Backlight3Feature;
+#[cfg(feature = "feat-battery")]
+use super::battery_status::BatteryStatusFeature;
+#[cfg(feature = "feat-battery")]
+use super::battery_voltage::BatteryVoltageFeature;
+#[cfg(feature = "feat-config")]
+use super::config_device_props::ConfigDevicePropsFeature;
+#[cfg(feature = "feat-wheel")]
+use super::crown::CrownFeature;
+#[cfg(feature = "feat-touchpad")]
+use super::cursor_ballistic::CursorBallisticFeature;
+#[cfg(feature = "feat-dfu")]
+use super::dfu::DfuFeature;
+#[cfg(feature = "feat-dfu")]
+use super::dfu_control::{
+    DfuControlBoltFeature,
+    DfuControlLegacyFeature,
+    DfuControlSignedFeature,
+    DfuControlUnsignedFeature,
+};
+#[cfg(feature = "feat-keyboard")]
+use super::dual_platform::DualPlatformFeature;
+#[cfg(feature = "feat-gaming")]
+use super::extended_adjustable_report_rate::ExtendedAdjustableReportRateFeature;
+#[cfg(feature = "feat-dfu")]
+use super::firmware_properties::FirmwarePropertiesFeature;
+#[cfg(feature = "feat-keyboard")]
+use super::fn_inversion::{
+    FnInversionFeature,
+    FnInversionForMultiHostDevicesFeature,
+    FnInversionWithDefaultStateFeature,
+};
+#[cfg(feature = "feat-gaming")]
+use super::force_feedback::ForceFeedbackFeature;
+#[cfg(feature = "feat-gaming")]
+use super::gaming_g_keys::GamingGKeysFeature;
+#[cfg(feature = "feat-gaming")]
+use super::gaming_m_keys::GamingMKeysFeature;
+#[cfg(feature = "feat-controls")]
+use super::gestures::GesturesFeature;
+#[cfg(feature = "feat-wheel")]
+use super::high_resolution_scrolling::HighResolutionScrollingFeature;
+#[cfg(feature = "feat-wheel")]
+use super::hires_wheel::{HiResWheelFeature, HiResWheelFeatureV1};
+#[cfg(feature = "feat-keyboard")]
+use super::lock_key_state::LockKeyStateFeature;
+#[cfg(feature = "feat-dpi")]
+use super::mouse_pointer::MousePointerFeature;
+#[cfg(feature = "feat-lighting")]
+use super::per_key_lighting::PerKeyLightingFeature;
+#[cfg(feature = "feat-controls")]
+use super::persistent_remappable_action::PersistentRemappableActionFeature;
+#[cfg(feature = "feat-dpi")]
+use super::pointer_axes_orientation::PointerAxesOrientationFeature;
+#[cfg(feature = "feat-dpi")]
+use super::pointer_motion_scaling::PointerMotionScalingFeature;
+#[cfg(feature = "feat-config")]
+use super::remaining_pairings::RemainingPairingsFeature;
+#[cfg(feature = "feat-controls")]
+use super::reprog_controls::ReprogControlsFeature;
+#[cfg(feature = "feat-dpi")]
+use super::sensor_angle_snapping::SensorAngleSnappingFeature;
+#[cfg(feature = "feat-wheel")]
+use super::smartshift::SmartShiftFeature;
+#[cfg(feature = "feat-battery")]
+use super::solar_keyboard_dashboard::SolarKeyboardDashboardFeature;
+#[cfg(feature = "feat-dpi")]
+use super::surface_tuning::SurfaceTuningFeature;
+#[cfg(feature = "feat-controls")]
+use super::swap_left_right_button::SwapLeftRightButtonFeature;
+#[cfg(feature = "feat-touchpad")]
+use super::tap_enable::{TapEnableExtendedFeature, TapEnableFeature};
+#[cfg(feature = "feat-wheel")]
+use super::thumbwheel::ThumbwheelFeature;
+#[cfg(feature = "feat-touchpad")]
+use super::touchpad_items::{TouchpadFwItemsFeature, TouchpadSwItemsFeature};
+#[cfg(feature = "feat-touchpad")]
+use super::touchpad_resolution_divider::TouchpadResolutionDividerFeature;
+#[cfg(feature = "feat-battery")]
+use super::unified_battery::{UnifiedBatteryFeature, UnifiedBatteryFeatureV1};
+#[cfg(feature = "feat-config")]
+use super::unique_random_id::UniqueRandomIdFeature;
+#[cfg(feature = "feat-wheel")]
+use super::vertical_scrolling::VerticalScrollingFeature;
+#[cfg(feature = "feat-wheel")]
+use super::wheel_stats::WheelStatsFeature;
+#[cfg(feature = "feat-wireless")]
+use super::wireless_device_status::WirelessDeviceStatusFeature;
+#[cfg(feature = "feat-wireless")]
+use super::wireless_signal_strength::WirelessSignalStrengthFeature;
+use super::{
+    CreatableFeature,
+    Feature,
+    device_friendly_name::DeviceFriendlyNameFeature,
+    device_information::DeviceInformationFeature,
+    device_type_and_name::DeviceTypeAndNameFeature,
+    feature_set::{FeatureSetFeature, FeatureSetFeatureV1, FeatureSetFeatureV2},
+    root::RootFeature,
 };
+use crate::channel::HidppChannel;
 
 /// Represents a function that creates a new dynamically sized feature
 /// implementation.
@@ -30,7 +122,11 @@ pub type FeatureImplProducer =
 
 /// Represents a known feature implementation starting from a specific feature
 /// version.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+///
+/// Implementations are compared by the addresses of their producers. These are
+/// not guaranteed to be unique, so two registrations of the same producer may
+/// compare unequal.
+#[derive(Clone, Copy, Debug)]
 pub struct FeatureVersion {
     /// The minimum feature version the implementation supports.
     pub starting_version: u8,
@@ -39,6 +135,23 @@ pub struct FeatureVersion {
     pub producer: FeatureImplProducer,
 }
 
+impl PartialEq for FeatureVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.starting_version == other.starting_version
+            && ptr::fn_addr_eq(self.producer, other.producer)
+    }
+}
+
+impl Eq for FeatureVersion {
+}
+
+impl Hash for FeatureVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.starting_version.hash(state);
+        (self.producer as usize).hash(state);
+    }
+}
+
 /// Represents a known HID++2.0 device feature.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct KnownFeature {
@@ -81,6 +194,22 @@ fn new_dyn<F: CreatableFeature>(
     )
 }
 
+/// Expands to the implementations of a feature if the cargo feature of its
+/// group is enabled, and to no implementations otherwise.
+macro_rules! implementations {
+    ($group:literal, $($feature:ty),+) => {{
+        #[cfg(feature = $group)]
+        let versions: &'static [FeatureVersion] = &[$(FeatureVersion {
+            starting_version: <$feature>::STARTING_VERSION,
+            producer: new_dyn::<$feature>,
+        }),+];
+        #[cfg(not(feature = $group))]
+        let versions: &'static [FeatureVersion] = &[];
+
+        versions
+    }};
+}
+
 lazy_static! {
     static ref KNOWN_FEATURES: HashMap<u16, KnownFeature> = HashMap::from([
         (0x0000, KnownFeature {
@@ -92,10 +221,20 @@ lazy_static! {
         }),
         (0x0001, KnownFeature {
             name: "FeatureSet",
-            versions: &[FeatureVersion {
-                starting_version: FeatureSetFeature::STARTING_VERSION,
-                producer: new_dyn::<FeatureSetFeature>
-            }]
+            versions: &[
+                FeatureVersion {
+                    starting_version: FeatureSetFeature::STARTING_VERSION,
+                    producer: new_dyn::<FeatureSetFeature>
+                },
+                FeatureVersion {
+                    starting_version: FeatureSetFeatureV1::STARTING_VERSION,
+                    producer: new_dyn::<FeatureSetFeatureV1>
+                },
+                FeatureVersion {
+                    starting_version: FeatureSetFeatureV2::STARTING_VERSION,
+                    producer: new_dyn::<FeatureSetFeatureV2>
+                }
+            ]
         }),
         (0x0002, KnownFeature {
             name: "FeatureInfo",
@@ -140,7 +279,7 @@ lazy_static! {
         }),
         (0x0021, KnownFeature {
             name: "UniqueRandomId",
-            versions: &[]
+            versions: implementations!("feat-config", UniqueRandomIdFeature)
         }),
         (0x0030, KnownFeature {
             name: "TargetSoftware",
@@ -148,27 +287,27 @@ lazy_static! {
         }),
         (0x0080, KnownFeature {
             name: "WirelessSignalStrength",
-            versions: &[]
+            versions: implementations!("feat-wireless", WirelessSignalStrengthFeature)
         }),
         (0x00c0, KnownFeature {
             name: "DfuControlLegacy",
-            versions: &[]
+            versions: implementations!("feat-dfu", DfuControlLegacyFeature)
         }),
         (0x00c1, KnownFeature {
             name: "DfuControlUnsigned",
-            versions: &[]
+            versions: implementations!("feat-dfu", DfuControlUnsignedFeature)
         }),
         (0x00c2, KnownFeature {
             name: "DfuControlSigned",
-            versions: &[]
+            versions: implementations!("feat-dfu", DfuControlSignedFeature)
         }),
         (0x00c3, KnownFeature {
             name: "DfuControlBolt",
-            versions: &[]
+            versions: implementations!("feat-dfu", DfuControlBoltFeature)
         }),
         (0x00d0, KnownFeature {
             name: "Dfu",
-            versions: &[]
+            versions: implementations!("feat-dfu", DfuFeature)
         }),
         (0x00d1, KnownFeature {
             name: "DfuResumable",
@@ -176,18 +315,19 @@ lazy_static! {
         }),
         (0x1000, KnownFeature {
             name: "BatteryStatus",
-            versions: &[]
+            versions: implementations!("feat-battery", BatteryStatusFeature)
         }),
         (0x1001, KnownFeature {
             name: "BatteryVoltage",
-            versions: &[]
+            versions: implementations!("feat-battery", BatteryVoltageFeature)
         }),
         (0x1004, KnownFeature {
             name: "UnifiedBattery",
-            versions: &[FeatureVersion {
-                starting_version: UnifiedBatteryFeature::STARTING_VERSION,
-                producer: new_dyn::<UnifiedBatteryFeature>
-            }]
+            versions: implementations!(
+                "feat-battery",
+                UnifiedBatteryFeature,
+                UnifiedBatteryFeatureV1
+            )
         }),
         (0x1010, KnownFeature {
             name: "ChargingControl",
@@ -211,7 +351,7 @@ lazy_static! {
         }),
         (0x1806, KnownFeature {
             name: "ConfigDeviceProps",
-            versions: &[]
+            versions: implementations!("feat-config", ConfigDevicePropsFeature)
         }),
         (0x1814, KnownFeature {
             name: "ChangeHost",
@@ -231,7 +371,7 @@ lazy_static! {
         }),
         (0x1983, KnownFeature {
             name: "Backlight3",
-            versions: &[]
+            versions: implementations!("feat-lighting", Backlight3Feature)
         }),
         (0x1990, KnownFeature {
             name: "Illumination",
@@ -263,7 +403,7 @@ lazy_static! {
         }),
         (0x1b04, KnownFeature {
             name: "ReprogControls5",
-            versions: &[]
+            versions: implementations!("feat-controls", ReprogControlsFeature)
         }),
         (0x1bc0, KnownFeature {
             name: "ReportHidUsages",
@@ -271,22 +411,19 @@ lazy_static! {
         }),
         (0x1c00, KnownFeature {
             name: "PersistentRemappableAction",
-            versions: &[]
+            versions: implementations!("feat-controls", PersistentRemappableActionFeature)
         }),
         (0x1d4b, KnownFeature {
             name: "WirelessDeviceStatus",
-            versions: &[FeatureVersion {
-                starting_version: WirelessDeviceStatusFeature::STARTING_VERSION,
-                producer: new_dyn::<WirelessDeviceStatusFeature>
-            }]
+            versions: implementations!("feat-wireless", WirelessDeviceStatusFeature)
         }),
         (0x1df0, KnownFeature {
             name: "RemainingPairings",
-            versions: &[]
+            versions: implementations!("feat-config", RemainingPairingsFeature)
         }),
         (0x1f1f, KnownFeature {
             name: "FirmwareProperties",
-            versions: &[]
+            versions: implementations!("feat-dfu", FirmwarePropertiesFeature)
         }),
         (0x1f20, KnownFeature {
             name: "AdcMeasurement",
@@ -294,7 +431,7 @@ lazy_static! {
         }),
         (0x2001, KnownFeature {
             name: "SwapLeftRightButton",
-            versions: &[]
+            versions: implementations!("feat-controls", SwapLeftRightButtonFeature)
         }),
         (0x2005, KnownFeature {
             name: "ButtonSwapCancel",
@@ -302,18 +439,15 @@ lazy_static! {
         }),
         (0x2006, KnownFeature {
             name: "PointerAxesOrientation",
-            versions: &[]
+            versions: implementations!("feat-dpi", PointerAxesOrientationFeature)
         }),
         (0x2100, KnownFeature {
             name: "VerticalScrolling",
-            versions: &[]
+            versions: implementations!("feat-wheel", VerticalScrollingFeature)
         }),
         (0x2110, KnownFeature {
             name: "SmartShiftWheel",
-            versions: &[FeatureVersion {
-                starting_version: SmartShiftFeature::STARTING_VERSION,
-                producer: new_dyn::<SmartShiftFeature>
-            }]
+            versions: implementations!("feat-wheel", SmartShiftFeature)
         }),
         (0x2111, KnownFeature {
             name: "SmartShiftWheelEnhanced",
@@ -321,14 +455,11 @@ lazy_static! {
         }),
         (0x2120, KnownFeature {
             name: "HighResolutionScrolling",
-            versions: &[]
+            versions: implementations!("feat-wheel", HighResolutionScrollingFeature)
         }),
         (0x2121, KnownFeature {
             name: "HiResWheel",
-            versions: &[FeatureVersion {
-                starting_version: HiResWheelFeature::STARTING_VERSION,
-                producer: new_dyn::<HiResWheelFeature>
-            }]
+            versions: implementations!("feat-wheel", HiResWheelFeature, HiResWheelFeatureV1)
         }),
         (0x2130, KnownFeature {
             name: "RatchetWheel",
@@ -336,18 +467,15 @@ lazy_static! {
         }),
         (0x2150, KnownFeature {
             name: "Thumbwheel",
-            versions: &[FeatureVersion {
-                starting_version: ThumbwheelFeature::STARTING_VERSION,
-                producer: new_dyn::<ThumbwheelFeature>
-            }]
+            versions: implementations!("feat-wheel", ThumbwheelFeature)
         }),
         (0x2200, KnownFeature {
             name: "MousePointer",
-            versions: &[]
+            versions: implementations!("feat-dpi", MousePointerFeature)
         }),
         (0x2201, KnownFeature {
             name: "AdjustableDpi",
-            versions: &[]
+            versions: implementations!("feat-dpi", AdjustableDpiFeature)
         }),
         (0x2202, KnownFeature {
             name: "ExtendedAdjustableDpi",
@@ -355,15 +483,15 @@ lazy_static! {
         }),
         (0x2205, KnownFeature {
             name: "PointerMotionScaling",
-            versions: &[]
+            versions: implementations!("feat-dpi", PointerMotionScalingFeature)
         }),
         (0x2230, KnownFeature {
             name: "SensorAngleSnapping",
-            versions: &[]
+            versions: implementations!("feat-dpi", SensorAngleSnappingFeature)
         }),
         (0x2240, KnownFeature {
             name: "SurfaceTuning",
-            versions: &[]
+            versions: implementations!("feat-dpi", SurfaceTuningFeature)
         }),
         (0x2250, KnownFeature {
             name: "XyStats",
@@ -371,7 +499,7 @@ lazy_static! {
         }),
         (0x2251, KnownFeature {
             name: "WheelStats",
-            versions: &[]
+            versions: implementations!("feat-wheel", WheelStatsFeature)
         }),
         (0x2400, KnownFeature {
             name: "HybridTrackingEngine",
@@ -379,15 +507,15 @@ lazy_static! {
         }),
         (0x40a0, KnownFeature {
             name: "FnInversion",
-            versions: &[]
+            versions: implementations!("feat-keyboard", FnInversionFeature)
         }),
         (0x40a2, KnownFeature {
             name: "FnInversionWithDefaultState",
-            versions: &[]
+            versions: implementations!("feat-keyboard", FnInversionWithDefaultStateFeature)
         }),
         (0x40a3, KnownFeature {
             name: "FnInversionForMultiHostDevices",
-            versions: &[]
+            versions: implementations!("feat-keyboard", FnInversionForMultiHostDevicesFeature)
         }),
         (0x4100, KnownFeature {
             name: "Encryption",
@@ -395,11 +523,11 @@ lazy_static! {
         }),
         (0x4220, KnownFeature {
             name: "LockKeyState",
-            versions: &[]
+            versions: implementations!("feat-keyboard", LockKeyStateFeature)
         }),
         (0x4301, KnownFeature {
             name: "SolarKeyboardDashboard",
-            versions: &[]
+            versions: implementations!("feat-battery", SolarKeyboardDashboardFeature)
         }),
         (0x4520, KnownFeature {
             name: "KeyboardLayout",
@@ -415,7 +543,7 @@ lazy_static! {
         }),
         (0x4530, KnownFeature {
             name: "DualPlatform",
-            versions: &[]
+            versions: implementations!("feat-keyboard", DualPlatformFeature)
         }),
         (0x4531, KnownFeature {
             name: "MultiPlatform",
@@ -427,15 +555,15 @@ lazy_static! {
         }),
         (0x4600, KnownFeature {
             name: "Crown",
-            versions: &[]
+            versions: implementations!("feat-wheel", CrownFeature)
         }),
         (0x6010, KnownFeature {
             name: "TouchpadFwItems",
-            versions: &[]
+            versions: implementations!("feat-touchpad", TouchpadFwItemsFeature)
         }),
         (0x6011, KnownFeature {
             name: "TouchpadSwItems",
-            versions: &[]
+            versions: implementations!("feat-touchpad", TouchpadSwItemsFeature)
         }),
         (0x6012, KnownFeature {
             name: "TouchpadWin8FwItems",
@@ -443,19 +571,19 @@ lazy_static! {
         }),
         (0x6020, KnownFeature {
             name: "TapEnable",
-            versions: &[]
+            versions: implementations!("feat-touchpad", TapEnableFeature)
         }),
         (0x6021, KnownFeature {
             name: "TapEnableExtended",
-            versions: &[]
+            versions: implementations!("feat-touchpad", TapEnableExtendedFeature)
         }),
         (0x6030, KnownFeature {
             name: "CursorBallistic",
-            versions: &[]
+            versions: implementations!("feat-touchpad", CursorBallisticFeature)
         }),
         (0x6040, KnownFeature {
             name: "TouchpadResolutionDivider",
-            versions: &[]
+            versions: implementations!("feat-touchpad", TouchpadResolutionDividerFeature)
         }),
         (0x6100, KnownFeature {
             name: "TouchpadRawXy",
@@ -475,15 +603,15 @@ lazy_static! {
         }),
         (0x6501, KnownFeature {
             name: "Gestures2",
-            versions: &[]
+            versions: implementations!("feat-controls", GesturesFeature)
         }),
         (0x8010, KnownFeature {
             name: "GamingGKeys",
-            versions: &[]
+            versions: implementations!("feat-gaming", GamingGKeysFeature)
         }),
         (0x8020, KnownFeature {
             name: "GamingMKeys",
-            versions: &[]
+            versions: implementations!("feat-gaming", GamingMKeysFeature)
         }),
         (0x8030, KnownFeature {
             name: "MacroRecord",
@@ -499,7 +627,7 @@ lazy_static! {
         }),
         (0x8061, KnownFeature {
             name: "ExtendedAdjustableReportRate",
-            versions: &[]
+            versions: implementations!("feat-gaming", ExtendedAdjustableReportRateFeature)
         }),
         (0x8070, KnownFeature {
             name: "ColorLedEffects",
@@ -515,7 +643,7 @@ lazy_static! {
         }),
         (0x8081, KnownFeature {
             name: "PerKeyLighting2",
-            versions: &[]
+            versions: implementations!("feat-lighting", PerKeyLightingFeature)
         }),
         (0x8090, KnownFeature {
             name: "ModeStatus",
@@ -539,7 +667,7 @@ lazy_static! {
         }),
         (0x8123, KnownFeature {
             name: "ForceFeedback",
-            versions: &[]
+            versions: implementations!("feat-gaming", ForceFeedbackFeature)
         }),
         (0x8300, KnownFeature {
             name: "Sidetone",
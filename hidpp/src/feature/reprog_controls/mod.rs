@@ -0,0 +1,459 @@
+//! Implements the `ReprogControls5` feature (ID `0x1b04`) that allows
+//! remapping and diverting the buttons and keys of a device.
+//!
+//! Starting with version 4, controls can additionally force raw XY
+//! reporting, report the movement of the wheel while they are held down and
+//! report analytics events for presses of controls that are not diverted.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use futures::{Stream, StreamExt, stream};
+
+use crate::{
+    buttons::{ButtonEvent, ButtonEventDecoder},
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `ReprogControls5` / `0x1b04` feature.
+///
+/// Controls are identified by their control ID (CID), which is stable across
+/// devices for controls with the same meaning. Every control belongs to a
+/// group and can be remapped to all controls of the groups in its group mask.
+pub struct ReprogControlsFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<ReprogControlsEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for ReprogControlsFeature {
+    const ID: u16 = 0x1b04;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::reprog_controls_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for ReprogControlsFeature {
+}
+
+impl EmittingFeature<ReprogControlsEvent> for ReprogControlsFeature {
+    fn listen(&self) -> async_channel::Receiver<ReprogControlsEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<ReprogControlsEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl ReprogControlsFeature {
+    /// Retrieves the amount of controls of the device.
+    pub async fn get_control_count(&self) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Retrieves the information about the control at an index, which has to
+    /// be lower than the count returned by [`Self::get_control_count`].
+    pub async fn get_control_info(&self, index: u8) -> Result<ControlInfo, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [index, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(ControlInfo {
+            cid: u16::from_be_bytes([payload[0], payload[1]]),
+            task_id: u16::from_be_bytes([payload[2], payload[3]]),
+            mouse_button: payload[4] & (1 << 0) != 0,
+            f_key: payload[4] & (1 << 1) != 0,
+            hotkey: payload[4] & (1 << 2) != 0,
+            fn_toggle: payload[4] & (1 << 3) != 0,
+            reprogrammable: payload[4] & (1 << 4) != 0,
+            divertable: payload[4] & (1 << 5) != 0,
+            persistently_divertable: payload[4] & (1 << 6) != 0,
+            virtual_control: payload[4] & (1 << 7) != 0,
+            raw_xy: payload[8] & (1 << 0) != 0,
+            force_raw_xy: payload[8] & (1 << 1) != 0,
+            analytics_key_events: payload[8] & (1 << 2) != 0,
+            raw_wheel: payload[8] & (1 << 3) != 0,
+            position: payload[5],
+            group: payload[6],
+            group_mask: payload[7],
+        })
+    }
+
+    /// Retrieves all controls of the device.
+    pub async fn get_controls(&self) -> Result<Vec<ControlInfo>, Hidpp20Error> {
+        let count = self.get_control_count().await?;
+
+        let mut controls = Vec::with_capacity(count as usize);
+        for index in 0..count {
+            controls.push(self.get_control_info(index).await?);
+        }
+
+        Ok(controls)
+    }
+
+    /// Retrieves how a control is currently reported.
+    pub async fn get_control_reporting(&self, cid: u16) -> Result<ControlReporting, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let cid_bytes = cid.to_be_bytes();
+
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(2),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [cid_bytes[0], cid_bytes[1], 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        // A remapped CID of `0` means that the control is not remapped.
+        let remapped = match u16::from_be_bytes([payload[3], payload[4]]) {
+            0 => cid,
+            remapped => remapped,
+        };
+
+        Ok(ControlReporting {
+            diverted: payload[2] & (1 << 0) != 0,
+            persistently_diverted: payload[2] & (1 << 2) != 0,
+            raw_xy_diverted: payload[2] & (1 << 4) != 0,
+            force_raw_xy_diverted: payload[2] & (1 << 6) != 0,
+            remapped,
+            analytics_key_events: payload[5] & (1 << 0) != 0,
+            raw_wheel_diverted: payload[5] & (1 << 2) != 0,
+        })
+    }
+
+    /// Changes how a control is reported.
+    ///
+    /// Only the settings that are `Some` are changed. Remapping a control to
+    /// its own CID restores its default behavior.
+    pub async fn set_control_reporting(
+        &self,
+        cid: u16,
+        change: ControlReportingChange,
+    ) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let cid_bytes = cid.to_be_bytes();
+
+        let flags = reporting_flags([
+            change.diverted,
+            change.persistently_diverted,
+            change.raw_xy_diverted,
+            change.force_raw_xy_diverted,
+        ]);
+        let extended_flags = reporting_flags([
+            change.analytics_key_events,
+            change.raw_wheel_diverted,
+            None,
+            None,
+        ]);
+
+        let remap_bytes = change.remapped.unwrap_or(0).to_be_bytes();
+
+        let mut payload = [0; 16];
+        payload[..6].copy_from_slice(&[
+            cid_bytes[0],
+            cid_bytes[1],
+            flags,
+            remap_bytes[0],
+            remap_bytes[1],
+            extended_flags,
+        ]);
+
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Long(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(3),
+                    software_id: chan.get_sw_id(),
+                },
+                payload,
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Creates a stream of presses and releases of diverted controls, decoded
+    /// from [`ReprogControlsEvent::DivertedButtons`] using a
+    /// [`ButtonEventDecoder`].
+    pub fn listen_buttons(&self) -> impl Stream<Item = ButtonEvent> + Send + use<> {
+        stream::unfold(
+            (self.listen_envelopes(), ButtonEventDecoder::new()),
+            |(rx, mut decoder)| async move {
+                loop {
+                    let envelope = rx.recv().await.ok()?;
+                    let ReprogControlsEvent::DivertedButtons(pressed) = &envelope.event else {
+                        continue;
+                    };
+
+                    let events = decoder.decode(pressed, envelope.received_at);
+                    if !events.is_empty() {
+                        return Some((stream::iter(events), (rx, decoder)));
+                    }
+                }
+            },
+        )
+        .flatten()
+    }
+}
+
+/// Encodes the flags of a [`ControlReportingChange`] into a byte, where every
+/// flag is followed by a bit marking it as valid.
+fn reporting_flags(flags: [Option<bool>; 4]) -> u8 {
+    flags
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, flag)| flag.map(|value| (value as u8 | 0b10) << (i * 2)))
+        .fold(0, |acc, x| acc | x)
+}
+
+/// Represents a control as reported by
+/// [`ReprogControlsFeature::get_control_info`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ControlInfo {
+    /// The control ID.
+    pub cid: u16,
+
+    /// The ID of the task the control performs by default.
+    pub task_id: u16,
+
+    /// Whether the control is a mouse button.
+    pub mouse_button: bool,
+
+    /// Whether the control is an F-key.
+    pub f_key: bool,
+
+    /// Whether the control is a hotkey not part of the standard key layout.
+    pub hotkey: bool,
+
+    /// Whether the behavior of the control is affected by the Fn key.
+    pub fn_toggle: bool,
+
+    /// Whether the control can be remapped to other controls.
+    pub reprogrammable: bool,
+
+    /// Whether the control can be diverted to be reported via HID++.
+    pub divertable: bool,
+
+    /// Whether the control can be diverted persistently.
+    pub persistently_divertable: bool,
+
+    /// Whether the control is virtual, like a gesture, and not a physical
+    /// button.
+    pub virtual_control: bool,
+
+    /// Whether the movement of the mouse can be diverted while the control is
+    /// held down.
+    pub raw_xy: bool,
+
+    /// Whether raw XY reporting can be forced, which reports the movement of
+    /// the mouse regardless of whether the control is held down.
+    ///
+    /// This was added in feature version 4.
+    pub force_raw_xy: bool,
+
+    /// Whether the control can report analytics events.
+    ///
+    /// This was added in feature version 4.
+    pub analytics_key_events: bool,
+
+    /// Whether the movement of the wheel can be diverted while the control is
+    /// held down.
+    ///
+    /// This was added in feature version 4.
+    pub raw_wheel: bool,
+
+    /// The position of F-keys, or `0` for other controls.
+    pub position: u8,
+
+    /// The group the control belongs to, or `0` if it belongs to none.
+    pub group: u8,
+
+    /// A bit mask of the groups whose controls this control can be remapped
+    /// to, where bit `n` corresponds to group `n + 1`.
+    pub group_mask: u8,
+}
+
+impl ControlInfo {
+    /// Whether this control can be remapped to another control.
+    pub fn can_remap_to(&self, target: &ControlInfo) -> bool {
+        self.reprogrammable
+            && (target.cid == self.cid
+                || (target.group != 0 && self.group_mask & (1 << (target.group - 1)) != 0))
+    }
+}
+
+/// Represents how a control is reported as reported by
+/// [`ReprogControlsFeature::get_control_reporting`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ControlReporting {
+    /// Whether the control is diverted to be reported via HID++.
+    pub diverted: bool,
+
+    /// Whether the control is diverted persistently.
+    pub persistently_diverted: bool,
+
+    /// Whether the movement of the mouse is diverted while the control is
+    /// held down.
+    pub raw_xy_diverted: bool,
+
+    /// Whether the movement of the mouse is diverted regardless of whether
+    /// the control is held down.
+    pub force_raw_xy_diverted: bool,
+
+    /// The CID the control is remapped to, which is its own CID if it is not
+    /// remapped.
+    pub remapped: u16,
+
+    /// Whether analytics events are reported for the control.
+    pub analytics_key_events: bool,
+
+    /// Whether the movement of the wheel is diverted while the control is
+    /// held down.
+    pub raw_wheel_diverted: bool,
+}
+
+/// Represents a change to how a control is reported, as passed to
+/// [`ReprogControlsFeature::set_control_reporting`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ControlReportingChange {
+    pub diverted: Option<bool>,
+    pub persistently_diverted: Option<bool>,
+    pub raw_xy_diverted: Option<bool>,
+    pub force_raw_xy_diverted: Option<bool>,
+    pub remapped: Option<u16>,
+    pub analytics_key_events: Option<bool>,
+    pub raw_wheel_diverted: Option<bool>,
+}
+
+/// Represents an event emitted by the [`ReprogControlsFeature`] feature.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum ReprogControlsEvent {
+    /// Is emitted whenever the set of held down diverted controls changes.
+    ///
+    /// Contains the CIDs of all diverted controls that are currently held
+    /// down, which is empty once all of them were released.
+    DivertedButtons(Vec<u16>),
+
+    /// Is emitted when the mouse moves while a control with diverted raw XY
+    /// reporting is held down.
+    DivertedRawXy {
+        dx: i16,
+        dy: i16,
+    },
+
+    /// Is emitted whenever a control with analytics reporting enabled is
+    /// pressed or released, regardless of whether it is diverted.
+    ///
+    /// Contains up to five changes, each consisting of the CID and whether
+    /// the control was pressed.
+    AnalyticsKeyEvents(Vec<(u16, bool)>),
+
+    /// Is emitted when the wheel moves while a control with diverted raw
+    /// wheel reporting is held down.
+    DivertedRawWheel {
+        /// Whether the movement is reported in high resolution.
+        high_resolution: bool,
+
+        /// The amount of sampling periods the movement was accumulated over.
+        periods: u8,
+
+        /// The vertical movement of the wheel.
+        delta: i16,
+    },
+}
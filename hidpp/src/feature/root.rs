@@ -1,11 +1,11 @@
 //! Implements the `Root` feature (ID `0x0000`) that every device supports by
 //! default.
 
-use std::sync::Arc;
+use std::sync::{Arc, Weak};
 
 use super::{CreatableFeature, Feature, FeatureType};
 use crate::{
-    channel::HidppChannel,
+    channel::{self, HidppChannel},
     nibble::U4,
     protocol::v20::{self, Hidpp20Error},
 };
@@ -18,7 +18,7 @@ use crate::{
 #[derive(Clone)]
 pub struct RootFeature {
     /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
+    chan: Weak<HidppChannel>,
 
     /// The index of the device to implement the feature for.
     device_index: u8,
@@ -30,7 +30,7 @@ impl CreatableFeature for RootFeature {
 
     fn new(chan: Arc<HidppChannel>, device_index: u8, _: u8) -> Self {
         Self {
-            chan,
+            chan: Arc::downgrade(&chan),
             device_index,
         }
     }
@@ -48,17 +48,20 @@ impl RootFeature {
     /// If the device only supports the root feature version 1, the
     /// [`FeatureInformation::version`] field will be `0` for all features.
     pub async fn get_feature(&self, id: u16) -> Result<Option<FeatureInformation>, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: 0,
-                    function_id: U4::from_lo(0),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [(id >> 8) as u8, id as u8, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: 0,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [(id >> 8) as u8, id as u8, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
@@ -77,29 +80,67 @@ impl RootFeature {
     /// with the same data if communication succeeds.
     ///
     /// The underlying function, as described in the protocol specification,
-    /// will also look up the protocol version supported by the device.\
-    /// This is not implemented here, as the
-    /// [`crate::protocol::determine_version`] function does so in a more
-    /// general manner.
+    /// will also look up the protocol version supported by the device, which
+    /// is provided by [`Self::get_protocol_version`].
     pub async fn ping(&self, data: u8) -> Result<u8, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: 0,
-                    function_id: U4::from_lo(1),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, data],
-            ))
+        Ok(self.get_protocol_version(data).await?.ping_data)
+    }
+
+    /// Retrieves the protocol version of the device, pinging it with an
+    /// arbitrary data byte at the same time.
+    ///
+    /// Unlike [`crate::protocol::determine_version`], this assumes the device
+    /// already is known to support HID++2.0 and does not handle HID++1.0
+    /// devices.
+    pub async fn get_protocol_version(
+        &self,
+        data: u8,
+    ) -> Result<RootProtocolVersion, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: 0,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, data],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
-        Ok(payload[2])
+        Ok(RootProtocolVersion {
+            protocol_num: payload[0],
+            target_sw: payload[1],
+            ping_data: payload[2],
+        })
     }
 }
 
+/// Represents the protocol version of a device as returned by the
+/// [`RootFeature::get_protocol_version`] function.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct RootProtocolVersion {
+    /// The protocol number hinting which host software should support the
+    /// device.
+    ///
+    /// See [`crate::protocol::ProtocolVersion::V20`] for more information.
+    pub protocol_num: u8,
+
+    /// The target software of the device if `protocol_num >= 3`, zero
+    /// otherwise.
+    pub target_sw: u8,
+
+    /// The data byte echoed back by the device.
+    pub ping_data: u8,
+}
+
 /// Represents information about a specific feature as returned by the
 /// [`RootFeature::get_feature`] function.
 #[derive(Clone, Copy, Hash, Debug)]
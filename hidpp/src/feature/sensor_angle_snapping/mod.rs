@@ -0,0 +1,85 @@
+//! Implements the `SensorAngleSnapping` feature (ID `0x2230`) that allows
+//! enabling angle snapping on gaming sensors, which straightens nearly
+//! horizontal and vertical movements.
+
+use std::sync::{Arc, Weak};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `SensorAngleSnapping` / `0x2230` feature.
+///
+/// Only the lowest bit of the state is known, so its other bits are ignored.
+pub struct SensorAngleSnappingFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for SensorAngleSnappingFeature {
+    const ID: u16 = 0x2230;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for SensorAngleSnappingFeature {
+}
+
+impl SensorAngleSnappingFeature {
+    /// Retrieves whether angle snapping is currently enabled.
+    pub async fn get_angle_snapping(&self) -> Result<bool, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0] & 1 != 0)
+    }
+
+    /// Enables or disables angle snapping.
+    pub async fn set_angle_snapping(&self, enabled: bool) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(2),
+                    software_id: chan.get_sw_id(),
+                },
+                [enabled as u8, 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
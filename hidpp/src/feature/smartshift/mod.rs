@@ -1,12 +1,15 @@
 //! Implements the `SmartShift` feature (ID `0x2110`) that allows controlling a
 //! smart shift enhanced scroll wheel.
 
-use std::{hash::Hash, sync::Arc};
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::{
-    channel::HidppChannel,
+    channel::{self, HidppChannel},
     feature::{CreatableFeature, Feature},
     nibble::U4,
     protocol::v20::{self, Hidpp20Error},
@@ -15,7 +18,7 @@ use crate::{
 /// Implements the `SmartShift` / `0x2110` feature.
 pub struct SmartShiftFeature {
     /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
+    chan: Weak<HidppChannel>,
 
     /// The index of the device to implement the feature for.
     device_index: u8,
@@ -30,7 +33,7 @@ impl CreatableFeature for SmartShiftFeature {
 
     fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
         Self {
-            chan,
+            chan: Arc::downgrade(&chan),
             device_index,
             feature_index,
         }
@@ -47,24 +50,28 @@ impl SmartShiftFeature {
     /// either by software or the wheel mode button. It will not provide
     /// information about whether the wheel is in auto-disengaged mode.
     pub async fn get_ratchet_control_mode(&self) -> Result<RatchetControlMode, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(0),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
 
         Ok(RatchetControlMode {
-            wheel_mode: WheelMode::try_from(payload[0])
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
+            wheel_mode: WheelMode::try_from(payload[0]).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
             auto_disengage: payload[1],
             auto_disengage_default: payload[2],
         })
@@ -88,21 +95,24 @@ impl SmartShiftFeature {
         auto_disengage: Option<u8>,
         auto_disengage_default: Option<u8>,
     ) -> Result<(), Hidpp20Error> {
-        self.chan
-            .send_v20(v20::Message::Short(
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
                 v20::MessageHeader {
                     device_index: self.device_index,
                     feature_index: self.feature_index,
                     function_id: U4::from_lo(1),
-                    software_id: self.chan.get_sw_id(),
+                    software_id: chan.get_sw_id(),
                 },
                 [
                     wheel_mode.map_or(0, u8::from),
                     auto_disengage.unwrap_or(0),
                     auto_disengage_default.unwrap_or(0),
                 ],
-            ))
-            .await?;
+            ),
+        )
+        .await?;
 
         Ok(())
     }
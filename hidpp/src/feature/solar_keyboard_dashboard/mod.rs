@@ -0,0 +1,150 @@
+//! Implements the `SolarKeyboardDashboard` feature (ID `0x4301`) that reports
+//! the battery charge and the light falling on solar powered keyboards.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `SolarKeyboardDashboard` / `0x4301` feature.
+///
+/// Notifications without the `GOOD` marker are ignored, as the meaning of their
+/// contents is unknown.
+pub struct SolarKeyboardDashboardFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<SolarEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for SolarKeyboardDashboardFeature {
+    const ID: u16 = 0x4301;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) =
+                        parse::solar_keyboard_dashboard_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for SolarKeyboardDashboardFeature {
+}
+
+impl EmittingFeature<SolarEvent> for SolarKeyboardDashboardFeature {
+    fn listen(&self) -> async_channel::Receiver<SolarEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<SolarEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl SolarKeyboardDashboardFeature {
+    /// Makes the keyboard measure the light falling on it `count` times, once
+    /// every `period` seconds.
+    ///
+    /// Every measurement is reported as [`SolarEvent::LightMeasurement`]. A
+    /// count of zero stops measuring.
+    pub async fn request_light_measurements(
+        &self,
+        count: u8,
+        period: u8,
+    ) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(0),
+                    software_id: chan.get_sw_id(),
+                },
+                [count, period, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents a measurement reported by a solar powered keyboard.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct SolarMeasurement {
+    /// The charge of the battery in percent.
+    pub charge: u8,
+
+    /// The illuminance of the solar panel in lux.
+    pub lux: u16,
+}
+
+/// Represents an event emitted by the [`SolarKeyboardDashboardFeature`]
+/// feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum SolarEvent {
+    /// Is emitted periodically by the keyboard to report its battery charge.
+    ///
+    /// The illuminance is not measured for this event.
+    BatteryBroadcast(SolarMeasurement),
+
+    /// Is emitted for every measurement requested via
+    /// [`SolarKeyboardDashboardFeature::request_light_measurements`].
+    LightMeasurement(SolarMeasurement),
+
+    /// Is emitted when the light check button of the keyboard is pressed.
+    ///
+    /// Software usually reacts by requesting light measurements to show
+    /// whether there is enough light to charge the keyboard.
+    LightCheckButton(SolarMeasurement),
+}
@@ -0,0 +1,121 @@
+//! Implements the `SurfaceTuning` feature (ID `0x2240`) that allows
+//! calibrating the sensor of a mouse to the surface it is used on.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `SurfaceTuning` / `0x2240` feature.
+///
+/// Only three calibration states are known. Any other state results in an
+/// unsupported response error.
+pub struct SurfaceTuningFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for SurfaceTuningFeature {
+    const ID: u16 = 0x2240;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for SurfaceTuningFeature {
+}
+
+impl SurfaceTuningFeature {
+    /// Retrieves the state of the surface calibration.
+    pub async fn get_calibration_state(&self) -> Result<SurfaceCalibrationState, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        SurfaceCalibrationState::try_from(response.extend_payload()[0])
+            .map_err(|_| Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID))
+    }
+
+    /// Starts calibrating the sensor to the current surface.
+    ///
+    /// The mouse should be moved on the surface until
+    /// [`Self::get_calibration_state`] no longer reports
+    /// [`SurfaceCalibrationState::InProgress`].
+    pub async fn start_calibration(&self) -> Result<(), Hidpp20Error> {
+        self.send_command(1).await
+    }
+
+    /// Clears the surface calibration, returning to the factory tuning.
+    pub async fn clear_calibration(&self) -> Result<(), Hidpp20Error> {
+        self.send_command(2).await
+    }
+
+    /// Sends a request without parameters to a function of the feature.
+    async fn send_command(&self, function_id: u8) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(function_id),
+                    software_id: chan.get_sw_id(),
+                },
+                [0x00, 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents the state of the surface calibration of a sensor.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum SurfaceCalibrationState {
+    /// The sensor uses its factory tuning.
+    NotCalibrated = 0,
+
+    /// A calibration is currently running.
+    InProgress = 1,
+
+    /// The sensor is calibrated to a surface.
+    Calibrated = 2,
+}
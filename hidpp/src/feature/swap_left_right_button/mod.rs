@@ -0,0 +1,82 @@
+//! Implements the `SwapLeftRightButton` feature (ID `0x2001`) that allows
+//! swapping the primary buttons of a mouse in firmware.
+
+use std::sync::{Arc, Weak};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `SwapLeftRightButton` / `0x2001` feature.
+pub struct SwapLeftRightButtonFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for SwapLeftRightButtonFeature {
+    const ID: u16 = 0x2001;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for SwapLeftRightButtonFeature {
+}
+
+impl SwapLeftRightButtonFeature {
+    /// Retrieves whether the left and right buttons are currently swapped.
+    pub async fn get_swap_state(&self) -> Result<bool, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0] & 1 != 0)
+    }
+
+    /// Sets whether the left and right buttons are swapped.
+    pub async fn set_swap_state(&self, swapped: bool) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(1),
+                    software_id: chan.get_sw_id(),
+                },
+                [swapped as u8, 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+//! Implements the `TapEnable` (ID `0x6020`) and `TapEnableExtended` (ID
+//! `0x6021`) features that allow configuring tap-to-click on touch devices.
+//!
+//! Both features share the [`TapConfig`] type. [`TapEnableFeature`] only
+//! toggles tap-to-click, while [`TapEnableExtendedFeature`] additionally
+//! toggles tap-and-drag and two-finger tapping.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Represents the tap configuration of a touch device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct TapConfig {
+    /// Whether tapping the surface clicks.
+    pub tap_to_click: bool,
+
+    /// Whether tapping twice and holding the second tap starts a drag.
+    ///
+    /// This is only reported by [`TapEnableExtendedFeature`].
+    pub tap_and_drag: Option<bool>,
+
+    /// Whether tapping with two fingers performs a secondary click.
+    ///
+    /// This is only reported by [`TapEnableExtendedFeature`].
+    pub two_finger_tap: Option<bool>,
+}
+
+impl TapConfig {
+    /// Creates a new configuration from its individual parts.
+    pub fn new(
+        tap_to_click: bool,
+        tap_and_drag: Option<bool>,
+        two_finger_tap: Option<bool>,
+    ) -> Self {
+        Self {
+            tap_to_click,
+            tap_and_drag,
+            two_finger_tap,
+        }
+    }
+}
+
+/// Contains the implementation shared by both features.
+struct TapEnable {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The ID of either `TapEnable` or `TapEnableExtended`.
+    feature_id: u16,
+
+    /// Whether `TapEnableExtended` is implemented.
+    extended: bool,
+}
+
+impl TapEnable {
+    fn new(
+        chan: Arc<HidppChannel>,
+        device_index: u8,
+        feature_index: u8,
+        feature_id: u16,
+        extended: bool,
+    ) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            feature_id,
+            extended,
+        }
+    }
+
+    async fn get(&self) -> Result<TapConfig, Hidpp20Error> {
+        let response = self.request(0, 0x00).await?;
+        let flags = response.extend_payload()[0];
+
+        Ok(TapConfig {
+            tap_to_click: flags & (1 << 0) != 0,
+            tap_and_drag: self.extended.then_some(flags & (1 << 1) != 0),
+            two_finger_tap: self.extended.then_some(flags & (1 << 2) != 0),
+        })
+    }
+
+    async fn set(&self, config: TapConfig) -> Result<(), Hidpp20Error> {
+        let mut flags = 0;
+
+        if config.tap_to_click {
+            flags |= 1 << 0
+        }
+        if self.extended && config.tap_and_drag == Some(true) {
+            flags |= 1 << 1
+        }
+        if self.extended && config.two_finger_tap == Some(true) {
+            flags |= 1 << 2
+        }
+
+        self.request(1, flags).await?;
+
+        Ok(())
+    }
+
+    async fn request(&self, function_id: u8, flags: u8) -> Result<v20::Message, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            self.feature_id,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(function_id),
+                    software_id: chan.get_sw_id(),
+                },
+                [flags, 0x00, 0x00],
+            ),
+        )
+        .await
+    }
+}
+
+/// Implements the `TapEnable` / `0x6020` feature.
+///
+/// Only the lowest bit of the tap flags is known. Setting the configuration
+/// clears all other bits.
+pub struct TapEnableFeature(TapEnable);
+
+impl CreatableFeature for TapEnableFeature {
+    const ID: u16 = 0x6020;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(TapEnable::new(
+            chan,
+            device_index,
+            feature_index,
+            Self::ID,
+            false,
+        ))
+    }
+}
+
+impl Feature for TapEnableFeature {
+}
+
+impl TapEnableFeature {
+    /// Retrieves whether tap-to-click is enabled.
+    pub async fn get_tap_config(&self) -> Result<TapConfig, Hidpp20Error> {
+        self.0.get().await
+    }
+
+    /// Enables or disables tap-to-click.
+    pub async fn set_tap_config(&self, config: TapConfig) -> Result<(), Hidpp20Error> {
+        self.0.set(config).await
+    }
+}
+
+/// Implements the `TapEnableExtended` / `0x6021` feature.
+///
+/// Only the three lowest bits of the tap flags are known. Setting the
+/// configuration clears all other bits.
+pub struct TapEnableExtendedFeature(TapEnable);
+
+impl CreatableFeature for TapEnableExtendedFeature {
+    const ID: u16 = 0x6021;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(TapEnable::new(
+            chan,
+            device_index,
+            feature_index,
+            Self::ID,
+            true,
+        ))
+    }
+}
+
+impl Feature for TapEnableExtendedFeature {
+}
+
+impl TapEnableExtendedFeature {
+    /// Retrieves the tap configuration.
+    pub async fn get_tap_config(&self) -> Result<TapConfig, Hidpp20Error> {
+        self.0.get().await
+    }
+
+    /// Sets the tap configuration.
+    ///
+    /// Options that are [`None`] are disabled.
+    pub async fn set_tap_config(&self, config: TapConfig) -> Result<(), Hidpp20Error> {
+        self.0.set(config).await
+    }
+}
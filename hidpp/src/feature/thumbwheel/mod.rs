@@ -1,22 +1,24 @@
 //! Implements the `Thumbwheel` feature (ID `0x2150`) that allows configuration
 //! and diversion of thumbwheel events.
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, Weak};
 
+use futures::{Stream, stream};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::{
-    channel::HidppChannel,
-    event::EventEmitter,
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
     feature::{CreatableFeature, EmittingFeature, Feature},
-    nibble::{self, U4},
+    nibble::U4,
+    parse,
     protocol::v20::{self, Hidpp20Error},
 };
 
 /// Implements the `Thumbwheel` / `0x2150` feature.
 pub struct ThumbwheelFeature {
     /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
+    chan: Weak<HidppChannel>,
 
     /// The index of the device to implement the feature for.
     device_index: u8,
@@ -27,10 +29,12 @@ pub struct ThumbwheelFeature {
     /// The emitter used to emit events.
     emitter: Arc<EventEmitter<ThumbwheelEvent>>,
 
-    /// The handle assigned to the message listener registered via
-    /// [`HidppChannel::add_msg_listener`].
-    /// This is used to remove the listener when the feature is dropped.
-    msg_listener_hdl: u32,
+    /// The last known status of the thumbwheel, see [`Self::cached_status`].
+    status: Arc<Mutex<Option<ThumbwheelStatus>>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
 }
 
 impl CreatableFeature for ThumbwheelFeature {
@@ -39,47 +43,39 @@ impl CreatableFeature for ThumbwheelFeature {
 
     fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
         let emitter = Arc::new(EventEmitter::new());
+        let status = Arc::new(Mutex::new(None::<ThumbwheelStatus>));
 
-        let hdl = chan.add_msg_listener({
+        let listener = chan.subscribe({
             let emitter = Arc::clone(&emitter);
+            let status = Arc::clone(&status);
 
             move |raw, matched| {
                 if matched {
                     return;
                 }
 
-                let msg = v20::Message::from(raw);
-
-                let header = msg.header();
-                if header.device_index != device_index
-                    || header.feature_index != feature_index
-                    || nibble::combine(header.software_id, header.function_id) != 0
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::thumbwheel_event(function_id, &payload)
                 {
-                    return;
-                }
+                    let ThumbwheelEvent::StatusUpdate(update) = event;
+                    if let Some(status) = status.lock().unwrap().as_mut() {
+                        status.touch = update.touch;
+                        status.proxy = update.proxy;
+                    }
 
-                let payload = msg.extend_payload();
-                let Ok(rotation_status) = ThumbwheelRotationStatus::try_from(payload[4]) else {
-                    return;
-                };
-
-                emitter.emit(ThumbwheelEvent::StatusUpdate(ThumbwheelStatusUpdate {
-                    rotation: i16::from_be_bytes(payload[0..=1].try_into().unwrap()),
-                    time_elapsed: u16::from_be_bytes(payload[2..=3].try_into().unwrap()),
-                    rotation_status,
-                    touch: payload[5] & (1 << 1) != 0,
-                    proxy: payload[5] & (1 << 2) != 0,
-                    single_tap: payload[5] & (1 << 3) != 0,
-                }));
+                    emitter.emit(event, raw);
+                }
             }
         });
 
         Self {
-            chan,
+            chan: Arc::downgrade(&chan),
             device_index,
             feature_index,
             emitter,
-            msg_listener_hdl: hdl,
+            status,
+            _listener: listener,
         }
     }
 }
@@ -91,28 +87,29 @@ impl EmittingFeature<ThumbwheelEvent> for ThumbwheelFeature {
     fn listen(&self) -> async_channel::Receiver<ThumbwheelEvent> {
         self.emitter.create_receiver()
     }
-}
 
-impl Drop for ThumbwheelFeature {
-    fn drop(&mut self) {
-        self.chan.remove_msg_listener(self.msg_listener_hdl);
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<ThumbwheelEvent>> {
+        self.emitter.create_envelope_receiver()
     }
 }
 
 impl ThumbwheelFeature {
     /// Retrieves some information about the thumbwheel.
     pub async fn get_thumbwheel_info(&self) -> Result<ThumbwheelInfo, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(0),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
@@ -121,36 +118,55 @@ impl ThumbwheelFeature {
             native_resolution: u16::from_be_bytes(payload[0..=1].try_into().unwrap()),
             diverted_resolution: u16::from_be_bytes(payload[2..=3].try_into().unwrap()),
             time_unit: u16::from_be_bytes(payload[6..=7].try_into().unwrap()),
-            default_direction: ThumbwheelDirection::try_from(payload[4] & 1)
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
+            default_direction: ThumbwheelDirection::try_from(payload[4] & 1).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
             capabilities: ThumbwheelCapabilities::from(payload[5]),
         })
     }
 
     /// Retrieves the custom status of the thumbwheel.
     pub async fn get_thumbwheel_status(&self) -> Result<ThumbwheelStatus, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(1),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload = response.extend_payload();
 
-        Ok(ThumbwheelStatus {
-            reporting_mode: ThumbwheelReportingMode::try_from(payload[0])
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
+        let status = ThumbwheelStatus {
+            reporting_mode: ThumbwheelReportingMode::try_from(payload[0]).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
             direction_inverted: payload[1] & 1 != 0,
             touch: payload[1] & (1 << 1) != 0,
             proxy: payload[1] & (1 << 2) != 0,
-        })
+        };
+        *self.status.lock().unwrap() = Some(status);
+
+        Ok(status)
+    }
+
+    /// Provides the last known status of the thumbwheel without communicating
+    /// with the device.
+    ///
+    /// The status is known once it was retrieved via
+    /// [`Self::get_thumbwheel_status`] and is kept up to date by
+    /// [`Self::set_thumbwheel_reporting`] and received
+    /// [`ThumbwheelEvent::StatusUpdate`] events.
+    pub fn cached_status(&self) -> Option<ThumbwheelStatus> {
+        *self.status.lock().unwrap()
     }
 
     /// Sets the reporting mode of the thumbwheel.
@@ -165,13 +181,15 @@ impl ThumbwheelFeature {
         mode: ThumbwheelReportingMode,
         invert_direction: bool,
     ) -> Result<(), Hidpp20Error> {
-        self.chan
-            .send_v20(v20::Message::Short(
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
                 v20::MessageHeader {
                     device_index: self.device_index,
                     feature_index: self.feature_index,
                     function_id: U4::from_lo(2),
-                    software_id: self.chan.get_sw_id(),
+                    software_id: chan.get_sw_id(),
                 },
                 [
                     mode.into(),
@@ -182,11 +200,89 @@ impl ThumbwheelFeature {
                     },
                     0x00,
                 ],
-            ))
-            .await?;
+            ),
+        )
+        .await?;
+
+        if let Some(status) = self.status.lock().unwrap().as_mut() {
+            status.reporting_mode = mode;
+            status.direction_inverted = invert_direction;
+        }
 
         Ok(())
     }
+
+    /// Sets the reporting mode of the thumbwheel, keeping whether its
+    /// direction is inverted.
+    ///
+    /// The inversion is taken from [`Self::cached_status`], retrieving the
+    /// status first if it is not known yet.
+    pub async fn set_reporting_mode(
+        &self,
+        mode: ThumbwheelReportingMode,
+    ) -> Result<(), Hidpp20Error> {
+        let status = self.known_status().await?;
+        self.set_thumbwheel_reporting(mode, status.direction_inverted)
+            .await
+    }
+
+    /// Sets whether the direction of the thumbwheel is inverted, keeping its
+    /// reporting mode.
+    ///
+    /// The reporting mode is taken from [`Self::cached_status`], retrieving the
+    /// status first if it is not known yet.
+    pub async fn set_direction_inverted(&self, inverted: bool) -> Result<(), Hidpp20Error> {
+        let status = self.known_status().await?;
+        self.set_thumbwheel_reporting(status.reporting_mode, inverted)
+            .await
+    }
+
+    /// Creates a stream of the rotation deltas reported by
+    /// [`ThumbwheelEvent::StatusUpdate`] events, skipping events without any
+    /// rotation.
+    ///
+    /// Requires the thumbwheel to be in diverted reporting mode.
+    pub fn listen_rotation(&self) -> impl Stream<Item = i16> + Send + use<> {
+        stream::unfold(self.listen(), |rx| async move {
+            loop {
+                let ThumbwheelEvent::StatusUpdate(update) = rx.recv().await.ok()?;
+                if update.rotation != 0 {
+                    return Some((update.rotation, rx));
+                }
+            }
+        })
+    }
+
+    /// Creates a stream of changes to whether the user touches or is close to
+    /// the thumbwheel, skipping [`ThumbwheelEvent::StatusUpdate`] events that
+    /// do not change either of them.
+    ///
+    /// The first event is always passed through. Requires the thumbwheel to be
+    /// in diverted reporting mode.
+    pub fn listen_contact(&self) -> impl Stream<Item = ThumbwheelContact> + Send + use<> {
+        stream::unfold((self.listen(), None), |(rx, mut last)| async move {
+            loop {
+                let ThumbwheelEvent::StatusUpdate(update) = rx.recv().await.ok()?;
+                let contact = ThumbwheelContact {
+                    touch: update.touch,
+                    proxy: update.proxy,
+                };
+
+                if last != Some(contact) {
+                    last = Some(contact);
+                    return Some((contact, (rx, last)));
+                }
+            }
+        })
+    }
+
+    /// Provides the cached status, retrieving it first if it is not known yet.
+    async fn known_status(&self) -> Result<ThumbwheelStatus, Hidpp20Error> {
+        match self.cached_status() {
+            Some(status) => Ok(status),
+            None => self.get_thumbwheel_status().await,
+        }
+    }
 }
 
 /// Represents information about the thumbwheel as reported by
@@ -364,6 +460,25 @@ pub struct ThumbwheelStatusUpdate {
     pub single_tap: bool,
 }
 
+/// Represents whether the user touches or is close to the thumbwheel, as
+/// reported by [`ThumbwheelFeature::listen_contact`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ThumbwheelContact {
+    /// Whether the user touches the thumbwheel.
+    ///
+    /// This is only set if the device supports touch detection as reported by
+    /// [`ThumbwheelCapabilities::touch`].
+    pub touch: bool,
+
+    /// Whether the user is close to the thumbwheel.
+    ///
+    /// This is only set if the device supports proximity detection as reported
+    /// by [`ThumbwheelCapabilities::proxy`].
+    pub proxy: bool,
+}
+
 /// Represents a thumbwheel rotation status as reported in
 /// [`ThumbwheelStatusUpdate::rotation_status`].
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
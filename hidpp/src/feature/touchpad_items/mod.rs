@@ -0,0 +1,157 @@
+//! Implements the `TouchpadFwItems` (ID `0x6010`) and `TouchpadSwItems` (ID
+//! `0x6011`) features that allow toggling behaviors of a touchpad, like
+//! tap-to-click or edge scrolling.
+//!
+//! Both features share the [`TouchpadItems`] type. [`TouchpadFwItemsFeature`]
+//! toggles behaviors implemented by the firmware of the touchpad, while
+//! [`TouchpadSwItemsFeature`] toggles behaviors expected to be implemented by
+//! the host software.
+//!
+//! Items are represented as bit masks, where every bit corresponds to one item.
+//! What the individual items mean differs between devices.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Represents the state of the items of a touchpad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct TouchpadItems {
+    /// The items supported by the touchpad.
+    pub presence: u8,
+
+    /// The items requested to be enabled.
+    pub desired_state: u8,
+
+    /// The items that are currently enabled.
+    pub state: u8,
+
+    /// The items whose state is kept when the touchpad is powered off.
+    pub persistent: u8,
+}
+
+impl TouchpadItems {
+    /// Returns whether an item is supported and currently enabled.
+    pub fn is_enabled(&self, item: u8) -> bool {
+        item < 8 && self.presence & self.state & (1 << item) != 0
+    }
+}
+
+/// Contains the implementation shared by both features.
+struct Items {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The ID of either `TouchpadFwItems` or `TouchpadSwItems`.
+    feature_id: u16,
+}
+
+impl Items {
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8, feature_id: u16) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            feature_id,
+        }
+    }
+
+    async fn request(&self, function_id: u8, desired: u8) -> Result<TouchpadItems, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                self.feature_id,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(function_id),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [desired, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(TouchpadItems {
+            presence: payload[0],
+            desired_state: payload[1],
+            state: payload[2],
+            persistent: payload[3],
+        })
+    }
+}
+
+/// Implements the `TouchpadFwItems` / `0x6010` feature.
+pub struct TouchpadFwItemsFeature(Items);
+
+impl CreatableFeature for TouchpadFwItemsFeature {
+    const ID: u16 = 0x6010;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(Items::new(chan, device_index, feature_index, Self::ID))
+    }
+}
+
+impl Feature for TouchpadFwItemsFeature {
+}
+
+impl TouchpadFwItemsFeature {
+    /// Retrieves the state of the firmware items.
+    pub async fn get_items(&self) -> Result<TouchpadItems, Hidpp20Error> {
+        self.0.request(0, 0x00).await
+    }
+
+    /// Sets which firmware items are enabled, returning the state reported by
+    /// the touchpad afterwards.
+    pub async fn set_items(&self, desired_state: u8) -> Result<TouchpadItems, Hidpp20Error> {
+        self.0.request(1, desired_state).await
+    }
+}
+
+/// Implements the `TouchpadSwItems` / `0x6011` feature.
+pub struct TouchpadSwItemsFeature(Items);
+
+impl CreatableFeature for TouchpadSwItemsFeature {
+    const ID: u16 = 0x6011;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(Items::new(chan, device_index, feature_index, Self::ID))
+    }
+}
+
+impl Feature for TouchpadSwItemsFeature {
+}
+
+impl TouchpadSwItemsFeature {
+    /// Retrieves the state of the software items.
+    pub async fn get_items(&self) -> Result<TouchpadItems, Hidpp20Error> {
+        self.0.request(0, 0x00).await
+    }
+
+    /// Sets which software items are enabled, returning the state reported by
+    /// the touchpad afterwards.
+    pub async fn set_items(&self, desired_state: u8) -> Result<TouchpadItems, Hidpp20Error> {
+        self.0.request(1, desired_state).await
+    }
+}
@@ -0,0 +1,135 @@
+//! Implements the `TouchpadResolutionDivider` feature (ID `0x6040`) that
+//! allows reducing the resolution a touchpad reports its movement at.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `TouchpadResolutionDivider` / `0x6040` feature.
+pub struct TouchpadResolutionDividerFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for TouchpadResolutionDividerFeature {
+    const ID: u16 = 0x6040;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for TouchpadResolutionDividerFeature {
+}
+
+impl TouchpadResolutionDividerFeature {
+    /// Retrieves the native resolution of the touchpad and the range of
+    /// supported dividers.
+    pub async fn get_resolution_info(&self) -> Result<ResolutionInfo, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(ResolutionInfo {
+            native_resolution: u16::from_be_bytes([payload[0], payload[1]]),
+            max_divider: payload[2],
+        })
+    }
+
+    /// Retrieves the divider currently applied to the native resolution.
+    pub async fn get_resolution_divider(&self) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+
+    /// Sets the divider applied to the native resolution, ranging from `1`
+    /// (the native resolution) to [`ResolutionInfo::max_divider`].
+    ///
+    /// Returns the divider actually set by the device.
+    pub async fn set_resolution_divider(&self, divider: u8) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(2),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [divider, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+}
+
+/// Represents the resolution capabilities of a touchpad.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ResolutionInfo {
+    /// The native resolution of the touchpad in DPI.
+    pub native_resolution: u16,
+
+    /// The highest divider the touchpad supports.
+    pub max_divider: u8,
+}
+
+impl ResolutionInfo {
+    /// Calculates the resolution in DPI resulting from a divider.
+    pub fn resolution(&self, divider: u8) -> u16 {
+        self.native_resolution / u16::from(divider.max(1))
+    }
+}
@@ -1,22 +1,30 @@
 //! Implements the `UnifiedBattery` feature (ID `0x1004`) that provides
 //! information about the battery status of the device.
 
-use std::{collections::HashSet, hash::Hash, sync::Arc};
+use std::{
+    collections::HashSet,
+    hash::Hash,
+    sync::{Arc, Weak},
+};
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::{
-    channel::HidppChannel,
-    event::EventEmitter,
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
     feature::{CreatableFeature, EmittingFeature, Feature},
-    nibble::{self, U4},
+    nibble::U4,
+    parse,
     protocol::v20::{self, Hidpp20Error},
 };
 
 /// Implements the `UnifiedBattery` / `0x1004` feature.
+///
+/// [`BatteryInfo::external_power_connected`] is always [`None`] for this
+/// implementation, use [`UnifiedBatteryFeatureV1`] for devices supporting it.
 pub struct UnifiedBatteryFeature {
     /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
+    chan: Weak<HidppChannel>,
 
     /// The index of the device to implement the feature for.
     device_index: u8,
@@ -24,13 +32,16 @@ pub struct UnifiedBatteryFeature {
     /// The index of the feature in the feature table.
     feature_index: u8,
 
+    /// Whether the status of the external power source is reported, which is
+    /// the case from feature version 1 on.
+    external_power: bool,
+
     /// The emitter used to emit events.
     emitter: Arc<EventEmitter<BatteryEvent>>,
 
-    /// The handle assigned to the message listener registered via
-    /// [`HidppChannel::add_msg_listener`].
-    /// This is used to remove the listener when the feature is dropped.
-    msg_listener_hdl: u32,
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
 }
 
 impl CreatableFeature for UnifiedBatteryFeature {
@@ -38,9 +49,35 @@ impl CreatableFeature for UnifiedBatteryFeature {
     const STARTING_VERSION: u8 = 0;
 
     fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self::with_external_power(chan, device_index, feature_index, false)
+    }
+}
+
+impl Feature for UnifiedBatteryFeature {
+}
+
+impl EmittingFeature<BatteryEvent> for UnifiedBatteryFeature {
+    fn listen(&self) -> async_channel::Receiver<BatteryEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<BatteryEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl UnifiedBatteryFeature {
+    /// Creates the feature, optionally parsing the status of the external
+    /// power source.
+    fn with_external_power(
+        chan: Arc<HidppChannel>,
+        device_index: u8,
+        feature_index: u8,
+        external_power: bool,
+    ) -> Self {
         let emitter = Arc::new(EventEmitter::new());
 
-        let hdl = chan.add_msg_listener({
+        let listener = chan.subscribe({
             let emitter = Arc::clone(&emitter);
 
             move |raw, matched| {
@@ -48,71 +85,45 @@ impl CreatableFeature for UnifiedBatteryFeature {
                     return;
                 }
 
-                let msg = v20::Message::from(raw);
-
-                let header = msg.header();
-                if header.device_index != device_index
-                    || header.feature_index != feature_index
-                    || nibble::combine(header.software_id, header.function_id) != 0
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = if external_power {
+                        parse::unified_battery_v1_event(function_id, &payload)
+                    } else {
+                        parse::unified_battery_event(function_id, &payload)
+                    }
                 {
-                    return;
+                    emitter.emit(event, raw);
                 }
-
-                let payload = msg.extend_payload();
-                let Ok(level) = BatteryLevel::try_from(payload[1]) else {
-                    return;
-                };
-                let Ok(status) = BatteryStatus::try_from(payload[2]) else {
-                    return;
-                };
-
-                emitter.emit(BatteryEvent::InfoUpdate(BatteryInfo {
-                    charging_percentage: payload[0],
-                    level,
-                    status,
-                }));
             }
         });
 
         Self {
-            chan,
+            chan: Arc::downgrade(&chan),
             device_index,
             feature_index,
+            external_power,
             emitter,
-            msg_listener_hdl: hdl,
+            _listener: listener,
         }
     }
-}
 
-impl Feature for UnifiedBatteryFeature {
-}
-
-impl EmittingFeature<BatteryEvent> for UnifiedBatteryFeature {
-    fn listen(&self) -> async_channel::Receiver<BatteryEvent> {
-        self.emitter.create_receiver()
-    }
-}
-
-impl Drop for UnifiedBatteryFeature {
-    fn drop(&mut self) {
-        self.chan.remove_msg_listener(self.msg_listener_hdl);
-    }
-}
-
-impl UnifiedBatteryFeature {
     /// Retrieves the capabilities of this feature and the battery in general.
     pub async fn get_battery_capabilities(&self) -> Result<BatteryCapabilities, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(0),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
         let payload: [u8; 2] = response.extend_payload()[..2].try_into().unwrap();
@@ -122,34 +133,71 @@ impl UnifiedBatteryFeature {
 
     /// Retrieves the current information about the battery status.
     pub async fn get_battery_info(&self) -> Result<BatteryInfo, Hidpp20Error> {
-        let response = self
-            .chan
-            .send_v20(v20::Message::Short(
-                v20::MessageHeader {
-                    device_index: self.device_index,
-                    feature_index: self.feature_index,
-                    function_id: U4::from_lo(1),
-                    software_id: self.chan.get_sw_id(),
-                },
-                [0x00, 0x00, 0x00],
-            ))
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(1),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
             .await?;
 
-        let payload = response.extend_payload();
+        BatteryInfo::parse(&response.extend_payload(), self.external_power)
+            .ok_or_else(|| Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID))
+    }
+}
+
+/// Implements version 1 of the `UnifiedBattery` / `0x1004` feature, which
+/// reports the status of the external power source via
+/// [`BatteryInfo::external_power_connected`].
+///
+/// All other functions behave like the ones of [`UnifiedBatteryFeature`].
+pub struct UnifiedBatteryFeatureV1(UnifiedBatteryFeature);
 
-        // payload[3] contains some kind of information about the status of the external
-        // power source (maybe 0 = disconnected and 1 = connected, I don't have enough
-        // info about that), according to https://github.com/torvalds/linux/blob/a8662bcd2ff152bfbc751cab20f33053d74d0963/drivers/hid/hid-logitech-hidpp.c#L1608
-        // and
-        // https://github.com/torvalds/linux/blob/a8662bcd2ff152bfbc751cab20f33053d74d0963/drivers/hid/hid-logitech-hidpp.c#L1679
+impl CreatableFeature for UnifiedBatteryFeatureV1 {
+    const ID: u16 = UnifiedBatteryFeature::ID;
+    const STARTING_VERSION: u8 = 1;
 
-        Ok(BatteryInfo {
-            charging_percentage: payload[0],
-            level: BatteryLevel::try_from(payload[1])
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-            status: BatteryStatus::try_from(payload[2])
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?,
-        })
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self(UnifiedBatteryFeature::with_external_power(
+            chan,
+            device_index,
+            feature_index,
+            true,
+        ))
+    }
+}
+
+impl Feature for UnifiedBatteryFeatureV1 {
+}
+
+impl EmittingFeature<BatteryEvent> for UnifiedBatteryFeatureV1 {
+    fn listen(&self) -> async_channel::Receiver<BatteryEvent> {
+        self.0.listen()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<BatteryEvent>> {
+        self.0.listen_envelopes()
+    }
+}
+
+impl UnifiedBatteryFeatureV1 {
+    /// Retrieves the capabilities of this feature and the battery in general.
+    pub async fn get_battery_capabilities(&self) -> Result<BatteryCapabilities, Hidpp20Error> {
+        self.0.get_battery_capabilities().await
+    }
+
+    /// Retrieves the current information about the battery status, including
+    /// the status of the external power source.
+    pub async fn get_battery_info(&self) -> Result<BatteryInfo, Hidpp20Error> {
+        self.0.get_battery_info().await
     }
 }
 
@@ -212,11 +260,94 @@ pub struct BatteryInfo {
 
     /// The current charging status of the battery.
     pub status: BatteryStatus,
+
+    /// Whether an external power source is connected.
+    ///
+    /// This field was added in feature version 1 and will always be [`None`]
+    /// for older versions.
+    pub external_power_connected: Option<bool>,
+}
+
+impl BatteryInfo {
+    /// Parses the battery information as contained in responses and
+    /// notifications, including the status of the external power source if
+    /// `external_power` is set.
+    ///
+    /// The status of the external power source is reported as in the Linux
+    /// kernel driver, where any non-zero value means that it is connected.
+    pub(crate) fn parse(payload: &[u8], external_power: bool) -> Option<Self> {
+        Some(Self {
+            charging_percentage: payload[0],
+            level: BatteryLevel::try_from(payload[1]).ok()?,
+            status: BatteryStatus::try_from(payload[2]).ok()?,
+            external_power_connected: external_power.then_some(payload[3] != 0),
+        })
+    }
+
+    /// Provides the charge percentage of the battery, estimating it from the
+    /// reported level if the device does not report percentages.
+    ///
+    /// Each supported level is assumed to cover the range from its nominal
+    /// lower bound up to the lower bound of the next higher supported level,
+    /// and the estimate is the midpoint of that range.
+    pub fn percentage(&self, capabilities: &BatteryCapabilities) -> BatteryPercentage {
+        if capabilities.percentage {
+            return BatteryPercentage::Reported(self.charging_percentage);
+        }
+
+        let lower = self.level.nominal_lower_bound();
+        let upper = [
+            BatteryLevel::Critical,
+            BatteryLevel::Low,
+            BatteryLevel::Good,
+            BatteryLevel::Full,
+        ]
+        .into_iter()
+        .map(BatteryLevel::nominal_lower_bound)
+        .filter(|&bound| bound > lower)
+        .find(|&bound| {
+            capabilities
+                .reported_levels
+                .iter()
+                .any(|level| level.nominal_lower_bound() == bound)
+        })
+        .unwrap_or(100);
+
+        BatteryPercentage::Estimated(lower + (upper - lower) / 2)
+    }
+}
+
+/// Represents the charge percentage of a battery as provided by
+/// [`BatteryInfo::percentage`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum BatteryPercentage {
+    /// The percentage as reported by the device.
+    Reported(u8),
+
+    /// A rough estimate derived from the reported [`BatteryLevel`], for
+    /// devices that don't report percentages.
+    Estimated(u8),
+}
+
+impl BatteryPercentage {
+    /// Provides the percentage, regardless of whether it is estimated.
+    pub fn value(self) -> u8 {
+        match self {
+            Self::Reported(value) | Self::Estimated(value) => value,
+        }
+    }
+
+    /// Whether the percentage is an estimate.
+    pub fn is_estimate(self) -> bool {
+        matches!(self, Self::Estimated(_))
+    }
 }
 
 /// Represents an approximate level of the battery charge.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[repr(u8)]
 pub enum BatteryLevel {
@@ -226,9 +357,30 @@ pub enum BatteryLevel {
     Full = 1 << 3,
 }
 
+impl BatteryLevel {
+    /// The charge percentage the level nominally starts at, as used to
+    /// estimate percentages.
+    fn nominal_lower_bound(self) -> u8 {
+        match self {
+            Self::Critical => 0,
+            Self::Low => 10,
+            Self::Good => 30,
+            Self::Full => 80,
+        }
+    }
+
+    /// Provides the level whose nominal range contains a charge percentage.
+    pub(crate) fn from_percentage(percentage: u8) -> Self {
+        [Self::Full, Self::Good, Self::Low]
+            .into_iter()
+            .find(|level| percentage >= level.nominal_lower_bound())
+            .unwrap_or(Self::Critical)
+    }
+}
+
 /// Represents the charging status of the battery.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 #[repr(u8)]
 pub enum BatteryStatus {
@@ -0,0 +1,76 @@
+//! Implements the `UniqueRandomId` feature (ID `0x0021`) that provides a
+//! random ID identifying the device, which stays the same across pairings.
+
+use std::sync::{Arc, Weak};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `UniqueRandomId` / `0x0021` feature.
+pub struct UniqueRandomIdFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for UniqueRandomIdFeature {
+    const ID: u16 = 0x0021;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for UniqueRandomIdFeature {
+}
+
+impl UniqueRandomIdFeature {
+    /// Retrieves the unique random ID of the device.
+    pub async fn get_unique_random_id(&self) -> Result<[u8; 16], Hidpp20Error> {
+        let response = self.request(0).await?;
+
+        Ok(response.extend_payload())
+    }
+
+    /// Makes the device generate a new unique random ID, which is returned.
+    ///
+    /// Not all devices support this, in which case an error is returned.
+    pub async fn regenerate_unique_random_id(&self) -> Result<[u8; 16], Hidpp20Error> {
+        self.request(1).await?;
+
+        self.get_unique_random_id().await
+    }
+
+    /// Sends a long request without parameters to a function of the feature.
+    async fn request(&self, function_id: u8) -> Result<v20::Message, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Long(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(function_id),
+                    software_id: chan.get_sw_id(),
+                },
+                [0; 16],
+            ),
+        )
+        .await
+    }
+}
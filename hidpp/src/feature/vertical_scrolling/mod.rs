@@ -0,0 +1,105 @@
+//! Implements the `VerticalScrolling` feature (ID `0x2100`) that describes the
+//! native scrolling characteristics of older mice.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `VerticalScrolling` / `0x2100` feature.
+pub struct VerticalScrollingFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for VerticalScrollingFeature {
+    const ID: u16 = 0x2100;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for VerticalScrollingFeature {
+}
+
+impl VerticalScrollingFeature {
+    /// Retrieves the type of the scroll roller and its sensitivity.
+    pub async fn get_roller_info(&self) -> Result<RollerInfo, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(RollerInfo {
+            roller_type: RollerType::try_from(payload[0]).map_err(|_| {
+                Hidpp20Error::unsupported_response(&response).with_feature_id(Self::ID)
+            })?,
+            ratchets_per_turn: payload[1],
+            lines_per_ratchet: payload[2],
+        })
+    }
+}
+
+/// Represents the scroll roller of a mouse as reported by
+/// [`VerticalScrollingFeature::get_roller_info`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct RollerInfo {
+    /// The kind of the roller.
+    pub roller_type: RollerType,
+
+    /// The amount of ratchets of a full turn of the roller.
+    pub ratchets_per_turn: u8,
+
+    /// The amount of lines the host should scroll per ratchet, or `0` if the
+    /// host should use its own default.
+    pub lines_per_ratchet: u8,
+}
+
+/// Represents the kind of a scroll roller.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum RollerType {
+    Standard = 1,
+    ThreeG = 3,
+    Micro = 4,
+    NormalTouchPad = 5,
+    InvertedTouchPad = 6,
+}
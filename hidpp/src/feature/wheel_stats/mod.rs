@@ -0,0 +1,113 @@
+//! Implements the `WheelStats` feature (ID `0x2251`) that collects usage
+//! statistics of the scroll wheel.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel},
+    feature::{CreatableFeature, Feature},
+    nibble::U4,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `WheelStats` / `0x2251` feature.
+pub struct WheelStatsFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+}
+
+impl CreatableFeature for WheelStatsFeature {
+    const ID: u16 = 0x2251;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+        }
+    }
+}
+
+impl Feature for WheelStatsFeature {
+}
+
+impl WheelStatsFeature {
+    /// Retrieves the distances the wheel was scrolled since the statistics
+    /// were last reset.
+    pub async fn get_wheel_stats(&self) -> Result<WheelStats, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        let payload = response.extend_payload();
+
+        Ok(WheelStats {
+            ratchet_distance: u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]),
+            freespin_distance: u32::from_be_bytes([payload[4], payload[5], payload[6], payload[7]]),
+        })
+    }
+
+    /// Resets all statistics to zero.
+    pub async fn reset_wheel_stats(&self) -> Result<(), Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        chan.send_v20_feature(
+            Self::ID,
+            v20::Message::Short(
+                v20::MessageHeader {
+                    device_index: self.device_index,
+                    feature_index: self.feature_index,
+                    function_id: U4::from_lo(1),
+                    software_id: chan.get_sw_id(),
+                },
+                [0x00, 0x00, 0x00],
+            ),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Represents the usage statistics of the scroll wheel as reported by
+/// [`WheelStatsFeature::get_wheel_stats`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct WheelStats {
+    /// The distance scrolled in ratchet mode, in increments of the wheel
+    /// sensor.
+    pub ratchet_distance: u32,
+
+    /// The distance scrolled in freespin mode, in increments of the wheel
+    /// sensor.
+    pub freespin_distance: u32,
+}
+
+impl WheelStats {
+    /// Provides the total distance scrolled in both modes.
+    pub fn total_distance(&self) -> u64 {
+        u64::from(self.ratchet_distance) + u64::from(self.freespin_distance)
+    }
+}
@@ -6,25 +6,20 @@ use std::sync::Arc;
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::{
-    channel::HidppChannel,
-    event::EventEmitter,
+    channel::{HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
     feature::{CreatableFeature, EmittingFeature, Feature},
-    nibble,
-    protocol::v20,
+    parse,
 };
 
 /// Implements the `WirelessDeviceStatus` / `0x1d4b` feature.
 pub struct WirelessDeviceStatusFeature {
-    /// The underlying HID++ channel.
-    chan: Arc<HidppChannel>,
-
     /// The emitter used to emit events.
     emitter: Arc<EventEmitter<WirelessDeviceStatusEvent>>,
 
-    /// The handle assigned to the message listener registered via
-    /// [`HidppChannel::add_msg_listener`].
-    /// This is used to remove the listener when the feature is dropped.
-    msg_listener_hdl: u32,
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
 }
 
 impl CreatableFeature for WirelessDeviceStatusFeature {
@@ -34,7 +29,7 @@ impl CreatableFeature for WirelessDeviceStatusFeature {
     fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
         let emitter = Arc::new(EventEmitter::new());
 
-        let hdl = chan.add_msg_listener({
+        let listener = chan.subscribe({
             let emitter = Arc::clone(&emitter);
 
             move |raw, matched| {
@@ -42,41 +37,18 @@ impl CreatableFeature for WirelessDeviceStatusFeature {
                     return;
                 }
 
-                let msg = v20::Message::from(raw);
-
-                let header = msg.header();
-                if header.device_index != device_index
-                    || header.feature_index != feature_index
-                    || nibble::combine(header.software_id, header.function_id) != 0
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) = parse::wireless_device_status_event(function_id, &payload)
                 {
-                    return;
+                    emitter.emit(event, raw);
                 }
-
-                let payload = msg.extend_payload();
-                let Ok(status) = WirelessDeviceStatus::try_from(payload[0]) else {
-                    return;
-                };
-                let Ok(request) = WirelessDeviceStatusRequest::try_from(payload[1]) else {
-                    return;
-                };
-                let Ok(reason) = WirelessDeviceStatusReason::try_from(payload[2]) else {
-                    return;
-                };
-
-                emitter.emit(WirelessDeviceStatusEvent::StatusBroadcast(
-                    WirelessDeviceStatusBroadcast {
-                        status,
-                        request,
-                        reason,
-                    },
-                ));
             }
         });
 
         Self {
-            chan,
             emitter,
-            msg_listener_hdl: hdl,
+            _listener: listener,
         }
     }
 }
@@ -88,11 +60,9 @@ impl EmittingFeature<WirelessDeviceStatusEvent> for WirelessDeviceStatusFeature
     fn listen(&self) -> async_channel::Receiver<WirelessDeviceStatusEvent> {
         self.emitter.create_receiver()
     }
-}
 
-impl Drop for WirelessDeviceStatusFeature {
-    fn drop(&mut self) {
-        self.chan.remove_msg_listener(self.msg_listener_hdl);
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<WirelessDeviceStatusEvent>> {
+        self.emitter.create_envelope_receiver()
     }
 }
 
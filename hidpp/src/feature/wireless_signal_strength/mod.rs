@@ -0,0 +1,117 @@
+//! Implements the `WirelessSignalStrength` feature (ID `0x0080`) that provides
+//! information about the quality of the wireless link to the device.
+
+use std::{
+    hash::Hash,
+    sync::{Arc, Weak},
+};
+
+use crate::{
+    channel::{self, HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    feature::{CreatableFeature, EmittingFeature, Feature},
+    nibble::U4,
+    parse,
+    protocol::v20::{self, Hidpp20Error},
+};
+
+/// Implements the `WirelessSignalStrength` / `0x0080` feature.
+pub struct WirelessSignalStrengthFeature {
+    /// The underlying HID++ channel.
+    chan: Weak<HidppChannel>,
+
+    /// The index of the device to implement the feature for.
+    device_index: u8,
+
+    /// The index of the feature in the feature table.
+    feature_index: u8,
+
+    /// The emitter used to emit events.
+    emitter: Arc<EventEmitter<WirelessSignalStrengthEvent>>,
+
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the feature is dropped.
+    _listener: ListenerGuard,
+}
+
+impl CreatableFeature for WirelessSignalStrengthFeature {
+    const ID: u16 = 0x0080;
+    const STARTING_VERSION: u8 = 0;
+
+    fn new(chan: Arc<HidppChannel>, device_index: u8, feature_index: u8) -> Self {
+        let emitter = Arc::new(EventEmitter::new());
+
+        let listener = chan.subscribe({
+            let emitter = Arc::clone(&emitter);
+
+            move |raw, matched| {
+                if matched {
+                    return;
+                }
+
+                if let Some((function_id, payload)) =
+                    parse::v20_notification(raw, device_index, feature_index)
+                    && let Some(event) =
+                        parse::wireless_signal_strength_event(function_id, &payload)
+                {
+                    emitter.emit(event, raw);
+                }
+            }
+        });
+
+        Self {
+            chan: Arc::downgrade(&chan),
+            device_index,
+            feature_index,
+            emitter,
+            _listener: listener,
+        }
+    }
+}
+
+impl Feature for WirelessSignalStrengthFeature {
+}
+
+impl EmittingFeature<WirelessSignalStrengthEvent> for WirelessSignalStrengthFeature {
+    fn listen(&self) -> async_channel::Receiver<WirelessSignalStrengthEvent> {
+        self.emitter.create_receiver()
+    }
+
+    fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<WirelessSignalStrengthEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+}
+
+impl WirelessSignalStrengthFeature {
+    /// Retrieves the current strength of the wireless signal in percent.
+    pub async fn get_signal_strength(&self) -> Result<u8, Hidpp20Error> {
+        let chan = channel::upgrade(&self.chan)?;
+        let response = chan
+            .send_v20_feature(
+                Self::ID,
+                v20::Message::Short(
+                    v20::MessageHeader {
+                        device_index: self.device_index,
+                        feature_index: self.feature_index,
+                        function_id: U4::from_lo(0),
+                        software_id: chan.get_sw_id(),
+                    },
+                    [0x00, 0x00, 0x00],
+                ),
+            )
+            .await?;
+
+        Ok(response.extend_payload()[0])
+    }
+}
+
+/// Represents an event emitted by the [`WirelessSignalStrengthFeature`]
+/// feature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum WirelessSignalStrengthEvent {
+    /// Is emitted whenever the strength of the wireless signal changes
+    /// noticeably, containing the new strength in percent.
+    StrengthChange(u8),
+}
@@ -0,0 +1,472 @@
+//! Restores the settings of a device after it reconnected.
+//!
+//! Most settings, like diverted controls or the mode of the scroll wheel, are
+//! reset by the device whenever it loses power or its connection to the
+//! receiver. A [`SettingsKeeper`] records every setting applied through it and
+//! applies all of them again whenever the device announces its reconnection
+//! via the
+//! [`WirelessDeviceStatusFeature`](crate::feature::wireless_device_status::WirelessDeviceStatusFeature).
+//!
+//! The module requires the `feat-wireless` cargo feature and at least one of
+//! the groups whose settings it keeps: `feat-controls`, `feat-dpi`,
+//! `feat-gaming` or `feat-wheel`.
+//!
+//! ```ignore
+//! use std::sync::Arc;
+//!
+//! use hidpp::{
+//!     feature::thumbwheel::ThumbwheelReportingMode,
+//!     keeper::{Setting, SettingsKeeper},
+//! };
+//!
+//! let keeper = Arc::new(SettingsKeeper::new(device));
+//! keeper
+//!     .apply(Setting::ThumbwheelReporting {
+//!         mode: ThumbwheelReportingMode::Diverted,
+//!         invert_direction: false,
+//!     })
+//!     .await
+//!     .expect("could not divert thumbwheel");
+//!
+//! tokio::spawn({
+//!     let keeper = Arc::clone(&keeper);
+//!
+//!     async move {
+//!         keeper
+//!             .keep(|setting, err| eprintln!("could not restore {:?}: {}", setting, err))
+//!             .await
+//!     }
+//! });
+//! ```
+
+use std::sync::{Arc, Mutex};
+
+use thiserror::Error;
+
+#[cfg(feature = "feat-dpi")]
+use crate::feature::adjustable_dpi::AdjustableDpiFeature;
+#[cfg(feature = "feat-gaming")]
+use crate::feature::extended_adjustable_report_rate::{
+    ExtendedAdjustableReportRateFeature,
+    ReportRate,
+};
+#[cfg(feature = "feat-controls")]
+use crate::feature::reprog_controls::{ControlReportingChange, ReprogControlsFeature};
+#[cfg(feature = "feat-wheel")]
+use crate::feature::{
+    crown::{CrownFeature, CrownRatchetMode, CrownReportingMode},
+    hires_wheel::{HiResWheelFeature, WheelEventTarget, WheelResolution},
+    smartshift::{self, SmartShiftFeature},
+    thumbwheel::{ThumbwheelFeature, ThumbwheelReportingMode},
+};
+#[cfg(feature = "feat-controls")]
+use crate::remap::{self, Remap, RemapError};
+use crate::{
+    device::Device,
+    feature::{
+        EmittingFeature,
+        Feature,
+        wireless_device_status::{
+            WirelessDeviceStatus,
+            WirelessDeviceStatusEvent,
+            WirelessDeviceStatusFeature,
+        },
+    },
+    protocol::v20::Hidpp20Error,
+};
+
+/// Represents a setting that is lost when the device reconnects.
+///
+/// Every variant corresponds to the function of a feature implementation
+/// taking the same parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum Setting {
+    /// Changes how a control is reported, see
+    /// [`ReprogControlsFeature::set_control_reporting`].
+    ///
+    /// Fields that are `None` keep the value of a previously recorded change
+    /// of the same control.
+    #[cfg(feature = "feat-controls")]
+    ControlReporting {
+        cid: u16,
+        change: ControlReportingChange,
+    },
+
+    /// Remaps a control, see [`remap::apply`].
+    #[cfg(feature = "feat-controls")]
+    Remap(Remap),
+
+    /// Sets the mode of the hi-res wheel, see
+    /// [`HiResWheelFeature::set_wheel_mode`].
+    #[cfg(feature = "feat-wheel")]
+    WheelMode {
+        target: WheelEventTarget,
+        resolution: WheelResolution,
+        inverted: bool,
+    },
+
+    /// Sets how the thumbwheel reports its events, see
+    /// [`ThumbwheelFeature::set_thumbwheel_reporting`].
+    #[cfg(feature = "feat-wheel")]
+    ThumbwheelReporting {
+        mode: ThumbwheelReportingMode,
+        invert_direction: bool,
+    },
+
+    /// Sets the ratchet control mode of the scroll wheel, see
+    /// [`SmartShiftFeature::set_ratchet_control_mode`].
+    ///
+    /// Fields that are `None` keep the value of a previously recorded mode.
+    #[cfg(feature = "feat-wheel")]
+    RatchetControlMode {
+        wheel_mode: Option<smartshift::WheelMode>,
+        auto_disengage: Option<u8>,
+        auto_disengage_default: Option<u8>,
+    },
+
+    /// Sets how the crown reports its events and whether it ratchets, see
+    /// [`CrownFeature::set_crown_mode`].
+    ///
+    /// Fields that are `None` keep the value of a previously recorded mode.
+    #[cfg(feature = "feat-wheel")]
+    CrownMode {
+        reporting: Option<CrownReportingMode>,
+        ratchet: Option<CrownRatchetMode>,
+    },
+
+    /// Sets the DPI of a sensor, see [`AdjustableDpiFeature::set_sensor_dpi`].
+    #[cfg(feature = "feat-dpi")]
+    SensorDpi {
+        sensor: u8,
+        dpi: u16,
+    },
+
+    /// Sets the report rate of the current connection, see
+    /// [`ExtendedAdjustableReportRateFeature::set_report_rate`].
+    #[cfg(feature = "feat-gaming")]
+    ReportRate(ReportRate),
+}
+
+impl Setting {
+    /// Applies the setting to a device.
+    ///
+    /// Returns [`SettingError::Unsupported`] if the device does not implement
+    /// the feature of the setting.
+    pub async fn apply(&self, device: &Device) -> Result<(), SettingError> {
+        match *self {
+            #[cfg(feature = "feat-controls")]
+            Self::ControlReporting {
+                cid,
+                change,
+            } => {
+                feature::<ReprogControlsFeature>(device)?
+                    .set_control_reporting(cid, change)
+                    .await?;
+            },
+            #[cfg(feature = "feat-controls")]
+            Self::Remap(remap) => {
+                remap::apply(device, &remap).await?;
+            },
+            #[cfg(feature = "feat-wheel")]
+            Self::WheelMode {
+                target,
+                resolution,
+                inverted,
+            } => {
+                feature::<HiResWheelFeature>(device)?
+                    .set_wheel_mode(target, resolution, inverted)
+                    .await?;
+            },
+            #[cfg(feature = "feat-wheel")]
+            Self::ThumbwheelReporting {
+                mode,
+                invert_direction,
+            } => {
+                feature::<ThumbwheelFeature>(device)?
+                    .set_thumbwheel_reporting(mode, invert_direction)
+                    .await?;
+            },
+            #[cfg(feature = "feat-wheel")]
+            Self::RatchetControlMode {
+                wheel_mode,
+                auto_disengage,
+                auto_disengage_default,
+            } => {
+                feature::<SmartShiftFeature>(device)?
+                    .set_ratchet_control_mode(wheel_mode, auto_disengage, auto_disengage_default)
+                    .await?;
+            },
+            #[cfg(feature = "feat-wheel")]
+            Self::CrownMode {
+                reporting,
+                ratchet,
+            } => {
+                feature::<CrownFeature>(device)?
+                    .set_crown_mode(reporting, ratchet)
+                    .await?;
+            },
+            #[cfg(feature = "feat-dpi")]
+            Self::SensorDpi {
+                sensor,
+                dpi,
+            } => {
+                feature::<AdjustableDpiFeature>(device)?
+                    .set_sensor_dpi(sensor, dpi)
+                    .await?;
+            },
+            #[cfg(feature = "feat-gaming")]
+            Self::ReportRate(rate) => {
+                feature::<ExtendedAdjustableReportRateFeature>(device)?
+                    .set_report_rate(rate)
+                    .await?;
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Merges a newer setting into this one if both change the same thing.
+    ///
+    /// Returns whether the setting was merged.
+    fn merge(&mut self, newer: &Setting) -> bool {
+        match (self, newer) {
+            #[cfg(feature = "feat-controls")]
+            (
+                Self::ControlReporting {
+                    cid,
+                    change,
+                },
+                Self::ControlReporting {
+                    cid: newer_cid,
+                    change: newer_change,
+                },
+            ) if cid == newer_cid => {
+                change.diverted = newer_change.diverted.or(change.diverted);
+                change.persistently_diverted = newer_change
+                    .persistently_diverted
+                    .or(change.persistently_diverted);
+                change.raw_xy_diverted = newer_change.raw_xy_diverted.or(change.raw_xy_diverted);
+                change.force_raw_xy_diverted = newer_change
+                    .force_raw_xy_diverted
+                    .or(change.force_raw_xy_diverted);
+                change.remapped = newer_change.remapped.or(change.remapped);
+                change.analytics_key_events = newer_change
+                    .analytics_key_events
+                    .or(change.analytics_key_events);
+                change.raw_wheel_diverted = newer_change
+                    .raw_wheel_diverted
+                    .or(change.raw_wheel_diverted);
+                true
+            },
+            #[cfg(feature = "feat-controls")]
+            (Self::Remap(remap), Self::Remap(newer_remap))
+                if remap.control == newer_remap.control =>
+            {
+                *remap = *newer_remap;
+                true
+            },
+            #[cfg(feature = "feat-wheel")]
+            (
+                this @ Self::WheelMode {
+                    ..
+                },
+                Self::WheelMode {
+                    ..
+                },
+            )
+            | (
+                this @ Self::ThumbwheelReporting {
+                    ..
+                },
+                Self::ThumbwheelReporting {
+                    ..
+                },
+            ) => {
+                *this = *newer;
+                true
+            },
+            #[cfg(feature = "feat-wheel")]
+            (
+                Self::RatchetControlMode {
+                    wheel_mode,
+                    auto_disengage,
+                    auto_disengage_default,
+                },
+                Self::RatchetControlMode {
+                    wheel_mode: newer_wheel_mode,
+                    auto_disengage: newer_auto_disengage,
+                    auto_disengage_default: newer_auto_disengage_default,
+                },
+            ) => {
+                *wheel_mode = newer_wheel_mode.or(*wheel_mode);
+                *auto_disengage = newer_auto_disengage.or(*auto_disengage);
+                *auto_disengage_default = newer_auto_disengage_default.or(*auto_disengage_default);
+                true
+            },
+            #[cfg(feature = "feat-wheel")]
+            (
+                Self::CrownMode {
+                    reporting,
+                    ratchet,
+                },
+                Self::CrownMode {
+                    reporting: newer_reporting,
+                    ratchet: newer_ratchet,
+                },
+            ) => {
+                *reporting = newer_reporting.or(*reporting);
+                *ratchet = newer_ratchet.or(*ratchet);
+                true
+            },
+            #[cfg(feature = "feat-dpi")]
+            (
+                Self::SensorDpi {
+                    sensor,
+                    dpi,
+                },
+                Self::SensorDpi {
+                    sensor: newer_sensor,
+                    dpi: newer_dpi,
+                },
+            ) if sensor == newer_sensor => {
+                *dpi = *newer_dpi;
+                true
+            },
+            #[cfg(feature = "feat-gaming")]
+            (Self::ReportRate(rate), Self::ReportRate(newer_rate)) => {
+                *rate = *newer_rate;
+                true
+            },
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+}
+
+/// Looks up a feature implementation of a device.
+#[cfg(any(
+    feature = "feat-controls",
+    feature = "feat-dpi",
+    feature = "feat-gaming",
+    feature = "feat-wheel"
+))]
+fn feature<F: Feature>(device: &Device) -> Result<Arc<F>, SettingError> {
+    device.get_feature::<F>().ok_or(SettingError::Unsupported)
+}
+
+/// Represents an error that may occur when applying a [`Setting`].
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum SettingError {
+    /// Indicates that the device does not implement the feature of the
+    /// setting.
+    #[error("the device does not support the setting")]
+    Unsupported,
+
+    /// Indicates that the feature reported an error.
+    #[error(transparent)]
+    Feature(#[from] Hidpp20Error),
+
+    /// Indicates that the control could not be remapped.
+    #[cfg(feature = "feat-controls")]
+    #[error(transparent)]
+    Remap(#[from] RemapError),
+}
+
+/// Records the settings applied to a device and restores them after the
+/// device reconnected.
+pub struct SettingsKeeper {
+    device: Device,
+    settings: Mutex<Vec<Setting>>,
+}
+
+impl SettingsKeeper {
+    /// Creates a keeper for a device without any recorded settings.
+    pub fn new(device: Device) -> Self {
+        Self {
+            device,
+            settings: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Returns the device the settings are kept for.
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Applies a setting to the device and records it if it succeeded.
+    pub async fn apply(&self, setting: Setting) -> Result<(), SettingError> {
+        setting.apply(&self.device).await?;
+        self.record(setting);
+
+        Ok(())
+    }
+
+    /// Records a setting without applying it, as if it was applied through
+    /// [`Self::apply`].
+    ///
+    /// A previously recorded setting changing the same thing is replaced.
+    pub fn record(&self, setting: Setting) {
+        let mut settings = self.settings.lock().unwrap();
+        if !settings.iter_mut().any(|recorded| recorded.merge(&setting)) {
+            settings.push(setting);
+        }
+    }
+
+    /// Returns all recorded settings in the order they were first recorded.
+    pub fn settings(&self) -> Vec<Setting> {
+        self.settings.lock().unwrap().clone()
+    }
+
+    /// Forgets all recorded settings.
+    pub fn clear(&self) {
+        self.settings.lock().unwrap().clear();
+    }
+
+    /// Applies all recorded settings again, in the order they were first
+    /// recorded.
+    ///
+    /// Settings that fail to apply are skipped and returned together with
+    /// their errors.
+    pub async fn replay(&self) -> Vec<(Setting, SettingError)> {
+        let mut failed = Vec::new();
+        for setting in self.settings() {
+            if let Err(err) = setting.apply(&self.device).await {
+                failed.push((setting, err));
+            }
+        }
+
+        failed
+    }
+
+    /// Replays the recorded settings whenever the device announces its
+    /// reconnection, calling `report` for every setting that fails to apply.
+    ///
+    /// This only returns once the device stops emitting events, or
+    /// immediately with [`SettingError::Unsupported`] if the device does not
+    /// implement the [`WirelessDeviceStatusFeature`].
+    pub async fn keep(
+        &self,
+        mut report: impl FnMut(&Setting, &SettingError),
+    ) -> Result<(), SettingError> {
+        let Some(feature) = self.device.get_feature::<WirelessDeviceStatusFeature>() else {
+            return Err(SettingError::Unsupported);
+        };
+        let rx = feature.listen();
+        drop(feature);
+
+        while let Ok(WirelessDeviceStatusEvent::StatusBroadcast(broadcast)) = rx.recv().await {
+            if broadcast.status != WirelessDeviceStatus::Reconnection {
+                continue;
+            }
+
+            for (setting, err) in self.replay().await {
+                report(&setting, &err);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -34,31 +34,31 @@
 //! re-exported for annotating your implementing type.
 //!
 //! The crate primarily used while testing and developing is [`async-hid`](https://crates.io/crates/async-hid).
-//! Providing an implementation for this crate behind a feature gate is
-//! planned and will be implemented once [retrieving the raw report descriptor](https://github.com/sidit77/async-hid/issues/17)
-//! is supported.
+//! Enabling the `async-hid` feature provides an implementation for it in the
+//! `async_hid` module.
 //!
 //! ## Initialize HID++ communication
 //!
 //! Once you have a working implementation of [`channel::RawHidChannel`], you
 //! can start by creating a [`channel::HidppChannel`]:
 //!
-//! ```
+//! ```no_run
 //! use std::sync::Arc;
 //!
 //! use hidpp::{
-//!     channel::HidppChannel,
+//!     channel::{HidppChannel, RawHidChannel},
 //!     device::Device,
 //!     feature::{
-//!        CreatableFeature,
-//!        EmittingFeature,
-//!        feature_set::v0::FeatureSetFeatureV0,
-//!        thumbwheel::v0::{ThumbwheelEvent, ThumbwheelFeatureV0, ThumbwheelReportingMode},
-//!    },
-//!    nibble::U4,
-//!    receiver::{self, Receiver, bolt::BoltEvent},
+//!         CreatableFeature,
+//!         EmittingFeature,
+//!         feature_set::FeatureSetFeature,
+//!         thumbwheel::{ThumbwheelEvent, ThumbwheelFeature, ThumbwheelReportingMode},
+//!     },
+//!     nibble::U4,
+//!     receiver::{self, Receiver, bolt::BoltEvent},
 //! };
-
+//!
+//! # async fn example(my_hid_channel: impl RawHidChannel) {
 //! // First, we will create the HID++ channel.
 //! // This function will return `ChannelError::HidppNotSupported`
 //! // if the passed HID channel does not support HID++.
@@ -81,28 +81,24 @@
 //!
 //! // If a wireless receiver is handling the HID++ communication,
 //! // we can detect it.
-//! let receiver = receiver::detect(Arc::clone(&channel)).expect("no receiver
-//! was found");
+//! let receiver = receiver::detect(Arc::clone(&channel)).expect("no receiver was found");
 //!
-//! // Assuming we have a Bolt receiver, we will now detect all connected
-//! devices. let Receiver::Bolt(bolt) = receiver else {
+//! // Assuming we have a Bolt receiver, we will now detect all connected devices.
+//! let Receiver::Bolt(bolt) = receiver else {
 //!     panic!("no Bolt receiver");
 //! };
-//! tokio::spawn({
-//!     let rx = bolt.listen();
-//!
-//!     async move {
-//!         while let Ok(BoltEvent::DeviceConnection(event)) = rx.recv() {
-//!             println!("Paired device found: {:x?}", event);
-//!         }
+//! let rx = bolt.listen();
+//! std::thread::spawn(move || {
+//!     while let Ok(BoltEvent::DeviceConnection(event)) = rx.recv_blocking() {
+//!         println!("Paired device found: {:x?}", event);
 //!     }
 //! });
 //! bolt.trigger_device_arrival()
 //!     .await
 //!     .expect("could not trigger device arrival notification");
 //!
-//! // Let's say we found a device with the index 0x02 using this enumeration.
-//! We // can now initialize it:
+//! // Let's say we found a device with the index 0x02 using this enumeration. We
+//! // can now initialize it:
 //! let mut device = Device::new(Arc::clone(&channel), 0x02)
 //!     .await
 //!     .expect("could not initialize device");
@@ -119,13 +115,13 @@
 //! // The resulting value will contain some information about the feature,
 //! // including its index:
 //! let info = root
-//!     .get_feature(FeatureSetFeatureV0::ID)
+//!     .get_feature(FeatureSetFeature::ID)
 //!     .await
 //!     .expect("could not look up feature")
 //!     .expect("FeatureSet feature is not supported");
 //!
-//! // As there are a lot of possible features and a given device only supports
-//! a // small subset of these, looking up every single feature ID using this
+//! // As there are a lot of possible features and a given device only supports a
+//! // small subset of these, looking up every single feature ID using this
 //! // technique is not practicable. That's why the `IFeatureSet` feature can be
 //! // used to enumerate over all supported features, but only if this feature
 //! // itself is supported by the device.
@@ -138,26 +134,47 @@
 //! // This crate provides Rust implementations for many HID++2.0 features. A
 //! // registry in the `hidpp::feature::registry` module maintains a list of all
 //! // known features and, if provided, a link to its implementation. The
-//! // `enumerate_features` function we just called automatically registers
-//! these // implementations for our device and we can now access them like
-//! this: let thumbwheel = device
-//!     .get_feature::<ThumbwheelFeatureV0>()
+//! // `enumerate_features` function we just called automatically registers these
+//! // implementations for our device and we can now access them like this:
+//! let thumbwheel = device
+//!     .get_feature::<ThumbwheelFeature>()
 //!     .expect("Thumbwheel feature is not supported");
 //! thumbwheel
 //!     .set_thumbwheel_reporting(ThumbwheelReportingMode::Diverted, false)
 //!     .await
 //!     .expect("could not divert thumbwheel");
+//! # }
 //! ```
-//! 
+//!
 //! That should cover the basic use case of this crate.
 
 pub use async_trait::async_trait;
 
-mod bcd;
+#[cfg(feature = "async-hid")]
+pub mod async_hid;
+pub mod bcd;
+pub mod broadcast;
+#[cfg(feature = "feat-controls")]
+pub mod buttons;
 pub mod channel;
 pub mod device;
-mod event;
+pub mod event;
 pub mod feature;
+#[cfg(all(
+    feature = "feat-wireless",
+    any(
+        feature = "feat-controls",
+        feature = "feat-dpi",
+        feature = "feat-gaming",
+        feature = "feat-wheel"
+    )
+))]
+pub mod keeper;
 pub mod nibble;
+pub mod parse;
+pub mod products;
 pub mod protocol;
 pub mod receiver;
+#[cfg(feature = "feat-controls")]
+pub mod remap;
+pub mod usage;
@@ -1,34 +1,140 @@
 //! A very simple u4/nibble implementation.
 
+use std::{
+    fmt,
+    ops::{Add, AddAssign, Sub, SubAssign},
+};
+
+use thiserror::Error;
+
 /// Represents an unsigned 4-bit value (nibble) encoded as a byte.
+///
+/// Arithmetic on nibbles wraps around, so `U4::MAX + U4::new(1)` is
+/// [`U4::MIN`].
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct U4(u8);
 
+/// Represents the error returned when converting a byte greater than `15` to
+/// a [`U4`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Error)]
+#[error("{0} is out of range for a nibble")]
+pub struct U4OutOfRangeError(pub u8);
+
 impl U4 {
+    /// The largest value of a nibble, `15`.
+    pub const MAX: Self = Self(0x0f);
+    /// The smallest value of a nibble, `0`.
+    pub const MIN: Self = Self(0);
+
+    /// Constructs a nibble from a value, which has to be at most `15`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is out of range, which fails compilation when used
+    /// in a const context.
+    pub const fn new(value: u8) -> Self {
+        assert!(value <= 0x0f, "value is out of range for a nibble");
+        Self(value)
+    }
+
     /// Constructs a nibble from the 4 low/rightmost bits of a byte.
-    pub fn from_lo(raw: u8) -> Self {
+    pub const fn from_lo(raw: u8) -> Self {
         Self(raw & 0x0f)
     }
 
     /// Constructs a nibble from the 4 high/leftmost bits of a byte.
-    pub fn from_hi(raw: u8) -> Self {
+    pub const fn from_hi(raw: u8) -> Self {
         Self(raw >> 4)
     }
 
     /// Constructs a byte with the nibble set as the 4 low/rightmost bits.
-    pub fn to_lo(self) -> u8 {
+    pub const fn to_lo(self) -> u8 {
         self.0
     }
 
     /// Constructs a byte with the nibble set as the 4 high/leftmost bits.
-    pub fn to_hi(self) -> u8 {
+    pub const fn to_hi(self) -> u8 {
         self.0 << 4
     }
+
+    /// Adds two nibbles, wrapping around at the boundary of the type.
+    pub const fn wrapping_add(self, rhs: Self) -> Self {
+        Self::from_lo(self.0 + rhs.0)
+    }
+
+    /// Subtracts two nibbles, wrapping around at the boundary of the type.
+    pub const fn wrapping_sub(self, rhs: Self) -> Self {
+        Self::from_lo(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl TryFrom<u8> for U4 {
+    type Error = U4OutOfRangeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if value > 0x0f {
+            return Err(U4OutOfRangeError(value));
+        }
+
+        Ok(Self(value))
+    }
+}
+
+impl From<U4> for u8 {
+    fn from(value: U4) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for U4 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl fmt::LowerHex for U4 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl fmt::UpperHex for U4 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::UpperHex::fmt(&self.0, f)
+    }
+}
+
+impl Add for U4 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.wrapping_add(rhs)
+    }
+}
+
+impl AddAssign for U4 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for U4 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.wrapping_sub(rhs)
+    }
+}
+
+impl SubAssign for U4 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
 }
 
 /// Combines two nibbles to a byte, with `a` being set to the 4 leftmost and
 /// `b` being set to the 4 rightmost bits.
-pub fn combine(a: U4, b: U4) -> u8 {
+pub const fn combine(a: U4, b: U4) -> u8 {
     a.to_hi() | b.to_lo()
 }
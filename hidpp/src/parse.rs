@@ -0,0 +1,591 @@
+//! Parses raw bytes into HID++ messages, errors and events.
+//!
+//! All functions in this module are pure: they perform no I/O and don't
+//! depend on a [`HidppChannel`](crate::channel::HidppChannel). The channel,
+//! features and receivers use them internally, but they are also exposed so
+//! fuzz targets and property tests can exercise them directly.
+//!
+//! None of the functions panic, regardless of the input. Input that can't be
+//! parsed yields [`None`].
+
+#[cfg(feature = "feat-lighting")]
+use crate::feature::backlight::{BacklightEvent, BacklightInfo};
+#[cfg(feature = "feat-dfu")]
+use crate::feature::dfu::{DfuEvent, DfuPacketStatus};
+#[cfg(feature = "feat-controls")]
+use crate::feature::reprog_controls::ReprogControlsEvent;
+#[cfg(feature = "feat-battery")]
+use crate::feature::{
+    battery_status::{BatteryLevelStatus, BatteryStatusEvent},
+    battery_voltage::{BatteryVoltageEvent, BatteryVoltageInfo},
+    solar_keyboard_dashboard::{SolarEvent, SolarMeasurement},
+    unified_battery::{BatteryEvent, BatteryInfo},
+};
+#[cfg(feature = "feat-wheel")]
+use crate::feature::{
+    crown::{CrownEvent, CrownPressState, CrownUpdate},
+    hires_wheel::{
+        HiResWheelEvent,
+        WheelAnalyticsData,
+        WheelMovementData,
+        WheelRatchetState,
+        WheelResolution,
+    },
+    thumbwheel::{ThumbwheelEvent, ThumbwheelRotationStatus, ThumbwheelStatusUpdate},
+};
+#[cfg(feature = "feat-keyboard")]
+use crate::feature::{
+    dual_platform::{DualPlatform, DualPlatformEvent},
+    fn_inversion::{FnInversionEvent, FnInversionVariant},
+    lock_key_state::{LockKeyState, LockKeyStateEvent},
+};
+#[cfg(feature = "feat-gaming")]
+use crate::feature::{
+    extended_adjustable_report_rate::{ReportRate, ReportRateEvent},
+    gaming_g_keys::GKeysEvent,
+    gaming_m_keys::MKeysEvent,
+};
+#[cfg(feature = "feat-wireless")]
+use crate::feature::{
+    wireless_device_status::{
+        WirelessDeviceStatus,
+        WirelessDeviceStatusBroadcast,
+        WirelessDeviceStatusEvent,
+        WirelessDeviceStatusReason,
+        WirelessDeviceStatusRequest,
+    },
+    wireless_signal_strength::WirelessSignalStrengthEvent,
+};
+use crate::{
+    channel::HidppMessage,
+    nibble::{self, U4},
+    protocol::{
+        v10::{self, Hidpp10Error, MessageType, RegisterContext},
+        v20::{self, CallContext, ErrorType, Hidpp20Error},
+    },
+    receiver::{
+        RECEIVER_DEVICE_INDEX,
+        bolt::{
+            BoltDeviceConnection,
+            BoltDeviceDiscoveryDeviceDetails,
+            BoltDeviceDiscoveryDeviceName,
+            BoltDeviceDiscoveryStatus,
+            BoltDeviceKind,
+            BoltEvent,
+            BoltPairingError,
+            BoltPairingPasskeyPressType,
+            BoltPairingPasskeyPressed,
+            BoltPairingPasskeyRequest,
+            BoltPairingStatus,
+        },
+    },
+};
+
+/// The payload of a HID++2.0 message, extended to the length of a long one.
+pub type V20Payload = [u8; 16];
+
+/// Classifies a raw HID report as a short or long HID++ message.
+pub fn message(raw: &[u8]) -> Option<HidppMessage> {
+    HidppMessage::read_raw(raw)
+}
+
+/// Decodes a raw HID report as a HID++1.0 message.
+pub fn v10_message(raw: &[u8]) -> Option<v10::Message> {
+    message(raw).map(v10::Message::from)
+}
+
+/// Decodes a raw HID report as a HID++2.0 message.
+pub fn v20_message(raw: &[u8]) -> Option<v20::Message> {
+    message(raw).map(v20::Message::from)
+}
+
+/// Checks whether a message is the response, successful or not, to a
+/// HID++1.0 register access of the given type.
+pub fn is_v10_response(
+    device_index: u8,
+    msg_type: MessageType,
+    address: u8,
+    msg: &HidppMessage,
+) -> bool {
+    let raw: [u8; 4] = match msg {
+        HidppMessage::Short(d) => [d[0], d[1], d[2], d[3]],
+        HidppMessage::Long(d) => [d[0], d[1], d[2], d[3]],
+    };
+
+    raw[0] == device_index
+        && ((raw[1] == msg_type.into() && raw[2] == address)
+            || (raw[1] == MessageType::Error.into()
+                && raw[2] == msg_type.into()
+                && raw[3] == address))
+}
+
+/// Parses the error carried by the response to a HID++1.0 register access.
+///
+/// Returns [`None`] if the response is not an error.
+pub fn v10_error(context: RegisterContext, response: &v10::Message) -> Option<Hidpp10Error> {
+    if response.header().sub_id != MessageType::Error.into() {
+        return None;
+    }
+
+    let payload = response.extend_payload();
+
+    Some(match v10::ErrorType::try_from(payload[2]) {
+        Ok(kind) => Hidpp10Error::RegisterAccess {
+            kind,
+            context,
+            response: payload.to_vec(),
+        },
+        Err(_) => Hidpp10Error::unsupported_response(context, &payload),
+    })
+}
+
+/// Checks whether a message is the response, successful or not, to the
+/// HID++2.0 request with the given header.
+pub fn is_v20_response(request: v20::MessageHeader, response: &v20::Message) -> bool {
+    let resp_header = response.header();
+
+    // A HID++2.0 error response sets the feature index to 0xFF and moves all header
+    // values starting from the real feature index one byte to the right.
+    let is_error = resp_header.device_index == request.device_index
+        && resp_header.feature_index == 0xff
+        && nibble::combine(resp_header.function_id, resp_header.software_id)
+            == request.feature_index
+        && response.extend_payload()[0]
+            == nibble::combine(request.function_id, request.software_id);
+
+    is_error || resp_header == request
+}
+
+/// Parses the error carried by the response to the HID++2.0 request with the
+/// given header.
+///
+/// Returns [`None`] if the response is not an error.
+pub fn v20_error(request: v20::MessageHeader, response: &v20::Message) -> Option<Hidpp20Error> {
+    if response.header().feature_index != 0xff {
+        return None;
+    }
+
+    let payload = response.extend_payload();
+    let context = CallContext::from(request);
+
+    Some(match ErrorType::try_from(payload[1]) {
+        Ok(kind) => Hidpp20Error::Feature {
+            kind,
+            context,
+            payload: payload.to_vec(),
+        },
+        Err(_) => Hidpp20Error::UnsupportedResponse {
+            context,
+            payload: payload.to_vec(),
+        },
+    })
+}
+
+/// Extracts the function ID and payload of a HID++2.0 notification sent by a
+/// feature of a device.
+///
+/// Returns [`None`] if the message is not a notification or belongs to
+/// another device or feature.
+pub fn v20_notification(
+    msg: HidppMessage,
+    device_index: u8,
+    feature_index: u8,
+) -> Option<(U4, V20Payload)> {
+    let msg = v20::Message::from(msg);
+    let header = msg.header();
+
+    if header.device_index != device_index
+        || header.feature_index != feature_index
+        || header.software_id.to_lo() != 0
+    {
+        return None;
+    }
+
+    Some((header.function_id, msg.extend_payload()))
+}
+
+#[cfg(feature = "feat-lighting")]
+/// Parses a notification of the `Backlight3` / `0x1983` feature.
+pub fn backlight_event(function_id: U4, payload: &V20Payload) -> Option<BacklightEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(BacklightEvent::InfoUpdate(BacklightInfo::from([
+        payload[0], payload[1], payload[2],
+    ])))
+}
+
+#[cfg(feature = "feat-battery")]
+/// Parses a notification of the `BatteryStatus` / `0x1000` feature.
+pub fn battery_status_event(function_id: U4, payload: &V20Payload) -> Option<BatteryStatusEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(BatteryStatusEvent::LevelStatusBroadcast(
+        BatteryLevelStatus::try_from([payload[0], payload[1], payload[2]]).ok()?,
+    ))
+}
+
+#[cfg(feature = "feat-battery")]
+/// Parses a notification of the `BatteryVoltage` / `0x1001` feature.
+pub fn battery_voltage_event(function_id: U4, payload: &V20Payload) -> Option<BatteryVoltageEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(BatteryVoltageEvent::InfoUpdate(BatteryVoltageInfo::from([
+        payload[0], payload[1], payload[2],
+    ])))
+}
+
+#[cfg(feature = "feat-wheel")]
+/// Parses a notification of the `Crown` / `0x4600` feature.
+pub fn crown_event(function_id: U4, payload: &V20Payload) -> Option<CrownEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(CrownEvent::Update(CrownUpdate {
+        rotation: payload[1] as i8,
+        ratchets: payload[2] as i8,
+        tapped: payload[5] == 0x01,
+        press: CrownPressState::from(payload[6]),
+    }))
+}
+
+#[cfg(feature = "feat-dfu")]
+/// Parses a notification of the `Dfu` / `0x00d0` feature.
+///
+/// The device reports the status of a block with the function ID of the
+/// request carrying the block, so all function IDs are accepted.
+pub fn dfu_event(_function_id: U4, payload: &V20Payload) -> Option<DfuEvent> {
+    Some(DfuEvent::Status(DfuPacketStatus::parse(payload)?))
+}
+
+#[cfg(feature = "feat-keyboard")]
+/// Parses a notification of the `DualPlatform` / `0x4530` feature.
+pub fn dual_platform_event(function_id: U4, payload: &V20Payload) -> Option<DualPlatformEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(DualPlatformEvent::PlatformChange(
+        DualPlatform::try_from(payload[0]).ok()?,
+    ))
+}
+
+#[cfg(feature = "feat-gaming")]
+/// Parses a notification of the `ExtendedAdjustableReportRate` / `0x8061`
+/// feature.
+pub fn extended_adjustable_report_rate_event(
+    function_id: U4,
+    payload: &V20Payload,
+) -> Option<ReportRateEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(ReportRateEvent::RateChange(
+        ReportRate::try_from(payload[0]).ok()?,
+    ))
+}
+
+#[cfg(feature = "feat-keyboard")]
+/// Parses a notification of a feature of the `FnInversion` family.
+pub fn fn_inversion_event(
+    variant: FnInversionVariant,
+    function_id: U4,
+    payload: &V20Payload,
+) -> Option<FnInversionEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(FnInversionEvent::StateChange(variant.parse_state(payload)))
+}
+
+#[cfg(feature = "feat-gaming")]
+/// Parses a notification of the `GamingGKeys` / `0x8010` feature.
+pub fn gaming_g_keys_event(function_id: U4, payload: &V20Payload) -> Option<GKeysEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    let pressed = u32::from_le_bytes([payload[0], payload[1], payload[2], payload[3]]);
+
+    Some(GKeysEvent::KeysDown(
+        (0..32)
+            .filter(|bit| pressed & (1 << bit) != 0)
+            .map(|bit| bit + 1)
+            .collect(),
+    ))
+}
+
+#[cfg(feature = "feat-gaming")]
+/// Parses a notification of the `GamingMKeys` / `0x8020` feature.
+pub fn gaming_m_keys_event(function_id: U4, payload: &V20Payload) -> Option<MKeysEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(MKeysEvent::KeysDown(
+        (0..8)
+            .filter(|bit| payload[0] & (1 << bit) != 0)
+            .map(|bit| bit + 1)
+            .collect(),
+    ))
+}
+
+#[cfg(feature = "feat-keyboard")]
+/// Parses a notification of the `LockKeyState` / `0x4220` feature.
+pub fn lock_key_state_event(function_id: U4, payload: &V20Payload) -> Option<LockKeyStateEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(LockKeyStateEvent::StateChange(LockKeyState::from(
+        payload[0],
+    )))
+}
+
+#[cfg(feature = "feat-wheel")]
+/// Parses a notification of the `HiResWheel` / `0x2121` feature.
+pub fn hires_wheel_event(function_id: U4, payload: &V20Payload) -> Option<HiResWheelEvent> {
+    match function_id.to_lo() {
+        0 => Some(HiResWheelEvent::WheelMovement(WheelMovementData {
+            resolution: WheelResolution::try_from((payload[0] & (1 << 4)) >> 4).ok()?,
+            periods: U4::from_lo(payload[0]),
+            delta_vertical: i16::from_be_bytes([payload[1], payload[2]]),
+        })),
+        1 => Some(HiResWheelEvent::RatchetSwitch(
+            WheelRatchetState::try_from(payload[0] & 1).ok()?,
+        )),
+        2 => Some(HiResWheelEvent::Analytics(WheelAnalyticsData {
+            raw: *payload,
+        })),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "feat-controls")]
+/// Parses a notification of the `ReprogControls` / `0x1b04` feature.
+pub fn reprog_controls_event(function_id: U4, payload: &V20Payload) -> Option<ReprogControlsEvent> {
+    match function_id.to_lo() {
+        0 => Some(ReprogControlsEvent::DivertedButtons(
+            payload[..8]
+                .chunks_exact(2)
+                .map(|x| u16::from_be_bytes([x[0], x[1]]))
+                .filter(|&cid| cid != 0)
+                .collect(),
+        )),
+        1 => Some(ReprogControlsEvent::DivertedRawXy {
+            dx: i16::from_be_bytes([payload[0], payload[1]]),
+            dy: i16::from_be_bytes([payload[2], payload[3]]),
+        }),
+        2 => Some(ReprogControlsEvent::AnalyticsKeyEvents(
+            payload[..15]
+                .chunks_exact(3)
+                .map(|x| (u16::from_be_bytes([x[0], x[1]]), x[2] != 0))
+                .filter(|&(cid, _)| cid != 0)
+                .collect(),
+        )),
+        3 => Some(ReprogControlsEvent::DivertedRawWheel {
+            high_resolution: payload[0] & (1 << 4) != 0,
+            periods: payload[0] & 0x0f,
+            delta: i16::from_be_bytes([payload[1], payload[2]]),
+        }),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "feat-battery")]
+/// Parses a notification of the `SolarKeyboardDashboard` / `0x4301` feature.
+pub fn solar_keyboard_dashboard_event(function_id: U4, payload: &V20Payload) -> Option<SolarEvent> {
+    // The keyboard marks notifications containing valid measurements.
+    if &payload[5..9] != b"GOOD" {
+        return None;
+    }
+
+    let measurement = SolarMeasurement {
+        charge: payload[0],
+        lux: u16::from_be_bytes([payload[1], payload[2]]),
+    };
+
+    match function_id.to_lo() {
+        0 => Some(SolarEvent::BatteryBroadcast(measurement)),
+        1 => Some(SolarEvent::LightMeasurement(measurement)),
+        2 => Some(SolarEvent::LightCheckButton(measurement)),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "feat-wheel")]
+/// Parses a notification of the `Thumbwheel` / `0x2150` feature.
+pub fn thumbwheel_event(function_id: U4, payload: &V20Payload) -> Option<ThumbwheelEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(ThumbwheelEvent::StatusUpdate(ThumbwheelStatusUpdate {
+        rotation: i16::from_be_bytes([payload[0], payload[1]]),
+        time_elapsed: u16::from_be_bytes([payload[2], payload[3]]),
+        rotation_status: ThumbwheelRotationStatus::try_from(payload[4]).ok()?,
+        touch: payload[5] & (1 << 1) != 0,
+        proxy: payload[5] & (1 << 2) != 0,
+        single_tap: payload[5] & (1 << 3) != 0,
+    }))
+}
+
+#[cfg(feature = "feat-battery")]
+/// Parses a notification of the `UnifiedBattery` / `0x1004` feature.
+pub fn unified_battery_event(function_id: U4, payload: &V20Payload) -> Option<BatteryEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(BatteryEvent::InfoUpdate(BatteryInfo::parse(
+        payload, false,
+    )?))
+}
+
+#[cfg(feature = "feat-battery")]
+/// Parses a notification of version 1 of the `UnifiedBattery` / `0x1004`
+/// feature, which includes the status of the external power source.
+pub fn unified_battery_v1_event(function_id: U4, payload: &V20Payload) -> Option<BatteryEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(BatteryEvent::InfoUpdate(BatteryInfo::parse(payload, true)?))
+}
+
+#[cfg(feature = "feat-wireless")]
+/// Parses a notification of the `WirelessDeviceStatus` / `0x1d4b` feature.
+pub fn wireless_device_status_event(
+    function_id: U4,
+    payload: &V20Payload,
+) -> Option<WirelessDeviceStatusEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(WirelessDeviceStatusEvent::StatusBroadcast(
+        WirelessDeviceStatusBroadcast {
+            status: WirelessDeviceStatus::try_from(payload[0]).ok()?,
+            request: WirelessDeviceStatusRequest::try_from(payload[1]).ok()?,
+            reason: WirelessDeviceStatusReason::try_from(payload[2]).ok()?,
+        },
+    ))
+}
+
+#[cfg(feature = "feat-wireless")]
+/// Parses a notification of the `WirelessSignalStrength` / `0x0080` feature.
+pub fn wireless_signal_strength_event(
+    function_id: U4,
+    payload: &V20Payload,
+) -> Option<WirelessSignalStrengthEvent> {
+    if function_id.to_lo() != 0 {
+        return None;
+    }
+
+    Some(WirelessSignalStrengthEvent::StrengthChange(payload[0]))
+}
+
+/// Parses a notification sent by a Bolt receiver.
+pub fn bolt_event(msg: HidppMessage) -> Option<BoltEvent> {
+    let parsed = v10::Message::from(msg);
+    let header = parsed.header();
+    let payload = parsed.extend_payload();
+
+    if header.device_index != RECEIVER_DEVICE_INDEX && header.sub_id != 0x41 {
+        return None;
+    }
+
+    let counter = |payload: &[u8]| payload[0] as u16 + payload[1] as u16 * 256;
+
+    match header.sub_id {
+        // Device connection
+        0x41 => Some(BoltEvent::DeviceConnection(BoltDeviceConnection {
+            index: header.device_index,
+            kind: BoltDeviceKind::try_from(payload[1] & 0x0f).ok()?,
+            encrypted: payload[1] & (1 << 5) != 0,
+            online: payload[1] & (1 << 6) == 0,
+            wpid: u16::from_le_bytes([payload[2], payload[3]]),
+        })),
+        // Device discovery
+        0x4f => match payload[2] {
+            // Device data
+            0 => {
+                // I have no idea what payload[2]. payload[12], payload[13] and
+                // payload[15] contain.
+                // Last one seems to change marginally, maybe some kind of
+                // connection metric?
+
+                Some(BoltEvent::DeviceDiscoveryDeviceDetails(
+                    BoltDeviceDiscoveryDeviceDetails {
+                        counter: counter(&payload),
+                        kind: BoltDeviceKind::try_from(payload[4] & 0x0f).ok()?,
+                        wpid: u16::from_le_bytes([payload[5], payload[6]]),
+                        address: payload[7..=12].try_into().unwrap(),
+                        authentication: payload[15],
+                    },
+                ))
+            },
+            // Device name
+            1 => {
+                let name = payload.get(4..4 + payload[3] as usize)?;
+
+                Some(BoltEvent::DeviceDiscoveryDeviceName(
+                    BoltDeviceDiscoveryDeviceName {
+                        counter: counter(&payload),
+                        name: str::from_utf8(name).ok()?.to_string(),
+                    },
+                ))
+            },
+            _ => None,
+        },
+        // Device discovery status
+        0x53 => Some(BoltEvent::DeviceDiscoveryStatus(
+            BoltDeviceDiscoveryStatus {
+                discovery_enabled: payload[0] == 0x00,
+            },
+        )),
+        // Pairing status
+        0x54 => {
+            // payload[0] contains some kind of information about the status. I don't
+            // know how to map that though.
+
+            let pairing_error = match payload[1] {
+                0x00 => None,
+                error => Some(BoltPairingError::try_from(error).ok()?),
+            };
+
+            Some(BoltEvent::PairingStatus(BoltPairingStatus {
+                device_address: payload[2..=7].try_into().unwrap(),
+                pairing_error,
+                slot: if payload[8] == 0x00 {
+                    None
+                } else {
+                    Some(payload[8])
+                },
+            }))
+        },
+        // Passkey request
+        0x4d => Some(BoltEvent::PairingPasskeyRequest(
+            BoltPairingPasskeyRequest {
+                device_address: payload[7..=12].try_into().unwrap(),
+                passkey: str::from_utf8(&payload[1..=6]).ok()?.to_string(),
+            },
+        )),
+        // Passkey pressed
+        0x4e => Some(BoltEvent::PairingPasskeyPressed(
+            BoltPairingPasskeyPressed {
+                device_address: payload[1..=6].try_into().unwrap(),
+                press_type: BoltPairingPasskeyPressType::try_from(payload[0]).ok()?,
+            },
+        )),
+        _ => None,
+    }
+}
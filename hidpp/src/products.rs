@@ -0,0 +1,90 @@
+//! A database of known products and their product IDs.
+//!
+//! Receivers report the wireless product ID (WPID) of paired devices even if
+//! they are offline, while their name features are unreachable. This module
+//! maps these IDs to the marketing names and device types of the products.
+//!
+//! Bolt receivers and Bluetooth use the same product IDs, so they are also
+//! found in the model IDs reported by the `DeviceInformation` feature.
+//!
+//! ```
+//! use hidpp::products;
+//!
+//! assert_eq!(products::lookup_wpid(0xb034).unwrap().name, "MX Master 3S");
+//! assert_eq!(
+//!     products::lookup_model_id(&[0xb023, 0xc52b, 0x4082])
+//!         .unwrap()
+//!         .name,
+//!     "MX Master 3"
+//! );
+//! assert!(products::lookup_wpid(0x0000).is_none());
+//! ```
+
+use crate::feature::device_type_and_name::DeviceType;
+
+/// Represents a known product.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct Product {
+    /// The marketing name of the product.
+    pub name: &'static str,
+
+    /// The type of the product.
+    pub kind: DeviceType,
+
+    /// The wireless and Bluetooth product IDs the product is known by.
+    pub pids: &'static [u16],
+}
+
+/// All known products.
+pub const PRODUCTS: &[Product] = &[
+    product("MX Master", DeviceType::Mouse, &[
+        0x4041, 0x4060, 0x4071, 0xb012,
+    ]),
+    product("MX Master 2S", DeviceType::Mouse, &[0x4069, 0xb019]),
+    product("MX Master 3", DeviceType::Mouse, &[0x4082, 0xb023]),
+    product("MX Master 3 for Business", DeviceType::Mouse, &[0xb028]),
+    product("MX Master 3S", DeviceType::Mouse, &[0xb034]),
+    product("MX Anywhere 2", DeviceType::Mouse, &[0x404a, 0x4072]),
+    product("MX Anywhere 2S", DeviceType::Mouse, &[0x406a, 0xb01a]),
+    product("MX Anywhere 3", DeviceType::Mouse, &[0x4090, 0xb025]),
+    product("MX Anywhere 3S", DeviceType::Mouse, &[0xb037]),
+    product("MX Vertical", DeviceType::Mouse, &[0x407b, 0xb020]),
+    product("Lift", DeviceType::Mouse, &[0xb031]),
+    product("M720 Triathlon", DeviceType::Mouse, &[0x405e, 0xb015]),
+    product("MX Ergo", DeviceType::Trackball, &[0x406f]),
+    product("MX Keys", DeviceType::Keyboard, &[0x408a, 0xb35b]),
+    product("MX Keys for Business", DeviceType::Keyboard, &[0xb363]),
+    product("MX Keys Mini", DeviceType::Keyboard, &[0xb369]),
+    product("MX Keys S", DeviceType::Keyboard, &[0xb378]),
+    product("MX Mechanical", DeviceType::Keyboard, &[0xb366]),
+    product("MX Mechanical Mini", DeviceType::Keyboard, &[0xb367]),
+    product("Craft", DeviceType::Keyboard, &[0x4066, 0xb350]),
+    product("K400 Plus", DeviceType::Keyboard, &[0x404d]),
+    product("K800", DeviceType::Keyboard, &[0x2010]),
+];
+
+/// Constructs a product entry.
+const fn product(name: &'static str, kind: DeviceType, pids: &'static [u16]) -> Product {
+    Product {
+        name,
+        kind,
+        pids,
+    }
+}
+
+/// Looks up a product by the wireless product ID reported by a receiver or
+/// its Bluetooth product ID.
+pub fn lookup_wpid(wpid: u16) -> Option<&'static Product> {
+    PRODUCTS.iter().find(|product| product.pids.contains(&wpid))
+}
+
+/// Looks up a product by the model ID reported by the `DeviceInformation`
+/// feature, which contains the product IDs of all transports of the device.
+pub fn lookup_model_id(model_id: &[u16; 3]) -> Option<&'static Product> {
+    model_id
+        .iter()
+        .filter(|&&pid| pid != 0)
+        .find_map(|&pid| lookup_wpid(pid))
+}
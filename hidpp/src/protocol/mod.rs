@@ -42,13 +42,34 @@ pub enum ProtocolVersion {
     },
 }
 
+/// Represents the result of probing a device index using
+/// [`determine_version`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum DeviceProbe {
+    /// A device responded and supports the given protocol version.
+    Found(ProtocolVersion),
+
+    /// A device is paired to the device index, but is currently not connected,
+    /// which usually means it is asleep.
+    Offline,
+
+    /// There is no device paired to the device index.
+    NotFound,
+
+    /// The device index responded with an unexpected HID++1.0 error code.
+    Unknown(u8),
+}
+
 /// Tries to determine the protocol version of a specific device.
 ///
-/// Returns `Ok(None)` if no device was found for the given device index.
+/// HID++1.0 errors returned for the device index are classified to tell
+/// offline devices apart from absent ones.
 pub async fn determine_version(
     chan: &HidppChannel,
     device_index: u8,
-) -> Result<Option<ProtocolVersion>, ChannelError> {
+) -> Result<DeviceProbe, ChannelError> {
     // To determine the protocol version, we send a HID++2.0 ping message
     // feature with index 0x00, function 0x01).
     // Devices supporting protocol >=2.0 will respond with a defined response
@@ -77,16 +98,15 @@ pub async fn determine_version(
 
             // We only care about HID++1.0 error messages, which are always short according
             // to the spec.
-            if let v10::Message::Short(header, payload) = v10::Message::from(*resp) {
-                if header.device_index == device_index
-                    && header.sub_id == v10::MessageType::Error.into()
-                    // The feature index we sent would be interpreted as the sub ID by HID++1.0, which is included in the error message.
-                    && payload[0] == 0x00
-                    // The function & software IDs would be interpreted as the register address in HID++1.0.
-                    && payload[1] == nibble::combine(msg.header().function_id, sw_id)
-                {
-                    return true;
-                }
+            if let v10::Message::Short(header, payload) = v10::Message::from(*resp)
+                && header.device_index == device_index
+                && header.sub_id == v10::MessageType::Error.into()
+                // The feature index we sent would be interpreted as the sub ID by HID++1.0, which is included in the error message.
+                && payload[0] == 0x00
+                // The function & software IDs would be interpreted as the register address in HID++1.0.
+                && payload[1] == nibble::combine(msg.header().function_id, sw_id)
+            {
+                return true;
             }
 
             false
@@ -96,19 +116,29 @@ pub async fn determine_version(
     let v20_msg = v20::Message::from(response);
     if v20_msg.header() == msg.header() {
         let payload = v20_msg.extend_payload();
-        return Ok(Some(ProtocolVersion::V20 {
+        return Ok(DeviceProbe::Found(ProtocolVersion::V20 {
             protocol_num: payload[0],
             target_sw: payload[1],
         }));
     }
 
     let v10::Message::Short(_, payload) = v10::Message::from(response) else {
-        return Ok(None);
+        return Ok(DeviceProbe::NotFound);
     };
 
-    if payload[2] == v10::ErrorType::InvalidSubId.into() {
-        Ok(Some(ProtocolVersion::V10))
-    } else {
-        Ok(None)
-    }
+    Ok(match v10::ErrorType::try_from(payload[2]) {
+        Ok(v10::ErrorType::InvalidSubId) => DeviceProbe::Found(ProtocolVersion::V10),
+        Ok(v10::ErrorType::ResourceError) => DeviceProbe::Offline,
+        Ok(v10::ErrorType::UnknownDevice) => DeviceProbe::NotFound,
+        _ => DeviceProbe::Unknown(payload[2]),
+    })
+}
+
+/// Formats bytes as space-separated hexadecimal values for error messages.
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
 }
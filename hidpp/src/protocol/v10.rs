@@ -1,14 +1,14 @@
 //! Implements functionality specific to HID++1.0.
 
+use std::fmt;
+
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use thiserror::Error;
 
-use crate::channel::{
-    ChannelError,
-    HidppChannel,
-    HidppMessage,
-    LONG_REPORT_LENGTH,
-    SHORT_REPORT_LENGTH,
+use crate::{
+    channel::{ChannelError, HidppChannel, HidppMessage, LONG_REPORT_LENGTH, SHORT_REPORT_LENGTH},
+    parse,
+    protocol::hex,
 };
 
 /// Represents the header that every [`HidppMessage`] of HID++1.0 starts with.
@@ -101,19 +101,6 @@ impl From<Message> for HidppMessage {
     }
 }
 
-fn is_rap_response(device: u8, msg_type: MessageType, address: u8, msg: &HidppMessage) -> bool {
-    let raw: [u8; 4] = match msg {
-        HidppMessage::Short(d) => d[..4].try_into().unwrap(),
-        HidppMessage::Long(d) => d[..4].try_into().unwrap(),
-    };
-
-    raw[0] == device
-        && ((raw[1] == msg_type.into() && raw[2] == address)
-            || (raw[1] == MessageType::Error.into()
-                && raw[2] == msg_type.into()
-                && raw[3] == address))
-}
-
 impl HidppChannel {
     /// Reads the data from a short 3-byte register using HID++1.0/RAP.
     pub async fn read_register(
@@ -135,18 +122,18 @@ impl HidppChannel {
                     data,
                 )
                 .into(),
-                move |raw| is_rap_response(device, MessageType::GetRegister, address, raw),
+                move |raw| parse::is_v10_response(device, MessageType::GetRegister, address, raw),
             )
             .await?,
         );
 
         let payload = response.extend_payload();
 
-        if response.header().sub_id == MessageType::Error.into() {
-            let err =
-                ErrorType::try_from(payload[2]).map_err(|_| Hidpp10Error::UnsupportedResponse)?;
-
-            return Err(Hidpp10Error::RegisterAccess(err));
+        if let Some(err) = parse::v10_error(
+            RegisterContext::new(device, MessageType::GetRegister, address),
+            &response,
+        ) {
+            return Err(err);
         }
 
         Ok(payload[1..=3].try_into().unwrap())
@@ -172,16 +159,16 @@ impl HidppChannel {
                     data,
                 )
                 .into(),
-                move |raw| is_rap_response(device, MessageType::SetRegister, address, raw),
+                move |raw| parse::is_v10_response(device, MessageType::SetRegister, address, raw),
             )
             .await?,
         );
 
-        if response.header().sub_id == MessageType::Error.into() {
-            let err = ErrorType::try_from(response.extend_payload()[2])
-                .map_err(|_| Hidpp10Error::UnsupportedResponse)?;
-
-            return Err(Hidpp10Error::RegisterAccess(err));
+        if let Some(err) = parse::v10_error(
+            RegisterContext::new(device, MessageType::SetRegister, address),
+            &response,
+        ) {
+            return Err(err);
         }
 
         Ok(())
@@ -207,18 +194,20 @@ impl HidppChannel {
                     data,
                 )
                 .into(),
-                move |raw| is_rap_response(device, MessageType::GetLongRegister, address, raw),
+                move |raw| {
+                    parse::is_v10_response(device, MessageType::GetLongRegister, address, raw)
+                },
             )
             .await?,
         );
 
         let payload = response.extend_payload();
 
-        if response.header().sub_id == MessageType::Error.into() {
-            let err =
-                ErrorType::try_from(payload[2]).map_err(|_| Hidpp10Error::UnsupportedResponse)?;
-
-            return Err(Hidpp10Error::RegisterAccess(err));
+        if let Some(err) = parse::v10_error(
+            RegisterContext::new(device, MessageType::GetLongRegister, address),
+            &response,
+        ) {
+            return Err(err);
         }
 
         Ok(payload[1..=16].try_into().unwrap())
@@ -245,16 +234,18 @@ impl HidppChannel {
                     data,
                 )
                 .into(),
-                move |raw| is_rap_response(device, MessageType::SetLongRegister, address, raw),
+                move |raw| {
+                    parse::is_v10_response(device, MessageType::SetLongRegister, address, raw)
+                },
             )
             .await?,
         );
 
-        if response.header().sub_id == MessageType::Error.into() {
-            let err = ErrorType::try_from(response.extend_payload()[2])
-                .map_err(|_| Hidpp10Error::UnsupportedResponse)?;
-
-            return Err(Hidpp10Error::RegisterAccess(err));
+        if let Some(err) = parse::v10_error(
+            RegisterContext::new(device, MessageType::SetLongRegister, address),
+            &response,
+        ) {
+            return Err(err);
         }
 
         Ok(())
@@ -350,6 +341,40 @@ pub enum ErrorType {
     WrongPinCode = 0x0c,
 }
 
+/// Identifies the HID++1.0 register access an error belongs to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct RegisterContext {
+    /// The index of the device the register belongs to.
+    pub device_index: u8,
+
+    /// The sub ID of the request, determining the kind of access.
+    pub sub_id: u8,
+
+    /// The address of the register.
+    pub address: u8,
+}
+
+impl RegisterContext {
+    pub fn new(device_index: u8, sub_id: MessageType, address: u8) -> Self {
+        Self {
+            device_index,
+            sub_id: sub_id.into(),
+            address,
+        }
+    }
+}
+
+impl fmt::Display for RegisterContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "register {:#04x} (sub ID {:#04x}) of device {:#04x}",
+            self.address, self.sub_id, self.device_index
+        )
+    }
+}
+
 /// Represents an error that may occur when accessing registers using HID++1.0.
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -360,10 +385,38 @@ pub enum Hidpp10Error {
     Channel(#[from] ChannelError),
 
     /// Indicates that a register access failed.
-    #[error("a HID++1.0 register access resulted in an error")]
-    RegisterAccess(ErrorType),
+    #[error(
+        "accessing {context} resulted in error {kind:?} (response {})",
+        hex(response)
+    )]
+    RegisterAccess {
+        kind: ErrorType,
+        context: RegisterContext,
+
+        /// The raw payload of the error response.
+        response: Vec<u8>,
+    },
 
     /// Indicates that a received response is not fully supported.
-    #[error("the received response from the device is (partly) unsupported")]
-    UnsupportedResponse,
+    #[error(
+        "the response for {context} is (partly) unsupported (response {})",
+        hex(response)
+    )]
+    UnsupportedResponse {
+        context: RegisterContext,
+
+        /// The raw bytes that could not be parsed.
+        response: Vec<u8>,
+    },
+}
+
+impl Hidpp10Error {
+    /// Constructs an [`Hidpp10Error::UnsupportedResponse`] for register data
+    /// that could not be parsed.
+    pub fn unsupported_response(context: RegisterContext, response: &[u8]) -> Self {
+        Self::UnsupportedResponse {
+            context,
+            response: response.to_vec(),
+        }
+    }
 }
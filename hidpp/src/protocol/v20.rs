@@ -1,11 +1,15 @@
 //! Implements functionality specific to HID++2.0.
 
+use std::fmt;
+
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use thiserror::Error;
 
 use crate::{
     channel::{ChannelError, HidppChannel, HidppMessage, LONG_REPORT_LENGTH, SHORT_REPORT_LENGTH},
     nibble::{self, U4},
+    parse,
+    protocol::hex,
 };
 
 /// Represents the header that every [`HidppMessage`] of HID++2.0 starts with.
@@ -124,32 +128,29 @@ impl HidppChannel {
 
         let response = Message::from(
             self.send(msg.into(), move |&response| {
-                let resp_msg = Message::from(response);
-                let resp_header = resp_msg.header();
-
-                // A HID++2.0 error response sets the feature index to 0xFF and moves all header
-                // values starting from the real feature index one byte to the right.
-                let is_error = resp_header.device_index == header.device_index
-                    && resp_header.feature_index == 0xff
-                    && nibble::combine(resp_header.function_id, resp_header.software_id)
-                        == header.feature_index
-                    && resp_msg.extend_payload()[0]
-                        == nibble::combine(header.function_id, header.software_id);
-
-                is_error || resp_header == header
+                parse::is_v20_response(header, &Message::from(response))
             })
             .await?,
         );
 
-        if response.header().feature_index == 0xff {
-            let err = ErrorType::try_from(response.extend_payload()[1])
-                .map_err(|_| Hidpp20Error::UnsupportedResponse)?;
-
-            return Err(Hidpp20Error::Feature(err));
+        if let Some(err) = parse::v20_error(header, &response) {
+            return Err(err);
         }
 
         Ok(response)
     }
+
+    /// Sends a HID++2.0 message to a feature like [`Self::send_v20`], but
+    /// records the ID of the feature in the [`CallContext`] of any error.
+    pub async fn send_v20_feature(
+        &self,
+        feature_id: u16,
+        msg: Message,
+    ) -> Result<Message, Hidpp20Error> {
+        self.send_v20(msg)
+            .await
+            .map_err(|err| err.with_feature_id(feature_id))
+    }
 }
 
 /// Represents the type of an error a HID++2.0 device returns if a feature
@@ -171,6 +172,52 @@ pub enum ErrorType {
     Unsupported = 9,
 }
 
+/// Identifies the HID++2.0 feature function call an error belongs to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CallContext {
+    /// The index of the device the function was called on.
+    pub device_index: u8,
+
+    /// The index of the feature the function belongs to.
+    pub feature_index: u8,
+
+    /// The ID of the feature the function belongs to, if it was known to the
+    /// caller. Messages only carry the feature index.
+    pub feature_id: Option<u16>,
+
+    /// The ID of the function that was called.
+    pub function_id: U4,
+}
+
+impl From<MessageHeader> for CallContext {
+    fn from(header: MessageHeader) -> Self {
+        Self {
+            device_index: header.device_index,
+            feature_index: header.feature_index,
+            feature_id: None,
+            function_id: header.function_id,
+        }
+    }
+}
+
+impl fmt::Display for CallContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.feature_id {
+            Some(feature_id) => write!(
+                f,
+                "function {} of feature {:#06x} (index {:#04x}) on device {:#04x}",
+                self.function_id, feature_id, self.feature_index, self.device_index
+            ),
+            None => write!(
+                f,
+                "function {} of feature index {:#04x} on device {:#04x}",
+                self.function_id, self.feature_index, self.device_index
+            ),
+        }
+    }
+}
+
 /// Represents an error that may occur when calling a HID++2.0 feature function.
 #[derive(Debug, Error)]
 #[non_exhaustive]
@@ -182,10 +229,68 @@ pub enum Hidpp20Error {
 
     /// Indicates that a call to a HID++2.0 feature function resulted in an
     /// error.
-    #[error("a HID++2.0 feature returned an error")]
-    Feature(ErrorType),
+    #[error("{context} returned error {kind:?} (payload {})", hex(payload))]
+    Feature {
+        kind: ErrorType,
+        context: CallContext,
+
+        /// The raw payload of the error response.
+        payload: Vec<u8>,
+    },
 
     /// Indicates that a received response is not fully supported.
-    #[error("the received response from the device is (partly) unsupported")]
-    UnsupportedResponse,
+    #[error(
+        "the response to {context} is (partly) unsupported (payload {})",
+        hex(payload)
+    )]
+    UnsupportedResponse {
+        context: CallContext,
+
+        /// The raw payload that could not be parsed.
+        payload: Vec<u8>,
+    },
+
+    /// Indicates that a function was not called because the device supports
+    /// an older version of the feature than the one the function was added
+    /// in.
+    #[error("{context} requires feature version {required}, but the device supports {version}")]
+    UnsupportedVersion {
+        context: CallContext,
+
+        /// The version of the feature supported by the device.
+        version: u8,
+
+        /// The version of the feature the function was added in.
+        required: u8,
+    },
+}
+
+impl Hidpp20Error {
+    /// Constructs an [`Hidpp20Error::UnsupportedResponse`] for a response that
+    /// could not be parsed.
+    pub fn unsupported_response(response: &Message) -> Self {
+        Self::UnsupportedResponse {
+            context: response.header().into(),
+            payload: response.extend_payload().to_vec(),
+        }
+    }
+
+    /// Records the ID of the feature in the [`CallContext`] of the error, if
+    /// it has one.
+    pub fn with_feature_id(mut self, feature_id: u16) -> Self {
+        if let Self::Feature {
+            context, ..
+        }
+        | Self::UnsupportedResponse {
+            context, ..
+        }
+        | Self::UnsupportedVersion {
+            context, ..
+        } = &mut self
+        {
+            context.feature_id = Some(feature_id);
+        }
+
+        self
+    }
 }
@@ -15,23 +15,44 @@ use std::sync::Arc;
 use futures::{FutureExt, pin_mut, select};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
-use super::{RECEIVER_DEVICE_INDEX, ReceiverError};
+use super::{
+    RECEIVER_DEVICE_INDEX,
+    ReceiverError,
+    info::{self, ReceiverInfo},
+    notifications::{self, NotificationFlags},
+};
 use crate::{
-    channel::HidppChannel,
-    event::EventEmitter,
-    protocol::v10::{self, Hidpp10Error},
+    channel::{HidppChannel, ListenerGuard},
+    event::{Envelope, EventEmitter},
+    parse,
+    protocol::v10::{ErrorType, Hidpp10Error, MessageType, RegisterContext},
 };
 
+pub mod pairing;
+
 /// Contains all known USB vendor and product ID pairs representing Bolt
 /// receivers.
 pub const BOLT_VPID_PAIRS: &[(u16, u16)] = &[(0x046d, 0xc548)];
 
+/// The amount of pairing slots of a Bolt receiver, numbered starting at `1`.
+pub const BOLT_PAIRING_SLOTS: u8 = 6;
+
+/// The maximum amount of codename bytes returned by a single read of the
+/// device codename.
+pub const CODENAME_CHUNK_SIZE: usize = 13;
+
 /// Represents the known registers of the Bolt receiver.
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, IntoPrimitive, TryFromPrimitive)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[non_exhaustive]
 #[repr(u8)]
 pub enum BoltRegister {
+    /// Controls which notifications the receiver sends.
+    ///
+    /// Exposed by [`BoltReceiver::get_notification_flags`] and
+    /// [`BoltReceiver::set_notification_flags`].
+    NotificationFlags = 0x00,
+
     /// Provides information about the amount of currently paired devices.
     ///
     /// This count is exposed by [`BoltReceiver::count_pairings`].
@@ -48,6 +69,11 @@ pub enum BoltRegister {
     /// Provides pairing and unpairing support.
     Pairing = 0xc1,
 
+    /// Provides the firmware and bootloader versions of the receiver.
+    ///
+    /// Exposed by [`BoltReceiver::get_info`].
+    Firmware = 0xf1,
+
     /// Provides the unique ID of the receiver.
     ///
     /// Exposed by [`BoltReceiver::get_unique_id`].
@@ -72,6 +98,24 @@ pub enum BoltInfoSubRegister {
     DeviceCodename = 0x60, // 0x6N with N = device index
 }
 
+/// Represents the actions written to the [`BoltRegister::Pairing`]
+/// register, followed by the slot they apply to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, IntoPrimitive, TryFromPrimitive)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+#[repr(u8)]
+pub enum BoltPairingAction {
+    /// Pairs a discovered device.
+    ///
+    /// Performed by [`BoltReceiver::pair_device`].
+    Pair = 0x01,
+
+    /// Unpairs a paired device.
+    ///
+    /// Performed by [`BoltReceiver::unpair_device`].
+    Unpair = 0x03,
+}
+
 /// Implements the Bolt wireless receiver.
 #[derive(Clone)]
 pub struct BoltReceiver {
@@ -81,10 +125,10 @@ pub struct BoltReceiver {
     /// The emitter used to emit events.
     emitter: Arc<EventEmitter<BoltEvent>>,
 
-    /// The handle assigned to the message listener registered via
-    /// [`HidppChannel::add_msg_listener`].
-    /// This is used to remove the listener when the receiver is dropped.
-    msg_listener_hdl: u32,
+    /// The guard of the message listener registered via
+    /// [`HidppChannel::subscribe`], removing it when the receiver and all of
+    /// its clones are dropped.
+    _listener: Arc<ListenerGuard>,
 }
 
 impl BoltReceiver {
@@ -100,7 +144,7 @@ impl BoltReceiver {
 
         let emitter = Arc::new(EventEmitter::new());
 
-        let hdl = chan.add_msg_listener({
+        let listener = chan.subscribe({
             let emitter = Arc::clone(&emitter);
 
             move |raw, matched| {
@@ -108,134 +152,8 @@ impl BoltReceiver {
                     return;
                 }
 
-                let parsed = v10::Message::from(raw);
-                let header = parsed.header();
-                let payload = parsed.extend_payload();
-
-                if header.device_index != RECEIVER_DEVICE_INDEX && header.sub_id != 0x41 {
-                    return;
-                }
-
-                match header.sub_id {
-                    // Device connection
-                    0x41 => {
-                        let Ok(kind) = BoltDeviceKind::try_from(payload[1] & 0x0f) else {
-                            return;
-                        };
-
-                        emitter.emit(BoltEvent::DeviceConnection(BoltDeviceConnection {
-                            index: header.device_index,
-                            kind,
-                            encrypted: payload[1] & (1 << 5) != 0,
-                            online: payload[1] & (1 << 6) == 0,
-                            wpid: u16::from_le_bytes(payload[2..=3].try_into().unwrap()),
-                        }));
-                    },
-                    // Device discovery
-                    0x4f => {
-                        match payload[2] {
-                            // Device data
-                            0 => {
-                                let Ok(kind) = BoltDeviceKind::try_from(payload[4] & 0x0f) else {
-                                    return;
-                                };
-
-                                // I have no idea what payload[2]. payload[12], payload[13] and
-                                // payload[15] contain.
-                                // Last one seems to change marginally, maybe some kind of
-                                // connection metric?
-
-                                emitter.emit(BoltEvent::DeviceDiscoveryDeviceDetails(
-                                    BoltDeviceDiscoveryDeviceDetails {
-                                        counter: payload[0] as u16 + payload[1] as u16 * 256,
-                                        kind,
-                                        wpid: u16::from_le_bytes(
-                                            payload[5..=6].try_into().unwrap(),
-                                        ),
-                                        address: payload[7..=12].try_into().unwrap(),
-                                        authentication: payload[15],
-                                    },
-                                ));
-                            },
-                            // Device name
-                            1 => {
-                                let Ok(name) =
-                                    str::from_utf8(&payload[4..(4 + payload[3] as usize)])
-                                else {
-                                    return;
-                                };
-
-                                emitter.emit(BoltEvent::DeviceDiscoveryDeviceName(
-                                    BoltDeviceDiscoveryDeviceName {
-                                        counter: payload[0] as u16 + payload[1] as u16 * 256,
-                                        name: name.to_string(),
-                                    },
-                                ));
-                            },
-                            _ => (),
-                        }
-                    },
-                    // Device discovery status
-                    0x53 => {
-                        emitter.emit(BoltEvent::DeviceDiscoveryStatus(
-                            BoltDeviceDiscoveryStatus {
-                                discovery_enabled: payload[0] == 0x00,
-                            },
-                        ));
-                    },
-                    // Pairing status
-                    0x54 => {
-                        // payload[0] contains some kind of information about the status. I don't
-                        // know how to map that though.
-
-                        let error = if payload[1] == 0x00 {
-                            None
-                        } else {
-                            let Ok(parsed) = BoltPairingError::try_from(payload[1]) else {
-                                return;
-                            };
-
-                            Some(parsed)
-                        };
-
-                        emitter.emit(BoltEvent::PairingStatus(BoltPairingStatus {
-                            device_address: payload[2..=7].try_into().unwrap(),
-                            pairing_error: error,
-                            slot: if payload[8] == 0x00 {
-                                None
-                            } else {
-                                Some(payload[8])
-                            },
-                        }));
-                    },
-                    // Passkey request
-                    0x4d => {
-                        let Ok(passkey) = str::from_utf8(&payload[1..=6]) else {
-                            return;
-                        };
-
-                        emitter.emit(BoltEvent::PairingPasskeyRequest(
-                            BoltPairingPasskeyRequest {
-                                device_address: payload[7..=12].try_into().unwrap(),
-                                passkey: passkey.to_string(),
-                            },
-                        ));
-                    },
-                    // Passkey pressed
-                    0x4e => {
-                        let Ok(press_type) = BoltPairingPasskeyPressType::try_from(payload[0])
-                        else {
-                            return;
-                        };
-
-                        emitter.emit(BoltEvent::PairingPasskeyPressed(
-                            BoltPairingPasskeyPressed {
-                                device_address: payload[1..=6].try_into().unwrap(),
-                                press_type,
-                            },
-                        ));
-                    },
-                    _ => (),
+                if let Some(event) = parse::bolt_event(raw) {
+                    emitter.emit(event, raw);
                 }
             }
         });
@@ -243,7 +161,7 @@ impl BoltReceiver {
         Ok(BoltReceiver {
             chan,
             emitter,
-            msg_listener_hdl: hdl,
+            _listener: Arc::new(listener),
         })
     }
 
@@ -252,6 +170,12 @@ impl BoltReceiver {
         self.emitter.create_receiver()
     }
 
+    /// Creates a new listener like [`Self::listen`], but wraps every event in
+    /// an [`Envelope`] carrying the time it was received and its raw message.
+    pub fn listen_envelopes(&self) -> async_channel::Receiver<Envelope<BoltEvent>> {
+        self.emitter.create_envelope_receiver()
+    }
+
     /// Counts the amount of devices currently paired to this receiver. The
     /// devices don't have to be online to be included here as pairings are
     /// persistent.
@@ -268,6 +192,44 @@ impl BoltReceiver {
         Ok(response[1])
     }
 
+    /// Reads the notification flags of the receiver.
+    pub async fn get_notification_flags(&self) -> Result<NotificationFlags, ReceiverError> {
+        Ok(notifications::get_notification_flags(&self.chan, RECEIVER_DEVICE_INDEX).await?)
+    }
+
+    /// Writes the notification flags of the receiver.
+    ///
+    /// [`NotificationFlags::wireless`] has to be set for the receiver to emit
+    /// [`BoltEvent::DeviceConnection`] events when devices connect or
+    /// disconnect.
+    pub async fn set_notification_flags(
+        &self,
+        flags: NotificationFlags,
+    ) -> Result<(), ReceiverError> {
+        notifications::set_notification_flags(&self.chan, RECEIVER_DEVICE_INDEX, flags).await?;
+
+        Ok(())
+    }
+
+    /// Reads the notification flags of a paired device.
+    pub async fn get_device_notification_flags(
+        &self,
+        device_index: u8,
+    ) -> Result<NotificationFlags, ReceiverError> {
+        Ok(notifications::get_notification_flags(&self.chan, device_index).await?)
+    }
+
+    /// Writes the notification flags of a paired device.
+    pub async fn set_device_notification_flags(
+        &self,
+        device_index: u8,
+        flags: NotificationFlags,
+    ) -> Result<(), ReceiverError> {
+        notifications::set_notification_flags(&self.chan, device_index, flags).await?;
+
+        Ok(())
+    }
+
     /// Triggers device arrival notifications for all devices currently
     /// connected to the receiver. This is useful for device enumeration.
     pub async fn trigger_device_arrival(&self) -> Result<(), ReceiverError> {
@@ -333,10 +295,32 @@ impl BoltReceiver {
         // I have no clue how to retrieve the serial number of the receiver.
 
         Ok(str::from_utf8(&response)
-            .map_err(|_| Hidpp10Error::UnsupportedResponse)?
+            .map_err(|_| {
+                Hidpp10Error::unsupported_response(
+                    RegisterContext::new(
+                        RECEIVER_DEVICE_INDEX,
+                        MessageType::GetLongRegister,
+                        BoltRegister::UniqueId.into(),
+                    ),
+                    &response,
+                )
+            })?
             .to_string())
     }
 
+    /// Provides information about the firmware and version of the receiver.
+    ///
+    /// The serial number is not included, as it is unknown how to retrieve it
+    /// from Bolt receivers. [`Self::get_unique_id`] can be used to identify
+    /// the receiver instead.
+    pub async fn get_info(&self) -> Result<ReceiverInfo, ReceiverError> {
+        Ok(ReceiverInfo {
+            firmware: info::get_firmware_version(&self.chan, RECEIVER_DEVICE_INDEX).await?,
+            bootloader: info::get_bootloader_version(&self.chan, RECEIVER_DEVICE_INDEX).await?,
+            serial: None,
+        })
+    }
+
     /// Provides the pairing information of a specific paired device.
     pub async fn get_device_pairing_information(
         &self,
@@ -353,39 +337,107 @@ impl BoltReceiver {
 
         Ok(BoltDevicePairingInformation {
             wpid: u16::from_le_bytes(response[2..=3].try_into().unwrap()),
-            kind: BoltDeviceKind::try_from(response[1] & 0x0f)
-                .map_err(|_| Hidpp10Error::UnsupportedResponse)?,
+            kind: BoltDeviceKind::try_from(response[1] & 0x0f).map_err(|_| {
+                Hidpp10Error::unsupported_response(
+                    RegisterContext::new(
+                        RECEIVER_DEVICE_INDEX,
+                        MessageType::GetLongRegister,
+                        BoltRegister::ReceiverInfo.into(),
+                    ),
+                    &response,
+                )
+            })?,
             encrypted: response[1] & (1 << 5) != 0,
             online: response[1] & (1 << 6) == 0,
             unit_id: response[4..=7].try_into().unwrap(),
         })
     }
 
+    /// Provides the pairing information of every pairing slot of the receiver.
+    ///
+    /// The element at index `i` represents slot `i + 1` and is [`None`] if no
+    /// device is paired in that slot, so this can be used to find a free slot
+    /// before calling [`Self::pair_device`]. Slots are read one after another
+    /// and reading stops once all pairings counted by
+    /// [`Self::count_pairings`] have been found.
+    pub async fn get_pairing_slots(
+        &self,
+    ) -> Result<Vec<Option<BoltDevicePairingInformation>>, ReceiverError> {
+        let count = self.count_pairings().await?;
+
+        let mut slots = Vec::with_capacity(BOLT_PAIRING_SLOTS as usize);
+        let mut found = 0;
+        for slot in 1..=BOLT_PAIRING_SLOTS {
+            if found >= count {
+                slots.push(None);
+                continue;
+            }
+
+            let info = match self.get_device_pairing_information(slot).await {
+                Ok(info) if info.wpid != 0 => Some(info),
+                Ok(_)
+                | Err(ReceiverError::Protocol(Hidpp10Error::RegisterAccess {
+                    kind:
+                        ErrorType::InvalidAddress
+                        | ErrorType::InvalidValue
+                        | ErrorType::InvalidParamValue,
+                    ..
+                })) => None,
+                Err(err) => return Err(err),
+            };
+
+            if info.is_some() {
+                found += 1;
+            }
+            slots.push(info);
+        }
+
+        Ok(slots)
+    }
+
     /// Provides the codename of a specific paired device.
+    ///
+    /// Every read of the codename returns a chunk of at most
+    /// [`CODENAME_CHUNK_SIZE`] bytes along with the total length of the
+    /// codename, so longer codenames are read in multiple chunks selected by
+    /// the second parameter byte, starting at `1`.
     pub async fn get_device_codename(&self, device_index: u8) -> Result<String, ReceiverError> {
-        // For device names longer than 13 characters this may need to be called
-        // multiple times with different parameters. I don't have a device with
-        // such a name to be able to test this.
-
-        let response = self
-            .chan
-            .read_long_register(RECEIVER_DEVICE_INDEX, BoltRegister::ReceiverInfo.into(), [
-                u8::from(BoltInfoSubRegister::DeviceCodename) + (device_index & 0x0f),
-                0x01,
-                0x00,
-            ])
-            .await?;
+        let mut name = Vec::new();
+        let mut length = None;
+        let mut chunk = 1u8;
+
+        while length.is_none_or(|length| name.len() < length) {
+            let response = self
+                .chan
+                .read_long_register(RECEIVER_DEVICE_INDEX, BoltRegister::ReceiverInfo.into(), [
+                    u8::from(BoltInfoSubRegister::DeviceCodename) + (device_index & 0x0f),
+                    chunk,
+                    0x00,
+                ])
+                .await?;
+
+            let total = *length.get_or_insert(response[2] as usize);
+            let part = (total - name.len()).min(CODENAME_CHUNK_SIZE);
+            name.extend_from_slice(&response[3..3 + part]);
+            chunk += 1;
+        }
 
-        let end_idx = 3 + response[2] as usize;
-        Ok(str::from_utf8(&response[3..end_idx])
-            .map_err(|_| Hidpp10Error::UnsupportedResponse)?
-            .to_string())
+        Ok(String::from_utf8(name).map_err(|err| {
+            Hidpp10Error::unsupported_response(
+                RegisterContext::new(
+                    RECEIVER_DEVICE_INDEX,
+                    MessageType::GetLongRegister,
+                    BoltRegister::ReceiverInfo.into(),
+                ),
+                err.as_bytes(),
+            )
+        })?)
     }
 
     /// Unpairs a device from the receiver by its index.
     pub async fn unpair_device(&self, device_index: u8) -> Result<(), ReceiverError> {
         let mut payload = [0u8; 16];
-        payload[0] = 0x03;
+        payload[0] = BoltPairingAction::Unpair.into();
         payload[1] = device_index;
 
         self.chan
@@ -397,6 +449,9 @@ impl BoltReceiver {
 
     /// Starts the pairing process for a new device.
     ///
+    /// [`pairing::BoltPairingSession`] drives the whole process, including
+    /// device discovery and passkey entry.
+    ///
     /// The required `address` and `authentication` values are usually
     /// discovered from the [`BoltEvent::DeviceDiscoveryDeviceDetails`]
     /// event which is emitted regularly when actively discovering available
@@ -413,7 +468,7 @@ impl BoltReceiver {
         entropy: u8,
     ) -> Result<(), ReceiverError> {
         let mut payload = [0u8; 16];
-        payload[0] = 0x01;
+        payload[0] = BoltPairingAction::Pair.into();
         payload[1] = slot;
         payload[2..=7].copy_from_slice(&address);
         payload[8] = authentication;
@@ -459,14 +514,9 @@ impl BoltReceiver {
     }
 }
 
-impl Drop for BoltReceiver {
-    fn drop(&mut self) {
-        self.chan.remove_msg_listener(self.msg_listener_hdl);
-    }
-}
-
 /// Represents some information about a specific device pairing as returned by
-/// [`BoltReceiver::get_device_pairing_information`].
+/// [`BoltReceiver::get_device_pairing_information`] and
+/// [`BoltReceiver::get_pairing_slots`].
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[non_exhaustive]
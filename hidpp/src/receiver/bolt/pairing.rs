@@ -0,0 +1,389 @@
+//! Implements a guided process for pairing new devices to a Bolt receiver.
+//!
+//! Pairing a device requires discovering it, starting the pairing with the
+//! address reported during discovery and following the passkey the user has
+//! to enter on the device, all while correlating the events the receiver
+//! emits. [`BoltPairingSession`] drives this process and only reports the
+//! steps the user has to know about.
+//!
+//! ```ignore
+//! use hidpp::receiver::bolt::pairing::{BoltPairingOptions, BoltPairingSession, BoltPairingStep};
+//!
+//! let mut session = BoltPairingSession::start(&bolt, BoltPairingOptions::default()).await?;
+//! while let Some(step) = session.next_step().await? {
+//!     match step {
+//!         BoltPairingStep::ShowPasskey(passkey) => println!("Enter {passkey} on the device"),
+//!         BoltPairingStep::Completed { slot } => println!("Paired to slot {slot}"),
+//!         _ => {},
+//!     }
+//! }
+//! ```
+
+use std::{collections::HashMap, time::Duration};
+
+use futures::{FutureExt, pin_mut, select};
+use futures_timer::Delay;
+
+use super::{
+    BOLT_PAIRING_SLOTS,
+    BoltDeviceDiscoveryDeviceDetails,
+    BoltDeviceKind,
+    BoltEvent,
+    BoltPairingError,
+    BoltPairingPasskeyPressType,
+    BoltReceiver,
+};
+use crate::{channel::ChannelError, protocol::v10::Hidpp10Error, receiver::ReceiverError};
+
+/// Configures how a [`BoltPairingSession`] pairs a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct BoltPairingOptions {
+    /// The address of the device to pair. If [`None`], the first discovered
+    /// device is paired.
+    pub address: Option<[u8; 6]>,
+
+    /// The slot to pair the device to, numbered starting at `1`. If [`None`],
+    /// the first free slot is used.
+    pub slot: Option<u8>,
+
+    /// The entropy of the passkey, see [`BoltReceiver::pair_device`]. If
+    /// [`None`], it is chosen based on the kind of the device like Solaar
+    /// does.
+    pub entropy: Option<u8>,
+
+    /// The time in seconds to discover devices for ([`None`] = receiver
+    /// default), see [`BoltReceiver::discover_devices`].
+    pub discovery_timeout: Option<u8>,
+
+    /// The time to wait for the next step before the session fails.
+    pub step_timeout: Duration,
+}
+
+impl Default for BoltPairingOptions {
+    fn default() -> Self {
+        Self {
+            address: None,
+            slot: None,
+            entropy: None,
+            discovery_timeout: None,
+            step_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Represents a step of a [`BoltPairingSession`] as returned by
+/// [`BoltPairingSession::next_step`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum BoltPairingStep {
+    /// A device was discovered and the receiver started pairing it.
+    Pairing(BoltPairingDevice),
+
+    /// The receiver reported the name of the device being paired only after
+    /// the pairing started, so [`BoltPairingDevice::name`] was [`None`].
+    DeviceName(String),
+
+    /// The user has to enter this passkey on the device, see
+    /// [`BoltPairingPasskeyRequest::passkey`](super::BoltPairingPasskeyRequest::passkey).
+    ShowPasskey(String),
+
+    /// The user pressed a key while entering the passkey.
+    KeyPressed(BoltPairingPasskeyPressType),
+
+    /// The device was paired successfully.
+    Completed {
+        /// The slot the device was paired to.
+        slot: u8,
+    },
+
+    /// Pairing the device failed.
+    Failed(BoltPairingFailure),
+}
+
+/// Represents the device paired by a [`BoltPairingSession`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct BoltPairingDevice {
+    /// The address of the device.
+    pub address: [u8; 6],
+
+    /// The kind of the device.
+    pub kind: BoltDeviceKind,
+
+    /// The wireless product ID of the device.
+    pub wpid: u16,
+
+    /// The name of the device, if the receiver reported it before the
+    /// pairing started. Otherwise, it is reported via
+    /// [`BoltPairingStep::DeviceName`] once known.
+    pub name: Option<String>,
+}
+
+/// Represents the reason a [`BoltPairingSession`] failed.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum BoltPairingFailure {
+    /// The next step did not happen within
+    /// [`BoltPairingOptions::step_timeout`].
+    Timeout,
+
+    /// Device discovery ended without finding a device to pair.
+    NoDeviceFound,
+
+    /// All pairing slots of the receiver are in use.
+    NoFreeSlot,
+
+    /// The receiver reported an error while pairing the device.
+    Pairing(BoltPairingError),
+}
+
+/// Represents the state of a [`BoltPairingSession`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum SessionState {
+    /// Devices are being discovered.
+    Discovering,
+
+    /// The device with the address is being paired.
+    Pairing {
+        address: [u8; 6],
+
+        /// The discovery event counter of the device if its name is not
+        /// known yet.
+        unnamed: Option<u16>,
+    },
+
+    /// The session has completed or failed.
+    Finished,
+}
+
+/// Drives the process of pairing a new device to a Bolt receiver.
+///
+/// The session starts discovering devices when it is created. Once a matching
+/// device is discovered, discovery is stopped and the device is paired. All
+/// progress is reported via [`Self::next_step`].
+pub struct BoltPairingSession {
+    receiver: BoltReceiver,
+    events: async_channel::Receiver<BoltEvent>,
+    options: BoltPairingOptions,
+    state: SessionState,
+
+    /// The names of discovered devices by their discovery event counter.
+    names: HashMap<u16, String>,
+}
+
+impl BoltPairingSession {
+    /// Starts a new pairing session by discovering devices.
+    ///
+    /// Returns [`ReceiverError::InvalidSlot`] if [`BoltPairingOptions::slot`]
+    /// does not exist on the receiver.
+    pub async fn start(
+        receiver: &BoltReceiver,
+        options: BoltPairingOptions,
+    ) -> Result<Self, ReceiverError> {
+        if let Some(slot) = options.slot
+            && !(1..=BOLT_PAIRING_SLOTS).contains(&slot)
+        {
+            return Err(ReceiverError::InvalidSlot(slot));
+        }
+
+        // The listener has to exist before discovery starts so no event is
+        // missed.
+        let events = receiver.listen();
+        receiver.discover_devices(options.discovery_timeout).await?;
+
+        Ok(Self {
+            receiver: receiver.clone(),
+            events,
+            options,
+            state: SessionState::Discovering,
+            names: HashMap::new(),
+        })
+    }
+
+    /// Waits for the next step of the pairing process.
+    ///
+    /// Returns `Ok(None)` once the session has completed or failed.
+    ///
+    /// Returns [`ChannelError::ChannelGone`] if the receiver stopped emitting
+    /// events, as happens when its channel is closed.
+    pub async fn next_step(&mut self) -> Result<Option<BoltPairingStep>, ReceiverError> {
+        let timeout = Delay::new(self.options.step_timeout).fuse();
+        pin_mut!(timeout);
+
+        while self.state != SessionState::Finished {
+            let event = select! {
+                event = self.events.recv().fuse() => event,
+                _ = timeout => return self.fail(BoltPairingFailure::Timeout).await,
+            };
+            let Ok(event) = event else {
+                self.state = SessionState::Finished;
+                return Err(Hidpp10Error::Channel(ChannelError::ChannelGone).into());
+            };
+
+            if let Some(step) = self.handle_event(event).await? {
+                return Ok(Some(step));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Cancels the session, stopping device discovery if it is still
+    /// running.
+    ///
+    /// A pairing that has already started can not be cancelled and is
+    /// finished by the receiver on its own.
+    pub async fn cancel(self) -> Result<(), ReceiverError> {
+        if self.state == SessionState::Discovering {
+            self.receiver.cancel_device_discovery().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Advances the session based on an event, returning the step to report,
+    /// if any.
+    async fn handle_event(
+        &mut self,
+        event: BoltEvent,
+    ) -> Result<Option<BoltPairingStep>, ReceiverError> {
+        match (self.state, event) {
+            (SessionState::Discovering, BoltEvent::DeviceDiscoveryDeviceName(name)) => {
+                self.names.insert(name.counter, name.name);
+                Ok(None)
+            },
+            (SessionState::Discovering, BoltEvent::DeviceDiscoveryDeviceDetails(details))
+                if self
+                    .options
+                    .address
+                    .is_none_or(|address| address == details.address) =>
+            {
+                self.start_pairing(details).await
+            },
+            (SessionState::Discovering, BoltEvent::DeviceDiscoveryStatus(status))
+                if !status.discovery_enabled =>
+            {
+                self.finish(BoltPairingStep::Failed(BoltPairingFailure::NoDeviceFound))
+            },
+            (
+                SessionState::Pairing {
+                    address,
+                    unnamed: Some(counter),
+                },
+                BoltEvent::DeviceDiscoveryDeviceName(name),
+            ) if name.counter == counter => {
+                self.state = SessionState::Pairing {
+                    address,
+                    unnamed: None,
+                };
+                Ok(Some(BoltPairingStep::DeviceName(name.name)))
+            },
+            (
+                SessionState::Pairing {
+                    address, ..
+                },
+                BoltEvent::PairingPasskeyRequest(request),
+            ) if request.device_address == address => {
+                Ok(Some(BoltPairingStep::ShowPasskey(request.passkey)))
+            },
+            (
+                SessionState::Pairing {
+                    address, ..
+                },
+                BoltEvent::PairingPasskeyPressed(pressed),
+            ) if pressed.device_address == address => {
+                Ok(Some(BoltPairingStep::KeyPressed(pressed.press_type)))
+            },
+            (
+                SessionState::Pairing {
+                    address, ..
+                },
+                BoltEvent::PairingStatus(status),
+            ) if status.device_address == address => {
+                match (status.pairing_error, status.slot) {
+                    (Some(error), _) => {
+                        self.finish(BoltPairingStep::Failed(BoltPairingFailure::Pairing(error)))
+                    },
+                    (None, Some(slot)) => self.finish(BoltPairingStep::Completed {
+                        slot,
+                    }),
+                    // The receiver also reports the start of the pairing.
+                    (None, None) => Ok(None),
+                }
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Stops discovery and starts pairing a discovered device.
+    async fn start_pairing(
+        &mut self,
+        details: BoltDeviceDiscoveryDeviceDetails,
+    ) -> Result<Option<BoltPairingStep>, ReceiverError> {
+        self.receiver.cancel_device_discovery().await?;
+
+        let slot = match self.options.slot {
+            Some(slot) => slot,
+            None => match self
+                .receiver
+                .get_pairing_slots()
+                .await?
+                .iter()
+                .position(Option::is_none)
+            {
+                Some(i) => i as u8 + 1,
+                None => {
+                    return self.finish(BoltPairingStep::Failed(BoltPairingFailure::NoFreeSlot));
+                },
+            },
+        };
+
+        // Solaar uses longer passkeys for keyboards, which can enter them more
+        // easily than the two buttons of a mouse.
+        let entropy = self.options.entropy.unwrap_or(match details.kind {
+            BoltDeviceKind::Keyboard => 20,
+            _ => 10,
+        });
+
+        self.receiver
+            .pair_device(slot, details.address, details.authentication, entropy)
+            .await?;
+
+        // The name of the device may also arrive after its details.
+        let name = self.names.remove(&details.counter);
+        self.state = SessionState::Pairing {
+            address: details.address,
+            unnamed: name.is_none().then_some(details.counter),
+        };
+
+        Ok(Some(BoltPairingStep::Pairing(BoltPairingDevice {
+            address: details.address,
+            kind: details.kind,
+            wpid: details.wpid,
+            name,
+        })))
+    }
+
+    /// Stops device discovery if necessary and finishes the session with a
+    /// failure.
+    async fn fail(
+        &mut self,
+        failure: BoltPairingFailure,
+    ) -> Result<Option<BoltPairingStep>, ReceiverError> {
+        if self.state == SessionState::Discovering {
+            self.receiver.cancel_device_discovery().await?;
+        }
+
+        self.finish(BoltPairingStep::Failed(failure))
+    }
+
+    /// Finishes the session with a final step.
+    fn finish(&mut self, step: BoltPairingStep) -> Result<Option<BoltPairingStep>, ReceiverError> {
+        self.state = SessionState::Finished;
+        Ok(Some(step))
+    }
+}
@@ -0,0 +1,135 @@
+//! Implements retrieving firmware and version information of receivers.
+//!
+//! All receivers known so far report their firmware and bootloader versions
+//! via the HID++1.0 firmware register (`0xf1`), which uses its first parameter
+//! byte to select the reported part. This module reads it the same way Solaar
+//! does, so the implementations of all receivers can share [`ReceiverInfo`].
+
+use std::fmt;
+
+use crate::{
+    channel::HidppChannel,
+    protocol::v10::{ErrorType, Hidpp10Error},
+};
+
+/// The address of the firmware register.
+pub const FIRMWARE_REGISTER: u8 = 0xf1;
+
+/// Represents the known parts of the firmware register, selected by its first
+/// parameter byte.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[repr(u8)]
+enum FirmwarePart {
+    /// The major and minor version of the firmware.
+    Version = 0x01,
+
+    /// The build number of the firmware.
+    Build = 0x02,
+
+    /// The major and minor version of the bootloader.
+    Bootloader = 0x04,
+}
+
+/// Represents general information about a receiver.
+///
+/// Receivers do not support all parts of this information, so unsupported
+/// parts are [`None`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ReceiverInfo {
+    /// The version of the receiver firmware.
+    pub firmware: Option<ReceiverVersion>,
+
+    /// The version of the receiver bootloader.
+    pub bootloader: Option<ReceiverVersion>,
+
+    /// The serial number of the receiver.
+    pub serial: Option<String>,
+}
+
+/// Represents the version of a receiver firmware or bootloader.
+///
+/// Receivers report the parts of their versions as hexadecimal digits, so
+/// versions are formatted like `05.01.B0012`, matching how Solaar and the
+/// Logitech software display them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ReceiverVersion {
+    pub major: u8,
+    pub minor: u8,
+
+    /// The build number, which is only reported for firmwares.
+    pub build: Option<u16>,
+}
+
+impl fmt::Display for ReceiverVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}.{:02x}", self.major, self.minor)?;
+        if let Some(build) = self.build {
+            write!(f, ".B{:04x}", build)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the firmware version of a receiver, including its build number if
+/// reported.
+///
+/// Returns `Ok(None)` if the receiver does not support the firmware register.
+pub async fn get_firmware_version(
+    chan: &HidppChannel,
+    device_index: u8,
+) -> Result<Option<ReceiverVersion>, Hidpp10Error> {
+    let Some(version) = read_part(chan, device_index, FirmwarePart::Version).await? else {
+        return Ok(None);
+    };
+
+    let build = read_part(chan, device_index, FirmwarePart::Build)
+        .await?
+        .map(|build| u16::from_be_bytes([build[1], build[2]]));
+
+    Ok(Some(ReceiverVersion {
+        major: version[1],
+        minor: version[2],
+        build,
+    }))
+}
+
+/// Reads the bootloader version of a receiver.
+///
+/// Returns `Ok(None)` if the receiver does not report it.
+pub async fn get_bootloader_version(
+    chan: &HidppChannel,
+    device_index: u8,
+) -> Result<Option<ReceiverVersion>, Hidpp10Error> {
+    Ok(read_part(chan, device_index, FirmwarePart::Bootloader)
+        .await?
+        .map(|version| ReceiverVersion {
+            major: version[1],
+            minor: version[2],
+            build: None,
+        }))
+}
+
+/// Reads a part of the firmware register, mapping errors signaling that the
+/// part is not supported to `Ok(None)`.
+async fn read_part(
+    chan: &HidppChannel,
+    device_index: u8,
+    part: FirmwarePart,
+) -> Result<Option<[u8; 3]>, Hidpp10Error> {
+    match chan
+        .read_register(device_index, FIRMWARE_REGISTER, [part as u8, 0x00, 0x00])
+        .await
+    {
+        Ok(response) => Ok(Some(response)),
+        Err(Hidpp10Error::RegisterAccess {
+            kind: ErrorType::InvalidAddress | ErrorType::InvalidValue | ErrorType::InvalidParamValue,
+            ..
+        }) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
@@ -11,15 +11,33 @@
 //! product IDs, so the [`detect`] function does nothing more than matching
 //! those values to the sets of known vendor and product ID pairs of the
 //! different receivers.
+//!
+//! Code that does not care about the specific receiver should use the
+//! [`ReceiverDevice`] trait, which abstracts over pairings and events of all
+//! receivers and is implemented for [`Receiver`] itself.
 
 use std::sync::Arc;
 
-use bolt::{BOLT_VPID_PAIRS, BoltReceiver};
+use async_trait::async_trait;
+use bolt::{
+    BOLT_VPID_PAIRS,
+    BoltDeviceConnection,
+    BoltDeviceKind,
+    BoltEvent,
+    BoltPairingStatus,
+    BoltReceiver,
+};
+use futures::{StreamExt, future, stream::BoxStream};
+use info::ReceiverInfo;
+use notifications::NotificationFlags;
 use thiserror::Error;
 
-use crate::{channel::HidppChannel, protocol::v10::Hidpp10Error};
+use crate::{channel::HidppChannel, products, protocol::v10::Hidpp10Error};
 
 pub mod bolt;
+pub mod info;
+pub mod notifications;
+pub mod scan;
 
 /// The index to use when communicating with the receiver on any HID++ channel.
 pub const RECEIVER_DEVICE_INDEX: u8 = 0xff;
@@ -61,6 +79,339 @@ impl Receiver {
             Self::Bolt(bolt) => bolt.get_unique_id().await,
         }
     }
+
+    /// Provides information about the firmware and version of the receiver.
+    pub async fn get_info(&self) -> Result<ReceiverInfo, ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => bolt.get_info().await,
+        }
+    }
+
+    /// Provides the codename the receiver stores for a paired device.
+    pub async fn get_device_codename(&self, device_index: u8) -> Result<String, ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => bolt.get_device_codename(device_index).await,
+        }
+    }
+
+    /// Reads the notification flags of the receiver.
+    pub async fn get_notification_flags(&self) -> Result<NotificationFlags, ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => bolt.get_notification_flags().await,
+        }
+    }
+
+    /// Writes the notification flags of the receiver.
+    pub async fn set_notification_flags(
+        &self,
+        flags: NotificationFlags,
+    ) -> Result<(), ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => bolt.set_notification_flags(flags).await,
+        }
+    }
+
+    /// Reads the notification flags of a device connected to the receiver.
+    pub async fn get_device_notification_flags(
+        &self,
+        device_index: u8,
+    ) -> Result<NotificationFlags, ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => bolt.get_device_notification_flags(device_index).await,
+        }
+    }
+
+    /// Writes the notification flags of a device connected to the receiver.
+    pub async fn set_device_notification_flags(
+        &self,
+        device_index: u8,
+        flags: NotificationFlags,
+    ) -> Result<(), ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => {
+                bolt.set_device_notification_flags(device_index, flags)
+                    .await
+            },
+        }
+    }
+
+    /// Provides the unit ID the receiver stores for a paired device.
+    ///
+    /// This is available even if the device is currently offline.
+    pub async fn get_device_unit_id(&self, device_index: u8) -> Result<[u8; 4], ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => Ok(bolt
+                .get_device_pairing_information(device_index)
+                .await?
+                .unit_id),
+        }
+    }
+}
+
+#[async_trait]
+impl ReceiverDevice for Receiver {
+    async fn count_pairings(&self) -> Result<u8, ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => bolt.count_pairings().await,
+        }
+    }
+
+    async fn collect_paired_devices(&self) -> Result<Vec<PairedDeviceInfo>, ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => ReceiverDevice::collect_paired_devices(bolt).await,
+        }
+    }
+
+    async fn get_device_name(&self, device: &PairedDeviceInfo) -> Result<String, ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => bolt.get_device_name(device).await,
+        }
+    }
+
+    async fn pair(&self, request: PairingRequest) -> Result<(), ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => bolt.pair(request).await,
+        }
+    }
+
+    async fn unpair(&self, device_index: u8) -> Result<(), ReceiverError> {
+        match self {
+            Self::Bolt(bolt) => bolt.unpair(device_index).await,
+        }
+    }
+
+    fn listen_events(&self) -> BoxStream<'static, ReceiverEvent> {
+        match self {
+            Self::Bolt(bolt) => bolt.listen_events(),
+        }
+    }
+}
+
+/// Abstracts over the pairings and events of the different receivers, so code
+/// that does not care about the specific receiver doesn't have to match on
+/// [`Receiver`].
+///
+/// Features only supported by a specific receiver, like device discovery of
+/// Bolt, are still only available via the implementation of that receiver.
+#[async_trait]
+pub trait ReceiverDevice: Send + Sync {
+    /// Counts the amount of devices currently paired to the receiver, whether
+    /// they are online or not.
+    async fn count_pairings(&self) -> Result<u8, ReceiverError>;
+
+    /// Collects information about all devices currently paired to the
+    /// receiver, whether they are online or not.
+    async fn collect_paired_devices(&self) -> Result<Vec<PairedDeviceInfo>, ReceiverError>;
+
+    /// Provides a human-readable name for a paired device.
+    ///
+    /// This is the codename the receiver stores for the device, falling back
+    /// to the name of the known product with the WPID of the device if the
+    /// codename is missing.
+    async fn get_device_name(&self, device: &PairedDeviceInfo) -> Result<String, ReceiverError>;
+
+    /// Starts pairing a new device.
+    ///
+    /// Returns [`ReceiverError::Unsupported`] if the request is not meant for
+    /// this receiver. The result of the pairing process is reported via
+    /// [`ReceiverEvent::PairingFinished`].
+    async fn pair(&self, request: PairingRequest) -> Result<(), ReceiverError>;
+
+    /// Unpairs a device from the receiver by its index.
+    async fn unpair(&self, device_index: u8) -> Result<(), ReceiverError>;
+
+    /// Creates a new stream of the events emitted by the receiver that are
+    /// common to all receivers.
+    fn listen_events(&self) -> BoxStream<'static, ReceiverEvent>;
+}
+
+/// Represents a device paired to a receiver.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct PairedDeviceInfo {
+    /// The slot of the pairing, which is also the index used to communicate
+    /// with the device.
+    pub slot: u8,
+
+    /// The kind of the device.
+    pub kind: PairedDeviceKind,
+
+    /// Whether the device is online/reachable.
+    pub online: bool,
+
+    /// The wireless product ID of the device.
+    pub wpid: u16,
+}
+
+impl From<BoltDeviceConnection> for PairedDeviceInfo {
+    fn from(value: BoltDeviceConnection) -> Self {
+        Self {
+            slot: value.index,
+            kind: value.kind.into(),
+            online: value.online,
+            wpid: value.wpid,
+        }
+    }
+}
+
+/// Represents the kind of a device paired to a receiver.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum PairedDeviceKind {
+    Unknown,
+    Keyboard,
+    Mouse,
+    Numpad,
+    Presenter,
+    Remote,
+    Trackball,
+    Touchpad,
+    Tablet,
+    Gamepad,
+    Joystick,
+    Headset,
+}
+
+impl From<BoltDeviceKind> for PairedDeviceKind {
+    fn from(value: BoltDeviceKind) -> Self {
+        match value {
+            BoltDeviceKind::Unknown => Self::Unknown,
+            BoltDeviceKind::Keyboard => Self::Keyboard,
+            BoltDeviceKind::Mouse => Self::Mouse,
+            BoltDeviceKind::Numpad => Self::Numpad,
+            BoltDeviceKind::Presenter => Self::Presenter,
+            BoltDeviceKind::Remote => Self::Remote,
+            BoltDeviceKind::Trackball => Self::Trackball,
+            BoltDeviceKind::Touchpad => Self::Touchpad,
+            BoltDeviceKind::Tablet => Self::Tablet,
+            BoltDeviceKind::Gamepad => Self::Gamepad,
+            BoltDeviceKind::Joystick => Self::Joystick,
+            BoltDeviceKind::Headset => Self::Headset,
+        }
+    }
+}
+
+/// Represents a request to pair a new device to a receiver.
+///
+/// Receivers pair devices in very different ways, so every receiver has its
+/// own kind of request.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[non_exhaustive]
+pub enum PairingRequest {
+    /// Pairs a device discovered by a Bolt receiver, see
+    /// [`BoltReceiver::pair_device`].
+    Bolt {
+        slot: u8,
+        address: [u8; 6],
+        authentication: u8,
+        entropy: u8,
+    },
+}
+
+/// Represents an event emitted by any receiver.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum ReceiverEvent {
+    /// Is emitted whenever a device connects to or disconnects from the
+    /// receiver.
+    DeviceConnection(PairedDeviceInfo),
+
+    /// Is emitted whenever the receiver starts or stops accepting new
+    /// pairings.
+    PairingLockChanged {
+        /// Whether the receiver is accepting new pairings.
+        open: bool,
+    },
+
+    /// Is emitted once a pairing process started using
+    /// [`ReceiverDevice::pair`] has finished.
+    PairingFinished {
+        /// The slot of the newly paired device, or [`None`] if pairing
+        /// failed.
+        slot: Option<u8>,
+    },
+}
+
+impl ReceiverEvent {
+    /// Converts a Bolt event into the common event, if there is one.
+    fn from_bolt(event: BoltEvent) -> Option<Self> {
+        match event {
+            BoltEvent::DeviceConnection(connection) => {
+                Some(Self::DeviceConnection(connection.into()))
+            },
+            BoltEvent::DeviceDiscoveryStatus(status) => Some(Self::PairingLockChanged {
+                open: status.discovery_enabled,
+            }),
+            BoltEvent::PairingStatus(BoltPairingStatus {
+                pairing_error,
+                slot,
+                ..
+            }) => Some(Self::PairingFinished {
+                slot: slot.filter(|_| pairing_error.is_none()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl ReceiverDevice for BoltReceiver {
+    async fn count_pairings(&self) -> Result<u8, ReceiverError> {
+        BoltReceiver::count_pairings(self).await
+    }
+
+    async fn collect_paired_devices(&self) -> Result<Vec<PairedDeviceInfo>, ReceiverError> {
+        Ok(BoltReceiver::collect_paired_devices(self)
+            .await?
+            .into_iter()
+            .map(PairedDeviceInfo::from)
+            .collect())
+    }
+
+    async fn get_device_name(&self, device: &PairedDeviceInfo) -> Result<String, ReceiverError> {
+        let codename = self.get_device_codename(device.slot).await;
+        name_or_product(codename, device.wpid)
+    }
+
+    async fn pair(&self, request: PairingRequest) -> Result<(), ReceiverError> {
+        let PairingRequest::Bolt {
+            slot,
+            address,
+            authentication,
+            entropy,
+        } = request;
+
+        self.pair_device(slot, address, authentication, entropy)
+            .await
+    }
+
+    async fn unpair(&self, device_index: u8) -> Result<(), ReceiverError> {
+        self.unpair_device(device_index).await
+    }
+
+    fn listen_events(&self) -> BoxStream<'static, ReceiverEvent> {
+        self.listen()
+            .filter_map(|event| future::ready(ReceiverEvent::from_bolt(event)))
+            .boxed()
+    }
+}
+
+/// Falls back to the name of the known product with a WPID if reading the
+/// codename of a device failed or the codename is empty.
+fn name_or_product(
+    codename: Result<String, ReceiverError>,
+    wpid: u16,
+) -> Result<String, ReceiverError> {
+    match codename {
+        Ok(name) if !name.is_empty() => Ok(name),
+        result => match products::lookup_wpid(wpid) {
+            Some(product) => Ok(product.name.to_string()),
+            None => result,
+        },
+    }
 }
 
 /// Represents an error returned by a receiver.
@@ -75,4 +426,12 @@ pub enum ReceiverError {
     /// Indicates that a HID++1.0 register access resulted in an error.
     #[error("a HID++1.0 error occurred")]
     Protocol(#[from] Hidpp10Error),
+
+    /// Indicates that the receiver does not support an operation or request.
+    #[error("the operation is not supported by this receiver")]
+    Unsupported,
+
+    /// Indicates that a pairing slot does not exist on the receiver.
+    #[error("the receiver has no pairing slot {0}")]
+    InvalidSlot(u8),
 }
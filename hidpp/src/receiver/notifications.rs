@@ -0,0 +1,187 @@
+//! Implements the HID++1.0 notification flags register (`0x00`) that controls
+//! which notifications a receiver or a device connected to it sends.
+//!
+//! The register is shared by all receivers, and devices connected to a
+//! receiver expose it under their own device index. The meaning of the flags
+//! is taken from Solaar, which enables [`NotificationFlags::wireless`] and
+//! [`NotificationFlags::software_present`] on receivers to get notified about
+//! connecting devices.
+//!
+//! ```ignore
+//! use hidpp::receiver::{RECEIVER_DEVICE_INDEX, notifications};
+//!
+//! let mut flags = notifications::get_notification_flags(&channel, RECEIVER_DEVICE_INDEX).await?;
+//! flags.wireless = true;
+//! flags.software_present = true;
+//! notifications::set_notification_flags(&channel, RECEIVER_DEVICE_INDEX, flags).await?;
+//! ```
+
+use crate::{channel::HidppChannel, protocol::v10::Hidpp10Error};
+
+/// The address of the notification flags register.
+pub const NOTIFICATION_FLAGS_REGISTER: u8 = 0x00;
+
+/// Reads the notification flags of a receiver or a device connected to it.
+pub async fn get_notification_flags(
+    chan: &HidppChannel,
+    device_index: u8,
+) -> Result<NotificationFlags, Hidpp10Error> {
+    let response = chan
+        .read_register(device_index, NOTIFICATION_FLAGS_REGISTER, [0u8; 3])
+        .await?;
+
+    Ok(NotificationFlags::from(response))
+}
+
+/// Writes the notification flags of a receiver or a device connected to it.
+///
+/// All flags are written at once, so flags should usually be read using
+/// [`get_notification_flags`] and modified before writing them back.
+pub async fn set_notification_flags(
+    chan: &HidppChannel,
+    device_index: u8,
+    flags: NotificationFlags,
+) -> Result<(), Hidpp10Error> {
+    chan.write_register(device_index, NOTIFICATION_FLAGS_REGISTER, flags.into())
+        .await
+}
+
+/// Represents the bitfield stored in the notification flags register.
+///
+/// Not every receiver or device supports every flag. Unsupported flags are
+/// usually ignored when written and reported as `false` when read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct NotificationFlags {
+    /// Whether numerical keys of the numpad are reported.
+    pub numpad_numerical_keys: bool,
+
+    /// Whether changes of the F-lock status are reported.
+    pub f_lock_status: bool,
+
+    /// Whether horizontal roller movement is reported.
+    pub roller_horizontal: bool,
+
+    /// Whether changes of the battery status are reported.
+    pub battery_status: bool,
+
+    /// Whether presses of extra mouse buttons are reported.
+    pub mouse_extra_buttons: bool,
+
+    /// Whether vertical roller movement is reported.
+    pub roller_vertical: bool,
+
+    /// Whether system control keys, like sleep, are reported.
+    pub power_keys: bool,
+
+    /// Whether consumer control keys, like mute, are reported in their raw
+    /// form.
+    pub keyboard_multimedia_raw: bool,
+
+    /// Whether changes of multi-touch input are reported.
+    pub multi_touch: bool,
+
+    /// Whether software is present and controls parts of the behavior of the
+    /// device.
+    pub software_present: bool,
+
+    /// Whether changes of the link quality are reported.
+    pub link_quality: bool,
+
+    /// Whether changes of the user interface are reported.
+    pub ui: bool,
+
+    /// Whether devices connecting to or disconnecting from the receiver are
+    /// reported.
+    pub wireless: bool,
+
+    /// Whether the completion of a configuration is reported.
+    pub configuration_complete: bool,
+
+    /// Whether VoIP telephony keys are reported.
+    pub voip_telephony: bool,
+
+    /// Whether 3D gestures are reported.
+    pub threed_gesture: bool,
+}
+
+impl From<[u8; 3]> for NotificationFlags {
+    fn from(value: [u8; 3]) -> Self {
+        Self {
+            numpad_numerical_keys: value[0] & (1 << 7) != 0,
+            f_lock_status: value[0] & (1 << 6) != 0,
+            roller_horizontal: value[0] & (1 << 5) != 0,
+            battery_status: value[0] & (1 << 4) != 0,
+            mouse_extra_buttons: value[0] & (1 << 3) != 0,
+            roller_vertical: value[0] & (1 << 2) != 0,
+            power_keys: value[0] & (1 << 1) != 0,
+            keyboard_multimedia_raw: value[0] & 1 != 0,
+            multi_touch: value[1] & (1 << 4) != 0,
+            software_present: value[1] & (1 << 3) != 0,
+            link_quality: value[1] & (1 << 2) != 0,
+            ui: value[1] & (1 << 1) != 0,
+            wireless: value[1] & 1 != 0,
+            configuration_complete: value[2] & (1 << 2) != 0,
+            voip_telephony: value[2] & (1 << 1) != 0,
+            threed_gesture: value[2] & 1 != 0,
+        }
+    }
+}
+
+impl From<NotificationFlags> for [u8; 3] {
+    fn from(value: NotificationFlags) -> Self {
+        let mut raw = [0; 3];
+
+        if value.numpad_numerical_keys {
+            raw[0] |= 1 << 7
+        }
+        if value.f_lock_status {
+            raw[0] |= 1 << 6
+        }
+        if value.roller_horizontal {
+            raw[0] |= 1 << 5
+        }
+        if value.battery_status {
+            raw[0] |= 1 << 4
+        }
+        if value.mouse_extra_buttons {
+            raw[0] |= 1 << 3
+        }
+        if value.roller_vertical {
+            raw[0] |= 1 << 2
+        }
+        if value.power_keys {
+            raw[0] |= 1 << 1
+        }
+        if value.keyboard_multimedia_raw {
+            raw[0] |= 1
+        }
+        if value.multi_touch {
+            raw[1] |= 1 << 4
+        }
+        if value.software_present {
+            raw[1] |= 1 << 3
+        }
+        if value.link_quality {
+            raw[1] |= 1 << 2
+        }
+        if value.ui {
+            raw[1] |= 1 << 1
+        }
+        if value.wireless {
+            raw[1] |= 1
+        }
+        if value.configuration_complete {
+            raw[2] |= 1 << 2
+        }
+        if value.voip_telephony {
+            raw[2] |= 1 << 1
+        }
+        if value.threed_gesture {
+            raw[2] |= 1
+        }
+
+        raw
+    }
+}
@@ -0,0 +1,208 @@
+//! Probes the HID++1.0 registers of a receiver to find out which ones exist.
+//!
+//! Most receiver registers are undocumented and were found by trying every
+//! address. [`scan`] makes this reproducible: it only ever reads registers,
+//! waits between two requests so the receiver is not flooded, and classifies
+//! every response, producing a [`ScanReport`] of the responsive registers.
+//!
+//! ```ignore
+//! use hidpp::receiver::{RECEIVER_DEVICE_INDEX, scan};
+//!
+//! let report = scan::scan(&channel, RECEIVER_DEVICE_INDEX, &scan::ScanOptions::default()).await;
+//! for probe in report.responsive() {
+//!     println!("{:#04x}/{:#04x}: {:?}", probe.address, probe.sub_register, probe.outcome);
+//! }
+//! ```
+
+use std::{ops::RangeInclusive, time::Duration};
+
+use futures::{FutureExt, select};
+use futures_timer::Delay;
+
+use crate::{
+    channel::HidppChannel,
+    protocol::v10::{ErrorType, Hidpp10Error},
+};
+
+/// Configures which registers [`scan`] probes and how fast.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ScanOptions {
+    /// The register addresses to probe.
+    pub addresses: RangeInclusive<u8>,
+
+    /// The values of the first parameter byte to probe every address with.
+    /// Many registers use it to select a sub-register, like the slot of a
+    /// paired device.
+    pub sub_registers: RangeInclusive<u8>,
+
+    /// Whether to also read every register as a long register.
+    pub long: bool,
+
+    /// The time to wait between two requests.
+    pub interval: Duration,
+
+    /// The time to wait for a response before giving up on a request.
+    pub timeout: Duration,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            addresses: 0x00..=0xff,
+            sub_registers: 0x00..=0x00,
+            long: true,
+            interval: Duration::from_millis(20),
+            timeout: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Represents the result of reading a single register.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct RegisterProbe {
+    /// The address of the register.
+    pub address: u8,
+
+    /// The first parameter byte the register was read with.
+    pub sub_register: u8,
+
+    /// Whether the register was read as a long register.
+    pub long: bool,
+
+    pub outcome: ProbeOutcome,
+}
+
+/// Represents the classified response to reading a register.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum ProbeOutcome {
+    /// The register exists and returned data.
+    Data(Vec<u8>),
+
+    /// The register exists, but refused the read, for example because the
+    /// sub-register is invalid or the register is write-only.
+    Rejected(ErrorType),
+
+    /// The register does not exist.
+    Unsupported(ErrorType),
+
+    /// The receiver returned a response that could not be parsed.
+    Malformed(Vec<u8>),
+
+    /// The receiver did not respond in time.
+    NoResponse,
+}
+
+impl ProbeOutcome {
+    /// Whether the outcome indicates that the register exists.
+    pub fn is_responsive(&self) -> bool {
+        matches!(self, Self::Data(_) | Self::Rejected(_))
+    }
+}
+
+/// Represents the results of a [`scan`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub struct ScanReport {
+    /// The results of all probed registers, in the order they were probed.
+    pub probes: Vec<RegisterProbe>,
+}
+
+impl ScanReport {
+    /// Provides the results of all registers that exist.
+    pub fn responsive(&self) -> impl Iterator<Item = &RegisterProbe> {
+        self.probes
+            .iter()
+            .filter(|probe| probe.outcome.is_responsive())
+    }
+}
+
+/// Reads all registers selected by the options and classifies the responses.
+///
+/// Registers are only read, never written. Sub-registers are only probed for
+/// addresses that did not report [`ProbeOutcome::Unsupported`] for the first
+/// sub-register.
+pub async fn scan(chan: &HidppChannel, device_index: u8, options: &ScanOptions) -> ScanReport {
+    let mut report = ScanReport::default();
+    let mut first = true;
+
+    for long in [false, true] {
+        if long && !options.long {
+            continue;
+        }
+
+        for address in options.addresses.clone() {
+            for sub_register in options.sub_registers.clone() {
+                if !first {
+                    Delay::new(options.interval).await;
+                }
+                first = false;
+
+                let outcome = probe(chan, device_index, address, sub_register, long, options).await;
+                let unsupported = matches!(outcome, ProbeOutcome::Unsupported(_));
+
+                report.probes.push(RegisterProbe {
+                    address,
+                    sub_register,
+                    long,
+                    outcome,
+                });
+
+                if unsupported {
+                    break;
+                }
+            }
+        }
+    }
+
+    report
+}
+
+/// Reads a single register and classifies the response.
+async fn probe(
+    chan: &HidppChannel,
+    device_index: u8,
+    address: u8,
+    sub_register: u8,
+    long: bool,
+    options: &ScanOptions,
+) -> ProbeOutcome {
+    let parameters = [sub_register, 0x00, 0x00];
+    let read = async {
+        if long {
+            chan.read_long_register(device_index, address, parameters)
+                .await
+                .map(|data| data.to_vec())
+        } else {
+            chan.read_register(device_index, address, parameters)
+                .await
+                .map(|data| data.to_vec())
+        }
+    };
+
+    let result = select! {
+        result = read.fuse() => result,
+        _ = Delay::new(options.timeout).fuse() => return ProbeOutcome::NoResponse,
+    };
+
+    match result {
+        Ok(data) => ProbeOutcome::Data(data),
+        Err(Hidpp10Error::RegisterAccess {
+            kind: kind @ (ErrorType::InvalidSubId | ErrorType::InvalidAddress),
+            ..
+        }) => ProbeOutcome::Unsupported(kind),
+        Err(Hidpp10Error::RegisterAccess {
+            kind, ..
+        }) => ProbeOutcome::Rejected(kind),
+        Err(Hidpp10Error::UnsupportedResponse {
+            response, ..
+        }) => ProbeOutcome::Malformed(response),
+        Err(_) => ProbeOutcome::NoResponse,
+    }
+}
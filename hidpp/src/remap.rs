@@ -0,0 +1,277 @@
+//! Remaps the controls of a device, regardless of which remapping feature it
+//! implements.
+//!
+//! Older devices only implement
+//! [`ReprogControlsFeature`](crate::feature::reprog_controls::ReprogControlsFeature)
+//! (`0x1b04`), which can remap a control to another control of the device and
+//! divert it to software. Newer devices additionally implement
+//! [`PersistentRemappableActionFeature`](crate::feature::persistent_remappable_action::PersistentRemappableActionFeature)
+//! (`0x1c00`), which assigns arbitrary keys, mouse buttons and consumer
+//! usages and stores them on the device. [`apply`] picks the feature able to
+//! perform a [`Remap`] and translates its action accordingly.
+
+use thiserror::Error;
+
+use crate::{
+    device::Device,
+    feature::{
+        persistent_remappable_action::{
+            ActionKind,
+            PersistentRemappableActionFeature,
+            RemappableAction,
+        },
+        reprog_controls::{ControlReportingChange, ReprogControlsFeature},
+    },
+    protocol::v20::Hidpp20Error,
+};
+
+/// The CIDs of the standard mouse buttons, where index `n` holds button
+/// `n + 1`.
+const MOUSE_BUTTON_CIDS: [u16; 5] = [0x0050, 0x0051, 0x0052, 0x0053, 0x0056];
+
+/// The consumer page usages sent by media controls, together with the CIDs
+/// of the controls.
+const CONSUMER_CIDS: [(u16, u16); 7] = [
+    (0x00e9, 0x0001),
+    (0x00ea, 0x0002),
+    (0x00e2, 0x0003),
+    (0x00cd, 0x0004),
+    (0x00b5, 0x0005),
+    (0x00b6, 0x0006),
+    (0x00b7, 0x0007),
+];
+
+/// Represents the remapping of a control to an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct Remap {
+    /// The CID of the remapped control.
+    pub control: u16,
+
+    /// The action the control performs.
+    pub action: RemapAction,
+
+    /// Whether the remapping should be stored on the device and survive
+    /// reconnects.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub persistent: bool,
+}
+
+impl Remap {
+    /// Constructs a remapping that is not stored on the device.
+    pub fn new(control: u16, action: RemapAction) -> Self {
+        Self {
+            control,
+            action,
+            persistent: false,
+        }
+    }
+
+    /// Constructs a remapping that is stored on the device.
+    pub fn persistent(control: u16, action: RemapAction) -> Self {
+        Self {
+            control,
+            action,
+            persistent: true,
+        }
+    }
+}
+
+/// Represents an action a control can be remapped to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "action", rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum RemapAction {
+    /// Restores the default action of the control.
+    Default,
+
+    /// Sends a keyboard page HID usage together with modifiers, using the bit
+    /// layout of HID keyboard reports.
+    Key {
+        usage: u8,
+        modifiers: u8,
+    },
+
+    /// Clicks a mouse button, starting at `1` for the left button.
+    MouseButton {
+        button: u8,
+    },
+
+    /// Sends a consumer page HID usage.
+    Consumer {
+        usage: u16,
+    },
+
+    /// Does nothing.
+    NoOp,
+
+    /// Diverts the control to be reported via HID++ instead of performing any
+    /// action.
+    Divert,
+}
+
+/// Represents the feature used to apply a [`Remap`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum RemapBackend {
+    ReprogControls,
+    PersistentRemappableAction,
+}
+
+/// Represents an error that may occur when remapping a control.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum RemapError {
+    /// Indicates that the device implements none of the remapping features.
+    #[error("the device does not support remapping controls")]
+    Unsupported,
+
+    /// Indicates that the device has no remappable control with the CID.
+    #[error("the device has no remappable control {0:#06x}")]
+    UnknownControl(u16),
+
+    /// Indicates that the control can't be remapped to the action on this
+    /// device.
+    #[error("control {control:#06x} can't be remapped to {action:?} on this device")]
+    UnsupportedAction {
+        control: u16,
+        action: RemapAction,
+    },
+
+    /// Indicates that the device returned an error.
+    #[error("the device returned an error")]
+    Hidpp(#[from] Hidpp20Error),
+}
+
+/// Applies a remapping to a device.
+///
+/// [`RemapAction::Divert`] is always applied using `0x1b04`. Other actions
+/// prefer `0x1b04` for remappings that are not persistent, as long as it can
+/// express them, and use `0x1c00` otherwise.
+///
+/// Returns the feature that was used.
+pub async fn apply(device: &Device, remap: &Remap) -> Result<RemapBackend, RemapError> {
+    let reprog = device.get_feature::<ReprogControlsFeature>();
+    let persistent = device.get_feature::<PersistentRemappableActionFeature>();
+
+    if reprog.is_none() && persistent.is_none() {
+        return Err(RemapError::Unsupported);
+    }
+
+    if let Some(feature) = &reprog
+        && (!remap.persistent || persistent.is_none() || remap.action == RemapAction::Divert)
+        && let Some(change) = reprog_change(feature, remap).await?
+    {
+        feature.set_control_reporting(remap.control, change).await?;
+        return Ok(RemapBackend::ReprogControls);
+    }
+
+    let Some(feature) = persistent else {
+        if let Some(feature) = &reprog
+            && !feature
+                .get_controls()
+                .await?
+                .iter()
+                .any(|x| x.cid == remap.control)
+        {
+            return Err(RemapError::UnknownControl(remap.control));
+        }
+
+        return Err(RemapError::UnsupportedAction {
+            control: remap.control,
+            action: remap.action,
+        });
+    };
+
+    if !feature.get_controls().await?.contains(&remap.control) {
+        return Err(RemapError::UnknownControl(remap.control));
+    }
+
+    let action = match remap.action {
+        RemapAction::Default => {
+            feature.reset_action(remap.control).await?;
+            return Ok(RemapBackend::PersistentRemappableAction);
+        },
+        RemapAction::Key {
+            usage,
+            modifiers,
+        } => RemappableAction::new(ActionKind::Key, usage as u16, modifiers),
+        RemapAction::MouseButton {
+            button: button @ 1..=16,
+        } => RemappableAction::new(ActionKind::Mouse, 1 << (button - 1), 0),
+        RemapAction::Consumer {
+            usage,
+        } => RemappableAction::new(ActionKind::Consumer, usage, 0),
+        RemapAction::NoOp => RemappableAction::new(ActionKind::Empty, 0, 0),
+        _ => {
+            return Err(RemapError::UnsupportedAction {
+                control: remap.control,
+                action: remap.action,
+            });
+        },
+    };
+
+    feature.set_action(remap.control, action).await?;
+    Ok(RemapBackend::PersistentRemappableAction)
+}
+
+/// Translates a remapping to a change of the control reporting of `0x1b04`.
+///
+/// Returns `Ok(None)` if the feature can't express the remapping or does not
+/// know the control.
+async fn reprog_change(
+    feature: &ReprogControlsFeature,
+    remap: &Remap,
+) -> Result<Option<ControlReportingChange>, RemapError> {
+    let controls = feature.get_controls().await?;
+    let Some(control) = controls.iter().find(|x| x.cid == remap.control) else {
+        return Ok(None);
+    };
+
+    let diverted = |persistent: bool| ControlReportingChange {
+        diverted: Some(true),
+        persistently_diverted: Some(persistent),
+        ..Default::default()
+    };
+
+    let target = match remap.action {
+        RemapAction::Default => control.cid,
+        RemapAction::Divert | RemapAction::NoOp
+            if control.divertable && (!remap.persistent || control.persistently_divertable) =>
+        {
+            // Nothing performs an action for diverted controls that are not
+            // being listened to, which makes diverting them a no-op.
+            return Ok(Some(diverted(remap.persistent)));
+        },
+        RemapAction::MouseButton {
+            button: button @ 1..=5,
+        } => MOUSE_BUTTON_CIDS[button as usize - 1],
+        RemapAction::Consumer {
+            usage,
+        } => match CONSUMER_CIDS.iter().find(|(x, _)| *x == usage) {
+            Some(&(_, cid)) => cid,
+            None => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    if !controls
+        .iter()
+        .any(|x| x.cid == target && control.can_remap_to(x))
+    {
+        return Ok(None);
+    }
+
+    Ok(Some(ControlReportingChange {
+        diverted: Some(false),
+        persistently_diverted: Some(false),
+        remapped: Some(target),
+        ..Default::default()
+    }))
+}
@@ -0,0 +1,41 @@
+//! Usages of the consumer page (`0x0c`).
+
+usage_table! {
+    u16;
+    POWER = 0x0030 => "Power",
+    SLEEP = 0x0032 => "Sleep",
+    MENU = 0x0040 => "Menu",
+    BRIGHTNESS_UP = 0x006f => "Brightness Up",
+    BRIGHTNESS_DOWN = 0x0070 => "Brightness Down",
+    PLAY = 0x00b0 => "Play",
+    PAUSE = 0x00b1 => "Pause",
+    RECORD = 0x00b2 => "Record",
+    FAST_FORWARD = 0x00b3 => "Fast Forward",
+    REWIND = 0x00b4 => "Rewind",
+    NEXT_TRACK = 0x00b5 => "Next Track",
+    PREVIOUS_TRACK = 0x00b6 => "Previous Track",
+    STOP = 0x00b7 => "Stop",
+    EJECT = 0x00b8 => "Eject",
+    PLAY_PAUSE = 0x00cd => "Play/Pause",
+    MUTE = 0x00e2 => "Mute",
+    VOLUME_UP = 0x00e9 => "Volume Up",
+    VOLUME_DOWN = 0x00ea => "Volume Down",
+    MEDIA_SELECT = 0x0183 => "Media Select",
+    EMAIL = 0x018a => "Email",
+    CALCULATOR = 0x0192 => "Calculator",
+    FILE_BROWSER = 0x0194 => "File Browser",
+    SCREEN_SAVER = 0x019e => "Screen Saver",
+    KEYBOARD_LAYOUT = 0x01ae => "Keyboard Layout",
+    FIND = 0x021f => "Find",
+    SEARCH = 0x0221 => "Search",
+    HOME = 0x0223 => "Home",
+    BACK = 0x0224 => "Back",
+    FORWARD = 0x0225 => "Forward",
+    BROWSER_STOP = 0x0226 => "Browser Stop",
+    REFRESH = 0x0227 => "Refresh",
+    BOOKMARKS = 0x022a => "Bookmarks",
+    ZOOM_IN = 0x022d => "Zoom In",
+    ZOOM_OUT = 0x022e => "Zoom Out",
+    PAN_HORIZONTAL = 0x0238 => "Horizontal Pan",
+    NEXT_KEYBOARD_LAYOUT = 0x029d => "Next Keyboard Layout",
+}
@@ -0,0 +1,162 @@
+//! Usages of the keyboard/keypad page (`0x07`).
+
+usage_table! {
+    u8;
+    A = 0x04 => "A",
+    B = 0x05 => "B",
+    C = 0x06 => "C",
+    D = 0x07 => "D",
+    E = 0x08 => "E",
+    F = 0x09 => "F",
+    G = 0x0a => "G",
+    H = 0x0b => "H",
+    I = 0x0c => "I",
+    J = 0x0d => "J",
+    K = 0x0e => "K",
+    L = 0x0f => "L",
+    M = 0x10 => "M",
+    N = 0x11 => "N",
+    O = 0x12 => "O",
+    P = 0x13 => "P",
+    Q = 0x14 => "Q",
+    R = 0x15 => "R",
+    S = 0x16 => "S",
+    T = 0x17 => "T",
+    U = 0x18 => "U",
+    V = 0x19 => "V",
+    W = 0x1a => "W",
+    X = 0x1b => "X",
+    Y = 0x1c => "Y",
+    Z = 0x1d => "Z",
+    DIGIT_1 = 0x1e => "1",
+    DIGIT_2 = 0x1f => "2",
+    DIGIT_3 = 0x20 => "3",
+    DIGIT_4 = 0x21 => "4",
+    DIGIT_5 = 0x22 => "5",
+    DIGIT_6 = 0x23 => "6",
+    DIGIT_7 = 0x24 => "7",
+    DIGIT_8 = 0x25 => "8",
+    DIGIT_9 = 0x26 => "9",
+    DIGIT_0 = 0x27 => "0",
+    ENTER = 0x28 => "Enter",
+    ESCAPE = 0x29 => "Escape",
+    BACKSPACE = 0x2a => "Backspace",
+    TAB = 0x2b => "Tab",
+    SPACE = 0x2c => "Space",
+    MINUS = 0x2d => "-",
+    EQUAL = 0x2e => "=",
+    LEFT_BRACKET = 0x2f => "[",
+    RIGHT_BRACKET = 0x30 => "]",
+    BACKSLASH = 0x31 => "\\",
+    NON_US_HASH = 0x32 => "Non-US #",
+    SEMICOLON = 0x33 => ";",
+    APOSTROPHE = 0x34 => "'",
+    GRAVE = 0x35 => "`",
+    COMMA = 0x36 => ",",
+    PERIOD = 0x37 => ".",
+    SLASH = 0x38 => "/",
+    CAPS_LOCK = 0x39 => "Caps Lock",
+    F1 = 0x3a => "F1",
+    F2 = 0x3b => "F2",
+    F3 = 0x3c => "F3",
+    F4 = 0x3d => "F4",
+    F5 = 0x3e => "F5",
+    F6 = 0x3f => "F6",
+    F7 = 0x40 => "F7",
+    F8 = 0x41 => "F8",
+    F9 = 0x42 => "F9",
+    F10 = 0x43 => "F10",
+    F11 = 0x44 => "F11",
+    F12 = 0x45 => "F12",
+    PRINT_SCREEN = 0x46 => "Print Screen",
+    SCROLL_LOCK = 0x47 => "Scroll Lock",
+    PAUSE = 0x48 => "Pause",
+    INSERT = 0x49 => "Insert",
+    HOME = 0x4a => "Home",
+    PAGE_UP = 0x4b => "Page Up",
+    DELETE = 0x4c => "Delete",
+    END = 0x4d => "End",
+    PAGE_DOWN = 0x4e => "Page Down",
+    RIGHT_ARROW = 0x4f => "Right Arrow",
+    LEFT_ARROW = 0x50 => "Left Arrow",
+    DOWN_ARROW = 0x51 => "Down Arrow",
+    UP_ARROW = 0x52 => "Up Arrow",
+    NUM_LOCK = 0x53 => "Num Lock",
+    KEYPAD_DIVIDE = 0x54 => "Keypad /",
+    KEYPAD_MULTIPLY = 0x55 => "Keypad *",
+    KEYPAD_SUBTRACT = 0x56 => "Keypad -",
+    KEYPAD_ADD = 0x57 => "Keypad +",
+    KEYPAD_ENTER = 0x58 => "Keypad Enter",
+    KEYPAD_1 = 0x59 => "Keypad 1",
+    KEYPAD_2 = 0x5a => "Keypad 2",
+    KEYPAD_3 = 0x5b => "Keypad 3",
+    KEYPAD_4 = 0x5c => "Keypad 4",
+    KEYPAD_5 = 0x5d => "Keypad 5",
+    KEYPAD_6 = 0x5e => "Keypad 6",
+    KEYPAD_7 = 0x5f => "Keypad 7",
+    KEYPAD_8 = 0x60 => "Keypad 8",
+    KEYPAD_9 = 0x61 => "Keypad 9",
+    KEYPAD_0 = 0x62 => "Keypad 0",
+    KEYPAD_DECIMAL = 0x63 => "Keypad .",
+    NON_US_BACKSLASH = 0x64 => "Non-US \\",
+    APPLICATION = 0x65 => "Application",
+    POWER = 0x66 => "Power",
+    KEYPAD_EQUAL = 0x67 => "Keypad =",
+    F13 = 0x68 => "F13",
+    F14 = 0x69 => "F14",
+    F15 = 0x6a => "F15",
+    F16 = 0x6b => "F16",
+    F17 = 0x6c => "F17",
+    F18 = 0x6d => "F18",
+    F19 = 0x6e => "F19",
+    F20 = 0x6f => "F20",
+    F21 = 0x70 => "F21",
+    F22 = 0x71 => "F22",
+    F23 = 0x72 => "F23",
+    F24 = 0x73 => "F24",
+    EXECUTE = 0x74 => "Execute",
+    HELP = 0x75 => "Help",
+    MENU = 0x76 => "Menu",
+    SELECT = 0x77 => "Select",
+    STOP = 0x78 => "Stop",
+    AGAIN = 0x79 => "Again",
+    UNDO = 0x7a => "Undo",
+    CUT = 0x7b => "Cut",
+    COPY = 0x7c => "Copy",
+    PASTE = 0x7d => "Paste",
+    FIND = 0x7e => "Find",
+    MUTE = 0x7f => "Mute",
+    VOLUME_UP = 0x80 => "Volume Up",
+    VOLUME_DOWN = 0x81 => "Volume Down",
+    LEFT_CONTROL = 0xe0 => "Left Control",
+    LEFT_SHIFT = 0xe1 => "Left Shift",
+    LEFT_ALT = 0xe2 => "Left Alt",
+    LEFT_GUI = 0xe3 => "Left GUI",
+    RIGHT_CONTROL = 0xe4 => "Right Control",
+    RIGHT_SHIFT = 0xe5 => "Right Shift",
+    RIGHT_ALT = 0xe6 => "Right Alt",
+    RIGHT_GUI = 0xe7 => "Right GUI",
+}
+
+/// The names of the modifiers in the modifier byte of keyboard reports,
+/// where index `n` corresponds to bit `n`.
+const MODIFIER_NAMES: [&str; 8] = [
+    "Left Control",
+    "Left Shift",
+    "Left Alt",
+    "Left GUI",
+    "Right Control",
+    "Right Shift",
+    "Right Alt",
+    "Right GUI",
+];
+
+/// Provides the names of all modifiers set in the modifier byte of a keyboard
+/// report.
+pub fn modifier_names(modifiers: u8) -> impl Iterator<Item = &'static str> {
+    MODIFIER_NAMES
+        .into_iter()
+        .enumerate()
+        .filter(move |(bit, _)| modifiers & (1 << bit) != 0)
+        .map(|(_, name)| name)
+}
@@ -0,0 +1,79 @@
+//! HID usage tables used to decode diverted key reports.
+//!
+//! Several features report keys by their HID usage instead of a control ID,
+//! like `ReportHidUsages` (`0x1bc0`) or `DisableKeysByUsage` (`0x4522`). This
+//! module contains constants and names for the usages of the keyboard and
+//! consumer pages.
+//!
+//! ```
+//! use hidpp::usage::{self, UsagePage, consumer, keyboard};
+//!
+//! assert_eq!(keyboard::name(keyboard::A), Some("A"));
+//! assert_eq!(consumer::name(consumer::PLAY_PAUSE), Some("Play/Pause"));
+//! assert_eq!(usage::name(UsagePage::Keyboard, 0x29), Some("Escape"));
+//! assert_eq!(keyboard::modifier_names(0x03).collect::<Vec<_>>(), [
+//!     "Left Control",
+//!     "Left Shift"
+//! ]);
+//! ```
+
+/// Defines usage constants together with a `name` function looking up the
+/// names of the usages.
+macro_rules! usage_table {
+    ($ty:ty; $($name:ident = $value:literal => $display:literal,)*) => {
+        $(
+            #[doc = concat!("The `", $display, "` usage.")]
+            pub const $name: $ty = $value;
+        )*
+
+        /// Looks up the name of a usage.
+        pub fn name(usage: $ty) -> Option<&'static str> {
+            match usage {
+                $($value => Some($display),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+pub mod consumer;
+pub mod keyboard;
+
+/// Represents a HID usage page with a usage table in this module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[non_exhaustive]
+pub enum UsagePage {
+    /// The keyboard/keypad page (`0x07`).
+    Keyboard,
+
+    /// The consumer page (`0x0c`).
+    Consumer,
+}
+
+impl UsagePage {
+    /// Looks up a usage page by its ID.
+    pub fn from_id(id: u16) -> Option<Self> {
+        match id {
+            0x07 => Some(Self::Keyboard),
+            0x0c => Some(Self::Consumer),
+            _ => None,
+        }
+    }
+
+    /// Provides the ID of the usage page.
+    pub fn id(self) -> u16 {
+        match self {
+            Self::Keyboard => 0x07,
+            Self::Consumer => 0x0c,
+        }
+    }
+}
+
+/// Looks up the name of a usage of a page.
+pub fn name(page: UsagePage, usage: u16) -> Option<&'static str> {
+    match page {
+        UsagePage::Keyboard => u8::try_from(usage).ok().and_then(keyboard::name),
+        UsagePage::Consumer => consumer::name(usage),
+    }
+}
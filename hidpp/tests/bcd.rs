@@ -0,0 +1,64 @@
+//! Checks the packed BCD conversions against each other and against invalid
+//! input.
+
+use hidpp::bcd::{self, BcdError};
+
+#[test]
+fn round_trip_u8() {
+    for value in 0..=99 {
+        let encoded = bcd::encode_packed_u8(value).unwrap();
+        assert_eq!(encoded, ((value / 10) << 4) | (value % 10));
+        assert_eq!(bcd::decode_packed_u8(encoded), Ok(value));
+    }
+}
+
+#[test]
+fn round_trip_u16() {
+    for value in 0..=9999 {
+        let encoded = bcd::encode_packed_u16(value).unwrap();
+        assert_eq!(bcd::decode_packed_u16(encoded), Ok(value));
+    }
+
+    assert_eq!(bcd::encode_packed_u16(1234), Ok(0x1234));
+    assert_eq!(bcd::encode_packed_u16(7), Ok(0x0007));
+}
+
+#[test]
+fn invalid_digits() {
+    for bcd in [0x0a, 0xa0, 0x1f, 0xf1, 0xff] {
+        assert_eq!(
+            bcd::decode_packed_u8(bcd),
+            Err(BcdError::InvalidDigit(bcd as u16))
+        );
+    }
+
+    for bcd in [0x000a, 0x00a0, 0x0a00, 0xa000, 0x12f4, 0xffff] {
+        assert_eq!(
+            bcd::decode_packed_u16(bcd),
+            Err(BcdError::InvalidDigit(bcd))
+        );
+    }
+
+    // Every byte with a nibble above 9 is rejected, every other one decodes.
+    for bcd in 0..=u8::MAX {
+        let valid = bcd >> 4 <= 9 && bcd & 0x0f <= 9;
+        assert_eq!(bcd::decode_packed_u8(bcd).is_ok(), valid, "{bcd:#04x}");
+    }
+}
+
+#[test]
+fn out_of_range() {
+    for value in [100, 200, u8::MAX] {
+        assert_eq!(
+            bcd::encode_packed_u8(value),
+            Err(BcdError::OutOfRange(value as u16))
+        );
+    }
+
+    for value in [10000, 12345, u16::MAX] {
+        assert_eq!(
+            bcd::encode_packed_u16(value),
+            Err(BcdError::OutOfRange(value))
+        );
+    }
+}
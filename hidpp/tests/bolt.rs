@@ -0,0 +1,208 @@
+//! Replays transcripts of a Bolt receiver against its implementation.
+
+mod harness;
+
+use std::time::Duration;
+
+use futures::executor::block_on;
+use hidpp::{
+    protocol::v10::{ErrorType, Hidpp10Error},
+    receiver::{
+        ReceiverError,
+        bolt::{
+            BoltDeviceKind,
+            BoltDevicePairingInformation,
+            BoltPairingError,
+            BoltPairingPasskeyPressType,
+            BoltReceiver,
+            pairing::{
+                BoltPairingFailure,
+                BoltPairingOptions,
+                BoltPairingSession,
+                BoltPairingStep,
+            },
+        },
+    },
+};
+
+/// Starts a pairing session against a transcript and collects all of its
+/// steps.
+async fn pair(
+    transcript: &str,
+    options: BoltPairingOptions,
+) -> Result<Vec<BoltPairingStep>, ReceiverError> {
+    let (chan, device) = harness::replay(transcript).await;
+    let receiver = BoltReceiver::new(chan).unwrap();
+
+    let res = async {
+        let mut session = BoltPairingSession::start(&receiver, options).await?;
+        let mut steps = Vec::new();
+        while let Some(step) = session.next_step().await? {
+            steps.push(step);
+        }
+
+        Ok(steps)
+    }
+    .await;
+
+    device.finish();
+    res
+}
+
+#[test]
+fn pairing_slots() {
+    block_on(async {
+        let (chan, device) = harness::replay("bolt_pairing_slots.json").await;
+        let receiver = BoltReceiver::new(chan).unwrap();
+
+        let slots = receiver.get_pairing_slots().await.unwrap();
+        assert_eq!(slots.len(), 6);
+
+        let occupied = slots.iter().map(Option::is_some).collect::<Vec<_>>();
+        assert_eq!(occupied, [true, false, false, true, false, false]);
+
+        let Some(BoltDevicePairingInformation {
+            wpid,
+            kind,
+            encrypted,
+            online,
+            unit_id,
+            ..
+        }) = slots[0]
+        else {
+            panic!("slot 1 is empty");
+        };
+        assert_eq!(wpid, 0x402b);
+        assert_eq!(kind, BoltDeviceKind::Mouse);
+        assert!(encrypted && online);
+        assert_eq!(unit_id, [0x01, 0x02, 0x03, 0x04]);
+
+        let keyboard = slots[3].unwrap();
+        assert_eq!(keyboard.wpid, 0x4071);
+        assert_eq!(keyboard.kind, BoltDeviceKind::Keyboard);
+        assert!(!keyboard.online);
+
+        device.finish();
+    });
+}
+
+#[test]
+fn pairing() {
+    block_on(async {
+        let steps = pair("bolt_pairing.json", BoltPairingOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(steps.len(), 6);
+
+        let BoltPairingStep::Pairing(device) = &steps[0] else {
+            panic!("unexpected step {:?}", steps[0]);
+        };
+        assert_eq!(device.address, [0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff]);
+        assert_eq!(device.kind, BoltDeviceKind::Mouse);
+        assert_eq!(device.wpid, 0x401b);
+        assert_eq!(device.name, None);
+
+        assert_eq!(steps[1..], [
+            BoltPairingStep::DeviceName("MX 3S".to_string()),
+            BoltPairingStep::ShowPasskey("123456".to_string()),
+            BoltPairingStep::KeyPressed(BoltPairingPasskeyPressType::Initialization),
+            BoltPairingStep::KeyPressed(BoltPairingPasskeyPressType::Submit),
+            BoltPairingStep::Completed {
+                slot: 2,
+            },
+        ]);
+    });
+}
+
+#[test]
+fn pairing_failed() {
+    block_on(async {
+        let steps = pair("bolt_pairing_failed.json", BoltPairingOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(steps.len(), 2);
+
+        let BoltPairingStep::Pairing(device) = &steps[0] else {
+            panic!("unexpected step {:?}", steps[0]);
+        };
+        assert_eq!(device.kind, BoltDeviceKind::Keyboard);
+        assert_eq!(device.name.as_deref(), Some("K855"));
+
+        assert_eq!(
+            steps[1],
+            BoltPairingStep::Failed(BoltPairingFailure::Pairing(BoltPairingError::Failed))
+        );
+    });
+}
+
+#[test]
+fn pairing_receiver_error() {
+    block_on(async {
+        let err = pair(
+            "bolt_pairing_receiver_error.json",
+            BoltPairingOptions::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            ReceiverError::Protocol(Hidpp10Error::RegisterAccess {
+                kind: ErrorType::InvalidValue,
+                ..
+            })
+        ));
+    });
+}
+
+#[test]
+fn pairing_no_device_found() {
+    block_on(async {
+        let steps = pair("bolt_pairing_no_device.json", BoltPairingOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(steps, [BoltPairingStep::Failed(
+            BoltPairingFailure::NoDeviceFound
+        )]);
+    });
+}
+
+#[test]
+fn pairing_no_free_slot() {
+    block_on(async {
+        let steps = pair(
+            "bolt_pairing_no_free_slot.json",
+            BoltPairingOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(steps, [BoltPairingStep::Failed(
+            BoltPairingFailure::NoFreeSlot
+        )]);
+    });
+}
+
+#[test]
+fn pairing_timeout() {
+    block_on(async {
+        let mut options = BoltPairingOptions::default();
+        options.step_timeout = Duration::from_millis(50);
+
+        let steps = pair("bolt_pairing_timeout.json", options).await.unwrap();
+        assert_eq!(steps, [BoltPairingStep::Failed(
+            BoltPairingFailure::Timeout
+        )]);
+    });
+}
+
+#[test]
+fn pairing_invalid_slot() {
+    block_on(async {
+        for slot in [0, 7] {
+            let mut options = BoltPairingOptions::default();
+            options.slot = Some(slot);
+
+            let err = pair("silent.json", options).await.unwrap_err();
+            assert!(matches!(err, ReceiverError::InvalidSlot(s) if s == slot));
+        }
+    });
+}
@@ -0,0 +1,168 @@
+//! Floods a channel with notifications while its listeners are busy.
+
+use std::{
+    error::Error,
+    mem,
+    sync::{
+        Arc,
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+        mpsc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures::executor::block_on;
+use hidpp::{
+    async_trait,
+    channel::{HidppChannel, HidppMessage, RawHidChannel},
+};
+
+/// How many notifications to send, well above the length of the dispatch
+/// queue.
+const FLOOD_LENGTH: usize = 1024;
+
+/// How long to wait for a listener before considering the channel deadlocked.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+const NOTIFICATION: [u8; 7] = [0x10, 0x01, 0x05, 0x00, 0x00, 0x00, 0x00];
+
+/// A device echoing every request back as its response.
+#[derive(Clone)]
+struct EchoDevice {
+    incoming: async_channel::Sender<Vec<u8>>,
+    incoming_rx: async_channel::Receiver<Vec<u8>>,
+}
+
+impl EchoDevice {
+    fn new() -> Self {
+        let (incoming, incoming_rx) = async_channel::unbounded();
+        Self {
+            incoming,
+            incoming_rx,
+        }
+    }
+
+    /// Sends notifications and waits until the channel read all of them, or
+    /// got stuck trying to.
+    fn notify(&self, count: usize) {
+        for _ in 0..count {
+            self.incoming.try_send(NOTIFICATION.to_vec()).unwrap();
+        }
+
+        let start = Instant::now();
+        while !self.incoming.is_empty() && start.elapsed() < TIMEOUT {
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+#[async_trait]
+impl RawHidChannel for EchoDevice {
+    fn vendor_id(&self) -> u16 {
+        0x046d
+    }
+
+    fn product_id(&self) -> u16 {
+        0xc548
+    }
+
+    async fn write_report(&self, src: &[u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        self.incoming.try_send(src.to_vec())?;
+        Ok(src.len())
+    }
+
+    async fn read_report(&self, buf: &mut [u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let report = self.incoming_rx.recv().await?;
+        buf[..report.len()].copy_from_slice(&report);
+        Ok(report.len())
+    }
+
+    fn supports_short_long_hidpp(&self) -> Option<(bool, bool)> {
+        Some((true, true))
+    }
+
+    async fn get_report_descriptor(
+        &self,
+        _: &mut [u8],
+    ) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        Err("the echo device has no report descriptor".into())
+    }
+}
+
+/// Blocks the first listener call until the flood was sent, then runs `f`.
+fn on_first_message(
+    flooded: mpsc::Receiver<()>,
+    f: impl Fn() + Send + Sync + 'static,
+) -> impl Fn(HidppMessage, bool) + Send + Sync + 'static {
+    let flooded = Mutex::new(flooded);
+    let called = AtomicBool::new(false);
+
+    move |_, _| {
+        if !called.swap(true, Ordering::SeqCst) {
+            flooded.lock().unwrap().recv().unwrap();
+            f();
+        }
+    }
+}
+
+#[test]
+fn listener_waits_for_response() {
+    let device = EchoDevice::new();
+    let chan = Arc::new(block_on(HidppChannel::from_raw_channel(device.clone())).unwrap());
+
+    let (flooded_tx, flooded_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel();
+    chan.add_msg_listener(on_first_message(flooded_rx, {
+        let chan = Arc::downgrade(&chan);
+        let done_tx = Mutex::new(done_tx);
+
+        move || {
+            let request = HidppMessage::Short([0x01, 0x05, 0x1a, 0x00, 0x00, 0x00]);
+            let chan = chan.upgrade().unwrap();
+            let res = block_on(chan.send(request, move |msg| *msg == request));
+            done_tx.lock().unwrap().send(res.is_ok()).unwrap();
+        }
+    }));
+
+    device.notify(FLOOD_LENGTH);
+    flooded_tx.send(()).unwrap();
+
+    let Ok(res) = done_rx.recv_timeout(TIMEOUT) else {
+        // Dropping the deadlocked channel would block forever.
+        mem::forget(chan);
+        panic!("the listener did not receive the response");
+    };
+    assert!(res);
+    assert!(chan.dropped_message_count() > 0);
+}
+
+#[test]
+fn listener_drops_channel() {
+    let device = EchoDevice::new();
+    let chan = Arc::new(block_on(HidppChannel::from_raw_channel(device.clone())).unwrap());
+    let slot = Arc::new(Mutex::new(None::<Arc<HidppChannel>>));
+
+    let (flooded_tx, flooded_rx) = mpsc::channel();
+    let (done_tx, done_rx) = mpsc::channel();
+    chan.add_msg_listener(on_first_message(flooded_rx, {
+        let slot = Arc::clone(&slot);
+        let done_tx = Mutex::new(done_tx);
+
+        move || {
+            let chan = slot.lock().unwrap().take();
+            drop(chan);
+            done_tx.lock().unwrap().send(()).unwrap();
+        }
+    }));
+    *slot.lock().unwrap() = Some(chan);
+
+    device.notify(FLOOD_LENGTH);
+    flooded_tx.send(()).unwrap();
+
+    assert!(
+        done_rx.recv_timeout(TIMEOUT).is_ok(),
+        "dropping the channel from a listener did not return"
+    );
+}
@@ -0,0 +1,174 @@
+//! Replays device transcripts against the feature implementations.
+
+mod harness;
+
+use std::sync::Arc;
+
+use futures::executor::block_on;
+use hidpp::{
+    feature::{
+        CreatableFeature,
+        EmittingFeature,
+        device_information::DeviceInformationFeature,
+        lighting::Color,
+        per_key_lighting::PerKeyLightingFeature,
+        reprog_controls::{ControlReportingChange, ReprogControlsEvent, ReprogControlsFeature},
+        unified_battery::{BatteryEvent, BatteryLevel, BatteryStatus, UnifiedBatteryFeature},
+    },
+    protocol::v20::{ErrorType, Hidpp20Error},
+};
+
+#[test]
+fn unified_battery() {
+    block_on(async {
+        let (chan, device) = harness::replay("unified_battery.json").await;
+        let feature = UnifiedBatteryFeature::new(Arc::clone(&chan), 0x02, 0x05);
+        let rx = feature.listen();
+
+        let capabilities = feature.get_battery_capabilities().await.unwrap();
+        assert_eq!(capabilities.reported_levels.len(), 4);
+        assert!(capabilities.rechargeable);
+        assert!(capabilities.percentage);
+
+        let info = feature.get_battery_info().await.unwrap();
+        assert_eq!(info.charging_percentage, 80);
+        assert_eq!(info.level, BatteryLevel::Good);
+        assert_eq!(info.status, BatteryStatus::Discharging);
+
+        let BatteryEvent::InfoUpdate(update) = harness::recv(&rx).await else {
+            panic!("unexpected event");
+        };
+        assert_eq!(update.charging_percentage, 75);
+        assert_eq!(update.status, BatteryStatus::Charging);
+
+        device.finish();
+    });
+}
+
+#[test]
+fn unified_battery_error() {
+    block_on(async {
+        let (chan, device) = harness::replay("unified_battery_error.json").await;
+        let feature = UnifiedBatteryFeature::new(Arc::clone(&chan), 0x02, 0x05);
+
+        let err = feature.get_battery_info().await.unwrap_err();
+        let Hidpp20Error::Feature {
+            kind,
+            context,
+            ..
+        } = err
+        else {
+            panic!("unexpected error {err:?}");
+        };
+        assert_eq!(kind, ErrorType::Unsupported);
+        assert_eq!(context.feature_index, 0x05);
+        assert_eq!(context.feature_id, Some(UnifiedBatteryFeature::ID));
+
+        device.finish();
+    });
+}
+
+#[test]
+fn device_information_versions() {
+    block_on(async {
+        let (chan, device) = harness::replay("device_information_v3.json").await;
+        let feature = DeviceInformationFeature::new(Arc::clone(&chan), 0x02, 0x05);
+        assert_eq!(feature.version(), None);
+
+        let info = feature.get_versioned_device_info().await.unwrap();
+        assert_eq!(feature.version(), Some(3));
+        assert_eq!(info.entity_count(), 2);
+        assert_eq!(info.unit_id(), Some([0xaa, 0xbb, 0xcc, 0xdd]));
+        assert_eq!(info.model_id(), Some([0xc082, 0x0000, 0x0000]));
+        assert_eq!(info.extended_model_id(), Some(0x07));
+        assert_eq!(info.capabilities(), None);
+        assert!(!info.supports_serial_number());
+
+        // The function does not exist before version 4, so it is not called.
+        let err = feature.get_serial_number().await.unwrap_err();
+        assert!(matches!(err, Hidpp20Error::UnsupportedVersion {
+            version: 3,
+            required: 4,
+            ..
+        }));
+
+        device.finish();
+    });
+}
+
+#[test]
+fn reprog_controls() {
+    block_on(async {
+        let (chan, device) = harness::replay("reprog_controls.json").await;
+        let feature = ReprogControlsFeature::new(Arc::clone(&chan), 0x02, 0x09);
+        let rx = feature.listen();
+
+        let controls = feature.get_controls().await.unwrap();
+        assert_eq!(controls.len(), 2);
+        assert_eq!(controls[0].cid, 0x0052);
+        assert_eq!(controls[0].task_id, 0x0038);
+        assert!(controls[0].mouse_button && controls[0].divertable);
+        assert!(!controls[0].persistently_divertable);
+        assert_eq!(controls[1].cid, 0x00c3);
+        assert!(controls[1].persistently_divertable && controls[1].raw_xy);
+
+        feature
+            .set_control_reporting(0x00c3, ControlReportingChange {
+                diverted: Some(true),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        let reporting = feature.get_control_reporting(0x00c3).await.unwrap();
+        assert!(reporting.diverted);
+        assert!(!reporting.persistently_diverted);
+        assert_eq!(reporting.remapped, 0x00c3);
+
+        assert_eq!(
+            harness::recv(&rx).await,
+            ReprogControlsEvent::DivertedButtons(vec![0x00c3])
+        );
+        assert_eq!(
+            harness::recv(&rx).await,
+            ReprogControlsEvent::DivertedButtons(vec![])
+        );
+
+        device.finish();
+    });
+}
+
+#[test]
+fn per_key_lighting() {
+    block_on(async {
+        let (chan, device) = harness::replay("per_key_lighting.json").await;
+        let feature = PerKeyLightingFeature::new(Arc::clone(&chan), 0x02, 0x0a);
+
+        // Zones beyond 255 are reported by the last page, but can't exist.
+        assert_eq!(feature.get_zones().await.unwrap(), [
+            0x04, 0x05, 0x06, 0x07, 0x70, 0xe0
+        ]);
+
+        feature
+            .set_zones(&[
+                (0x04, Color::new(0xff, 0x00, 0x00)),
+                (0x05, Color::new(0x00, 0xff, 0x00)),
+                (0x06, Color::new(0x00, 0x00, 0xff)),
+                (0x07, Color::new(0xff, 0xff, 0xff)),
+                (0xe0, Color::new(0x10, 0x20, 0x30)),
+            ])
+            .await
+            .unwrap();
+
+        let colors = (1..=6).map(|i| Color::new(i, i, i)).collect::<Vec<_>>();
+        feature.set_consecutive_zones(0x04, &colors).await.unwrap();
+
+        feature
+            .set_zone_range(0x04, 0x07, Color::new(0x80, 0x40, 0x20))
+            .await
+            .unwrap();
+        feature.commit_frame().await.unwrap();
+
+        device.finish();
+    });
+}
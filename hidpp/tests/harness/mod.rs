@@ -0,0 +1,233 @@
+//! Drives a real [`HidppChannel`] against a virtual device replaying a
+//! transcript.
+//!
+//! Transcripts live in `tests/transcripts` and are JSON files listing the
+//! reports exchanged with the device in order:
+//!
+//! ```json
+//! {
+//!     "description": "Reads the battery status",
+//!     "steps": [
+//!         { "request": "10 02 08 11 00 00 00", "responses": ["10 02 08 11 50 04 00"] },
+//!         { "notification": "10 02 08 00 4b 04 00" }
+//!     ]
+//! }
+//! ```
+//!
+//! A request step expects the channel to write exactly the given report and
+//! answers it with the given responses. Notification steps are delivered as
+//! soon as all preceding requests were made. Reports are written in
+//! hexadecimal, starting with the report ID.
+
+// Every test binary includes the harness, but not every one uses all of it.
+#![allow(dead_code)]
+
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{FutureExt, select};
+use futures_timer::Delay;
+use hidpp::{
+    async_trait,
+    channel::{HidppChannel, RawHidChannel},
+};
+use serde::{Deserialize, Deserializer, de};
+
+/// How long [`recv`] waits for an event before failing.
+const EVENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Represents a recorded exchange with a device.
+#[derive(Debug, Deserialize)]
+pub struct Transcript {
+    #[serde(default)]
+    pub description: String,
+
+    pub steps: Vec<Step>,
+}
+
+/// Represents a single step of a [`Transcript`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Step {
+    /// A report the channel is expected to write, answered by the device.
+    Request {
+        #[serde(deserialize_with = "report")]
+        request: Vec<u8>,
+
+        #[serde(default, deserialize_with = "reports")]
+        responses: Vec<Vec<u8>>,
+    },
+
+    /// A report sent by the device on its own.
+    Notification {
+        #[serde(deserialize_with = "report")]
+        notification: Vec<u8>,
+    },
+}
+
+fn parse_report(hex: &str) -> Result<Vec<u8>, String> {
+    hex.split_whitespace()
+        .map(|byte| u8::from_str_radix(byte, 16).map_err(|_| format!("invalid byte {byte:?}")))
+        .collect()
+}
+
+fn report<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    parse_report(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+}
+
+fn reports<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<u8>>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|hex| parse_report(hex).map_err(de::Error::custom))
+        .collect()
+}
+
+/// A virtual device replaying a [`Transcript`].
+#[derive(Clone)]
+pub struct VirtualDevice {
+    description: Arc<str>,
+    state: Arc<Mutex<State>>,
+    incoming: async_channel::Sender<Vec<u8>>,
+    incoming_rx: async_channel::Receiver<Vec<u8>>,
+}
+
+struct State {
+    steps: VecDeque<Step>,
+    errors: Vec<String>,
+}
+
+impl VirtualDevice {
+    pub fn new(transcript: Transcript) -> Self {
+        let (incoming, incoming_rx) = async_channel::unbounded();
+        let device = Self {
+            description: transcript.description.into(),
+            state: Arc::new(Mutex::new(State {
+                steps: transcript.steps.into(),
+                errors: Vec::new(),
+            })),
+            incoming,
+            incoming_rx,
+        };
+
+        device.deliver_notifications(&mut device.state.lock().unwrap());
+        device
+    }
+
+    /// Queues all notifications up to the next request.
+    fn deliver_notifications(&self, state: &mut State) {
+        while let Some(Step::Notification {
+            notification,
+        }) = state.steps.front()
+        {
+            self.incoming.try_send(notification.clone()).unwrap();
+            state.steps.pop_front();
+        }
+    }
+
+    /// Asserts that the whole transcript was replayed without any unexpected
+    /// request.
+    pub fn finish(&self) {
+        let state = self.state.lock().unwrap();
+
+        assert!(
+            state.errors.is_empty(),
+            "{}: {}",
+            self.description,
+            state.errors.join("\n")
+        );
+        assert!(
+            state.steps.is_empty(),
+            "{}: the transcript has unplayed steps: {:x?}",
+            self.description,
+            state.steps
+        );
+    }
+}
+
+#[async_trait]
+impl RawHidChannel for VirtualDevice {
+    fn vendor_id(&self) -> u16 {
+        0x046d
+    }
+
+    fn product_id(&self) -> u16 {
+        0xc548
+    }
+
+    async fn write_report(&self, src: &[u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let mut state = self.state.lock().unwrap();
+
+        let Some(Step::Request {
+            request,
+            responses,
+        }) = state.steps.front().cloned()
+        else {
+            let error = format!("unexpected request {src:02x?}");
+            state.errors.push(error.clone());
+            return Err(error.into());
+        };
+
+        if request != src {
+            let error = format!("expected request {request:02x?}, got {src:02x?}");
+            state.errors.push(error.clone());
+            return Err(error.into());
+        }
+
+        state.steps.pop_front();
+        for response in responses {
+            self.incoming.try_send(response).unwrap();
+        }
+        self.deliver_notifications(&mut state);
+
+        Ok(src.len())
+    }
+
+    async fn read_report(&self, buf: &mut [u8]) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        let report = self.incoming_rx.recv().await?;
+        buf[..report.len()].copy_from_slice(&report);
+        Ok(report.len())
+    }
+
+    fn supports_short_long_hidpp(&self) -> Option<(bool, bool)> {
+        Some((true, true))
+    }
+
+    async fn get_report_descriptor(
+        &self,
+        _: &mut [u8],
+    ) -> Result<usize, Box<dyn Error + Sync + Send>> {
+        Err("virtual devices have no report descriptor".into())
+    }
+}
+
+/// Loads a transcript from `tests/transcripts` and connects a channel to a
+/// virtual device replaying it.
+pub async fn replay(name: &str) -> (Arc<HidppChannel>, VirtualDevice) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/transcripts")
+        .join(name);
+    let json = fs::read_to_string(&path).unwrap_or_else(|err| panic!("{path:?}: {err}"));
+    let transcript: Transcript =
+        serde_json::from_str(&json).unwrap_or_else(|err| panic!("{path:?}: {err}"));
+
+    let device = VirtualDevice::new(transcript);
+    let chan = HidppChannel::from_raw_channel(device.clone())
+        .await
+        .unwrap();
+
+    (Arc::new(chan), device)
+}
+
+/// Receives the next event from a listener, failing if none arrives in time.
+pub async fn recv<T>(rx: &async_channel::Receiver<T>) -> T {
+    select! {
+        event = rx.recv().fuse() => event.unwrap(),
+        _ = Delay::new(EVENT_TIMEOUT).fuse() => panic!("no event was received"),
+    }
+}
@@ -0,0 +1,179 @@
+//! Reads battery states from devices, regardless of which battery feature they
+//! implement.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use hidpp::feature::{
+    EmittingFeature,
+    battery_status::{
+        BatteryLevelStatus,
+        BatteryStatusCapability,
+        BatteryStatusEvent,
+        BatteryStatusFeature,
+    },
+    battery_voltage::{BatteryVoltageEvent, BatteryVoltageFeature},
+    unified_battery::{BatteryEvent, BatteryLevel, BatteryStatus, UnifiedBatteryFeature},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+
+use crate::devices::{DeviceSummary, DiscoveredDevice};
+
+/// Represents the battery state of a device, as reported by whichever battery
+/// feature it supports.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Deserialize, Serialize)]
+pub struct BatteryState {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<BatteryLevel>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<BatteryStatus>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voltage: Option<u16>,
+
+    pub charging: bool,
+}
+
+/// Reads the current battery state of a device.
+///
+/// Returns `Ok(None)` if the device does not support any battery feature.
+pub async fn read_battery_state(device: &DiscoveredDevice) -> Result<Option<BatteryState>> {
+    if let Some(feature) = device.device.get_feature::<UnifiedBatteryFeature>() {
+        let capabilities = feature.get_battery_capabilities().await?;
+        let info = feature.get_battery_info().await?;
+
+        return Ok(Some(BatteryState {
+            percentage: capabilities.percentage.then_some(info.charging_percentage),
+            level: Some(info.level),
+            status: Some(info.status),
+            voltage: None,
+            charging: matches!(
+                info.status,
+                BatteryStatus::Charging | BatteryStatus::ChargingSlow
+            ),
+        }));
+    }
+
+    if let Some(feature) = device.device.get_feature::<BatteryStatusFeature>() {
+        let capability = feature.get_battery_capability().await?;
+        let status = feature.get_battery_level_status().await?;
+
+        return Ok(Some(battery_status_state(&status, &capability)));
+    }
+
+    if let Some(feature) = device.device.get_feature::<BatteryVoltageFeature>() {
+        let info = feature.get_battery_voltage().await?;
+
+        return Ok(Some(BatteryState {
+            voltage: Some(info.voltage),
+            charging: info.charging,
+            ..Default::default()
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Converts the state reported by the legacy `BatteryStatus` feature.
+fn battery_status_state(
+    status: &BatteryLevelStatus,
+    capability: &BatteryStatusCapability,
+) -> BatteryState {
+    let battery_status = status.battery_status();
+
+    BatteryState {
+        percentage: (status.discharge_level != 0).then_some(status.discharge_level),
+        level: status.level(capability),
+        status: Some(battery_status),
+        voltage: None,
+        charging: matches!(
+            battery_status,
+            BatteryStatus::Charging | BatteryStatus::ChargingSlow
+        ),
+    }
+}
+
+/// Forwards all battery events of a device to a channel until the device's
+/// features are dropped.
+pub fn spawn_battery_listeners(
+    device: &DiscoveredDevice,
+    tx: mpsc::UnboundedSender<(DeviceSummary, BatteryState)>,
+) {
+    if let Some(feature) = device.device.get_feature::<UnifiedBatteryFeature>() {
+        let rx = feature.listen();
+        let summary = device.summary.clone();
+        let tx = tx.clone();
+
+        tokio::spawn(async move {
+            while let Ok(BatteryEvent::InfoUpdate(info)) = rx.recv().await {
+                let state = BatteryState {
+                    // The capabilities are not re-read for every event, so a
+                    // percentage of zero is treated as unsupported.
+                    percentage: (info.charging_percentage != 0).then_some(info.charging_percentage),
+                    level: Some(info.level),
+                    status: Some(info.status),
+                    voltage: None,
+                    charging: matches!(
+                        info.status,
+                        BatteryStatus::Charging | BatteryStatus::ChargingSlow
+                    ),
+                };
+
+                if tx.send((summary.clone(), state)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    if let Some(feature) = device.device.get_feature::<BatteryStatusFeature>() {
+        let rx = feature.listen();
+        let summary = device.summary.clone();
+        let tx = tx.clone();
+        let feature = Arc::downgrade(&feature);
+
+        tokio::spawn(async move {
+            // The capability is read once, as it does not change while the
+            // device is connected. Only a weak reference is kept, so the
+            // listener still ends once the feature is dropped.
+            let Some(feature) = feature.upgrade() else {
+                return;
+            };
+            let Ok(capability) = feature.get_battery_capability().await else {
+                return;
+            };
+            drop(feature);
+
+            while let Ok(BatteryStatusEvent::LevelStatusBroadcast(status)) = rx.recv().await {
+                let state = battery_status_state(&status, &capability);
+                if tx.send((summary.clone(), state)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+
+    if let Some(feature) = device.device.get_feature::<BatteryVoltageFeature>() {
+        let rx = feature.listen();
+        let summary = device.summary.clone();
+
+        tokio::spawn(async move {
+            while let Ok(BatteryVoltageEvent::InfoUpdate(info)) = rx.recv().await {
+                let state = BatteryState {
+                    voltage: Some(info.voltage),
+                    charging: info.charging,
+                    ..Default::default()
+                };
+
+                if tx.send((summary.clone(), state)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{BufWriter, Write},
+};
+
+use anyhow::Result;
+use clap::Args;
+use hidpp::feature::unified_battery::BatteryLevel;
+use owo_colors::OwoColorize;
+use serde_json::{Value, json};
+use tokio::sync::mpsc;
+
+use super::{
+    Cli,
+    output::{EventLine, short_timestamp, write_structured},
+    status_bar::{self, StatusBarFormat},
+};
+use crate::{
+    battery::{BatteryState, read_battery_state, spawn_battery_listeners},
+    control::{ControlClient, DeviceBattery, GetBatteryParams},
+    devices::{self, DeviceSummary, Discovery},
+};
+
+/// View the battery state of connected devices.
+///
+/// If the daemon is running, the battery states it last received are shown
+/// instead of reading them from the devices.
+#[derive(Args)]
+pub struct BatteryCommand {
+    /// Keep running and print battery state changes as they are reported
+    #[arg(short, long)]
+    watch: bool,
+
+    /// Print a single status bar module line instead, for the device whose
+    /// battery is lowest. Takes precedence over --output
+    #[arg(short, long)]
+    format: Option<StatusBarFormat>,
+}
+
+/// Provides the battery states of the selected devices and, if watching,
+/// their changes.
+struct BatterySource {
+    states: Vec<(DeviceSummary, BatteryState)>,
+    updates: Option<mpsc::UnboundedReceiver<(DeviceSummary, BatteryState)>>,
+
+    /// Keeps the channels of devices accessed directly open while their
+    /// changes are watched.
+    _discovery: Option<Discovery>,
+}
+
+impl BatteryCommand {
+    pub async fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+
+        let source = match root.daemon().await {
+            Some(client) => self.remote(client, root).await?,
+            None => self.local(root).await?,
+        };
+        let states = source.states;
+
+        if let Some(format) = self.format {
+            return self
+                .execute_status_bar(format, states, source.updates)
+                .await;
+        }
+
+        let json = if self.watch {
+            root.json_lines()?
+        } else if let Some(format) = root.output_format() {
+            let entries = states
+                .iter()
+                .map(|(device, state)| json!({ "device": device, "battery": state }))
+                .collect::<Vec<_>>();
+            return write_structured(&mut stdout, format, &entries);
+        } else {
+            false
+        };
+
+        if states.is_empty() && !self.watch {
+            writeln!(
+                stdout,
+                "{}",
+                "No devices reporting their battery state were found.".bright_black()
+            )
+            .unwrap();
+            stdout.flush().unwrap();
+            return Ok(());
+        }
+
+        for (device, state) in &states {
+            if json {
+                EventLine::now("battery", Some(device), state).write(&mut stdout);
+            } else {
+                writeln!(stdout, "{}: {}", device.name, format_state(state)).unwrap();
+            }
+        }
+        stdout.flush().unwrap();
+
+        let Some(mut rx) = source.updates else {
+            return Ok(());
+        };
+
+        loop {
+            let (device, state) = tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                update = rx.recv() => match update {
+                    Some(update) => update,
+                    None => break,
+                },
+            };
+
+            if json {
+                EventLine::now("battery", Some(&device), &state).write(&mut stdout);
+                continue;
+            }
+
+            writeln!(
+                stdout,
+                "{} {}: {}",
+                short_timestamp().bright_black(),
+                device.name,
+                format_state(&state)
+            )
+            .unwrap();
+            stdout.flush().unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// Reads the battery states from the devices directly.
+    async fn local(&self, root: &Cli) -> Result<BatterySource> {
+        let mut discovery = devices::discover().await?;
+        discovery.select(root.device.as_ref()).await?;
+
+        let mut states = Vec::new();
+        for device in &discovery.devices {
+            if let Some(state) = read_battery_state(device).await? {
+                states.push((device.summary.clone(), state));
+            }
+        }
+
+        let updates = self.watch.then(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            for device in &discovery.devices {
+                spawn_battery_listeners(device, tx.clone());
+            }
+            rx
+        });
+
+        Ok(BatterySource {
+            states,
+            updates,
+            _discovery: Some(discovery),
+        })
+    }
+
+    /// Requests the battery states last reported to the running daemon.
+    async fn remote(&self, mut client: ControlClient, root: &Cli) -> Result<BatterySource> {
+        if self.watch {
+            client.call::<Value>("subscribe", json!({})).await?;
+        }
+
+        let batteries: Vec<DeviceBattery> = client
+            .call("get_battery", GetBatteryParams {
+                device: root.device.as_ref().map(ToString::to_string),
+            })
+            .await?;
+
+        // Without a selector, devices connecting later are watched as well.
+        let selected = root.device.is_some().then(|| {
+            batteries
+                .iter()
+                .map(|battery| battery.device.clone())
+                .collect::<HashSet<_>>()
+        });
+
+        let updates = self.watch.then(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(async move {
+                while let Ok(Some(event)) = client.next_event().await {
+                    let Some(device) = event.device else {
+                        continue;
+                    };
+                    if event.kind != "battery"
+                        || selected.as_ref().is_some_and(|x| !x.contains(&device))
+                    {
+                        continue;
+                    }
+
+                    let Ok(state) = serde_json::from_value(event.data) else {
+                        continue;
+                    };
+                    if tx.send((device, state)).is_err() {
+                        break;
+                    }
+                }
+            });
+            rx
+        });
+
+        Ok(BatterySource {
+            states: batteries
+                .into_iter()
+                .map(|battery| (battery.device, battery.battery))
+                .collect(),
+            updates,
+            _discovery: None,
+        })
+    }
+
+    /// Prints the battery state in a status bar format, re-printing it on
+    /// every change if watching.
+    async fn execute_status_bar(
+        &self,
+        format: StatusBarFormat,
+        states: Vec<(DeviceSummary, BatteryState)>,
+        updates: Option<mpsc::UnboundedReceiver<(DeviceSummary, BatteryState)>>,
+    ) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+
+        let mut states = states.into_iter().collect::<HashMap<_, _>>();
+
+        let mut line = status_bar::render(format, status_bar::select(&states));
+        writeln!(stdout, "{}", line).unwrap();
+        stdout.flush().unwrap();
+
+        let Some(mut rx) = updates else {
+            return Ok(());
+        };
+
+        loop {
+            let (device, state) = tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                update = rx.recv() => match update {
+                    Some(update) => update,
+                    None => break,
+                },
+            };
+            states.insert(device, state);
+
+            let new_line = status_bar::render(format, status_bar::select(&states));
+            if new_line != line {
+                line = new_line;
+                writeln!(stdout, "{}", line).unwrap();
+                stdout.flush().unwrap();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Formats a battery state for human-readable output.
+pub fn format_state(state: &BatteryState) -> String {
+    let mut parts = Vec::new();
+
+    if let Some(level) = state.level {
+        parts.push(format!("{:?}", match level {
+            BatteryLevel::Full | BatteryLevel::Good => level.green().into_styled(),
+            BatteryLevel::Low => level.yellow().into_styled(),
+            BatteryLevel::Critical => level.bright_red().into_styled(),
+            _ => level.default_color().into_styled(),
+        }));
+    }
+    if let Some(percentage) = state.percentage {
+        parts.push(format!("{}%", percentage).blue().to_string());
+    }
+    if let Some(voltage) = state.voltage {
+        parts.push(format!("{}mV", voltage).blue().to_string());
+    }
+    if let Some(status) = state.status {
+        parts.push(format!("{:?}", status).bright_black().to_string());
+    } else if state.charging {
+        parts.push("Charging".bright_black().to_string());
+    }
+
+    parts.join(", ")
+}
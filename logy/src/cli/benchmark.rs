@@ -0,0 +1,313 @@
+use std::{
+    io::{BufWriter, Write},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Result, bail};
+use clap::Args;
+use hidpp::feature::{
+    EmittingFeature,
+    hires_wheel::{HiResWheelEvent, HiResWheelFeature, WheelEventTarget},
+    thumbwheel::{ThumbwheelEvent, ThumbwheelFeature, ThumbwheelReportingMode},
+};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use super::{Cli, output::write_structured};
+use crate::devices::{self, DeviceSummary};
+
+/// Intervals between two events longer than this are treated as a pause in
+/// the movement and excluded from the statistics.
+const IDLE_GAP: Duration = Duration::from_millis(100);
+
+/// Measure the round-trip latency and event report rate of a device.
+///
+/// While measuring the report rate, the scroll wheel and thumbwheel are
+/// diverted to HID++ and restored afterwards, so they have to be moved
+/// during the test period.
+///
+/// The benchmark needs exclusive access to the device, so it refuses to run
+/// while the daemon is running, unless --no-daemon is given.
+#[derive(Args)]
+pub struct BenchmarkCommand {
+    /// The amount of pings sent to measure the round-trip latency
+    #[arg(long, default_value_t = 50)]
+    pings: u32,
+
+    /// How long to record events for, in seconds
+    #[arg(long, default_value_t = 10)]
+    duration: u64,
+}
+
+impl BenchmarkCommand {
+    pub async fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+        let human = root.output_format().is_none();
+
+        // Requests going through the daemon would measure its latency as
+        // well, and the daemon also handles the diverted wheels.
+        if root.daemon().await.is_some() {
+            bail!(
+                "the daemon is running and would distort the measurements, stop it or pass \
+                 --no-daemon to benchmark while it is running"
+            );
+        }
+
+        let mut discovery = devices::discover().await?;
+        discovery.select(root.device.as_ref()).await?;
+        let device = discovery.into_single()?;
+
+        let mut round_trips = Vec::with_capacity(self.pings as usize);
+        for i in 0..self.pings {
+            let start = Instant::now();
+            device.device.root().ping(i as u8).await?;
+            round_trips.push(start.elapsed());
+        }
+
+        let wheel = device.device.get_feature::<HiResWheelFeature>();
+        let thumbwheel = device.device.get_feature::<ThumbwheelFeature>();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut restore_wheel = None;
+        if let Some(feature) = &wheel {
+            let mode = feature.get_wheel_mode().await?;
+            feature
+                .set_wheel_mode(WheelEventTarget::Diverted, mode.resolution, mode.inverted)
+                .await?;
+            restore_wheel = Some(mode);
+
+            let rx = feature.listen();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                while let Ok(event) = rx.recv().await {
+                    if let HiResWheelEvent::WheelMovement(_) = event
+                        && tx.send((EventSource::Wheel, Instant::now())).is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+        let mut restore_thumbwheel = None;
+        if let Some(feature) = &thumbwheel {
+            let status = feature.get_thumbwheel_status().await?;
+            feature
+                .set_thumbwheel_reporting(
+                    ThumbwheelReportingMode::Diverted,
+                    status.direction_inverted,
+                )
+                .await?;
+            restore_thumbwheel = Some(status);
+
+            let rx = feature.listen();
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                while let Ok(ThumbwheelEvent::StatusUpdate(_)) = rx.recv().await {
+                    if tx.send((EventSource::Thumbwheel, Instant::now())).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        let mut wheel_events = Vec::new();
+        let mut thumbwheel_events = Vec::new();
+        if wheel.is_some() || thumbwheel.is_some() {
+            if human {
+                writeln!(
+                    stdout,
+                    "{}",
+                    format!(
+                        "Move the scroll wheel or thumbwheel of {} continuously for {} seconds...",
+                        device.summary.name, self.duration
+                    )
+                    .bright_black()
+                )
+                .unwrap();
+                stdout.flush().unwrap();
+            }
+
+            let deadline = tokio::time::sleep(Duration::from_secs(self.duration));
+            tokio::pin!(deadline);
+            loop {
+                tokio::select! {
+                    _ = &mut deadline => break,
+                    _ = tokio::signal::ctrl_c() => break,
+                    event = rx.recv() => match event {
+                        Some((EventSource::Wheel, time)) => wheel_events.push(time),
+                        Some((EventSource::Thumbwheel, time)) => thumbwheel_events.push(time),
+                        None => break,
+                    },
+                }
+            }
+        }
+
+        if let (Some(feature), Some(mode)) = (&wheel, restore_wheel) {
+            feature
+                .set_wheel_mode(mode.target, mode.resolution, mode.inverted)
+                .await?;
+        }
+        if let (Some(feature), Some(status)) = (&thumbwheel, restore_thumbwheel) {
+            feature
+                .set_thumbwheel_reporting(status.reporting_mode, status.direction_inverted)
+                .await?;
+        }
+
+        let report = BenchmarkReport {
+            device: device.summary,
+            round_trip: DurationStats::new(round_trips),
+            wheel: wheel.map(|_| EventStats::new(&wheel_events)),
+            thumbwheel: thumbwheel.map(|_| EventStats::new(&thumbwheel_events)),
+        };
+
+        if let Some(format) = root.output_format() {
+            return write_structured(&mut stdout, format, &report);
+        }
+
+        writeln!(stdout, "{}", report.device.name).unwrap();
+        if let Some(stats) = &report.round_trip {
+            writeln!(stdout, "  Round-trip latency: {}", stats).unwrap();
+        }
+        for (name, stats) in [
+            ("Scroll wheel", &report.wheel),
+            ("Thumbwheel", &report.thumbwheel),
+        ] {
+            let Some(stats) = stats else {
+                continue;
+            };
+
+            let Some(intervals) = &stats.intervals else {
+                writeln!(
+                    stdout,
+                    "  {}: {}",
+                    name,
+                    "not enough events recorded".bright_black()
+                )
+                .unwrap();
+                continue;
+            };
+
+            writeln!(
+                stdout,
+                "  {}: {} events, {:.1} events/s, jitter {:.2}ms",
+                name, stats.events, stats.rate, stats.jitter_ms,
+            )
+            .unwrap();
+            writeln!(stdout, "    Report interval: {}", intervals).unwrap();
+        }
+        stdout.flush().unwrap();
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum EventSource {
+    Wheel,
+    Thumbwheel,
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize)]
+struct BenchmarkReport {
+    device: DeviceSummary,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    round_trip: Option<DurationStats>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    wheel: Option<EventStats>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thumbwheel: Option<EventStats>,
+}
+
+/// Contains percentiles of a set of durations, in milliseconds.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+struct DurationStats {
+    min_ms: f64,
+    p50_ms: f64,
+    p90_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+impl DurationStats {
+    fn new(mut durations: Vec<Duration>) -> Option<Self> {
+        if durations.is_empty() {
+            return None;
+        }
+        durations.sort();
+
+        let percentile = |p: f64| {
+            let index = ((durations.len() - 1) as f64 * p).round() as usize;
+            durations[index].as_secs_f64() * 1000.0
+        };
+
+        Some(Self {
+            min_ms: percentile(0.0),
+            p50_ms: percentile(0.5),
+            p90_ms: percentile(0.9),
+            p99_ms: percentile(0.99),
+            max_ms: percentile(1.0),
+        })
+    }
+}
+
+impl std::fmt::Display for DurationStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "min {:.2}ms, p50 {:.2}ms, p90 {:.2}ms, p99 {:.2}ms, max {:.2}ms",
+            self.min_ms, self.p50_ms, self.p90_ms, self.p99_ms, self.max_ms
+        )
+    }
+}
+
+/// Contains statistics about the timing of received events.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+struct EventStats {
+    events: usize,
+
+    /// The average amount of events per second while the control was moving.
+    rate: f64,
+
+    /// The standard deviation of the report intervals.
+    jitter_ms: f64,
+
+    /// The report intervals while the control was moving.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    intervals: Option<DurationStats>,
+}
+
+impl EventStats {
+    fn new(events: &[Instant]) -> Self {
+        let intervals = events
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .filter(|&interval| interval <= IDLE_GAP)
+            .collect::<Vec<_>>();
+
+        let active = intervals.iter().sum::<Duration>().as_secs_f64();
+        let (rate, jitter_ms) = if intervals.is_empty() || active == 0.0 {
+            (0.0, 0.0)
+        } else {
+            let mean = active / intervals.len() as f64;
+            let variance = intervals
+                .iter()
+                .map(|interval| (interval.as_secs_f64() - mean).powi(2))
+                .sum::<f64>()
+                / intervals.len() as f64;
+
+            (intervals.len() as f64 / active, variance.sqrt() * 1000.0)
+        };
+
+        Self {
+            events: events.len(),
+            rate,
+            jitter_ms,
+            intervals: DurationStats::new(intervals),
+        }
+    }
+}
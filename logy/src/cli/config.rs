@@ -0,0 +1,405 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{Args, Subcommand};
+use owo_colors::OwoColorize;
+
+use super::{Cli, output::write_structured};
+use crate::{
+    config::{self, Config},
+    control::{AppliedProfile, ApplyProfileParams},
+    devices,
+    interop::{Import, ratbag, solaar},
+    lint::{self, Severity},
+    profile,
+};
+
+/// Manage the configuration file.
+#[derive(Args)]
+pub struct ConfigCommand {
+    #[command(subcommand)]
+    command: ConfigSubcommand,
+}
+
+#[derive(Subcommand)]
+enum ConfigSubcommand {
+    Apply(ApplyCommand),
+    Lint(LintCommand),
+    Schema(SchemaCommand),
+    ImportSolaar(ImportSolaarCommand),
+    ImportRatbag(ImportRatbagCommand),
+    ExportRatbag(ExportRatbagCommand),
+}
+
+impl ConfigCommand {
+    pub async fn execute(&self, root: &Cli) -> Result<()> {
+        match &self.command {
+            ConfigSubcommand::Apply(cmd) => cmd.execute(root).await,
+            ConfigSubcommand::Lint(cmd) => cmd.execute(root),
+            ConfigSubcommand::Schema(cmd) => cmd.execute(root),
+            ConfigSubcommand::ImportSolaar(cmd) => cmd.execute(root),
+            ConfigSubcommand::ImportRatbag(cmd) => cmd.execute(root),
+            ConfigSubcommand::ExportRatbag(cmd) => cmd.execute(root),
+        }
+    }
+
+    /// Returns whether the subcommand honors --dry-run.
+    pub fn supports_dry_run(&self) -> bool {
+        matches!(self.command, ConfigSubcommand::Apply(_))
+    }
+}
+
+/// Apply a profile from the configuration file to devices.
+///
+/// The profile is applied to all devices selected using --device. With
+/// --dry-run, the feature functions that would be called are printed
+/// instead. If the daemon is running, the profile is applied through it,
+/// using the configuration of the daemon.
+#[derive(Args)]
+pub struct ApplyCommand {
+    /// The name of the profile to apply
+    profile: String,
+}
+
+impl ApplyCommand {
+    async fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+
+        // A daemon uses its own configuration file, so it is only used if no
+        // other file was requested.
+        let applied = match root.daemon().await {
+            Some(mut client) if root.config.is_none() => {
+                client
+                    .call("apply_profile", ApplyProfileParams {
+                        profile: self.profile.clone(),
+                        device: root.device.as_ref().map(ToString::to_string),
+                        dry_run: root.dry_run,
+                    })
+                    .await?
+            },
+            _ => self.apply(root).await?,
+        };
+
+        if let Some(format) = root.output_format() {
+            return write_structured(&mut stdout, format, &applied);
+        }
+
+        for applied in &applied {
+            let verb = if root.dry_run {
+                "Would apply"
+            } else {
+                "Applied"
+            };
+            writeln!(
+                stdout,
+                "{} profile {} to {}",
+                verb,
+                self.profile.bright_blue(),
+                applied.device.name
+            )
+            .unwrap();
+
+            if root.dry_run {
+                for operation in &applied.operations {
+                    writeln!(stdout, "  {}", operation).unwrap();
+                }
+            }
+        }
+
+        stdout.flush().unwrap();
+
+        Ok(())
+    }
+
+    /// Applies the profile to devices without going through the daemon.
+    async fn apply(&self, root: &Cli) -> Result<Vec<AppliedProfile>> {
+        let config = Config::load(root.config.as_deref())?;
+        let Some(profile) = config.profiles.get(&self.profile) else {
+            bail!("unknown profile \"{}\"", self.profile);
+        };
+
+        let mut discovery = devices::discover().await?;
+        discovery.select(root.device.as_ref()).await?;
+        if discovery.devices.is_empty() {
+            bail!("no matching device was found");
+        }
+
+        let mut applied = Vec::new();
+        for device in &discovery.devices {
+            let operations = profile::plan(&device.device, profile)
+                .await
+                .with_context(|| {
+                    format!("could not apply the profile to {}", device.summary.name)
+                })?;
+
+            if !root.dry_run {
+                for operation in &operations {
+                    operation.execute(&device.device).await?;
+                }
+            }
+
+            applied.push(AppliedProfile {
+                device: device.summary.clone(),
+                operations: operations.into_iter().map(|x| x.info).collect(),
+            });
+        }
+
+        Ok(applied)
+    }
+}
+
+/// Check a configuration file for problems.
+///
+/// The file is checked against the configuration schema, including unknown
+/// keys, and for values that are out of range or reference unknown profiles.
+/// No device is accessed.
+#[derive(Args)]
+pub struct LintCommand {
+    /// The configuration file to check. Defaults to the file given using
+    /// --config or the default location
+    file: Option<PathBuf>,
+}
+
+impl LintCommand {
+    fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+
+        let path = match (&self.file, &root.config) {
+            (Some(path), _) | (None, Some(path)) => path.clone(),
+            (None, None) => config::default_path()
+                .ok_or_else(|| anyhow!("could not determine the configuration directory"))?,
+        };
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("could not read config file {}", path.display()))?;
+
+        let problems = lint::lint(&content);
+        let errors = problems
+            .iter()
+            .filter(|problem| problem.severity == Severity::Error)
+            .count();
+
+        if let Some(format) = root.output_format() {
+            write_structured(&mut stdout, format, &problems)?;
+        } else {
+            for problem in &problems {
+                let severity = match problem.severity {
+                    Severity::Error => "error:".red().bold().to_string(),
+                    Severity::Warning => "warning:".yellow().bold().to_string(),
+                };
+
+                write!(stdout, "{}", path.display()).unwrap();
+                if let Some(line) = problem.line {
+                    write!(stdout, ":{}", line).unwrap();
+                }
+                write!(stdout, ": {} ", severity).unwrap();
+                if !problem.path.is_empty() {
+                    write!(stdout, "{}: ", problem.path.bright_black()).unwrap();
+                }
+                writeln!(stdout, "{}", problem.message).unwrap();
+            }
+
+            if problems.is_empty() {
+                writeln!(stdout, "{}", "No problems were found.".bright_black()).unwrap();
+            }
+        }
+
+        stdout.flush().unwrap();
+
+        if errors > 0 {
+            bail!("found {} error(s) in {}", errors, path.display());
+        }
+
+        Ok(())
+    }
+}
+
+/// Print the JSON schema of the configuration file.
+///
+/// The schema can be used by editors to validate and complete configuration
+/// files. Note that the configuration file itself is written in TOML.
+#[derive(Args)]
+pub struct SchemaCommand {}
+
+impl SchemaCommand {
+    fn execute(&self, _root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+
+        let schema = schemars::schema_for!(Config);
+        writeln!(stdout, "{}", serde_json::to_string_pretty(&schema)?).unwrap();
+
+        Ok(())
+    }
+}
+
+/// Import device settings from a Solaar configuration.
+///
+/// Every device with supported settings is turned into a profile, together
+/// with a rule applying it when the device connects. The result is printed
+/// unless --write is given.
+#[derive(Args)]
+pub struct ImportSolaarCommand {
+    /// The Solaar configuration directory containing `config.yaml` and
+    /// `rules.yaml`. Defaults to `solaar` in the platform's configuration
+    /// directory
+    #[arg(long, value_name = "DIR")]
+    from: Option<PathBuf>,
+
+    /// Append the imported profiles and rules to the configuration file
+    #[arg(short, long)]
+    write: bool,
+}
+
+impl ImportSolaarCommand {
+    fn execute(&self, root: &Cli) -> Result<()> {
+        let dir = match &self.from {
+            Some(dir) => dir.clone(),
+            None => solaar::default_dir()
+                .ok_or_else(|| anyhow!("could not determine the Solaar configuration directory"))?,
+        };
+
+        let import = solaar::import(&dir, &existing_config(root)?.profiles)?;
+
+        write_import(root, &import, self.write)
+    }
+}
+
+/// Import profiles from a libratbag/Piper profile document.
+///
+/// The active resolution of every enabled profile is turned into a profile.
+/// The active profile of each device is applied when the device connects.
+/// The result is printed unless --write is given.
+#[derive(Args)]
+pub struct ImportRatbagCommand {
+    /// The JSON document containing a device or a list of devices
+    file: PathBuf,
+
+    /// Append the imported profiles and rules to the configuration file
+    #[arg(short, long)]
+    write: bool,
+}
+
+impl ImportRatbagCommand {
+    fn execute(&self, root: &Cli) -> Result<()> {
+        let content = fs::read_to_string(&self.file)
+            .with_context(|| format!("could not read {}", self.file.display()))?;
+        let import = ratbag::import(&content, &existing_config(root)?.profiles)
+            .with_context(|| format!("could not parse {}", self.file.display()))?;
+
+        write_import(root, &import, self.write)
+    }
+}
+
+/// Export profiles as a libratbag/Piper profile document.
+///
+/// Only settings ratbag can represent are exported, which currently is the
+/// DPI.
+#[derive(Args)]
+pub struct ExportRatbagCommand {
+    /// The profiles to export. Defaults to all profiles
+    #[arg(short, long = "profile", value_name = "NAME")]
+    profiles: Vec<String>,
+
+    /// The device name written to the document
+    #[arg(long, value_name = "NAME", default_value = "logy")]
+    device_name: String,
+}
+
+impl ExportRatbagCommand {
+    fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+        let mut stderr = anstream::stderr();
+
+        let mut profiles = Config::load(root.config.as_deref())?.profiles;
+        if !self.profiles.is_empty() {
+            if let Some(unknown) = self.profiles.iter().find(|x| !profiles.contains_key(*x)) {
+                bail!("unknown profile \"{}\"", unknown);
+            }
+            profiles.retain(|name, _| self.profiles.contains(name));
+        }
+
+        let (device, warnings) = ratbag::export(&self.device_name, &profiles);
+        for warning in &warnings {
+            writeln!(stderr, "{} {}", "warning:".yellow().bold(), warning).unwrap();
+        }
+
+        match root.output_format() {
+            Some(format) => write_structured(&mut stdout, format, &device),
+            None => {
+                writeln!(stdout, "{}", serde_json::to_string_pretty(&device)?).unwrap();
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Loads the configuration an import is merged into, which may not exist yet
+/// when it is written for the first time.
+fn existing_config(root: &Cli) -> Result<Config> {
+    match &root.config {
+        Some(path) if !path.exists() => Ok(Config::default()),
+        path => Config::load(path.as_deref()),
+    }
+}
+
+/// Writes the result of an import, either to the output or by appending it
+/// to the configuration file.
+fn write_import(root: &Cli, import: &Import, write: bool) -> Result<()> {
+    let mut stdout = BufWriter::new(anstream::stdout());
+    let mut stderr = anstream::stderr();
+
+    if let Some(format) = root.output_format() {
+        return write_structured(&mut stdout, format, import);
+    }
+
+    for warning in &import.warnings {
+        writeln!(stderr, "{} {}", "warning:".yellow().bold(), warning).unwrap();
+    }
+
+    if import.profiles.is_empty() {
+        writeln!(
+            stderr,
+            "{}",
+            "No supported settings were found.".bright_black()
+        )
+        .unwrap();
+        return Ok(());
+    }
+
+    let fragment = import.to_toml()?;
+    if !write {
+        write!(stdout, "{}", fragment).unwrap();
+        return Ok(());
+    }
+
+    let path = match &root.config {
+        Some(path) => path.clone(),
+        None => config::default_path()
+            .ok_or_else(|| anyhow!("could not determine the configuration directory"))?,
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("could not create {}", parent.display()))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("could not open config file {}", path.display()))?;
+    write!(file, "\n{}", fragment)
+        .with_context(|| format!("could not write config file {}", path.display()))?;
+
+    writeln!(
+        stdout,
+        "Imported {} profile(s) into {}.",
+        import.profiles.len(),
+        path.display()
+    )
+    .unwrap();
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anyhow::Result;
+use clap::Args;
+
+use super::Cli;
+use crate::{
+    config::{self, Config},
+    daemon,
+};
+
+/// Run in the background, sending notifications about the connected devices.
+///
+/// Changes to the configuration file are applied while the daemon is running.
+#[derive(Args)]
+pub struct DaemonCommand {}
+
+impl DaemonCommand {
+    pub async fn execute(&self, root: &Cli) -> Result<()> {
+        let config = Config::load(root.config.as_deref())?;
+        let path = root.config.clone().or_else(config::default_path);
+
+        daemon::run(config, path, root.device.as_ref()).await
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::{BufWriter, Write};
+
+use anyhow::Result;
+use clap::Args;
+use owo_colors::OwoColorize;
+
+use super::{Cli, output::write_structured};
+use crate::{
+    control::{ControlClient, DeviceFeatures, ListFeaturesParams},
+    devices,
+};
+
+/// List the HID++2.0 features supported by connected devices.
+#[derive(Args)]
+pub struct FeaturesCommand {}
+
+impl FeaturesCommand {
+    pub async fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+
+        let entries = match root.daemon().await {
+            Some(client) => self.remote(client, root).await?,
+            None => self.local(root).await?,
+        };
+
+        if let Some(format) = root.output_format() {
+            return write_structured(&mut stdout, format, &entries);
+        }
+
+        if entries.is_empty() {
+            writeln!(stdout, "{}", "No HID++ devices were found.".bright_black()).unwrap();
+            return Ok(());
+        }
+
+        for (entry_i, entry) in entries.into_iter().enumerate() {
+            if entry_i != 0 {
+                writeln!(stdout).unwrap();
+            }
+
+            writeln!(
+                stdout,
+                "{} {} ({:#06x}:{:#06x})",
+                "●".green(),
+                entry.device.name,
+                entry.device.vendor_id.bright_black(),
+                entry.device.product_id.bright_black()
+            )
+            .unwrap();
+
+            for feature in entry.features {
+                let mut flags = Vec::new();
+                if feature.typ.obsolete {
+                    flags.push("obsolete");
+                }
+                if feature.typ.hidden {
+                    flags.push("hidden");
+                }
+                if feature.typ.engineering {
+                    flags.push("engineering");
+                }
+
+                writeln!(
+                    stdout,
+                    "  {:>3} {} {} v{} {}",
+                    feature.index.bright_blue(),
+                    feature.id.bright_black(),
+                    feature.name.as_deref().unwrap_or("Unknown"),
+                    feature.version,
+                    if flags.is_empty() {
+                        String::new()
+                    } else {
+                        format!("({})", flags.join(", ")).bright_black().to_string()
+                    }
+                )
+                .unwrap();
+            }
+        }
+
+        stdout.flush().unwrap();
+
+        Ok(())
+    }
+
+    async fn local(&self, root: &Cli) -> Result<Vec<DeviceFeatures>> {
+        let mut discovery = devices::discover().await?;
+        discovery.select(root.device.as_ref()).await?;
+
+        Ok(discovery.devices.iter().map(DeviceFeatures::new).collect())
+    }
+
+    async fn remote(&self, mut client: ControlClient, root: &Cli) -> Result<Vec<DeviceFeatures>> {
+        client
+            .call("list_features", ListFeaturesParams {
+                device: root.device.as_ref().map(ToString::to_string),
+            })
+            .await
+    }
+}
@@ -0,0 +1,108 @@
+use std::io::{BufWriter, Write};
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use super::{
+    Cli,
+    output::write_structured,
+    probe::{format_properties, write_properties},
+};
+use crate::{
+    control::{ControlClient, DeviceStatus, ListDevicesParams},
+    devices::{self, DeviceSummary},
+    properties::{ProbeField, ProbedDeviceProperties, probe_properties},
+};
+
+/// View all known properties of connected devices.
+///
+/// If the daemon is running, the properties are retrieved through it.
+#[derive(Args)]
+pub struct InfoCommand {}
+
+impl InfoCommand {
+    pub async fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+
+        let entries = match root.daemon().await {
+            Some(client) => self.remote(client, root).await?,
+            None => self.local(root).await?,
+        };
+
+        if let Some(format) = root.output_format() {
+            return write_structured(&mut stdout, format, &entries);
+        }
+
+        if entries.is_empty() {
+            writeln!(stdout, "{}", "No HID++ devices were found.".bright_black()).unwrap();
+            return Ok(());
+        }
+
+        for (entry_i, entry) in entries.into_iter().enumerate() {
+            if entry_i != 0 {
+                writeln!(stdout).unwrap();
+            }
+
+            writeln!(
+                stdout,
+                "{} {} ({:#06x}:{:#06x})",
+                "●".green(),
+                entry.device.name,
+                entry.device.vendor_id.bright_black(),
+                entry.device.product_id.bright_black()
+            )
+            .unwrap();
+            writeln!(stdout, " │").unwrap();
+            write_properties(&mut stdout, " ", format_properties(entry.properties));
+        }
+
+        stdout.flush().unwrap();
+
+        Ok(())
+    }
+
+    /// Retrieves the properties from the devices directly.
+    async fn local(&self, root: &Cli) -> Result<Vec<DeviceInfo>> {
+        let mut discovery = devices::discover().await?;
+        discovery.select(root.device.as_ref()).await?;
+
+        let mut entries = Vec::with_capacity(discovery.devices.len());
+        for device in discovery.devices {
+            entries.push(DeviceInfo {
+                properties: probe_properties(device.device, ProbeField::value_variants()).await?,
+                device: device.summary,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Retrieves the properties of the online devices managed by the running
+    /// daemon.
+    async fn remote(&self, mut client: ControlClient, root: &Cli) -> Result<Vec<DeviceInfo>> {
+        let devices: Vec<DeviceStatus> = client
+            .call("list_devices", ListDevicesParams {
+                device: root.device.as_ref().map(ToString::to_string),
+                fields: ProbeField::value_variants().to_vec(),
+            })
+            .await?;
+
+        Ok(devices
+            .into_iter()
+            .filter_map(|status| {
+                Some(DeviceInfo {
+                    properties: status.properties?,
+                    device: status.device,
+                })
+            })
+            .collect())
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
+struct DeviceInfo {
+    device: DeviceSummary,
+    properties: ProbedDeviceProperties,
+}
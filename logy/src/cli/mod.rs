@@ -1,8 +1,34 @@
+mod battery;
+mod benchmark;
+mod config;
+mod daemon;
+mod features;
+mod info;
+mod monitor;
+mod output;
 mod probe;
+mod prompt;
+mod status_bar;
+mod unpair;
+mod watch;
 
-use anyhow::Result;
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+use battery::BatteryCommand;
+use benchmark::BenchmarkCommand;
 use clap::{Parser, Subcommand};
+use config::ConfigCommand;
+use daemon::DaemonCommand;
+use features::FeaturesCommand;
+use info::InfoCommand;
+use monitor::MonitorCommand;
+use output::OutputFormat;
 use probe::ProbeCommand;
+use unpair::UnpairCommand;
+use watch::WatchCommand;
+
+use crate::{control::ControlClient, devices::DeviceSelector};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -13,14 +39,100 @@ struct Cli {
     #[command(subcommand)]
     command: Commands,
 
-    /// Output plain JSON without color and interactivity
-    #[arg(short, long, global = true)]
+    /// Output plain JSON without color and interactivity, same as
+    /// `--output json`. Streaming commands emit one JSON object per event
+    /// (JSON Lines)
+    #[arg(short, long, global = true, conflicts_with = "output")]
     json: bool,
+
+    /// Output a machine-readable format without color and interactivity
+    #[arg(short, long, global = true, value_name = "FORMAT")]
+    output: Option<OutputFormat>,
+
+    /// Only operate on devices matching this selector. Selectors match the
+    /// device name, serial number or unit ID, slot or WPID, and can be
+    /// restricted to one of them using a prefix (`name:`, `serial:`, `unit:`,
+    /// `slot:`, `wpid:`). A plain number only matches the slot
+    #[arg(short, long, global = true, value_name = "SELECTOR")]
+    device: Option<DeviceSelector>,
+
+    /// Skip confirmation prompts of destructive operations
+    #[arg(short, long, global = true)]
+    yes: bool,
+
+    /// Print the operations a command would perform on devices instead of
+    /// performing them. Only supported by `config apply` and `unpair`
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Read the configuration from this file instead of the default location
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Access devices directly even if the daemon is running, instead of
+    /// going through its control socket
+    #[arg(long, global = true)]
+    no_daemon: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     Probe(ProbeCommand),
+    Info(InfoCommand),
+    Features(FeaturesCommand),
+    Battery(BatteryCommand),
+    Watch(WatchCommand),
+    Monitor(MonitorCommand),
+    Benchmark(BenchmarkCommand),
+    Unpair(UnpairCommand),
+    Daemon(DaemonCommand),
+    Config(ConfigCommand),
+}
+
+impl Commands {
+    /// Returns whether the command honors --dry-run.
+    fn supports_dry_run(&self) -> bool {
+        match self {
+            Commands::Unpair(_) => true,
+            Commands::Config(cmd) => cmd.supports_dry_run(),
+            _ => false,
+        }
+    }
+}
+
+impl Cli {
+    /// Returns the machine-readable format to write the output in, or `None`
+    /// if the output should be human-readable.
+    fn output_format(&self) -> Option<OutputFormat> {
+        if self.json {
+            Some(OutputFormat::Json)
+        } else {
+            self.output
+        }
+    }
+
+    /// Connects to the control socket of the daemon if it is running and
+    /// --no-daemon was not given.
+    async fn daemon(&self) -> Option<ControlClient> {
+        if self.no_daemon {
+            return None;
+        }
+
+        ControlClient::connect().await
+    }
+
+    /// Returns whether a streaming command should emit JSON Lines, failing if
+    /// a format was requested that can't be streamed.
+    fn json_lines(&self) -> Result<bool> {
+        match self.output_format() {
+            None => Ok(false),
+            Some(OutputFormat::Json) => Ok(true),
+            Some(format) => bail!(
+                "streaming output is only available as JSON Lines, not {:?}",
+                format
+            ),
+        }
+    }
 }
 
 pub async fn execute() -> Result<()> {
@@ -28,7 +140,20 @@ pub async fn execute() -> Result<()> {
 
     cli.color.write_global();
 
+    if cli.dry_run && !cli.command.supports_dry_run() {
+        bail!("--dry-run is only supported by `config apply` and `unpair`");
+    }
+
     match &cli.command {
         Commands::Probe(cmd) => cmd.execute(&cli).await,
+        Commands::Info(cmd) => cmd.execute(&cli).await,
+        Commands::Features(cmd) => cmd.execute(&cli).await,
+        Commands::Battery(cmd) => cmd.execute(&cli).await,
+        Commands::Watch(cmd) => cmd.execute(&cli).await,
+        Commands::Monitor(cmd) => cmd.execute(&cli).await,
+        Commands::Benchmark(cmd) => cmd.execute(&cli).await,
+        Commands::Unpair(cmd) => cmd.execute(&cli).await,
+        Commands::Daemon(cmd) => cmd.execute(&cli).await,
+        Commands::Config(cmd) => cmd.execute(&cli).await,
     }
 }
@@ -0,0 +1,234 @@
+use std::{
+    io::{BufWriter, Write},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use clap::Args;
+use hidpp::{
+    async_hid::enumerate_hidpp,
+    channel::{HidppChannel, ListenerGuard},
+};
+use itertools::Itertools;
+use owo_colors::OwoColorize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use super::{
+    Cli,
+    output::{EventLine, short_timestamp},
+};
+use crate::{
+    control::{ControlClient, DeviceStatus, ListDevicesParams, RawMessage, SubscribeParams},
+    devices::{self, DIRECT_DEVICE_INDEX, DeviceSummary},
+};
+
+/// Print every HID++ message received from any connected channel.
+///
+/// If the daemon is running, the messages received by the daemon are printed
+/// instead of opening the channels again.
+#[derive(Args)]
+pub struct MonitorCommand {
+    /// Also print messages that were matched as responses to requests
+    #[arg(long)]
+    responses: bool,
+}
+
+impl MonitorCommand {
+    pub async fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+        let json = root.json_lines()?;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let source = match root.daemon().await {
+            Some(client) => self.remote(client, root, tx).await?,
+            None => self.local(root, tx).await?,
+        };
+
+        if !json {
+            writeln!(
+                stdout,
+                "{}",
+                match &source {
+                    MessageSource::Channels(handles) => format!(
+                        "Monitoring {} HID++ channel(s), press Ctrl+C to stop.",
+                        handles.len()
+                    ),
+                    MessageSource::Daemon => {
+                        "Monitoring the HID++ channels of the daemon, press Ctrl+C to stop."
+                            .to_string()
+                    },
+                }
+                .bright_black()
+            )
+            .unwrap();
+            stdout.flush().unwrap();
+        }
+
+        loop {
+            let msg = tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                msg = rx.recv() => match msg {
+                    Some(msg) => msg,
+                    None => break,
+                },
+            };
+
+            if json {
+                EventLine::now("message", None, &msg).write(&mut stdout);
+                continue;
+            }
+
+            writeln!(
+                stdout,
+                "{} {:#06x}:{:#06x} {} {}",
+                short_timestamp().bright_black(),
+                msg.vendor_id.bright_black(),
+                msg.product_id.bright_black(),
+                if msg.matched {
+                    "◀".blue().into_styled()
+                } else {
+                    "●".green().into_styled()
+                },
+                msg.data
+                    .iter()
+                    .map(|byte| format!("{:02x}", byte))
+                    .join(" ")
+            )
+            .unwrap();
+            stdout.flush().unwrap();
+        }
+
+        drop(source);
+
+        Ok(())
+    }
+
+    /// Listens to the messages of all channels directly.
+    async fn local(
+        &self,
+        root: &Cli,
+        tx: mpsc::UnboundedSender<RawMessage>,
+    ) -> Result<MessageSource> {
+        // If only some devices are selected, messages are filtered by the
+        // channel and device index they belong to.
+        let selected = match &root.device {
+            Some(selector) => {
+                let mut discovery = devices::discover().await?;
+                discovery.select(Some(selector)).await?;
+
+                Some(
+                    discovery
+                        .devices
+                        .iter()
+                        .map(|x| message_origin(&x.summary))
+                        .collect::<Vec<_>>(),
+                )
+            },
+            None => None,
+        };
+
+        let channels: Vec<Arc<HidppChannel>> =
+            enumerate_hidpp().await?.into_iter().map(Arc::new).collect();
+
+        let mut handles = Vec::with_capacity(channels.len());
+        for channel in channels {
+            let (vendor_id, product_id) = (channel.vendor_id, channel.product_id);
+            let tx = tx.clone();
+            let responses = self.responses;
+            let selected = selected.clone();
+
+            let handle = channel.subscribe(move |msg, matched| {
+                let msg = RawMessage::new(vendor_id, product_id, msg, matched);
+                if is_monitored(&msg, responses, selected.as_deref()) {
+                    let _ = tx.send(msg);
+                }
+            });
+            handles.push((channel, handle));
+        }
+
+        Ok(MessageSource::Channels(handles))
+    }
+
+    /// Receives the messages of all channels of the running daemon.
+    async fn remote(
+        &self,
+        mut client: ControlClient,
+        root: &Cli,
+        tx: mpsc::UnboundedSender<RawMessage>,
+    ) -> Result<MessageSource> {
+        client
+            .call::<Value>("subscribe", SubscribeParams {
+                messages: true,
+            })
+            .await?;
+
+        let selected = match &root.device {
+            Some(selector) => {
+                let devices: Vec<DeviceStatus> = client
+                    .call("list_devices", ListDevicesParams {
+                        device: Some(selector.to_string()),
+                        ..Default::default()
+                    })
+                    .await?;
+
+                Some(
+                    devices
+                        .iter()
+                        .map(|x| message_origin(&x.device))
+                        .collect::<Vec<_>>(),
+                )
+            },
+            None => None,
+        };
+
+        let responses = self.responses;
+        tokio::spawn(async move {
+            while let Ok(Some(event)) = client.next_event().await {
+                if event.kind != "message" {
+                    continue;
+                }
+
+                let Ok(msg) = serde_json::from_value::<RawMessage>(event.data) else {
+                    continue;
+                };
+                if is_monitored(&msg, responses, selected.as_deref()) && tx.send(msg).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(MessageSource::Daemon)
+    }
+}
+
+/// Keeps the messages flowing while monitoring.
+enum MessageSource {
+    /// The channels opened directly, along with the listeners forwarding
+    /// their messages.
+    Channels(Vec<(Arc<HidppChannel>, ListenerGuard)>),
+
+    /// The running daemon, whose messages are forwarded by a task.
+    Daemon,
+}
+
+/// Identifies the messages of a device by the channel and device index they
+/// belong to.
+fn message_origin(device: &DeviceSummary) -> (u16, u16, u8) {
+    (
+        device.vendor_id,
+        device.product_id,
+        device.slot.unwrap_or(DIRECT_DEVICE_INDEX),
+    )
+}
+
+/// Checks whether a message should be printed.
+fn is_monitored(msg: &RawMessage, responses: bool, selected: Option<&[(u16, u16, u8)]>) -> bool {
+    if msg.matched && !responses {
+        return false;
+    }
+
+    selected.is_none_or(|selected| {
+        selected.contains(&(msg.vendor_id, msg.product_id, msg.device_index()))
+    })
+}
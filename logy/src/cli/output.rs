@@ -0,0 +1,183 @@
+//! Implements output helpers shared by multiple commands.
+
+use std::io::Write;
+
+use anyhow::Result;
+use chrono::{Local, SecondsFormat};
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::devices::DeviceSummary;
+
+/// Represents a machine-readable format for the output of non-streaming
+/// commands.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, ValueEnum)]
+pub enum OutputFormat {
+    /// A single JSON document
+    Json,
+
+    /// A single YAML document
+    Yaml,
+
+    /// A table with one row per record, where nested fields are flattened into
+    /// dot-separated columns
+    Csv,
+}
+
+/// Writes a value in a machine-readable output format.
+///
+/// For CSV, the value is flattened into rows, see [`flatten_rows`].
+pub fn write_structured(
+    out: &mut impl Write,
+    format: OutputFormat,
+    value: &impl Serialize,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string(value)?)?,
+        OutputFormat::Yaml => write!(out, "{}", serde_yaml::to_string(value)?)?,
+        OutputFormat::Csv => {
+            let rows = flatten_rows(&serde_json::to_value(value)?, "");
+
+            let mut columns: Vec<&str> = Vec::new();
+            for (key, _) in rows.iter().flatten() {
+                if !columns.contains(&key.as_str()) {
+                    columns.push(key);
+                }
+            }
+
+            let mut writer = csv::Writer::from_writer(&mut *out);
+            writer.write_record(&columns)?;
+            for row in &rows {
+                writer.write_record(columns.iter().map(|column| {
+                    row.iter()
+                        .find(|(key, _)| key == column)
+                        .map_or("", |(_, value)| value.as_str())
+                }))?;
+            }
+            writer.flush()?;
+        },
+    }
+
+    out.flush()?;
+    Ok(())
+}
+
+/// Flattens a JSON value into table rows of column/value pairs.
+///
+/// Top-level arrays produce one row per element and nested objects are
+/// flattened into dot-separated columns. Nested arrays of objects expand the
+/// row they are contained in into one row per element, so that e.g. a
+/// receiver with its paired devices produces one row per device. Arrays of
+/// plain values are joined using `;`.
+pub fn flatten_rows(value: &Value, prefix: &str) -> Vec<Vec<(String, String)>> {
+    match value {
+        Value::Array(values) if prefix.is_empty() || !values.iter().all(is_scalar) => values
+            .iter()
+            .flat_map(|value| flatten_rows(value, prefix))
+            .collect(),
+        Value::Object(object) => {
+            let mut rows = vec![Vec::new()];
+            for (key, value) in object {
+                let column = format!("{}{}", prefix, key);
+
+                if is_scalar(value) || value.as_array().is_some_and(|x| x.iter().all(is_scalar)) {
+                    for row in &mut rows {
+                        row.push((column.clone(), format_scalar(value)));
+                    }
+                    continue;
+                }
+
+                let nested = flatten_rows(value, &format!("{}.", column));
+                if nested.is_empty() {
+                    continue;
+                }
+
+                rows = rows
+                    .into_iter()
+                    .flat_map(|row| {
+                        nested.iter().map(move |nested_row| {
+                            let mut row = row.clone();
+                            row.extend(nested_row.iter().cloned());
+                            row
+                        })
+                    })
+                    .collect();
+            }
+            rows
+        },
+        value => {
+            let column = match prefix.trim_end_matches('.') {
+                "" => "value",
+                column => column,
+            };
+            vec![vec![(column.to_string(), format_scalar(value))]]
+        },
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Array(_) | Value::Object(_))
+}
+
+fn format_scalar(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        Value::Array(values) => values
+            .iter()
+            .map(format_scalar)
+            .collect::<Vec<_>>()
+            .join(";"),
+        value => value.to_string(),
+    }
+}
+
+/// Represents a single line of a JSON Lines event stream.
+///
+/// Every streaming command emits events using this schema, with
+/// [`Self::kind`] determining the schema of [`Self::data`].
+#[derive(Serialize)]
+pub struct EventLine<'a, T: Serialize> {
+    /// The local time the event was received at, formatted as RFC 3339.
+    pub timestamp: String,
+
+    /// The kind of the event.
+    pub kind: &'a str,
+
+    /// The device the event belongs to, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device: Option<&'a DeviceSummary>,
+
+    /// The event data.
+    pub data: T,
+}
+
+impl<'a, T: Serialize> EventLine<'a, T> {
+    /// Creates a new event line timestamped with the current time.
+    pub fn now(kind: &'a str, device: Option<&'a DeviceSummary>, data: T) -> Self {
+        Self {
+            timestamp: timestamp(),
+            kind,
+            device,
+            data,
+        }
+    }
+
+    /// Writes the event as a single JSON line and flushes the writer, so
+    /// consumers receive the event immediately.
+    pub fn write(&self, out: &mut impl Write) {
+        writeln!(out, "{}", serde_json::to_string(self).unwrap()).unwrap();
+        out.flush().unwrap();
+    }
+}
+
+/// Provides the current local time formatted as RFC 3339.
+pub fn timestamp() -> String {
+    Local::now().to_rfc3339_opts(SecondsFormat::Millis, false)
+}
+
+/// Provides the current local time formatted for human-readable output.
+pub fn short_timestamp() -> String {
+    Local::now().format("%H:%M:%S%.3f").to_string()
+}
@@ -1,163 +1,87 @@
 use std::{
-    io::{BufWriter, Write},
+    io::{BufWriter, IsTerminal, Write},
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::Result;
-use clap::Args;
+use clap::{Args, ValueEnum};
+use futures::{StreamExt, TryStreamExt, future, stream};
 use hidpp::{
+    async_hid::enumerate_hidpp,
     channel::HidppChannel,
-    device::Device,
-    feature::{
-        device_friendly_name::DeviceFriendlyNameFeature,
-        device_information::DeviceInformationFeature,
-        device_type_and_name::{DeviceType, DeviceTypeAndNameFeature},
-        unified_battery::{BatteryLevel, BatteryStatus, UnifiedBatteryFeature},
-    },
-    receiver,
+    device::{Device, DeviceError, PRESENCE_TIMEOUT},
+    feature::unified_battery::BatteryLevel,
+    receiver::{self, PairedDeviceKind, ReceiverDevice},
 };
+use indicatif::{ProgressBar, ProgressStyle};
 use owo_colors::OwoColorize;
 use serde::Serialize;
-use serde_json::json;
 
-use super::Cli;
+use super::{Cli, output::write_structured};
 use crate::{
-    async_hid_impl::enumerate_hidpp,
-    hidpp_ext::receiver::{LogyReceiver, PairedDeviceKind},
+    control::{ControlClient, DeviceStatus, ListDevicesParams},
+    devices::{DIRECT_DEVICE_INDEX, DeviceSelector, DeviceSummary},
+    properties::{ProbeField, ProbedDeviceProperties, probe_properties},
 };
 
+/// The maximum amount of devices behind a single receiver that are probed
+/// concurrently.
+const MAX_CONCURRENT_DEVICE_PROBES: usize = 3;
+
 /// Detect and view general information about connected devices.
+///
+/// If the daemon is running, the devices it manages are shown instead of
+/// probing all channels, as the daemon does not expose the receivers.
 #[derive(Args)]
-pub struct ProbeCommand {}
+pub struct ProbeCommand {
+    /// The device properties to retrieve
+    #[arg(
+        long,
+        value_delimiter = ',',
+        default_values_t = ProbeField::DEFAULT,
+        conflicts_with = "full"
+    )]
+    fields: Vec<ProbeField>,
+
+    /// Retrieve all known device properties
+    #[arg(long)]
+    full: bool,
+}
 
 impl ProbeCommand {
     pub async fn execute(&self, root: &Cli) -> Result<()> {
         let mut stdout = BufWriter::new(anstream::stdout());
 
-        let receivers = probe_receivers().await?;
+        let fields = if self.full {
+            ProbeField::value_variants().to_vec()
+        } else {
+            self.fields.clone()
+        };
 
-        if root.json {
-            writeln!(stdout, "{}", json!(receivers)).unwrap();
-            return Ok(());
+        let entries = match root.daemon().await {
+            Some(client) => probe_daemon(client, root, fields).await?,
+            None => probe_local(root, fields).await?,
+        };
+
+        if let Some(format) = root.output_format() {
+            return write_structured(&mut stdout, format, &entries);
         }
 
-        if receivers.is_empty() {
+        if entries.is_empty() {
             writeln!(stdout, "{}", "No HID++ devices were found.".bright_black()).unwrap();
             return Ok(());
         }
 
-        for (receiver_i, receiver) in receivers.into_iter().enumerate() {
-            if receiver_i != 0 {
+        for (entry_i, entry) in entries.into_iter().enumerate() {
+            if entry_i != 0 {
                 writeln!(stdout).unwrap();
             }
 
-            writeln!(
-                stdout,
-                "{}: {} ({:#06x}:{:#06x})",
-                receiver.unique_id.bright_black(),
-                receiver.name,
-                receiver.vendor_id.bright_black(),
-                receiver.product_id.bright_black()
-            )
-            .unwrap();
-            writeln!(stdout, " │").unwrap();
-
-            if receiver.paired_devices.is_empty() {
-                writeln!(
-                    stdout,
-                    " ╰─ {}",
-                    "No devices were found.".bright_black().italic()
-                )
-                .unwrap();
-                return Ok(());
-            }
-
-            let devices_len = receiver.paired_devices.len();
-            for (device_i, device) in receiver.paired_devices.into_iter().enumerate() {
-                if device_i != 0 {
-                    writeln!(stdout, " │").unwrap();
-                }
-
-                writeln!(
-                    stdout,
-                    "{} {}: {} {} ({:?}) ({:#06x})",
-                    if device_i == devices_len - 1 {
-                        " ╰─"
-                    } else {
-                        " ├─"
-                    },
-                    device.slot.bright_blue(),
-                    if device.online {
-                        "●".green().into_styled()
-                    } else {
-                        "●".red().into_styled()
-                    },
-                    if device.online {
-                        device.name
-                    } else {
-                        device.name.bright_black().italic().to_string()
-                    },
-                    device.kind.green(),
-                    device.wpid.bright_black(),
-                )
-                .unwrap();
-
-                if !device.online {
-                    continue;
-                }
-
-                let mut properties = Vec::new();
-                if let Some(kind) = device.properties.kind {
-                    properties.push(format!("TYPE: {:?}", kind.bright_black()));
-                }
-                if let Some(full_name) = device.properties.full_name {
-                    properties.push(format!("FULL NAME: {}", full_name.bright_black()));
-                }
-                if let Some(friendly_name) = device.properties.friendly_name {
-                    properties.push(format!("FRIENDLY NAME: {}", friendly_name.bright_black()));
-                }
-                if let Some(battery_percentage) = device.properties.battery_percentage {
-                    if let Some(battery_level) = device.properties.battery_level {
-                        if let Some(battery_status) = device.properties.battery_status {
-                            properties.push(format!(
-                                "BATTERY: {:?} ({}), {:?}",
-                                match battery_level {
-                                    BatteryLevel::Full | BatteryLevel::Good =>
-                                        battery_level.green().into_styled(),
-                                    BatteryLevel::Low => battery_level.yellow().into_styled(),
-                                    BatteryLevel::Critical =>
-                                        battery_level.bright_red().into_styled(),
-                                    _ => battery_level.default_color().into_styled(),
-                                },
-                                format!("{}%", battery_percentage).blue(),
-                                battery_status.bright_black()
-                            ));
-                        }
-                    }
-                }
-                if let Some(serial_number) = device.properties.serial_number {
-                    properties.push(format!("SERIAL NUMBER: {}", serial_number.bright_black()));
-                }
-
-                let properties_len = properties.len();
-                for (propery_i, property) in properties.into_iter().enumerate() {
-                    writeln!(
-                        stdout,
-                        "{}{} {}",
-                        if device_i == devices_len - 1 {
-                            "         "
-                        } else {
-                            " │       "
-                        },
-                        if propery_i == properties_len - 1 {
-                            "╰─"
-                        } else {
-                            "├─"
-                        },
-                        property
-                    )
-                    .unwrap();
-                }
+            match entry {
+                ProbedEntry::Receiver(receiver) => write_receiver(&mut stdout, receiver),
+                ProbedEntry::Device(device) => write_direct_device(&mut stdout, device),
+                ProbedEntry::ManagedDevice(device) => write_managed_device(&mut stdout, device),
             }
         }
 
@@ -167,92 +91,431 @@ impl ProbeCommand {
     }
 }
 
-async fn probe_receivers() -> Result<Vec<ProbedReceiver>> {
-    let channels: Vec<Arc<HidppChannel>> =
-        enumerate_hidpp().await?.into_iter().map(Arc::new).collect();
-
-    let mut receivers = Vec::with_capacity(channels.len());
-    for channel in channels {
-        let Some(receiver) = receiver::detect(Arc::clone(&channel)) else {
-            continue;
-        };
+fn write_receiver(stdout: &mut impl Write, receiver: ProbedReceiver) {
+    writeln!(
+        stdout,
+        "{}: {} ({:#06x}:{:#06x})",
+        receiver.unique_id.bright_black(),
+        receiver.name,
+        receiver.vendor_id.bright_black(),
+        receiver.product_id.bright_black()
+    )
+    .unwrap();
+    writeln!(stdout, " │").unwrap();
+
+    if receiver.paired_devices.is_empty() {
+        writeln!(
+            stdout,
+            " ╰─ {}",
+            "No devices were found.".bright_black().italic()
+        )
+        .unwrap();
+        return;
+    }
 
-        let mut paired_devices = receiver.get_paired_devices().await?;
-        paired_devices.sort_by_key(|x| x.slot);
+    let devices_len = receiver.paired_devices.len();
+    for (device_i, device) in receiver.paired_devices.into_iter().enumerate() {
+        if device_i != 0 {
+            writeln!(stdout, " │").unwrap();
+        }
 
-        let mut probed_devices = Vec::with_capacity(paired_devices.len());
-        for device in paired_devices {
-            let properties = if device.online {
-                let mut dev = Device::new(Arc::clone(&channel), device.slot).await?;
-                dev.enumerate_features().await?;
-                probe_properties(dev).await?
+        writeln!(
+            stdout,
+            "{} {}: {} {} ({:?}) ({:#06x})",
+            if device_i == devices_len - 1 {
+                " ╰─"
+            } else {
+                " ├─"
+            },
+            device.slot.bright_blue(),
+            if device.online {
+                "●".green().into_styled()
+            } else {
+                "●".red().into_styled()
+            },
+            if device.online {
+                device.name
             } else {
-                ProbedDeviceProperties::default()
-            };
-
-            let name = receiver.get_paired_device_name(device.slot).await?;
-
-            probed_devices.push(ProbedPairedDevice {
-                slot: device.slot,
-                name,
-                kind: device.kind,
-                wpid: device.wpid,
-                online: device.online,
-                properties,
-            });
+                device.name.bright_black().italic().to_string()
+            },
+            device.kind.green(),
+            device.wpid.bright_black(),
+        )
+        .unwrap();
+
+        if !device.online {
+            continue;
         }
 
-        receivers.push(ProbedReceiver {
-            name: receiver.name(),
-            unique_id: receiver.get_unique_id().await?,
-            vendor_id: channel.vendor_id,
-            product_id: channel.product_id,
-            paired_devices: probed_devices,
-        });
+        write_properties(
+            stdout,
+            if device_i == devices_len - 1 {
+                "         "
+            } else {
+                " │       "
+            },
+            format_properties(device.properties),
+        );
+    }
+}
+
+fn write_direct_device(stdout: &mut impl Write, device: ProbedDirectDevice) {
+    writeln!(
+        stdout,
+        "{} {} ({:#06x}:{:#06x})",
+        "●".green(),
+        device.name,
+        device.vendor_id.bright_black(),
+        device.product_id.bright_black()
+    )
+    .unwrap();
+
+    let properties = format_properties(device.properties);
+    if properties.is_empty() {
+        return;
     }
 
-    Ok(receivers)
+    writeln!(stdout, " │").unwrap();
+    write_properties(stdout, " ", properties);
 }
 
-async fn probe_properties(device: Device) -> Result<ProbedDeviceProperties> {
-    let mut properties = ProbedDeviceProperties::default();
+fn write_managed_device(stdout: &mut impl Write, device: ProbedManagedDevice) {
+    writeln!(
+        stdout,
+        "{} {} ({:#06x}:{:#06x}){}",
+        if device.online {
+            "●".green().into_styled()
+        } else {
+            "●".red().into_styled()
+        },
+        if device.online {
+            device.device.name
+        } else {
+            device.device.name.bright_black().italic().to_string()
+        },
+        device.device.vendor_id.bright_black(),
+        device.device.product_id.bright_black(),
+        match device.device.slot {
+            Some(slot) => format!(", slot {}", slot.bright_blue()),
+            None => String::new(),
+        }
+    )
+    .unwrap();
 
-    if let Some(feature) = device.get_feature::<DeviceTypeAndNameFeature>() {
-        properties.kind.replace(feature.get_device_type().await?);
-        properties
-            .full_name
-            .replace(feature.get_whole_device_name().await?);
+    let properties = format_properties(device.properties);
+    if properties.is_empty() {
+        return;
     }
 
-    if let Some(feature) = device.get_feature::<DeviceFriendlyNameFeature>() {
-        let default_friendly_name = feature.get_whole_default_friendly_name().await?;
-        let friendly_name = feature.get_whole_friendly_name().await?;
+    writeln!(stdout, " │").unwrap();
+    write_properties(stdout, " ", properties);
+}
+
+pub(super) fn write_properties(stdout: &mut impl Write, indent: &str, properties: Vec<String>) {
+    let properties_len = properties.len();
+    for (property_i, property) in properties.into_iter().enumerate() {
+        writeln!(
+            stdout,
+            "{}{} {}",
+            indent,
+            if property_i == properties_len - 1 {
+                "╰─"
+            } else {
+                "├─"
+            },
+            property
+        )
+        .unwrap();
+    }
+}
+
+pub(super) fn format_properties(properties: ProbedDeviceProperties) -> Vec<String> {
+    let mut formatted = Vec::new();
+    if let Some(kind) = properties.kind {
+        formatted.push(format!("TYPE: {:?}", kind.bright_black()));
+    }
+    if let Some(full_name) = properties.full_name {
+        formatted.push(format!("FULL NAME: {}", full_name.bright_black()));
+    }
+    if let Some(friendly_name) = properties.friendly_name {
+        formatted.push(format!("FRIENDLY NAME: {}", friendly_name.bright_black()));
+    }
+    if let (Some(battery_percentage), Some(battery_level), Some(battery_status)) = (
+        properties.battery_percentage,
+        properties.battery_level,
+        properties.battery_status,
+    ) {
+        formatted.push(format!(
+            "BATTERY: {:?} ({}), {:?}",
+            match battery_level {
+                BatteryLevel::Full | BatteryLevel::Good => battery_level.green().into_styled(),
+                BatteryLevel::Low => battery_level.yellow().into_styled(),
+                BatteryLevel::Critical => battery_level.bright_red().into_styled(),
+                _ => battery_level.default_color().into_styled(),
+            },
+            format!("{}%", battery_percentage).blue(),
+            battery_status.bright_black()
+        ));
+    }
+    if let Some(battery_voltage) = properties.battery_voltage {
+        formatted.push(format!(
+            "BATTERY VOLTAGE: {}",
+            format!("{}mV", battery_voltage).bright_black()
+        ));
+    }
+    if let Some(signal_strength) = properties.signal_strength {
+        formatted.push(format!(
+            "SIGNAL STRENGTH: {}",
+            format!("{}%", signal_strength).blue()
+        ));
+    }
+    if let Some(serial_number) = properties.serial_number {
+        formatted.push(format!("SERIAL NUMBER: {}", serial_number.bright_black()));
+    }
+    if let Some(firmware_version) = properties.firmware_version {
+        formatted.push(format!(
+            "FIRMWARE VERSION: {}",
+            firmware_version.bright_black()
+        ));
+    }
+    if let Some(protocol_number) = properties.protocol_number {
+        formatted.push(format!(
+            "PROTOCOL NUMBER: {}",
+            protocol_number.bright_black()
+        ));
+    }
+    if let Some(target_software) = properties.target_software {
+        formatted.push(format!(
+            "TARGET SOFTWARE: {}",
+            format!("{:#04x}", target_software).bright_black()
+        ));
+    }
+    if let Some(feature_count) = properties.feature_count {
+        formatted.push(format!("FEATURES: {}", feature_count.bright_black()));
+    }
+    formatted
+}
 
-        if default_friendly_name != friendly_name {
-            properties.friendly_name.replace(friendly_name);
+/// Probes all channels for receivers and devices.
+async fn probe_local(root: &Cli, mut fields: Vec<ProbeField>) -> Result<Vec<ProbedEntry>> {
+    let progress = if root.output_format().is_none() && std::io::stderr().is_terminal() {
+        let bar = ProgressBar::new(0).with_style(
+            ProgressStyle::with_template("{spinner} Probing devices... {pos}/{len}").unwrap(),
+        );
+        bar.enable_steady_tick(Duration::from_millis(100));
+        bar
+    } else {
+        ProgressBar::hidden()
+    };
+
+    // Make sure all properties needed to match the device selector are
+    // retrieved.
+    if let Some(selector) = &root.device {
+        if selector.needs_names() {
+            fields.extend([ProbeField::Name, ProbeField::FriendlyName]);
+        }
+        if selector.needs_serial() {
+            fields.push(ProbeField::Serial);
         }
     }
 
-    if let Some(feature) = device.get_feature::<UnifiedBatteryFeature>() {
-        let battery = feature.get_battery_info().await?;
-        properties
-            .battery_percentage
-            .replace(battery.charging_percentage);
-        properties.battery_level.replace(battery.level);
-        properties.battery_status.replace(battery.status);
+    let entries = probe_channels(&fields, &progress).await;
+    progress.finish_and_clear();
+    let mut entries = entries?;
+
+    if let Some(selector) = &root.device {
+        entries = select_entries(entries, selector);
     }
 
-    if let Some(feature) = device.get_feature::<DeviceInformationFeature>() {
-        let info = feature.get_device_info().await?;
+    Ok(entries)
+}
 
-        if info.capabilities.serial_number {
-            properties
-                .serial_number
-                .replace(feature.get_serial_number().await?);
-        }
+/// Retrieves the properties of the devices managed by the running daemon.
+async fn probe_daemon(
+    mut client: ControlClient,
+    root: &Cli,
+    fields: Vec<ProbeField>,
+) -> Result<Vec<ProbedEntry>> {
+    let devices: Vec<DeviceStatus> = client
+        .call("list_devices", ListDevicesParams {
+            device: root.device.as_ref().map(ToString::to_string),
+            fields,
+        })
+        .await?;
+
+    Ok(devices
+        .into_iter()
+        .map(|status| {
+            ProbedEntry::ManagedDevice(ProbedManagedDevice {
+                device: status.device,
+                online: status.online,
+                properties: status.properties.unwrap_or_default(),
+            })
+        })
+        .collect())
+}
+
+/// Removes all devices not matching a selector from the probe results, as well
+/// as receivers without any matching paired device.
+fn select_entries(entries: Vec<ProbedEntry>, selector: &DeviceSelector) -> Vec<ProbedEntry> {
+    entries
+        .into_iter()
+        .filter_map(|entry| match entry {
+            ProbedEntry::Receiver(mut receiver) => {
+                receiver.paired_devices.retain(|device| {
+                    selector.matches(&device.properties.selectable(
+                        &device.name,
+                        Some(device.slot),
+                        Some(device.wpid),
+                    ))
+                });
+
+                (!receiver.paired_devices.is_empty()).then_some(ProbedEntry::Receiver(receiver))
+            },
+            ProbedEntry::Device(device) => selector
+                .matches(&device.properties.selectable(&device.name, None, None))
+                .then_some(ProbedEntry::Device(device)),
+            ProbedEntry::ManagedDevice(device) => Some(ProbedEntry::ManagedDevice(device)),
+        })
+        .collect()
+}
+
+async fn probe_channels(fields: &[ProbeField], progress: &ProgressBar) -> Result<Vec<ProbedEntry>> {
+    let channels: Vec<Arc<HidppChannel>> =
+        enumerate_hidpp().await?.into_iter().map(Arc::new).collect();
+
+    let entries = future::try_join_all(channels.into_iter().map(|channel| async move {
+        Ok::<_, anyhow::Error>(match receiver::detect(Arc::clone(&channel)) {
+            Some(receiver) => Some(ProbedEntry::Receiver(
+                probe_receiver(channel, receiver, fields, progress).await?,
+            )),
+            None => {
+                progress.inc_length(1);
+                let device = probe_direct_device(channel, fields).await?;
+                progress.inc(1);
+                device.map(ProbedEntry::Device)
+            },
+        })
+    }))
+    .await?;
+
+    Ok(entries.into_iter().flatten().collect())
+}
+
+async fn probe_receiver(
+    channel: Arc<HidppChannel>,
+    receiver: receiver::Receiver,
+    fields: &[ProbeField],
+    progress: &ProgressBar,
+) -> Result<ProbedReceiver> {
+    let mut paired_devices = receiver.collect_paired_devices().await?;
+    paired_devices.sort_by_key(|x| x.slot);
+    progress.inc_length(paired_devices.len() as u64);
+
+    // Register responses of the receiver can't be mapped to their requests
+    // reliably when multiple reads of the same register are in flight, so the
+    // names are read one after another before probing the devices themselves.
+    let mut names = Vec::with_capacity(paired_devices.len());
+    for device in &paired_devices {
+        names.push(receiver.get_device_name(device).await?);
     }
 
-    Ok(properties)
+    let probed_devices = stream::iter(paired_devices.into_iter().zip(names))
+        .map(|(device, name)| {
+            let channel = Arc::clone(&channel);
+
+            async move {
+                let properties = if device.online {
+                    let dev = Device::new(channel, device.slot).await?;
+                    probe_properties(dev, fields).await?
+                } else {
+                    ProbedDeviceProperties::default()
+                };
+                progress.inc(1);
+
+                Ok::<_, anyhow::Error>(ProbedPairedDevice {
+                    slot: device.slot,
+                    name,
+                    kind: device.kind,
+                    wpid: device.wpid,
+                    online: device.online,
+                    properties,
+                })
+            }
+        })
+        .buffered(MAX_CONCURRENT_DEVICE_PROBES)
+        .try_collect()
+        .await?;
+
+    Ok(ProbedReceiver {
+        name: receiver.name(),
+        unique_id: receiver.get_unique_id().await?,
+        vendor_id: channel.vendor_id,
+        product_id: channel.product_id,
+        paired_devices: probed_devices,
+    })
+}
+
+/// Probes a channel that is not backed by a known receiver for a HID++2.0
+/// device connected directly to it.
+///
+/// Returns `Ok(None)` if the channel does not expose such a device, which is
+/// the case for unsupported receivers and devices only speaking HID++1.0.
+async fn probe_direct_device(
+    channel: Arc<HidppChannel>,
+    fields: &[ProbeField],
+) -> Result<Option<ProbedDirectDevice>> {
+    // A collection that never answers would otherwise stall the whole probe.
+    let Ok(dev) = tokio::time::timeout(
+        PRESENCE_TIMEOUT,
+        Device::new(Arc::clone(&channel), DIRECT_DEVICE_INDEX),
+    )
+    .await
+    else {
+        return Ok(None);
+    };
+
+    let dev = match dev {
+        Ok(dev) => dev,
+        Err(
+            DeviceError::DeviceNotFound
+            | DeviceError::DeviceOffline
+            | DeviceError::Unknown(_)
+            | DeviceError::UnsupportedProtocolVersion,
+        ) => {
+            return Ok(None);
+        },
+        Err(err) => return Err(err.into()),
+    };
+
+    let properties = probe_properties(dev.clone(), fields).await?;
+
+    let name = match (&properties.friendly_name, &properties.full_name) {
+        (Some(name), _) | (None, Some(name)) => name.clone(),
+        (None, None) => dev
+            .resolve_name(None)
+            .await
+            .map_or_else(|| "Unknown device".to_string(), |resolved| resolved.name),
+    };
+
+    Ok(Some(ProbedDirectDevice {
+        name,
+        vendor_id: channel.vendor_id,
+        product_id: channel.product_id,
+        properties,
+    }))
+}
+
+/// Represents a single top-level result of a probe, which is either a receiver
+/// with its paired devices or a device connected directly to the host.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ProbedEntry {
+    Receiver(ProbedReceiver),
+    Device(ProbedDirectDevice),
+
+    /// A device managed by the running daemon.
+    ManagedDevice(ProbedManagedDevice),
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
@@ -274,26 +537,17 @@ struct ProbedPairedDevice {
     properties: ProbedDeviceProperties,
 }
 
-#[derive(Clone, PartialEq, Eq, Hash, Debug, Default, Serialize)]
-struct ProbedDeviceProperties {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    kind: Option<DeviceType>,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
-    full_name: Option<String>,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
-    friendly_name: Option<String>,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
-    battery_percentage: Option<u8>,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
-    battery_level: Option<BatteryLevel>,
-
-    #[serde(skip_serializing_if = "Option::is_none")]
-    battery_status: Option<BatteryStatus>,
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
+struct ProbedDirectDevice {
+    name: String,
+    vendor_id: u16,
+    product_id: u16,
+    properties: ProbedDeviceProperties,
+}
 
-    #[serde(skip_serializing_if = "Option::is_none")]
-    serial_number: Option<String>,
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
+struct ProbedManagedDevice {
+    device: DeviceSummary,
+    online: bool,
+    properties: ProbedDeviceProperties,
 }
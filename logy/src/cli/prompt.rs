@@ -0,0 +1,34 @@
+//! Implements confirmations for destructive operations.
+
+use std::io::{IsTerminal, Write};
+
+use anyhow::{Result, bail};
+use owo_colors::OwoColorize;
+
+/// Asks the user to confirm an operation affecting multiple items, listing
+/// all of them first.
+///
+/// If `yes` is set, the confirmation is skipped. Without an interactive
+/// terminal, the operation is refused unless `yes` is set.
+pub fn confirm(question: &str, items: &[String], yes: bool) -> Result<bool> {
+    if yes {
+        return Ok(true);
+    }
+
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        bail!("refusing to continue without confirmation, pass --yes to skip it");
+    }
+
+    let mut stderr = anstream::stderr();
+    for item in items {
+        writeln!(stderr, " • {}", item)?;
+    }
+    write!(stderr, "{} {} ", question, "[y/N]".bright_black())?;
+    stderr.flush()?;
+
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
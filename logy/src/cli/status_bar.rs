@@ -0,0 +1,149 @@
+//! Renders battery states in the formats expected by common status bars.
+
+use clap::ValueEnum;
+use hidpp::feature::unified_battery::BatteryLevel;
+use serde_json::json;
+
+use crate::{battery::BatteryState, devices::DeviceSummary};
+
+/// The text color used for batteries in a low state.
+const LOW_COLOR: &str = "#ffb86c";
+
+/// The text color used for batteries in a critical state.
+const CRITICAL_COLOR: &str = "#ff5555";
+
+/// Represents a status bar whose module format can be produced.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, ValueEnum)]
+pub enum StatusBarFormat {
+    /// A JSON object for a custom Waybar module (`"return-type": "json"`)
+    Waybar,
+
+    /// A block of the i3bar protocol, as used by i3status and compatible
+    /// status line generators
+    I3status,
+
+    /// A line of text with Polybar formatting tags
+    Polybar,
+}
+
+/// Represents the severity class of a battery state.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+enum BatteryClass {
+    Unknown,
+    Full,
+    Good,
+    Low,
+    Critical,
+}
+
+impl BatteryClass {
+    fn of(state: &BatteryState) -> Self {
+        match state.level {
+            Some(BatteryLevel::Critical) => Self::Critical,
+            Some(BatteryLevel::Low) => Self::Low,
+            Some(BatteryLevel::Good) => Self::Good,
+            Some(BatteryLevel::Full) => Self::Full,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::Full => "full",
+            Self::Good => "good",
+            Self::Low => "low",
+            Self::Critical => "critical",
+        }
+    }
+
+    fn color(self) -> Option<&'static str> {
+        match self {
+            Self::Low => Some(LOW_COLOR),
+            Self::Critical => Some(CRITICAL_COLOR),
+            _ => None,
+        }
+    }
+}
+
+/// Selects the battery state most worth showing in a status bar, which is
+/// the one of the device that will run out of battery first.
+pub fn select<'a>(
+    states: impl IntoIterator<Item = (&'a DeviceSummary, &'a BatteryState)>,
+) -> Option<(&'a DeviceSummary, &'a BatteryState)> {
+    states.into_iter().max_by_key(|(_, state)| {
+        (
+            BatteryClass::of(state),
+            u8::MAX - state.percentage.unwrap_or(u8::MAX),
+        )
+    })
+}
+
+/// Renders a single line for the given status bar format.
+///
+/// If no device is given, a line representing an unavailable module is
+/// rendered so that the bar can hide it.
+pub fn render(format: StatusBarFormat, entry: Option<(&DeviceSummary, &BatteryState)>) -> String {
+    let Some((device, state)) = entry else {
+        return match format {
+            StatusBarFormat::Waybar => {
+                json!({ "text": "", "tooltip": "No device", "class": "unavailable" }).to_string()
+            },
+            StatusBarFormat::I3status => {
+                json!({ "name": "logy_battery", "full_text": "" }).to_string()
+            },
+            StatusBarFormat::Polybar => String::new(),
+        };
+    };
+
+    let class = BatteryClass::of(state);
+    let icon = if state.charging {
+        "⚡"
+    } else {
+        "🔋"
+    };
+    let value = match (state.percentage, state.level, state.voltage) {
+        (Some(percentage), ..) => format!("{}%", percentage),
+        (None, Some(level), _) => format!("{:?}", level),
+        (None, None, Some(voltage)) => format!("{}mV", voltage),
+        (None, None, None) => "?".to_string(),
+    };
+    let text = format!("{} {}", icon, value);
+
+    match format {
+        StatusBarFormat::Waybar => {
+            let mut classes = vec![class.name()];
+            if state.charging {
+                classes.push("charging");
+            }
+
+            let mut object = json!({
+                "text": text,
+                "tooltip": format!("{}: {}", device.name, value),
+                "class": classes,
+                "alt": class.name(),
+            });
+            if let Some(percentage) = state.percentage {
+                object["percentage"] = json!(percentage);
+            }
+            object.to_string()
+        },
+        StatusBarFormat::I3status => {
+            let mut object = json!({
+                "name": "logy_battery",
+                "instance": device.name,
+                "full_text": format!("{} {}", device.name, text),
+                "short_text": text,
+                "urgent": class == BatteryClass::Critical && !state.charging,
+            });
+            if let Some(color) = class.color() {
+                object["color"] = json!(color);
+            }
+            object.to_string()
+        },
+        StatusBarFormat::Polybar => match class.color() {
+            Some(color) => format!("%{{F{}}}{}%{{F-}}", color, text),
+            None => text,
+        },
+    }
+}
@@ -0,0 +1,156 @@
+use std::io::{BufWriter, Write};
+
+use anyhow::{Result, bail};
+use clap::Args;
+use hidpp::receiver::ReceiverDevice;
+use owo_colors::OwoColorize;
+use serde_json::{Value, json};
+
+use super::{Cli, output::write_structured, prompt};
+use crate::{
+    control::{ControlClient, UnpairParams},
+    devices::{self, DeviceSelector, Discovery, Pairing, SelectableDevice},
+};
+
+/// Unpair devices from their receivers.
+///
+/// Devices are selected using --device, which also matches offline devices
+/// by their name, slot or WPID. With --dry-run, the devices are only listed
+/// along with the register writes that would unpair them.
+#[derive(Args)]
+pub struct UnpairCommand {
+    /// Unpair all selected devices instead of requiring the selection to
+    /// match exactly one device. Without --device, this unpairs every device
+    /// from every receiver
+    #[arg(short, long)]
+    all: bool,
+}
+
+impl UnpairCommand {
+    pub async fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+
+        if root.device.is_none() && !self.all {
+            bail!("select a device to unpair using --device, or pass --all");
+        }
+        if let Some(DeviceSelector::Serial(_)) = &root.device {
+            bail!(
+                "devices to unpair can't be selected by serial number or unit ID, as offline \
+                 devices don't report them, select them by name, slot or WPID instead"
+            );
+        }
+
+        let mut source = match root.daemon().await {
+            Some(client) => PairingSource::Daemon(client),
+            None => PairingSource::Local(devices::discover().await?),
+        };
+
+        let targets = source
+            .pairings()
+            .await?
+            .into_iter()
+            .filter(|pairing| {
+                root.device.as_ref().is_none_or(|selector| {
+                    selector.matches(&SelectableDevice {
+                        names: vec![&pairing.name],
+                        slot: Some(pairing.slot),
+                        wpid: Some(pairing.wpid),
+                        ..Default::default()
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if targets.is_empty() {
+            bail!("no matching paired device was found");
+        }
+        if targets.len() > 1 && !self.all {
+            bail!(
+                "{} paired devices match, pass --all to unpair all of them",
+                targets.len()
+            );
+        }
+
+        let items = targets
+            .iter()
+            .map(|x| format!("{} ({}, slot {})", x.name, x.receiver, x.slot))
+            .collect::<Vec<_>>();
+        let question = format!("Unpair {} device(s)?", targets.len());
+        if !root.dry_run && !prompt::confirm(&question, &items, root.yes)? {
+            bail!("aborted");
+        }
+
+        for target in &targets {
+            if !root.dry_run {
+                source.unpair(target).await?;
+            }
+
+            if root.output_format().is_none() {
+                writeln!(
+                    stdout,
+                    "{} {} from slot {} of {}",
+                    if root.dry_run {
+                        "Would unpair"
+                    } else {
+                        "Unpaired"
+                    },
+                    target.name,
+                    target.slot.bright_blue(),
+                    target.receiver
+                )
+                .unwrap();
+
+                if root.dry_run
+                    && let Some(write) = &target.write
+                {
+                    writeln!(stdout, "  {}", write).unwrap();
+                }
+            }
+        }
+
+        if let Some(format) = root.output_format() {
+            return write_structured(&mut stdout, format, &targets);
+        }
+
+        stdout.flush().unwrap();
+
+        Ok(())
+    }
+}
+
+/// Provides the paired devices and unpairs them, either directly or through
+/// the running daemon.
+enum PairingSource {
+    Daemon(ControlClient),
+    Local(Discovery),
+}
+
+impl PairingSource {
+    async fn pairings(&mut self) -> Result<Vec<Pairing>> {
+        match self {
+            Self::Daemon(client) => client.call("list_pairings", json!({})).await,
+            Self::Local(discovery) => devices::collect_pairings(&discovery.receivers).await,
+        }
+    }
+
+    async fn unpair(&mut self, pairing: &Pairing) -> Result<()> {
+        match self {
+            Self::Daemon(client) => {
+                client
+                    .call::<Value>("unpair", UnpairParams {
+                        receiver_index: pairing.receiver_index,
+                        slot: pairing.slot,
+                    })
+                    .await?;
+            },
+            Self::Local(discovery) => {
+                discovery.receivers[pairing.receiver_index]
+                    .receiver
+                    .unpair(pairing.slot)
+                    .await?;
+            },
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    fmt::Debug,
+    io::{BufWriter, Write},
+};
+
+use anyhow::Result;
+use clap::Args;
+use futures::{Stream, StreamExt};
+use hidpp::{
+    feature::{
+        EmittingFeature,
+        Feature,
+        backlight::Backlight3Feature,
+        battery_status::BatteryStatusFeature,
+        battery_voltage::BatteryVoltageFeature,
+        crown::CrownFeature,
+        extended_adjustable_report_rate::ExtendedAdjustableReportRateFeature,
+        hires_wheel::HiResWheelFeature,
+        lock_key_state::LockKeyStateFeature,
+        solar_keyboard_dashboard::SolarKeyboardDashboardFeature,
+        thumbwheel::ThumbwheelFeature,
+        unified_battery::UnifiedBatteryFeature,
+        wireless_device_status::WirelessDeviceStatusFeature,
+        wireless_signal_strength::WirelessSignalStrengthFeature,
+    },
+    receiver::{ReceiverDevice, ReceiverEvent},
+};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use super::{
+    Cli,
+    output::{EventLine, short_timestamp},
+};
+use crate::{
+    control::{ControlClient, DeviceStatus, ListDevicesParams, SubscribeParams},
+    devices::{self, DeviceSummary, DiscoveredDevice},
+};
+
+/// Print events emitted by receivers and devices as they occur.
+///
+/// If the daemon is running, the events emitted by the daemon are printed
+/// instead of opening the devices again.
+#[derive(Args)]
+pub struct WatchCommand {}
+
+impl WatchCommand {
+    pub async fn execute(&self, root: &Cli) -> Result<()> {
+        let mut stdout = BufWriter::new(anstream::stdout());
+        let json = root.json_lines()?;
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let description = match root.daemon().await {
+            Some(client) => self.remote(client, root, tx).await?,
+            None => self.local(root, tx).await?,
+        };
+
+        if !json {
+            writeln!(stdout, "{}", description.bright_black()).unwrap();
+            stdout.flush().unwrap();
+        }
+
+        loop {
+            let event = tokio::select! {
+                _ = tokio::signal::ctrl_c() => break,
+                event = rx.recv() => match event {
+                    Some(event) => event,
+                    None => break,
+                },
+            };
+
+            if json {
+                EventLine::now(&event.kind, Some(&event.source), &event.data).write(&mut stdout);
+                continue;
+            }
+
+            writeln!(
+                stdout,
+                "{} {} {}: {}",
+                short_timestamp().bright_black(),
+                event.source.name,
+                event.kind.green(),
+                event.debug
+            )
+            .unwrap();
+            stdout.flush().unwrap();
+        }
+
+        Ok(())
+    }
+
+    /// Listens to the events of all receivers and devices directly. Returns a
+    /// description of what is watched.
+    async fn local(&self, root: &Cli, tx: mpsc::UnboundedSender<WatchedEvent>) -> Result<String> {
+        let mut discovery = devices::discover().await?;
+        discovery.select(root.device.as_ref()).await?;
+
+        for receiver in &discovery.receivers {
+            let summary = DeviceSummary {
+                name: receiver.receiver.name(),
+                vendor_id: receiver.channel.vendor_id,
+                product_id: receiver.channel.product_id,
+                slot: None,
+                wpid: None,
+            };
+
+            // If only some devices are selected, the receiver events are
+            // limited to the connection events of these devices.
+            let slots = root.device.as_ref().map(|_| {
+                discovery
+                    .devices
+                    .iter()
+                    .filter(|x| {
+                        x.summary.vendor_id == summary.vendor_id
+                            && x.summary.product_id == summary.product_id
+                    })
+                    .filter_map(|x| x.summary.slot)
+                    .collect::<Vec<_>>()
+            });
+
+            forward(
+                receiver.receiver.listen_events(),
+                "receiver",
+                summary,
+                tx.clone(),
+                move |event| match (&slots, event) {
+                    (None, _) => true,
+                    (Some(slots), ReceiverEvent::DeviceConnection(paired)) => {
+                        slots.contains(&paired.slot)
+                    },
+                    (Some(_), _) => false,
+                },
+            );
+        }
+        for device in &discovery.devices {
+            forward_feature::<WirelessDeviceStatusFeature, _>(
+                device,
+                "wireless_status",
+                tx.clone(),
+            );
+            forward_feature::<WirelessSignalStrengthFeature, _>(device, "signal", tx.clone());
+            forward_feature::<UnifiedBatteryFeature, _>(device, "battery", tx.clone());
+            forward_feature::<BatteryStatusFeature, _>(device, "battery_status", tx.clone());
+            forward_feature::<BatteryVoltageFeature, _>(device, "battery_voltage", tx.clone());
+            forward_feature::<SolarKeyboardDashboardFeature, _>(device, "solar", tx.clone());
+            forward_feature::<HiResWheelFeature, _>(device, "hires_wheel", tx.clone());
+            forward_feature::<ThumbwheelFeature, _>(device, "thumbwheel", tx.clone());
+            forward_feature::<CrownFeature, _>(device, "crown", tx.clone());
+            forward_feature::<LockKeyStateFeature, _>(device, "lock_keys", tx.clone());
+            forward_feature::<Backlight3Feature, _>(device, "backlight", tx.clone());
+            forward_feature::<ExtendedAdjustableReportRateFeature, _>(
+                device,
+                "report_rate",
+                tx.clone(),
+            );
+        }
+
+        Ok(format!(
+            "Watching {} receiver(s) and {} device(s), press Ctrl+C to stop.",
+            discovery.receivers.len(),
+            discovery.devices.len()
+        ))
+    }
+
+    /// Receives the events emitted by the running daemon. Returns a
+    /// description of what is watched.
+    async fn remote(
+        &self,
+        mut client: ControlClient,
+        root: &Cli,
+        tx: mpsc::UnboundedSender<WatchedEvent>,
+    ) -> Result<String> {
+        client
+            .call::<Value>("subscribe", SubscribeParams::default())
+            .await?;
+
+        let selected = match &root.device {
+            Some(selector) => {
+                let devices: Vec<DeviceStatus> = client
+                    .call("list_devices", ListDevicesParams {
+                        device: Some(selector.to_string()),
+                        ..Default::default()
+                    })
+                    .await?;
+
+                Some(devices.into_iter().map(|x| x.device).collect::<Vec<_>>())
+            },
+            None => None,
+        };
+
+        tokio::spawn(async move {
+            while let Ok(Some(event)) = client.next_event().await {
+                let Some(source) = event.device else {
+                    continue;
+                };
+                if selected
+                    .as_ref()
+                    .is_some_and(|selected| !selected.contains(&source))
+                {
+                    continue;
+                }
+
+                let event = WatchedEvent {
+                    source,
+                    kind: event.kind,
+                    debug: event.data.to_string(),
+                    data: event.data,
+                };
+
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok("Watching the events of the daemon, press Ctrl+C to stop.".to_string())
+    }
+}
+
+/// Represents an event received from a receiver or device.
+struct WatchedEvent {
+    source: DeviceSummary,
+    kind: String,
+    data: Value,
+    debug: String,
+}
+
+/// Forwards all events of a specific feature of a device to a channel, if the
+/// device supports the feature.
+fn forward_feature<F, T>(
+    device: &DiscoveredDevice,
+    kind: &'static str,
+    tx: mpsc::UnboundedSender<WatchedEvent>,
+) where
+    F: Feature + EmittingFeature<T>,
+    T: Serialize + Debug + Send + 'static,
+{
+    if let Some(feature) = device.device.get_feature::<F>() {
+        forward(feature.listen(), kind, device.summary.clone(), tx, |_| true);
+    }
+}
+
+/// Forwards all events received by an event listener that pass a filter to a
+/// channel.
+fn forward<T: Serialize + Debug + Send + 'static>(
+    rx: impl Stream<Item = T> + Send + 'static,
+    kind: &'static str,
+    source: DeviceSummary,
+    tx: mpsc::UnboundedSender<WatchedEvent>,
+    filter: impl Fn(&T) -> bool + Send + 'static,
+) {
+    tokio::spawn(async move {
+        let mut rx = std::pin::pin!(rx);
+        while let Some(event) = rx.next().await {
+            if !filter(&event) {
+                continue;
+            }
+
+            let event = WatchedEvent {
+                source: source.clone(),
+                kind: kind.to_string(),
+                data: serde_json::to_value(&event).unwrap(),
+                debug: format!("{:?}", event),
+            };
+
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    });
+}
@@ -0,0 +1,364 @@
+//! Loads the logy configuration file.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, bail};
+use hidpp::feature::adjustable_dpi::DpiPreset;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Represents the contents of the configuration file.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub daemon: DaemonConfig,
+
+    /// Named sets of device settings that can be applied by rules.
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Configures the behavior of `logy daemon`.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaemonConfig {
+    pub battery: BatteryNotificationConfig,
+
+    /// The rules evaluated for every event observed by the daemon, in order.
+    pub rules: Vec<Rule>,
+
+    pub dbus: DbusConfig,
+    pub mqtt: MqttConfig,
+}
+
+/// Configures the D-Bus service published by the daemon.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct DbusConfig {
+    /// Whether the service should be published at all.
+    pub enabled: bool,
+
+    /// The bus to publish the service on.
+    pub bus: DbusBus,
+
+    /// Whether device batteries should also be exported using UPower's
+    /// `org.freedesktop.UPower.Device` interface. The objects are published
+    /// under logy's own bus name, so UPower itself and desktop environments
+    /// don't pick them up, only clients pointed at logy's bus name do.
+    /// Requires logy to be built with the `upower` feature.
+    pub upower: bool,
+}
+
+/// Represents a D-Bus message bus.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DbusBus {
+    #[default]
+    Session,
+    System,
+}
+
+/// Configures publishing device telemetry to an MQTT broker.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct MqttConfig {
+    /// Whether telemetry should be published at all. Requires logy to be
+    /// built with the `mqtt` feature.
+    pub enabled: bool,
+
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub client_id: String,
+
+    /// The prefix of all state topics, followed by the device ID.
+    pub topic_prefix: String,
+
+    /// The prefix Home Assistant discovers entities below, or `None` to not
+    /// publish discovery messages.
+    pub discovery_prefix: Option<String>,
+
+    /// The kinds of events published to the `event` topic of a device:
+    /// `device_connected`, `device_disconnected` and `battery`.
+    pub events: Vec<String>,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: "localhost".to_string(),
+            port: 1883,
+            username: None,
+            password: None,
+            client_id: "logy".to_string(),
+            topic_prefix: "logy".to_string(),
+            discovery_prefix: Some("homeassistant".to_string()),
+            events: vec![
+                "device_connected".to_string(),
+                "device_disconnected".to_string(),
+            ],
+        }
+    }
+}
+
+/// Configures the desktop notifications sent when the battery of a device is
+/// running low.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct BatteryNotificationConfig {
+    /// Whether notifications should be sent at all.
+    pub enabled: bool,
+
+    /// The battery percentages at which a notification is sent when the
+    /// battery discharges below them.
+    pub thresholds: Vec<u8>,
+
+    /// How many percentage points the battery has to recover above a
+    /// threshold before a notification is sent for it again.
+    pub hysteresis: u8,
+}
+
+impl Default for BatteryNotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            thresholds: vec![20, 10, 5],
+            hysteresis: 5,
+        }
+    }
+}
+
+/// Represents a set of device settings.
+///
+/// Settings for features a device does not support are ignored when applying
+/// the profile to it.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub smartshift: Option<SmartShiftSettings>,
+    pub hires_wheel: Option<HiResWheelSettings>,
+    pub thumbwheel: Option<ThumbwheelSettings>,
+    pub dpi: Option<DpiSettings>,
+}
+
+/// Configures the SmartShift ratchet control of the scroll wheel.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmartShiftSettings {
+    pub mode: Option<RatchetMode>,
+
+    /// The amount of quarter-turns per second it takes for the wheel to
+    /// automatically disengage, where `255` disables the automatic disengage.
+    pub auto_disengage: Option<u8>,
+}
+
+/// Represents the mode of the scroll wheel ratchet.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RatchetMode {
+    Freespin,
+    Ratchet,
+}
+
+/// Configures the high-resolution scroll wheel.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct HiResWheelSettings {
+    pub high_resolution: Option<bool>,
+    pub inverted: Option<bool>,
+}
+
+/// Configures the thumbwheel.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThumbwheelSettings {
+    pub inverted: Option<bool>,
+}
+
+/// Configures the pointer resolution of the first sensor.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(default, deny_unknown_fields)]
+pub struct DpiSettings {
+    /// The DPI to set, taking precedence over the default step of the preset.
+    pub dpi: Option<u16>,
+
+    /// The DPI steps of the sensor. The sensor is set to the default step
+    /// unless `dpi` is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<DpiPresetSchema>")]
+    pub preset: Option<DpiPreset>,
+}
+
+/// Describes the schema of a [`DpiPreset`], which is defined without
+/// [`JsonSchema`] support.
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct DpiPresetSchema {
+    /// The steps of the preset, in the order they are cycled through.
+    steps: Vec<DpiStepSchema>,
+
+    /// The index of the step the sensor is set to by default.
+    #[serde(default)]
+    default_index: usize,
+}
+
+/// Describes the schema of a
+/// [`DpiStep`](hidpp::feature::adjustable_dpi::DpiStep).
+#[derive(JsonSchema)]
+#[allow(dead_code)]
+struct DpiStepSchema {
+    /// An optional name describing the step, like `"precision"`.
+    #[serde(default)]
+    name: Option<String>,
+
+    /// The DPI of the step.
+    dpi: u16,
+}
+
+/// Represents a rule that runs a list of actions whenever a matching event
+/// occurs.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// The event that triggers the rule.
+    pub on: Trigger,
+
+    /// Only match events of devices whose name contains this text
+    /// (case-insensitive).
+    #[serde(default)]
+    pub device: Option<String>,
+
+    /// Only match if the host name of this machine equals this value.
+    #[serde(default)]
+    pub host: Option<String>,
+
+    /// The actions to run, in order.
+    pub actions: Vec<Action>,
+}
+
+/// Represents an event a [`Rule`] can be triggered by.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "event", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Trigger {
+    /// A device was found when the daemon started or (re)connected to this
+    /// host, for example after switching back to it from another host.
+    DeviceConnected,
+
+    /// A device lost its connection to this host, for example after switching
+    /// to another host or being turned off.
+    ///
+    /// Devices don't tell a switch to another host apart from losing the
+    /// connection otherwise, so there is no separate trigger for it.
+    DeviceDisconnected,
+
+    /// The battery percentage of a device dropped to or below a value.
+    BatteryBelow {
+        percentage: u8,
+    },
+
+    /// A control, identified by its control ID (CID), was pressed.
+    ///
+    /// The daemon diverts the control on every device supporting it, so it
+    /// no longer performs its default action while the rule exists.
+    ButtonPressed {
+        control: u16,
+    },
+}
+
+/// Represents an action run by a [`Rule`].
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Action {
+    /// Applies a profile from the `profiles` section to the device.
+    ApplyProfile {
+        profile: String,
+    },
+
+    /// Runs a command using the system shell.
+    ///
+    /// The event is passed to the command using the `LOGY_EVENT` and
+    /// `LOGY_DEVICE` environment variables. The command line itself is run as
+    /// is, as names reported by devices must never be interpreted by the
+    /// shell.
+    Command {
+        command: String,
+    },
+
+    /// Shows a desktop notification.
+    ///
+    /// In the summary and body, `{device}` is replaced with the name of the
+    /// device the event originated from.
+    Notify {
+        summary: String,
+        #[serde(default)]
+        body: Option<String>,
+    },
+}
+
+impl Config {
+    /// Loads the configuration from the given path, or from the default
+    /// location if no path is given.
+    ///
+    /// A missing file at the default location results in the default
+    /// configuration, while an explicitly given path has to exist.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound && !explicit => {
+                return Ok(Self::default());
+            },
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("could not read config file {}", path.display()));
+            },
+        };
+
+        Self::parse(&content)
+            .with_context(|| format!("could not parse config file {}", path.display()))
+    }
+
+    /// Parses and validates the contents of a configuration file.
+    pub fn parse(content: &str) -> Result<Self> {
+        let config: Self = toml::from_str(content)?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Checks references between sections of the configuration.
+    fn validate(&self) -> Result<()> {
+        for rule in &self.daemon.rules {
+            for action in &rule.actions {
+                if let Action::ApplyProfile {
+                    profile,
+                } = action
+                    && !self.profiles.contains_key(profile)
+                {
+                    bail!("rule references unknown profile \"{}\"", profile);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the default location of the configuration file, which is
+/// `logy/config.toml` in the platform's configuration directory.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("logy").join("config.toml"))
+}
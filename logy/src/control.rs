@@ -0,0 +1,526 @@
+//! Defines the control API of the daemon and a client for it.
+//!
+//! The daemon listens on a Unix domain socket, or a named pipe on Windows,
+//! located at [`socket_path`]. Clients send JSON-RPC 2.0 requests and receive
+//! responses, each encoded as a single line of JSON. The following methods are
+//! available:
+//!
+//! - `list_devices` returns the [`DeviceStatus`] of all managed devices
+//!   matching the optional `device` selector. If `fields` are given, the
+//!   properties of online devices are retrieved as well.
+//! - `list_features` returns the [`DeviceFeatures`] of all managed devices
+//!   matching the optional `device` selector.
+//! - `list_pairings` returns the [`Pairing`]s of all receivers, including
+//!   offline devices.
+//! - `unpair` unpairs the device in slot `slot` of the receiver at
+//!   `receiver_index`, as returned by `list_pairings`.
+//! - `get_battery` returns the last known [`DeviceBattery`] of all devices
+//!   matching the optional `device` selector.
+//! - `apply_profile` applies the profile `profile` to all online devices
+//!   matching the optional `device` selector and returns the [`AppliedProfile`]
+//!   of every device. With `dry_run`, the operations are only planned.
+//! - `subscribe` makes the daemon send every following event as an `event`
+//!   notification with an [`Event`] as parameters. With `messages`, every HID++
+//!   message the daemon receives is sent as a `message` event with a
+//!   [`RawMessage`] as data as well.
+//!
+//! Other commands use the daemon through [`ControlClient`] if it is running,
+//! so that they don't compete with it for the HID devices.
+
+use std::{path::PathBuf, time::Duration};
+
+#[cfg(unix)]
+use anyhow::Context;
+use anyhow::{Result, anyhow, bail};
+use chrono::{Local, SecondsFormat};
+use hidpp::{
+    channel::{HidppMessage, LONG_REPORT_LENGTH},
+    feature::{FeatureType, registry},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines, ReadHalf, WriteHalf};
+
+use crate::{
+    battery::BatteryState,
+    devices::{DeviceSummary, DiscoveredDevice},
+    profile::OperationInfo,
+    properties::{ProbeField, ProbedDeviceProperties},
+};
+
+/// The JSON-RPC version implemented by the control API.
+const JSONRPC_VERSION: &str = "2.0";
+
+/// The JSON-RPC error code of a request that could not be parsed.
+pub const PARSE_ERROR: i64 = -32700;
+
+/// The JSON-RPC error code of an unknown method.
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The JSON-RPC error code of invalid method parameters.
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// The error code of a method that failed while accessing a device.
+pub const DEVICE_ERROR: i64 = -32000;
+
+/// How long to wait for the daemon to respond to a call, which covers
+/// applying a profile to several devices.
+const CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[cfg(unix)]
+type Stream = tokio::net::UnixStream;
+
+#[cfg(windows)]
+type Stream = tokio::net::windows::named_pipe::NamedPipeClient;
+
+/// Returns the location of the control socket.
+///
+/// On Unix, this is `logy.sock` in the runtime directory of the user, or in a
+/// private `logy-<user>` directory in the temporary directory if there is
+/// none. On Windows, it is a named pipe containing the user name.
+pub fn socket_path() -> PathBuf {
+    #[cfg(windows)]
+    return PathBuf::from(format!(r"\\.\pipe\logy-{}", whoami::username()));
+
+    #[cfg(not(windows))]
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("logy.sock"),
+        None => std::env::temp_dir()
+            .join(format!("logy-{}", whoami::username()))
+            .join("logy.sock"),
+    }
+}
+
+/// Creates the directory containing the control socket if it is missing and
+/// makes sure only the current user can access it.
+///
+/// The temporary directory is shared with other users, who could otherwise
+/// create the directory first to intercept the control API.
+#[cfg(unix)]
+pub fn prepare_socket_dir() -> Result<()> {
+    use std::{
+        fs::DirBuilder,
+        os::unix::fs::{DirBuilderExt, MetadataExt},
+    };
+
+    let path = socket_path();
+    let Some(dir) = path.parent() else {
+        return Ok(());
+    };
+
+    if !dir.exists() {
+        DirBuilder::new()
+            .mode(0o700)
+            .create(dir)
+            .with_context(|| format!("could not create {}", dir.display()))?;
+    }
+
+    let metadata = std::fs::symlink_metadata(dir)
+        .with_context(|| format!("could not access {}", dir.display()))?;
+    if !metadata.is_dir() || !owned_by_current_user(&metadata) || metadata.mode() & 0o077 != 0 {
+        bail!(
+            "{} must be a directory only accessible by the current user",
+            dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks whether a file is owned by the user running the process.
+#[cfg(unix)]
+fn owned_by_current_user(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    // SAFETY: `geteuid` has no preconditions and always succeeds.
+    metadata.uid() == unsafe { libc::geteuid() }
+}
+
+/// Represents a JSON-RPC request, or a notification if it has no ID.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Request {
+    pub jsonrpc: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+
+    pub method: String,
+
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub params: Value,
+}
+
+impl Request {
+    pub fn new(id: Option<Value>, method: &str, params: Value) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    /// Parses the parameters of the request, where missing parameters are
+    /// treated like an empty object.
+    pub fn params<T: DeserializeOwned>(&self) -> Result<T, RpcError> {
+        let params = match &self.params {
+            Value::Null => Value::Object(Default::default()),
+            params => params.clone(),
+        };
+
+        serde_json::from_value(params).map_err(|err| RpcError::new(INVALID_PARAMS, err))
+    }
+}
+
+/// Represents a JSON-RPC response.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Option<Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    pub fn new(id: Option<Value>, result: Result<Value, RpcError>) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+
+        Self {
+            jsonrpc: JSONRPC_VERSION.to_string(),
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+/// Represents the error of a failed JSON-RPC request.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub fn new(code: i64, message: impl ToString) -> Self {
+        Self {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Represents an event of the daemon sent to subscribed clients, using the
+/// same schema as the event lines of streaming commands.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Event {
+    /// The local time the event was observed at, formatted as RFC 3339.
+    pub timestamp: String,
+
+    /// The kind of the event, like `battery`.
+    pub kind: String,
+
+    /// The device the event belongs to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<DeviceSummary>,
+
+    /// The event data.
+    pub data: Value,
+}
+
+impl Event {
+    /// Creates a new event timestamped with the current time.
+    pub fn now(kind: &str, device: Option<DeviceSummary>, data: Value) -> Self {
+        Self {
+            timestamp: Local::now().to_rfc3339_opts(SecondsFormat::Millis, false),
+            kind: kind.to_string(),
+            device,
+            data,
+        }
+    }
+}
+
+/// Represents a device managed by the daemon, as returned by `list_devices`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DeviceStatus {
+    pub device: DeviceSummary,
+    pub online: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery: Option<BatteryState>,
+
+    /// The properties retrieved from the device, if any were requested and
+    /// the device is online.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub properties: Option<ProbedDeviceProperties>,
+}
+
+/// Represents the features supported by a device, as returned by
+/// `list_features`.
+#[derive(Clone, Hash, Debug, Deserialize, Serialize)]
+pub struct DeviceFeatures {
+    pub device: DeviceSummary,
+    pub features: Vec<ListedFeature>,
+}
+
+impl DeviceFeatures {
+    /// Lists the features enumerated when the device was discovered.
+    pub fn new(device: &DiscoveredDevice) -> Self {
+        Self {
+            device: device.summary.clone(),
+            features: device
+                .features
+                .iter()
+                .enumerate()
+                .map(|(i, info)| ListedFeature {
+                    index: i as u8 + 1,
+                    id: format!("{:#06x}", info.id),
+                    name: registry::lookup(info.id).map(|feature| feature.name.to_string()),
+                    version: info.version,
+                    typ: info.typ,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Represents a single feature supported by a device.
+#[derive(Clone, Hash, Debug, Deserialize, Serialize)]
+pub struct ListedFeature {
+    pub index: u8,
+    pub id: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    pub version: u8,
+
+    #[serde(rename = "type")]
+    pub typ: FeatureType,
+}
+
+/// Represents the battery state of a device, as returned by `get_battery`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DeviceBattery {
+    pub device: DeviceSummary,
+    pub battery: BatteryState,
+}
+
+/// Represents the operations performed to apply a profile to a device, as
+/// returned by `apply_profile`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AppliedProfile {
+    pub device: DeviceSummary,
+    pub operations: Vec<OperationInfo>,
+}
+
+/// Represents a raw HID++ message received by the daemon, as sent in
+/// `message` events.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+pub struct RawMessage {
+    pub vendor_id: u16,
+    pub product_id: u16,
+
+    /// Whether the message was a response to a previously sent request.
+    pub matched: bool,
+
+    /// The raw message bytes, including the report ID.
+    pub data: Vec<u8>,
+}
+
+impl RawMessage {
+    pub fn new(vendor_id: u16, product_id: u16, msg: HidppMessage, matched: bool) -> Self {
+        let mut buf = [0u8; LONG_REPORT_LENGTH];
+        let len = msg.write_raw(&mut buf);
+
+        Self {
+            vendor_id,
+            product_id,
+            matched,
+            data: buf[..len].to_vec(),
+        }
+    }
+
+    /// Returns the index of the device the message belongs to.
+    pub fn device_index(&self) -> u8 {
+        self.data[1]
+    }
+}
+
+/// Contains the parameters of `subscribe`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SubscribeParams {
+    /// Whether raw HID++ messages should be sent as well.
+    #[serde(default)]
+    pub messages: bool,
+}
+
+/// Contains the parameters of `list_devices`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ListDevicesParams {
+    /// A device selector, as passed to `--device`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+
+    /// The properties to retrieve from online devices.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<ProbeField>,
+}
+
+/// Contains the parameters of `list_features`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ListFeaturesParams {
+    /// A device selector, as passed to `--device`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+}
+
+/// Contains the parameters of `get_battery`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct GetBatteryParams {
+    /// A device selector, as passed to `--device`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+}
+
+/// Contains the parameters of `apply_profile`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ApplyProfileParams {
+    pub profile: String,
+
+    /// A device selector, as passed to `--device`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub device: Option<String>,
+
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Contains the parameters of `unpair`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UnpairParams {
+    pub receiver_index: usize,
+    pub slot: u8,
+}
+
+/// Represents a connection to the control socket of a running daemon.
+pub struct ControlClient {
+    reader: Lines<BufReader<ReadHalf<Stream>>>,
+    writer: WriteHalf<Stream>,
+    next_id: u64,
+}
+
+impl ControlClient {
+    /// Connects to the daemon, returning `None` if it is not running.
+    ///
+    /// On Unix, a socket owned by another user is ignored, as that user could
+    /// otherwise impersonate the daemon.
+    pub async fn connect() -> Option<Self> {
+        #[cfg(unix)]
+        let stream = {
+            let path = socket_path();
+            let metadata = std::fs::symlink_metadata(&path).ok()?;
+            if !owned_by_current_user(&metadata) {
+                eprintln!(
+                    "Ignoring the control socket {}, as it is owned by another user",
+                    path.display()
+                );
+                return None;
+            }
+
+            Stream::connect(path).await.ok()?
+        };
+
+        #[cfg(windows)]
+        let stream = tokio::net::windows::named_pipe::ClientOptions::new()
+            .open(socket_path())
+            .ok()?;
+
+        let (reader, writer) = tokio::io::split(stream);
+
+        Some(Self {
+            reader: BufReader::new(reader).lines(),
+            writer,
+            next_id: 0,
+        })
+    }
+
+    /// Calls a method and waits for its result, failing if the daemon does
+    /// not respond within [`CALL_TIMEOUT`].
+    ///
+    /// Events received while waiting are dropped.
+    pub async fn call<T: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: impl Serialize,
+    ) -> Result<T> {
+        match tokio::time::timeout(CALL_TIMEOUT, self.call_unbounded(method, params)).await {
+            Ok(result) => result,
+            Err(_) => bail!(
+                "the daemon did not respond to {} within {} seconds",
+                method,
+                CALL_TIMEOUT.as_secs()
+            ),
+        }
+    }
+
+    async fn call_unbounded<T: DeserializeOwned>(
+        &mut self,
+        method: &str,
+        params: impl Serialize,
+    ) -> Result<T> {
+        let id = Value::from(self.next_id);
+        self.next_id += 1;
+
+        let request = Request::new(Some(id.clone()), method, serde_json::to_value(params)?);
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await?;
+
+        loop {
+            let Some(line) = self.reader.next_line().await? else {
+                bail!("the daemon closed the connection");
+            };
+
+            let Ok(response) = serde_json::from_str::<Response>(&line) else {
+                continue;
+            };
+            if response.id.as_ref() != Some(&id) {
+                continue;
+            }
+
+            if let Some(error) = response.error {
+                bail!("{}", error.message);
+            }
+
+            return Ok(serde_json::from_value(
+                response
+                    .result
+                    .ok_or_else(|| anyhow!("the daemon returned no result"))?,
+            )?);
+        }
+    }
+
+    /// Waits for the next event after subscribing to them, returning `None`
+    /// once the daemon closed the connection.
+    pub async fn next_event(&mut self) -> Result<Option<Event>> {
+        while let Some(line) = self.reader.next_line().await? {
+            let Ok(notification) = serde_json::from_str::<Request>(&line) else {
+                continue;
+            };
+
+            if notification.id.is_none() && notification.method == "event" {
+                return Ok(Some(serde_json::from_value(notification.params)?));
+            }
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,275 @@
+//! Sends desktop notifications when the battery of a device is running low.
+
+use std::collections::HashMap;
+
+use hidpp::feature::unified_battery::BatteryLevel;
+use notify_rust::Notification;
+
+use crate::{battery::BatteryState, config::BatteryNotificationConfig, devices::DeviceSummary};
+
+/// Keeps track of which battery thresholds were already crossed by which
+/// devices, so that every threshold only triggers a single notification
+/// until the battery recovers or is being charged.
+pub struct BatteryNotifier {
+    config: BatteryNotificationConfig,
+    notified_percentages: HashMap<DeviceSummary, u8>,
+    notified_levels: HashMap<DeviceSummary, BatteryLevel>,
+}
+
+/// Represents a low battery warning that should be shown to the user.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BatteryAlert {
+    /// The battery discharged below a configured percentage threshold.
+    Percentage {
+        threshold: u8,
+        percentage: u8,
+    },
+
+    /// The battery of a device that only reports coarse levels reached a
+    /// low or critical level.
+    Level(BatteryLevel),
+}
+
+impl BatteryNotifier {
+    pub fn new(config: BatteryNotificationConfig) -> Self {
+        Self {
+            config,
+            notified_percentages: HashMap::new(),
+            notified_levels: HashMap::new(),
+        }
+    }
+
+    /// Replaces the configuration, keeping track of the thresholds that were
+    /// already notified about.
+    pub fn set_config(&mut self, config: BatteryNotificationConfig) {
+        self.config = config;
+    }
+
+    /// Processes a new battery state of a device and sends a notification if
+    /// it crossed a threshold that was not yet notified about.
+    pub fn update(&mut self, device: &DeviceSummary, state: &BatteryState) {
+        if let Some(alert) = self.check(device, state) {
+            send_notification(device, alert);
+        }
+    }
+
+    /// Processes a new battery state of a device and returns the alert that
+    /// should be shown for it, if any.
+    pub fn check(&mut self, device: &DeviceSummary, state: &BatteryState) -> Option<BatteryAlert> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        if state.charging {
+            self.notified_percentages.remove(device);
+            self.notified_levels.remove(device);
+            return None;
+        }
+
+        if let Some(percentage) = state.percentage {
+            return self.check_percentage(device, percentage);
+        }
+        if let Some(level) = state.level {
+            return self.check_level(device, level);
+        }
+
+        None
+    }
+
+    fn check_percentage(&mut self, device: &DeviceSummary, percentage: u8) -> Option<BatteryAlert> {
+        let crossed = self
+            .config
+            .thresholds
+            .iter()
+            .copied()
+            .filter(|&threshold| percentage <= threshold)
+            .min();
+        let notified = self.notified_percentages.get(device).copied();
+
+        match (crossed, notified) {
+            (Some(threshold), None) => {
+                self.notified_percentages.insert(device.clone(), threshold);
+                Some(BatteryAlert::Percentage {
+                    threshold,
+                    percentage,
+                })
+            },
+            (Some(threshold), Some(notified)) if threshold < notified => {
+                self.notified_percentages.insert(device.clone(), threshold);
+                Some(BatteryAlert::Percentage {
+                    threshold,
+                    percentage,
+                })
+            },
+            (crossed, Some(notified))
+                if percentage > notified.saturating_add(self.config.hysteresis) =>
+            {
+                // The battery recovered far enough to re-arm the notified
+                // threshold without notifying about the ones above it again.
+                match crossed {
+                    Some(threshold) => self.notified_percentages.insert(device.clone(), threshold),
+                    None => self.notified_percentages.remove(device),
+                };
+                None
+            },
+            _ => None,
+        }
+    }
+
+    fn check_level(&mut self, device: &DeviceSummary, level: BatteryLevel) -> Option<BatteryAlert> {
+        if !matches!(level, BatteryLevel::Low | BatteryLevel::Critical) {
+            self.notified_levels.remove(device);
+            return None;
+        }
+
+        let is_lower = self
+            .notified_levels
+            .get(device)
+            .is_none_or(|&notified| u8::from(level) < u8::from(notified));
+        if !is_lower {
+            return None;
+        }
+
+        self.notified_levels.insert(device.clone(), level);
+        Some(BatteryAlert::Level(level))
+    }
+}
+
+/// Shows a desktop notification for a battery alert in the background.
+fn send_notification(device: &DeviceSummary, alert: BatteryAlert) {
+    let (body, critical) = match alert {
+        BatteryAlert::Percentage {
+            threshold,
+            percentage,
+        } => (
+            format!("{}% remaining (below {}%)", percentage, threshold),
+            percentage <= 5,
+        ),
+        BatteryAlert::Level(level) => (
+            format!("Battery level is {:?}", level).to_lowercase(),
+            level == BatteryLevel::Critical,
+        ),
+    };
+
+    let mut notification = Notification::new();
+    notification
+        .appname("logy")
+        .summary(&format!("{}: battery low", device.name))
+        .body(&body)
+        .icon(if critical {
+            "battery-caution"
+        } else {
+            "battery-low"
+        });
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    notification.urgency(if critical {
+        notify_rust::Urgency::Critical
+    } else {
+        notify_rust::Urgency::Normal
+    });
+
+    let name = device.name.clone();
+    tokio::task::spawn_blocking(move || {
+        if let Err(err) = notification.show() {
+            eprintln!("Could not show battery notification for {}: {}", name, err);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device() -> DeviceSummary {
+        DeviceSummary {
+            name: "MX Master 3S".to_string(),
+            vendor_id: 0x046d,
+            product_id: 0xc548,
+            slot: Some(1),
+            wpid: Some(0xb034),
+        }
+    }
+
+    fn discharging(percentage: u8) -> BatteryState {
+        BatteryState {
+            percentage: Some(percentage),
+            ..Default::default()
+        }
+    }
+
+    fn alert(threshold: u8, percentage: u8) -> Option<BatteryAlert> {
+        Some(BatteryAlert::Percentage {
+            threshold,
+            percentage,
+        })
+    }
+
+    #[test]
+    fn crossing_threshold() {
+        let mut notifier = BatteryNotifier::new(BatteryNotificationConfig::default());
+
+        assert_eq!(notifier.check(&device(), &discharging(50)), None);
+        assert_eq!(notifier.check(&device(), &discharging(20)), alert(20, 20));
+        assert_eq!(notifier.check(&device(), &discharging(18)), None);
+    }
+
+    #[test]
+    fn dropping_to_lower_threshold() {
+        let mut notifier = BatteryNotifier::new(BatteryNotificationConfig::default());
+
+        assert_eq!(notifier.check(&device(), &discharging(15)), alert(20, 15));
+        assert_eq!(notifier.check(&device(), &discharging(10)), alert(10, 10));
+        assert_eq!(notifier.check(&device(), &discharging(3)), alert(5, 3));
+        assert_eq!(notifier.check(&device(), &discharging(2)), None);
+    }
+
+    #[test]
+    fn rearming_on_recovery() {
+        let mut notifier = BatteryNotifier::new(BatteryNotificationConfig::default());
+
+        assert_eq!(notifier.check(&device(), &discharging(9)), alert(10, 9));
+
+        // Recovering within the hysteresis keeps the threshold armed.
+        assert_eq!(notifier.check(&device(), &discharging(15)), None);
+        assert_eq!(notifier.check(&device(), &discharging(10)), None);
+
+        // Recovering beyond it re-arms the threshold, but not the ones above.
+        assert_eq!(notifier.check(&device(), &discharging(16)), None);
+        assert_eq!(notifier.check(&device(), &discharging(9)), alert(10, 9));
+    }
+
+    #[test]
+    fn reset_while_charging() {
+        let mut notifier = BatteryNotifier::new(BatteryNotificationConfig::default());
+
+        assert_eq!(notifier.check(&device(), &discharging(19)), alert(20, 19));
+
+        let charging = BatteryState {
+            charging: true,
+            ..discharging(19)
+        };
+        assert_eq!(notifier.check(&device(), &charging), None);
+        assert_eq!(notifier.check(&device(), &discharging(19)), alert(20, 19));
+    }
+
+    #[test]
+    fn levels() {
+        let mut notifier = BatteryNotifier::new(BatteryNotificationConfig::default());
+        let level = |level| BatteryState {
+            level: Some(level),
+            ..Default::default()
+        };
+
+        assert_eq!(notifier.check(&device(), &level(BatteryLevel::Good)), None);
+        assert_eq!(
+            notifier.check(&device(), &level(BatteryLevel::Low)),
+            Some(BatteryAlert::Level(BatteryLevel::Low))
+        );
+        assert_eq!(notifier.check(&device(), &level(BatteryLevel::Low)), None);
+        assert_eq!(
+            notifier.check(&device(), &level(BatteryLevel::Critical)),
+            Some(BatteryAlert::Level(BatteryLevel::Critical))
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! Serves the control API of the daemon, see [`crate::control`].
+//!
+//! Every connection is served by its own task. Requests are forwarded to the
+//! main loop of the daemon as [`ControlCall`]s, so they are handled in order
+//! with all other events, while subscriptions are served by the connection
+//! task directly.
+
+use anyhow::Result;
+use serde_json::Value;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+        oneshot,
+    },
+    task::JoinHandle,
+};
+
+use crate::control::{self, Event, PARSE_ERROR, Request, Response, RpcError, SubscribeParams};
+
+/// How many events are buffered for a subscribed client that does not keep
+/// up before older events are dropped.
+const EVENT_BUFFER: usize = 64;
+
+/// The kind of the events carrying raw HID++ messages, which are only sent
+/// to clients that asked for them.
+pub const MESSAGE_EVENT: &str = "message";
+
+/// Represents a request forwarded to the main loop of the daemon.
+pub struct ControlCall {
+    pub request: Request,
+    pub reply: oneshot::Sender<Response>,
+}
+
+/// Listens on the control socket while it exists.
+pub struct ControlServer {
+    events: broadcast::Sender<Event>,
+    task: JoinHandle<()>,
+}
+
+impl ControlServer {
+    /// Starts listening on the control socket, forwarding all requests to
+    /// the given channel.
+    ///
+    /// Fails if another daemon is already listening.
+    pub fn bind(calls: mpsc::UnboundedSender<ControlCall>) -> Result<Self> {
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let task = listen(calls, events.clone())?;
+
+        Ok(Self {
+            events,
+            task,
+        })
+    }
+
+    /// Sends an event to all subscribed clients.
+    pub fn emit(&self, event: Event) {
+        let _ = self.events.send(event);
+    }
+
+    /// Provides a sender for emitting events from outside the main loop.
+    pub fn sender(&self) -> broadcast::Sender<Event> {
+        self.events.clone()
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.task.abort();
+
+        #[cfg(unix)]
+        let _ = std::fs::remove_file(control::socket_path());
+    }
+}
+
+#[cfg(unix)]
+fn listen(
+    calls: mpsc::UnboundedSender<ControlCall>,
+    events: broadcast::Sender<Event>,
+) -> Result<JoinHandle<()>> {
+    use std::os::unix::net::UnixStream;
+
+    use anyhow::{Context, bail};
+    use tokio::net::UnixListener;
+
+    control::prepare_socket_dir()?;
+
+    let path = control::socket_path();
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            bail!("another daemon is already listening on {}", path.display());
+        }
+
+        // The socket was left behind by a daemon that did not shut down
+        // cleanly.
+        std::fs::remove_file(&path)
+            .with_context(|| format!("could not remove stale socket {}", path.display()))?;
+    }
+
+    let listener = UnixListener::bind(&path)
+        .with_context(|| format!("could not listen on {}", path.display()))?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve(stream, calls.clone(), events.subscribe()));
+                },
+                Err(err) => eprintln!("Could not accept control connection: {}", err),
+            }
+        }
+    }))
+}
+
+#[cfg(windows)]
+fn listen(
+    calls: mpsc::UnboundedSender<ControlCall>,
+    events: broadcast::Sender<Event>,
+) -> Result<JoinHandle<()>> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+
+    let path = control::socket_path();
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(&path)?;
+
+    Ok(tokio::spawn(async move {
+        loop {
+            if let Err(err) = server.connect().await {
+                eprintln!("Could not accept control connection: {}", err);
+                continue;
+            }
+
+            let next = match ServerOptions::new().create(&path) {
+                Ok(next) => next,
+                Err(err) => {
+                    eprintln!("Could not create control pipe: {}", err);
+                    break;
+                },
+            };
+            let stream = std::mem::replace(&mut server, next);
+            tokio::spawn(serve(stream, calls.clone(), events.subscribe()));
+        }
+    }))
+}
+
+/// Serves a single client until it disconnects.
+async fn serve<S: AsyncRead + AsyncWrite + Send + 'static>(
+    stream: S,
+    calls: mpsc::UnboundedSender<ControlCall>,
+    mut events: broadcast::Receiver<Event>,
+) {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut lines = BufReader::new(reader).lines();
+    let mut subscribed = false;
+    let mut messages = false;
+
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else {
+                    break;
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+
+                let response = match serde_json::from_str::<Request>(&line) {
+                    Ok(request) if request.method == "subscribe" => {
+                        let result = request.params::<SubscribeParams>().map(|params| {
+                            subscribed = true;
+                            messages = params.messages;
+                            events = events.resubscribe();
+                            Value::Bool(true)
+                        });
+                        request.id.map(|id| Response::new(Some(id), result))
+                    },
+                    Ok(request) => {
+                        let id = request.id.clone();
+                        let (reply, response) = oneshot::channel();
+                        if calls.send(ControlCall { request, reply }).is_err() {
+                            break;
+                        }
+                        let Ok(response) = response.await else {
+                            break;
+                        };
+                        id.map(|_| response)
+                    },
+                    Err(err) => Some(Response::new(None, Err(RpcError::new(PARSE_ERROR, err)))),
+                };
+
+                if let Some(response) = response
+                    && write(&mut writer, &response).await.is_err()
+                {
+                    break;
+                }
+            },
+            event = events.recv(), if subscribed => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+                if event.kind == MESSAGE_EVENT && !messages {
+                    continue;
+                }
+
+                let notification = Request::new(
+                    None,
+                    "event",
+                    serde_json::to_value(event).unwrap(),
+                );
+                if write(&mut writer, &notification).await.is_err() {
+                    break;
+                }
+            },
+        }
+    }
+}
+
+/// Writes a message as a single line.
+async fn write(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: &impl serde::Serialize,
+) -> Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+
+    Ok(())
+}
@@ -0,0 +1,311 @@
+//! Publishes the devices managed by the daemon on D-Bus.
+//!
+//! The service is available as `io.github.lus.Logy1`. Every device is
+//! exported as an object below `/io/github/lus/Logy1/devices` implementing
+//! the `io.github.lus.Logy1.Device` interface, and the root object implements
+//! `org.freedesktop.DBus.ObjectManager` so clients can discover devices and
+//! follow them appearing.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use anyhow::Result;
+use hidpp::device::Device;
+use zbus::{
+    Connection,
+    connection,
+    fdo::{self, ObjectManager},
+    interface,
+    object_server::SignalEmitter,
+    zvariant::OwnedObjectPath,
+};
+
+#[cfg(feature = "upower")]
+use super::upower::UpowerBridge;
+use crate::{
+    battery::BatteryState,
+    config::{DbusBus, DbusConfig, Profile},
+    devices::{DIRECT_DEVICE_INDEX, DeviceSummary, DiscoveredDevice},
+    profile,
+};
+
+/// The well-known bus name of the service.
+const SERVICE_NAME: &str = "io.github.lus.Logy1";
+
+/// The path of the root object of the service.
+const ROOT_PATH: &str = "/io/github/lus/Logy1";
+
+/// Holds the D-Bus connection of the daemon and the objects exported on it.
+pub struct DbusService {
+    conn: Connection,
+    profiles: Arc<BTreeMap<String, Profile>>,
+    paths: HashMap<DeviceSummary, OwnedObjectPath>,
+
+    #[cfg(feature = "upower")]
+    upower: Option<UpowerBridge>,
+}
+
+impl DbusService {
+    /// Connects to the configured bus and acquires the service name.
+    pub async fn connect(config: &DbusConfig, profiles: BTreeMap<String, Profile>) -> Result<Self> {
+        let builder = match config.bus {
+            DbusBus::Session => connection::Builder::session()?,
+            DbusBus::System => connection::Builder::system()?,
+        };
+
+        let conn = builder
+            .name(SERVICE_NAME)?
+            .serve_at(ROOT_PATH, ObjectManager)?
+            .build()
+            .await?;
+
+        #[cfg(not(feature = "upower"))]
+        if config.upower {
+            eprintln!("UPower export is enabled, but logy was built without the upower feature");
+        }
+
+        Ok(Self {
+            conn,
+            profiles: Arc::new(profiles),
+            paths: HashMap::new(),
+            #[cfg(feature = "upower")]
+            upower: config.upower.then(UpowerBridge::new),
+        })
+    }
+
+    /// Exports a device, or updates the handle of an exported device after it
+    /// was rediscovered.
+    pub async fn add_device(&mut self, device: &DiscoveredDevice) -> Result<()> {
+        if let Some(iface) = self.interface(&device.summary).await? {
+            iface.get_mut().await.device = Some(device.device.clone());
+            return Ok(());
+        }
+
+        let path =
+            OwnedObjectPath::try_from(format!("{}/devices/{}", ROOT_PATH, self.paths.len()))?;
+        self.conn
+            .object_server()
+            .at(&path, DeviceObject {
+                device: Some(device.device.clone()),
+                summary: device.summary.clone(),
+                profiles: Arc::clone(&self.profiles),
+                online: true,
+                battery: None,
+            })
+            .await?;
+        self.paths.insert(device.summary.clone(), path);
+
+        #[cfg(feature = "upower")]
+        if let Some(upower) = &mut self.upower {
+            upower.add_device(&self.conn, device).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the profiles that can be applied to exported devices.
+    pub async fn set_profiles(&mut self, profiles: BTreeMap<String, Profile>) -> Result<()> {
+        self.profiles = Arc::new(profiles);
+
+        for device in self.paths.keys() {
+            let Some(iface) = self.interface(device).await? else {
+                continue;
+            };
+
+            iface.get_mut().await.profiles = Arc::clone(&self.profiles);
+            iface
+                .get()
+                .await
+                .profiles_changed(&self.emitter(device)?)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Updates the battery state of an exported device.
+    pub async fn set_battery(&self, device: &DeviceSummary, state: BatteryState) -> Result<()> {
+        let Some(iface) = self.interface(device).await? else {
+            return Ok(());
+        };
+
+        iface.get_mut().await.battery = Some(state);
+
+        let iface = iface.get().await;
+        let emitter = self.emitter(device)?;
+        iface.battery_percentage_changed(&emitter).await?;
+        iface.battery_level_changed(&emitter).await?;
+        iface.charging_changed(&emitter).await?;
+
+        #[cfg(feature = "upower")]
+        if let Some(upower) = &self.upower {
+            upower.set_battery(&self.conn, device, state).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Updates whether an exported device is connected.
+    pub async fn set_online(&self, device: &DeviceSummary, online: bool) -> Result<()> {
+        let Some(iface) = self.interface(device).await? else {
+            return Ok(());
+        };
+
+        iface.get_mut().await.online = online;
+        iface
+            .get()
+            .await
+            .online_changed(&self.emitter(device)?)
+            .await?;
+
+        #[cfg(feature = "upower")]
+        if let Some(upower) = &self.upower {
+            upower.set_online(&self.conn, device, online).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Drops the handles of all exported devices, so their channels can be
+    /// closed. The objects stay exported until the devices are added again.
+    pub async fn release_devices(&self) -> Result<()> {
+        for path in self.paths.values() {
+            let iface = self
+                .conn
+                .object_server()
+                .interface::<_, DeviceObject>(path)
+                .await?;
+            iface.get_mut().await.device = None;
+        }
+
+        Ok(())
+    }
+
+    /// Emits the `Event` signal of an exported device.
+    pub async fn emit_event(&self, device: &DeviceSummary, kind: &str, data: &str) -> Result<()> {
+        if !self.paths.contains_key(device) {
+            return Ok(());
+        }
+
+        DeviceObject::event(&self.emitter(device)?, kind, data).await?;
+
+        Ok(())
+    }
+
+    async fn interface(
+        &self,
+        device: &DeviceSummary,
+    ) -> Result<Option<zbus::object_server::InterfaceRef<DeviceObject>>> {
+        let Some(path) = self.paths.get(device) else {
+            return Ok(None);
+        };
+
+        Ok(Some(self.conn.object_server().interface(path).await?))
+    }
+
+    fn emitter(&self, device: &DeviceSummary) -> Result<SignalEmitter<'static>> {
+        Ok(SignalEmitter::new(&self.conn, self.paths[device].clone())?)
+    }
+}
+
+/// Represents a device exported on D-Bus.
+struct DeviceObject {
+    /// The handle of the device, which is `None` while the daemon has
+    /// released it.
+    device: Option<Device>,
+    summary: DeviceSummary,
+    profiles: Arc<BTreeMap<String, Profile>>,
+    online: bool,
+    battery: Option<BatteryState>,
+}
+
+#[interface(name = "io.github.lus.Logy1.Device")]
+impl DeviceObject {
+    /// Applies a profile from the daemon configuration to the device.
+    async fn apply_profile(&self, profile: String) -> fdo::Result<()> {
+        let Some(settings) = self.profiles.get(&profile) else {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "unknown profile \"{}\"",
+                profile
+            )));
+        };
+
+        let Some(device) = &self.device else {
+            return Err(fdo::Error::Failed(
+                "the device is not available".to_string(),
+            ));
+        };
+
+        profile::apply(device, settings)
+            .await
+            .map_err(|err| fdo::Error::Failed(err.to_string()))
+    }
+
+    #[zbus(property)]
+    fn name(&self) -> String {
+        self.summary.name.clone()
+    }
+
+    #[zbus(property)]
+    fn vendor_id(&self) -> u16 {
+        self.summary.vendor_id
+    }
+
+    #[zbus(property)]
+    fn product_id(&self) -> u16 {
+        self.summary.product_id
+    }
+
+    /// The slot of the device on its receiver, or `255` for devices connected
+    /// directly to the host.
+    #[zbus(property)]
+    fn slot(&self) -> u8 {
+        self.summary.slot.unwrap_or(DIRECT_DEVICE_INDEX)
+    }
+
+    /// The wireless product ID of the device, or `0` if unknown.
+    #[zbus(property)]
+    fn wpid(&self) -> u16 {
+        self.summary.wpid.unwrap_or(0)
+    }
+
+    #[zbus(property)]
+    fn online(&self) -> bool {
+        self.online
+    }
+
+    /// The names of the profiles that can be passed to `ApplyProfile`.
+    #[zbus(property)]
+    fn profiles(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    /// The battery percentage, or `-1` if unknown.
+    #[zbus(property)]
+    fn battery_percentage(&self) -> i16 {
+        self.battery
+            .and_then(|state| state.percentage)
+            .map_or(-1, i16::from)
+    }
+
+    /// The coarse battery level (`critical`, `low`, `good` or `full`), or an
+    /// empty string if unknown.
+    #[zbus(property)]
+    fn battery_level(&self) -> String {
+        self.battery
+            .and_then(|state| state.level)
+            .map_or_else(String::new, |level| format!("{:?}", level).to_lowercase())
+    }
+
+    #[zbus(property)]
+    fn charging(&self) -> bool {
+        self.battery.is_some_and(|state| state.charging)
+    }
+
+    /// Is emitted for every event of the device, with the event data encoded
+    /// as JSON.
+    #[zbus(signal)]
+    async fn event(emitter: &SignalEmitter<'_>, kind: &str, data: &str) -> zbus::Result<()>;
+}
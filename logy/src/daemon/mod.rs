@@ -0,0 +1,967 @@
+//! Implements the long-running background service started by `logy daemon`.
+
+mod battery;
+mod control;
+mod dbus;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+mod reload;
+mod rules;
+#[cfg(target_os = "linux")]
+mod sleep;
+#[cfg(feature = "upower")]
+mod upower;
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Result;
+use battery::BatteryNotifier;
+use control::{ControlCall, ControlServer, MESSAGE_EVENT};
+use dbus::DbusService;
+use futures::StreamExt;
+use hidpp::{
+    buttons::ButtonState,
+    channel::{HidppChannel, ListenerGuard},
+    feature::{
+        EmittingFeature,
+        reprog_controls::{ControlReportingChange, ReprogControlsFeature},
+        wireless_device_status::{
+            WirelessDeviceStatus,
+            WirelessDeviceStatusEvent,
+            WirelessDeviceStatusFeature,
+        },
+    },
+    receiver::{
+        Receiver,
+        ReceiverDevice,
+        bolt::{BoltDeviceConnection, BoltEvent},
+    },
+};
+use reload::ConfigWatcher;
+use rules::{RuleEngine, RuleEvent};
+use serde_json::{Value, json};
+use sleep::SleepMonitor;
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::{
+    battery::{BatteryState, read_battery_state, spawn_battery_listeners},
+    config::Config,
+    control::{
+        AppliedProfile,
+        ApplyProfileParams,
+        DEVICE_ERROR,
+        DeviceBattery,
+        DeviceFeatures,
+        DeviceStatus,
+        Event,
+        GetBatteryParams,
+        INVALID_PARAMS,
+        ListDevicesParams,
+        ListFeaturesParams,
+        METHOD_NOT_FOUND,
+        RawMessage,
+        Response,
+        RpcError,
+        UnpairParams,
+    },
+    devices::{self, DeviceSelector, DeviceSummary, DiscoveredDevice, DiscoveredReceiver},
+    profile,
+    properties::probe_properties,
+};
+
+/// Represents an event forwarded to the main loop of the daemon.
+enum DaemonEvent {
+    Connection(Arc<DiscoveredReceiver>, BoltDeviceConnection),
+    Reconnection(DeviceSummary),
+    ButtonPressed(DeviceSummary, u16),
+}
+
+/// Holds the state of a running daemon.
+struct Daemon {
+    /// The configuration the daemon currently runs with.
+    config: Config,
+    devices: Vec<DiscoveredDevice>,
+    selector: Option<DeviceSelector>,
+    battery_notifier: BatteryNotifier,
+    rules: RuleEngine,
+    dbus: Option<DbusService>,
+    #[cfg(feature = "mqtt")]
+    mqtt: Option<mqtt::MqttPublisher>,
+    control: Option<ControlServer>,
+
+    /// The devices that are currently connected.
+    online: HashSet<DeviceSummary>,
+
+    /// The last known battery state of every device.
+    batteries: HashMap<DeviceSummary, BatteryState>,
+
+    events: mpsc::UnboundedSender<DaemonEvent>,
+    battery_events: mpsc::UnboundedSender<(DeviceSummary, BatteryState)>,
+
+    /// The tasks forwarding the events of receivers, which keep their
+    /// channels open until they are aborted.
+    receiver_tasks: Vec<JoinHandle<()>>,
+
+    /// The listeners forwarding the raw messages of all channels to the
+    /// control socket.
+    message_listeners: Vec<ListenerGuard>,
+
+    /// All receivers, in the order they were found in.
+    receivers: Vec<Arc<DiscoveredReceiver>>,
+}
+
+/// How long to wait after the system resumed before rediscovering devices,
+/// giving receivers time to reappear.
+const RESUME_DELAY: Duration = Duration::from_secs(2);
+
+/// Runs the daemon until it receives a termination signal.
+///
+/// If a selector is given, only devices matching it are managed. If the path
+/// of the configuration file is given, changes to it are applied while the
+/// daemon is running.
+pub async fn run(
+    config: Config,
+    path: Option<PathBuf>,
+    selector: Option<&DeviceSelector>,
+) -> Result<()> {
+    let mut discovery = devices::discover().await?;
+    discovery.select(selector).await?;
+    eprintln!(
+        "Managing {} receiver(s) and {} device(s)",
+        discovery.receivers.len(),
+        discovery.devices.len()
+    );
+
+    let dbus = if config.daemon.dbus.enabled {
+        Some(DbusService::connect(&config.daemon.dbus, config.profiles.clone()).await?)
+    } else {
+        None
+    };
+
+    #[cfg(not(feature = "mqtt"))]
+    if config.daemon.mqtt.enabled {
+        eprintln!("MQTT publishing is enabled, but logy was built without the mqtt feature");
+    }
+
+    let (control_tx, mut control_rx) = mpsc::unbounded_channel();
+    let control = match ControlServer::bind(control_tx) {
+        Ok(control) => Some(control),
+        Err(err) => {
+            eprintln!("Could not open the control socket: {:#}", err);
+            None
+        },
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let (battery_tx, mut battery_rx) = mpsc::unbounded_channel();
+    let mut daemon = Daemon {
+        devices: Vec::new(),
+        selector: selector.cloned(),
+        battery_notifier: BatteryNotifier::new(config.daemon.battery.clone()),
+        rules: RuleEngine::new(config.daemon.rules.clone(), config.profiles.clone()),
+        dbus,
+        #[cfg(feature = "mqtt")]
+        mqtt: config
+            .daemon
+            .mqtt
+            .enabled
+            .then(|| mqtt::MqttPublisher::connect(config.daemon.mqtt.clone())),
+        config,
+        control,
+        online: HashSet::new(),
+        batteries: HashMap::new(),
+        events: tx,
+        battery_events: battery_tx,
+        receiver_tasks: Vec::new(),
+        message_listeners: Vec::new(),
+        receivers: Vec::new(),
+    };
+
+    #[cfg(target_os = "linux")]
+    let mut sleep = match SleepMonitor::connect().await {
+        Ok(monitor) => Some(monitor),
+        Err(err) => {
+            eprintln!("Could not follow system suspend through logind: {}", err);
+            None
+        },
+    };
+    #[cfg(not(target_os = "linux"))]
+    let mut sleep: Option<SleepMonitor> = None;
+
+    let mut watcher = path.map(ConfigWatcher::new);
+
+    for receiver in discovery.receivers {
+        daemon.watch_receiver(Arc::new(receiver));
+    }
+    for device in discovery.devices {
+        daemon.add_device(device).await;
+    }
+
+    loop {
+        tokio::select! {
+            _ = shutdown_signal() => break,
+            Some(event) = rx.recv() => daemon.handle(event).await,
+            start = next_sleep(&mut sleep) => match (start, &mut sleep) {
+                (Some(true), Some(monitor)) => {
+                    daemon.suspend().await;
+                    monitor.release();
+                },
+                (Some(false), Some(monitor)) => {
+                    if let Err(err) = monitor.inhibit().await {
+                        eprintln!("Could not inhibit system suspend: {}", err);
+                    }
+                    tokio::time::sleep(RESUME_DELAY).await;
+                    daemon.resume().await;
+                },
+                _ => sleep = None,
+            },
+            config = next_config(&mut watcher) => daemon.reload(config).await,
+            Some(call) = control_rx.recv() => daemon.control(call).await,
+            Some((summary, state)) = battery_rx.recv() => {
+                if let Some(device) = daemon.find_device(&summary) {
+                    daemon.battery(&device, state).await;
+                }
+            },
+        }
+    }
+
+    Ok(())
+}
+
+impl Daemon {
+    /// Forwards the raw messages of a channel to the clients of the control
+    /// socket that asked for them.
+    fn forward_messages(&mut self, channel: &Arc<HidppChannel>) {
+        let Some(control) = &self.control else {
+            return;
+        };
+
+        let events = control.sender();
+        let (vendor_id, product_id) = (channel.vendor_id, channel.product_id);
+        self.message_listeners
+            .push(channel.subscribe(move |msg, matched| {
+                if events.receiver_count() == 0 {
+                    return;
+                }
+
+                let msg = RawMessage::new(vendor_id, product_id, msg, matched);
+                let _ = events.send(Event::now(
+                    MESSAGE_EVENT,
+                    None,
+                    serde_json::to_value(msg).unwrap(),
+                ));
+            }));
+    }
+
+    /// Forwards the connection events of a receiver to the main loop.
+    fn watch_receiver(&mut self, receiver: Arc<DiscoveredReceiver>) {
+        self.forward_messages(&receiver.channel);
+        self.receivers.push(Arc::clone(&receiver));
+
+        let Receiver::Bolt(bolt) = &receiver.receiver else {
+            return;
+        };
+
+        let rx = bolt.listen();
+        let tx = self.events.clone();
+        self.receiver_tasks.push(tokio::spawn(async move {
+            while let Ok(event) = rx.recv().await {
+                let BoltEvent::DeviceConnection(connection) = event else {
+                    continue;
+                };
+
+                if tx
+                    .send(DaemonEvent::Connection(Arc::clone(&receiver), connection))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }));
+    }
+
+    /// Applies a changed configuration.
+    ///
+    /// Rules, profiles and battery notifications take effect immediately, and
+    /// changed profiles are re-applied to the devices they are applied to on
+    /// connection. The D-Bus and MQTT connections are kept as they are.
+    async fn reload(&mut self, config: Config) {
+        eprintln!("Reloading the configuration");
+
+        if config.daemon.dbus != self.config.daemon.dbus
+            || config.daemon.mqtt != self.config.daemon.mqtt
+        {
+            eprintln!("Changes to the D-Bus and MQTT settings take effect after a restart");
+        }
+
+        self.battery_notifier
+            .set_config(config.daemon.battery.clone());
+
+        let previous_controls = self.rules.diverted_controls();
+
+        if let Some(dbus) = &mut self.dbus {
+            report_dbus_error(dbus.set_profiles(config.profiles.clone()).await);
+        }
+
+        self.rules
+            .reload(
+                config.daemon.rules.clone(),
+                config.profiles.clone(),
+                &self.devices,
+            )
+            .await;
+
+        let controls = self.rules.diverted_controls();
+        for device in &self.devices {
+            if !self.online.contains(&device.summary) {
+                continue;
+            }
+
+            set_diverted(device, controls.difference(&previous_controls), true).await;
+            set_diverted(device, previous_controls.difference(&controls), false).await;
+        }
+
+        self.config = config;
+    }
+
+    /// Releases all receivers and devices before the system goes to sleep.
+    ///
+    /// Devices are marked offline, but no disconnection events are emitted
+    /// as they did not actually disconnect.
+    async fn suspend(&mut self) {
+        eprintln!("Releasing all devices before suspend");
+
+        for task in self.receiver_tasks.drain(..) {
+            task.abort();
+        }
+        self.message_listeners.clear();
+        self.receivers.clear();
+
+        for device in &self.devices {
+            if let Some(dbus) = &self.dbus {
+                report_dbus_error(dbus.set_online(&device.summary, false).await);
+            }
+
+            #[cfg(feature = "mqtt")]
+            if let Some(mqtt) = &self.mqtt {
+                report_mqtt_error(mqtt.set_online(&device.summary, false).await);
+            }
+        }
+
+        if let Some(dbus) = &self.dbus {
+            report_dbus_error(dbus.release_devices().await);
+        }
+
+        // Dropping the last handles of the devices closes their channels, which
+        // also ends all listeners spawned for them.
+        self.devices.clear();
+        self.online.clear();
+    }
+
+    /// Rediscovers all receivers and devices after the system resumed,
+    /// applying rules to them as if they just connected.
+    async fn resume(&mut self) {
+        let mut discovery = match devices::discover().await {
+            Ok(discovery) => discovery,
+            Err(err) => {
+                eprintln!("Could not rediscover devices after resume: {}", err);
+                return;
+            },
+        };
+        if let Err(err) = discovery.select(self.selector.as_ref()).await {
+            eprintln!("Could not match devices after resume: {}", err);
+            return;
+        }
+        eprintln!(
+            "Managing {} receiver(s) and {} device(s) after resume",
+            discovery.receivers.len(),
+            discovery.devices.len()
+        );
+
+        for receiver in discovery.receivers {
+            self.watch_receiver(Arc::new(receiver));
+        }
+        for device in discovery.devices {
+            self.add_device(device).await;
+        }
+    }
+
+    /// Starts managing a device that was found at startup or just connected.
+    async fn add_device(&mut self, device: DiscoveredDevice) {
+        spawn_battery_listeners(&device, self.battery_events.clone());
+
+        // The messages of paired devices are forwarded with the ones of their
+        // receiver.
+        if device.summary.slot.is_none() {
+            self.forward_messages(&device.channel);
+        }
+
+        // Devices paired to a receiver are tracked through the connection
+        // events of the receiver instead.
+        if device.summary.slot.is_none()
+            && let Some(feature) = device.device.get_feature::<WirelessDeviceStatusFeature>()
+        {
+            let rx = feature.listen();
+            let summary = device.summary.clone();
+            let tx = self.events.clone();
+            tokio::spawn(async move {
+                while let Ok(WirelessDeviceStatusEvent::StatusBroadcast(broadcast)) =
+                    rx.recv().await
+                {
+                    if broadcast.status == WirelessDeviceStatus::Reconnection
+                        && tx.send(DaemonEvent::Reconnection(summary.clone())).is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+
+        if let Some(feature) = device.device.get_feature::<ReprogControlsFeature>() {
+            let mut buttons = Box::pin(feature.listen_buttons());
+            let summary = device.summary.clone();
+            let tx = self.events.clone();
+            tokio::spawn(async move {
+                while let Some(event) = buttons.next().await {
+                    if event.state == ButtonState::Pressed
+                        && tx
+                            .send(DaemonEvent::ButtonPressed(summary.clone(), event.control))
+                            .is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+
+        if let Some(dbus) = &mut self.dbus
+            && let Err(err) = dbus.add_device(&device).await
+        {
+            eprintln!(
+                "Could not publish {} on D-Bus: {}",
+                device.summary.name, err
+            );
+        }
+
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &self.mqtt {
+            report_mqtt_error(mqtt.add_device(&device.summary).await);
+        }
+
+        self.devices.push(device);
+        let device = self.devices.last().unwrap().clone();
+        self.connected(&device).await;
+    }
+
+    /// Starts managing a device that connected for the first time since the
+    /// daemon was started, if it matches the selector.
+    async fn add_new_device(&mut self, device: DiscoveredDevice) {
+        if let Some(selector) = &self.selector {
+            match device.matches(selector).await {
+                Ok(true) => (),
+                Ok(false) => return,
+                Err(err) => {
+                    eprintln!("Could not match device {}: {}", device.summary.name, err);
+                    return;
+                },
+            }
+        }
+
+        self.add_device(device).await;
+    }
+
+    /// Handles a device that was found or reconnected.
+    async fn connected(&mut self, device: &DiscoveredDevice) {
+        self.online.insert(device.summary.clone());
+        self.emit("device_connected", &device.summary, json!({}));
+
+        if let Some(dbus) = &self.dbus {
+            report_dbus_error(dbus.set_online(&device.summary, true).await);
+            report_dbus_error(
+                dbus.emit_event(&device.summary, "device_connected", "{}")
+                    .await,
+            );
+        }
+
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &self.mqtt {
+            report_mqtt_error(mqtt.set_online(&device.summary, true).await);
+            report_mqtt_error(
+                mqtt.publish_event(&device.summary, "device_connected", serde_json::json!({}))
+                    .await,
+            );
+        }
+
+        // Diverting controls does not survive reconnections.
+        set_diverted(device, &self.rules.diverted_controls(), true).await;
+
+        self.rules.handle(device, RuleEvent::DeviceConnected).await;
+
+        match read_battery_state(device).await {
+            Ok(Some(state)) => self.battery(device, state).await,
+            Ok(None) => (),
+            Err(err) => eprintln!(
+                "Could not read battery state of {}: {}",
+                device.summary.name, err
+            ),
+        }
+    }
+
+    /// Handles a new battery state of a device.
+    async fn battery(&mut self, device: &DiscoveredDevice, state: BatteryState) {
+        self.battery_notifier.update(&device.summary, &state);
+        self.batteries.insert(device.summary.clone(), state);
+        self.emit(
+            "battery",
+            &device.summary,
+            serde_json::to_value(state).unwrap(),
+        );
+
+        if let Some(dbus) = &self.dbus {
+            report_dbus_error(dbus.set_battery(&device.summary, state).await);
+            report_dbus_error(
+                dbus.emit_event(
+                    &device.summary,
+                    "battery",
+                    &serde_json::to_string(&state).unwrap(),
+                )
+                .await,
+            );
+        }
+
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &self.mqtt {
+            report_mqtt_error(mqtt.set_battery(&device.summary, state).await);
+            report_mqtt_error(
+                mqtt.publish_event(
+                    &device.summary,
+                    "battery",
+                    serde_json::to_value(state).unwrap(),
+                )
+                .await,
+            );
+        }
+
+        self.rules.handle(device, RuleEvent::Battery(state)).await;
+    }
+
+    /// Handles a device that lost its connection.
+    async fn disconnected(&mut self, device: &DiscoveredDevice) {
+        self.online.remove(&device.summary);
+        self.emit("device_disconnected", &device.summary, json!({}));
+
+        if let Some(dbus) = &self.dbus {
+            report_dbus_error(dbus.set_online(&device.summary, false).await);
+            report_dbus_error(
+                dbus.emit_event(&device.summary, "device_disconnected", "{}")
+                    .await,
+            );
+        }
+
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &self.mqtt {
+            report_mqtt_error(mqtt.set_online(&device.summary, false).await);
+            report_mqtt_error(
+                mqtt.publish_event(
+                    &device.summary,
+                    "device_disconnected",
+                    serde_json::json!({}),
+                )
+                .await,
+            );
+        }
+
+        self.rules
+            .handle(device, RuleEvent::DeviceDisconnected)
+            .await;
+    }
+
+    /// Sends an event to the clients subscribed on the control socket.
+    fn emit(&self, kind: &str, device: &DeviceSummary, data: Value) {
+        if let Some(control) = &self.control {
+            control.emit(Event::now(kind, Some(device.clone()), data));
+        }
+    }
+
+    /// Handles a request received on the control socket.
+    async fn control(&mut self, call: ControlCall) {
+        let request = call.request;
+        let result = match request.method.as_str() {
+            "list_devices" => match request.params() {
+                Ok(params) => self.list_devices(params).await,
+                Err(err) => Err(err),
+            },
+            "list_features" => match request.params() {
+                Ok(params) => self.list_features(params).await,
+                Err(err) => Err(err),
+            },
+            "list_pairings" => self.list_pairings().await,
+            "unpair" => match request.params() {
+                Ok(params) => self.unpair(params).await,
+                Err(err) => Err(err),
+            },
+            "get_battery" => match request.params() {
+                Ok(params) => self.get_battery(params).await,
+                Err(err) => Err(err),
+            },
+            "apply_profile" => match request.params() {
+                Ok(params) => self.apply_profile(params).await,
+                Err(err) => Err(err),
+            },
+            method => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("unknown method \"{}\"", method),
+            )),
+        };
+
+        let _ = call.reply.send(Response::new(request.id, result));
+    }
+
+    async fn list_devices(&self, params: ListDevicesParams) -> Result<Value, RpcError> {
+        let mut devices = Vec::new();
+        for device in self.select(params.device.as_deref()).await? {
+            let online = self.online.contains(&device.summary);
+
+            let properties = if online && !params.fields.is_empty() {
+                let properties = probe_properties(device.device.clone(), &params.fields)
+                    .await
+                    .map_err(|err| {
+                        RpcError::new(
+                            DEVICE_ERROR,
+                            format!(
+                                "could not retrieve the properties of {}: {}",
+                                device.summary.name, err
+                            ),
+                        )
+                    })?;
+                Some(properties)
+            } else {
+                None
+            };
+
+            devices.push(DeviceStatus {
+                battery: self.batteries.get(&device.summary).copied(),
+                device: device.summary,
+                online,
+                properties,
+            });
+        }
+
+        Ok(serde_json::to_value(devices).unwrap())
+    }
+
+    async fn list_features(&self, params: ListFeaturesParams) -> Result<Value, RpcError> {
+        let features = self
+            .select(params.device.as_deref())
+            .await?
+            .iter()
+            .map(DeviceFeatures::new)
+            .collect::<Vec<_>>();
+
+        Ok(serde_json::to_value(features).unwrap())
+    }
+
+    async fn list_pairings(&self) -> Result<Value, RpcError> {
+        let pairings = devices::collect_pairings(self.receivers.iter().map(AsRef::as_ref))
+            .await
+            .map_err(|err| {
+                RpcError::new(
+                    DEVICE_ERROR,
+                    format!("could not list the paired devices: {}", err),
+                )
+            })?;
+
+        Ok(serde_json::to_value(pairings).unwrap())
+    }
+
+    async fn unpair(&self, params: UnpairParams) -> Result<Value, RpcError> {
+        let Some(receiver) = self.receivers.get(params.receiver_index) else {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("there is no receiver {}", params.receiver_index),
+            ));
+        };
+
+        receiver.receiver.unpair(params.slot).await.map_err(|err| {
+            RpcError::new(
+                DEVICE_ERROR,
+                format!(
+                    "could not unpair slot {} of {}: {}",
+                    params.slot,
+                    receiver.receiver.name(),
+                    err
+                ),
+            )
+        })?;
+
+        Ok(Value::Bool(true))
+    }
+
+    async fn get_battery(&self, params: GetBatteryParams) -> Result<Value, RpcError> {
+        let batteries = self
+            .select(params.device.as_deref())
+            .await?
+            .into_iter()
+            .filter_map(|device| {
+                Some(DeviceBattery {
+                    battery: *self.batteries.get(&device.summary)?,
+                    device: device.summary,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        Ok(serde_json::to_value(batteries).unwrap())
+    }
+
+    async fn apply_profile(&self, params: ApplyProfileParams) -> Result<Value, RpcError> {
+        let Some(settings) = self.config.profiles.get(&params.profile) else {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("unknown profile \"{}\"", params.profile),
+            ));
+        };
+
+        let mut applied = Vec::new();
+        for device in self.select(params.device.as_deref()).await? {
+            if !self.online.contains(&device.summary) {
+                continue;
+            }
+
+            let failed = |err: anyhow::Error| {
+                RpcError::new(
+                    DEVICE_ERROR,
+                    format!(
+                        "could not apply the profile to {}: {}",
+                        device.summary.name, err
+                    ),
+                )
+            };
+
+            let operations = profile::plan(&device.device, settings)
+                .await
+                .map_err(failed)?;
+            if !params.dry_run {
+                for operation in &operations {
+                    operation.execute(&device.device).await.map_err(failed)?;
+                }
+            }
+
+            applied.push(AppliedProfile {
+                device: device.summary.clone(),
+                operations: operations.into_iter().map(|x| x.info).collect(),
+            });
+        }
+
+        if applied.is_empty() {
+            return Err(RpcError::new(DEVICE_ERROR, "no matching device is online"));
+        }
+
+        Ok(serde_json::to_value(applied).unwrap())
+    }
+
+    /// Returns the managed devices matching a device selector, or all of
+    /// them if none is given.
+    async fn select(&self, selector: Option<&str>) -> Result<Vec<DiscoveredDevice>, RpcError> {
+        let Some(selector) = selector else {
+            return Ok(self.devices.clone());
+        };
+        let selector = selector
+            .parse::<DeviceSelector>()
+            .map_err(|err| RpcError::new(INVALID_PARAMS, err))?;
+
+        let mut selected = Vec::new();
+        for device in &self.devices {
+            if device
+                .matches(&selector)
+                .await
+                .map_err(|err| RpcError::new(DEVICE_ERROR, err))?
+            {
+                selected.push(device.clone());
+            }
+        }
+
+        Ok(selected)
+    }
+
+    fn find_device(&self, summary: &DeviceSummary) -> Option<DiscoveredDevice> {
+        self.devices.iter().find(|x| &x.summary == summary).cloned()
+    }
+
+    async fn handle(&mut self, event: DaemonEvent) {
+        match event {
+            DaemonEvent::Reconnection(summary) => {
+                if let Some(device) = self.find_device(&summary) {
+                    self.connected(&device).await;
+                }
+            },
+            DaemonEvent::ButtonPressed(summary, control) => {
+                if let Some(device) = self.find_device(&summary) {
+                    self.emit("button_pressed", &summary, json!({ "control": control }));
+                    self.rules
+                        .handle(&device, RuleEvent::ButtonPressed(control))
+                        .await;
+                }
+            },
+            DaemonEvent::Connection(receiver, connection) => {
+                let known = self
+                    .devices
+                    .iter()
+                    .find(|x| {
+                        x.summary.vendor_id == receiver.channel.vendor_id
+                            && x.summary.product_id == receiver.channel.product_id
+                            && x.summary.slot == Some(connection.index)
+                    })
+                    .cloned();
+
+                match (known, connection.online) {
+                    (Some(device), true) => self.connected(&device).await,
+                    (Some(device), false) => self.disconnected(&device).await,
+                    (None, true) => {
+                        match devices::discover_paired_device(
+                            &receiver.channel,
+                            &receiver.receiver,
+                            connection.into(),
+                        )
+                        .await
+                        {
+                            Ok(Some(device)) => self.add_new_device(device).await,
+                            Ok(None) => (),
+                            Err(err) => eprintln!(
+                                "Could not initialize device in slot {}: {}",
+                                connection.index, err
+                            ),
+                        }
+                    },
+                    (None, false) => (),
+                }
+            },
+        }
+    }
+}
+
+/// Diverts controls of a device, or restores their default behavior.
+///
+/// Controls the device does not have or can't divert are skipped.
+async fn set_diverted<'a>(
+    device: &DiscoveredDevice,
+    controls: impl IntoIterator<Item = &'a u16>,
+    diverted: bool,
+) {
+    let controls = controls.into_iter().copied().collect::<BTreeSet<_>>();
+    if controls.is_empty() {
+        return;
+    }
+    let Some(feature) = device.device.get_feature::<ReprogControlsFeature>() else {
+        return;
+    };
+
+    let divertable = match feature.get_controls().await {
+        Ok(infos) => infos
+            .into_iter()
+            .filter(|info| info.divertable)
+            .map(|info| info.cid)
+            .collect::<BTreeSet<_>>(),
+        Err(err) => {
+            eprintln!(
+                "Could not read the controls of {}: {}",
+                device.summary.name, err
+            );
+            return;
+        },
+    };
+
+    for &control in controls.intersection(&divertable) {
+        let change = ControlReportingChange {
+            diverted: Some(diverted),
+            ..Default::default()
+        };
+        if let Err(err) = feature.set_control_reporting(control, change).await {
+            eprintln!(
+                "Could not change the reporting of control {:#06x} of {}: {}",
+                control, device.summary.name, err
+            );
+        }
+    }
+}
+
+/// Stands in for the logind sleep monitor on platforms without logind, where
+/// suspend is never followed.
+#[cfg(not(target_os = "linux"))]
+mod sleep {
+    use anyhow::Result;
+
+    pub enum SleepMonitor {}
+
+    impl SleepMonitor {
+        pub async fn inhibit(&mut self) -> Result<()> {
+            match *self {}
+        }
+
+        pub fn release(&mut self) {
+            match *self {}
+        }
+
+        pub async fn next(&mut self) -> Option<bool> {
+            match *self {}
+        }
+    }
+}
+
+/// Waits for the next sleep notification, if suspend is followed at all.
+async fn next_sleep(monitor: &mut Option<SleepMonitor>) -> Option<bool> {
+    match monitor {
+        Some(monitor) => monitor.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Waits for the next change of the configuration file, if it is watched at
+/// all.
+async fn next_config(watcher: &mut Option<ConfigWatcher>) -> Config {
+    match watcher {
+        Some(watcher) => watcher.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Logs a failed D-Bus operation, which should not stop the daemon.
+fn report_dbus_error(result: Result<()>) {
+    if let Err(err) = result {
+        eprintln!("D-Bus error: {}", err);
+    }
+}
+
+/// Logs a failed MQTT operation, which should not stop the daemon.
+#[cfg(feature = "mqtt")]
+fn report_mqtt_error(result: Result<()>) {
+    if let Err(err) = result {
+        eprintln!("MQTT error: {}", err);
+    }
+}
+
+/// Resolves once the process is asked to terminate, either by Ctrl+C or, on
+/// Unix, by `SIGTERM` as sent by service managers.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let Ok(mut sigterm) = signal(SignalKind::terminate()) else {
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        };
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => (),
+            _ = sigterm.recv() => (),
+        }
+    }
+
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
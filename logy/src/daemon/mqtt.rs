@@ -0,0 +1,218 @@
+//! Publishes device telemetry to an MQTT broker.
+//!
+//! Every device gets a set of topics below `<topic_prefix>/<device ID>`:
+//!
+//! - `availability`: `online` or `offline`
+//! - `battery`: the battery state as JSON
+//! - `event`: the selected events as JSON objects with a `kind` and `data`
+//!
+//! Discovery messages in the format of Home Assistant make the battery of
+//! every device show up as a sensor without further configuration.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::json;
+
+use crate::{
+    battery::BatteryState,
+    config::MqttConfig,
+    devices::{DeviceSummary, hex_string},
+};
+
+/// Holds the connection to the MQTT broker.
+pub struct MqttPublisher {
+    client: AsyncClient,
+    config: MqttConfig,
+}
+
+impl MqttPublisher {
+    /// Connects to the configured broker.
+    ///
+    /// The connection is kept alive and re-established in the background.
+    pub fn connect(config: MqttConfig) -> Self {
+        let status_topic = format!("{}/status", config.topic_prefix);
+
+        let mut options = MqttOptions::new(&config.client_id, &config.host, config.port);
+        options.set_keep_alive(Duration::from_secs(30));
+        options.set_last_will(LastWill::new(
+            &status_topic,
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.as_deref().unwrap_or_default());
+        }
+
+        let (client, mut eventloop) = AsyncClient::new(options, 64);
+
+        let status_client = client.clone();
+        tokio::spawn(async move {
+            loop {
+                match eventloop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        // The last will marks the daemon as offline whenever
+                        // the connection drops, so every new connection has to
+                        // mark it as online again.
+                        let _ = status_client.try_publish(
+                            &status_topic,
+                            QoS::AtLeastOnce,
+                            true,
+                            "online",
+                        );
+                    },
+                    Ok(_) => (),
+                    Err(err) => {
+                        eprintln!("MQTT error: {}", err);
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    },
+                }
+            }
+        });
+
+        Self {
+            client,
+            config,
+        }
+    }
+
+    /// Publishes the discovery messages of a device.
+    pub async fn add_device(&self, device: &DeviceSummary) -> Result<()> {
+        let Some(discovery_prefix) = &self.config.discovery_prefix else {
+            return Ok(());
+        };
+
+        let id = device_id(device);
+        let battery_topic = self.topic(device, "battery");
+        let device_info = json!({
+            "identifiers": [format!("logy_{}", id)],
+            "name": device.name,
+            "manufacturer": "Logitech",
+        });
+        let availability = json!([
+            { "topic": format!("{}/status", self.config.topic_prefix) },
+            { "topic": self.topic(device, "availability") },
+        ]);
+
+        self.publish_retained(
+            &format!("{}/sensor/logy_{}/battery/config", discovery_prefix, id),
+            json!({
+                "name": "Battery",
+                "unique_id": format!("logy_{}_battery", id),
+                "state_topic": battery_topic,
+                "value_template": "{{ value_json.percentage }}",
+                "device_class": "battery",
+                "unit_of_measurement": "%",
+                "availability": availability,
+                "availability_mode": "all",
+                "device": device_info,
+            })
+            .to_string(),
+        )
+        .await?;
+
+        self.publish_retained(
+            &format!(
+                "{}/binary_sensor/logy_{}/charging/config",
+                discovery_prefix, id
+            ),
+            json!({
+                "name": "Charging",
+                "unique_id": format!("logy_{}_charging", id),
+                "state_topic": battery_topic,
+                "value_template": "{{ 'ON' if value_json.charging else 'OFF' }}",
+                "device_class": "battery_charging",
+                "availability": availability,
+                "availability_mode": "all",
+                "device": device_info,
+            })
+            .to_string(),
+        )
+        .await
+    }
+
+    /// Publishes the battery state of a device.
+    pub async fn set_battery(&self, device: &DeviceSummary, state: BatteryState) -> Result<()> {
+        self.publish_retained(
+            &self.topic(device, "battery"),
+            serde_json::to_string(&state)?,
+        )
+        .await
+    }
+
+    /// Publishes whether a device is connected.
+    pub async fn set_online(&self, device: &DeviceSummary, online: bool) -> Result<()> {
+        self.publish_retained(
+            &self.topic(device, "availability"),
+            if online {
+                "online"
+            } else {
+                "offline"
+            }
+            .to_string(),
+        )
+        .await
+    }
+
+    /// Publishes an event of a device, if its kind is selected.
+    pub async fn publish_event(
+        &self,
+        device: &DeviceSummary,
+        kind: &str,
+        data: serde_json::Value,
+    ) -> Result<()> {
+        if !self.config.events.iter().any(|x| x == kind) {
+            return Ok(());
+        }
+
+        self.client
+            .publish(
+                self.topic(device, "event"),
+                QoS::AtLeastOnce,
+                false,
+                json!({ "kind": kind, "data": data }).to_string(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn publish_retained(&self, topic: &str, payload: String) -> Result<()> {
+        self.client
+            .publish(topic, QoS::AtLeastOnce, true, payload)
+            .await?;
+
+        Ok(())
+    }
+
+    fn topic(&self, device: &DeviceSummary, name: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.config.topic_prefix,
+            device_id(device),
+            name
+        )
+    }
+}
+
+/// Derives a topic-safe ID for a device from the IDs of its receiver and its
+/// wireless product ID.
+fn device_id(device: &DeviceSummary) -> String {
+    let mut id = hex_string(
+        &[
+            device.vendor_id.to_be_bytes(),
+            device.product_id.to_be_bytes(),
+        ]
+        .concat(),
+    );
+    if let Some(slot) = device.slot {
+        id.push_str(&format!("_{}", slot));
+    }
+    if let Some(wpid) = device.wpid {
+        id.push_str(&format!("_{}", hex_string(&wpid.to_be_bytes())));
+    }
+
+    id
+}
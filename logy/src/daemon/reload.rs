@@ -0,0 +1,98 @@
+//! Watches the configuration file of the daemon for changes.
+//!
+//! The file is polled instead of relying on file system notifications, as
+//! editors replace files in too many different ways to follow them reliably.
+//! A changed file is only picked up once it parses and validates, so a file
+//! that is saved half-way through editing never replaces a working
+//! configuration.
+
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use tokio::time::{Interval, MissedTickBehavior};
+
+use crate::config::Config;
+
+/// How often the configuration file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Polls the configuration file and yields every valid new version of it.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    interval: Interval,
+    modified: Option<SystemTime>,
+    content: Option<String>,
+}
+
+impl ConfigWatcher {
+    /// Starts watching a configuration file, treating its current contents
+    /// as already loaded.
+    pub fn new(path: PathBuf) -> Self {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let modified = modified(&path);
+        let content = fs::read_to_string(&path).ok();
+
+        Self {
+            path,
+            interval,
+            modified,
+            content,
+        }
+    }
+
+    /// Waits until the contents of the file changed to a valid configuration
+    /// and returns it.
+    ///
+    /// Invalid contents are reported once and otherwise ignored. A file that
+    /// is removed is ignored as well, as editors commonly remove a file
+    /// before writing its new version.
+    pub async fn next(&mut self) -> Config {
+        loop {
+            self.interval.tick().await;
+
+            let modified = modified(&self.path);
+            if modified.is_some() && modified == self.modified {
+                continue;
+            }
+            self.modified = modified;
+
+            let content = match fs::read_to_string(&self.path) {
+                Ok(content) => content,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => {
+                    eprintln!(
+                        "Could not read config file {}: {}",
+                        self.path.display(),
+                        err
+                    );
+                    continue;
+                },
+            };
+            if self.content.as_ref() == Some(&content) {
+                continue;
+            }
+
+            let config = Config::parse(&content);
+            self.content = Some(content);
+
+            match config {
+                Ok(config) => return config,
+                Err(err) => eprintln!(
+                    "Not reloading config file {}: {:#}",
+                    self.path.display(),
+                    err
+                ),
+            }
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
@@ -0,0 +1,268 @@
+//! Evaluates the rules configured for the daemon and runs their actions.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use notify_rust::Notification;
+
+use crate::{
+    battery::BatteryState,
+    config::{Action, Profile, Rule, Trigger},
+    devices::{DeviceSummary, DiscoveredDevice},
+    profile,
+};
+
+/// Represents an event observed by the daemon that rules can react to.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RuleEvent {
+    DeviceConnected,
+    DeviceDisconnected,
+    Battery(BatteryState),
+    ButtonPressed(u16),
+}
+
+impl RuleEvent {
+    /// Returns the name of the event as passed to commands.
+    fn name(&self) -> &'static str {
+        match self {
+            Self::DeviceConnected => "device_connected",
+            Self::DeviceDisconnected => "device_disconnected",
+            Self::Battery(_) => "battery",
+            Self::ButtonPressed(_) => "button_pressed",
+        }
+    }
+}
+
+/// Matches events against the configured rules and runs the actions of all
+/// matching rules.
+pub struct RuleEngine {
+    rules: Vec<Rule>,
+    profiles: BTreeMap<String, Profile>,
+    host: Option<String>,
+    last_percentages: HashMap<DeviceSummary, u8>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: Vec<Rule>, profiles: BTreeMap<String, Profile>) -> Self {
+        Self {
+            rules,
+            profiles,
+            host: whoami::fallible::hostname().ok(),
+            last_percentages: HashMap::new(),
+        }
+    }
+
+    /// Replaces the rules and profiles, re-applying profiles to devices whose
+    /// settings on connection changed.
+    ///
+    /// A profile is re-applied to a device if it is applied by a rule when
+    /// the device connects, and either was not applied by the previous rules
+    /// or its settings changed.
+    pub async fn reload(
+        &mut self,
+        rules: Vec<Rule>,
+        profiles: BTreeMap<String, Profile>,
+        devices: &[DiscoveredDevice],
+    ) {
+        let previous = devices
+            .iter()
+            .map(|device| self.connection_profiles(&device.summary))
+            .collect::<Vec<_>>();
+
+        self.rules = rules;
+        self.profiles = profiles;
+
+        for (device, previous) in devices.iter().zip(previous) {
+            for (name, settings) in self.connection_profiles(&device.summary) {
+                if previous.iter().any(|x| x.0 == name && x.1 == settings) {
+                    continue;
+                }
+
+                eprintln!(
+                    "Applying changed profile {} to {}",
+                    name, device.summary.name
+                );
+                if let Err(err) = profile::apply(&device.device, &settings).await {
+                    eprintln!(
+                        "Could not apply profile {} to {}: {}",
+                        name, device.summary.name, err
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns the controls that rules react to and therefore have to be
+    /// diverted.
+    pub fn diverted_controls(&self) -> BTreeSet<u16> {
+        self.rules
+            .iter()
+            .filter_map(|rule| match rule.on {
+                Trigger::ButtonPressed {
+                    control,
+                } => Some(control),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the profiles the rules apply to a device when it connects, in
+    /// order.
+    fn connection_profiles(&self, device: &DeviceSummary) -> Vec<(String, Profile)> {
+        self.rules
+            .iter()
+            .filter(|rule| self.matches(rule, device, RuleEvent::DeviceConnected, None))
+            .flat_map(|rule| &rule.actions)
+            .filter_map(|action| match action {
+                Action::ApplyProfile {
+                    profile,
+                } => self
+                    .profiles
+                    .get_key_value(profile)
+                    .map(|(name, settings)| (name.clone(), settings.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Runs the actions of all rules matching an event of a device.
+    pub async fn handle(&mut self, device: &DiscoveredDevice, event: RuleEvent) {
+        let previous_percentage = match event {
+            RuleEvent::Battery(BatteryState {
+                percentage: Some(percentage),
+                ..
+            }) => self
+                .last_percentages
+                .insert(device.summary.clone(), percentage),
+            _ => None,
+        };
+
+        for rule in &self.rules {
+            if !self.matches(rule, &device.summary, event, previous_percentage) {
+                continue;
+            }
+
+            for action in &rule.actions {
+                self.run_action(action, device, event).await;
+            }
+        }
+    }
+
+    fn matches(
+        &self,
+        rule: &Rule,
+        device: &DeviceSummary,
+        event: RuleEvent,
+        previous_percentage: Option<u8>,
+    ) -> bool {
+        if let Some(host) = &rule.host
+            && self.host.as_ref() != Some(host)
+        {
+            return false;
+        }
+
+        if let Some(filter) = &rule.device
+            && !device.name.to_lowercase().contains(&filter.to_lowercase())
+        {
+            return false;
+        }
+
+        match (&rule.on, event) {
+            (Trigger::DeviceConnected, RuleEvent::DeviceConnected) => true,
+            (Trigger::DeviceDisconnected, RuleEvent::DeviceDisconnected) => true,
+            (
+                Trigger::BatteryBelow {
+                    percentage: threshold,
+                },
+                RuleEvent::Battery(state),
+            ) => {
+                // Only trigger when the threshold is crossed, not on every
+                // report below it.
+                state
+                    .percentage
+                    .is_some_and(|percentage| percentage <= *threshold)
+                    && !state.charging
+                    && previous_percentage.is_none_or(|previous| previous > *threshold)
+            },
+            (
+                Trigger::ButtonPressed {
+                    control,
+                },
+                RuleEvent::ButtonPressed(pressed),
+            ) => *control == pressed,
+            _ => false,
+        }
+    }
+
+    async fn run_action(&self, action: &Action, device: &DiscoveredDevice, event: RuleEvent) {
+        let expand = |text: &str| text.replace("{device}", &device.summary.name);
+
+        match action {
+            Action::ApplyProfile {
+                profile,
+            } => {
+                let Some(settings) = self.profiles.get(profile) else {
+                    return;
+                };
+
+                if let Err(err) = profile::apply(&device.device, settings).await {
+                    eprintln!(
+                        "Could not apply profile {} to {}: {}",
+                        profile, device.summary.name, err
+                    );
+                }
+            },
+            Action::Command {
+                command,
+            } => {
+                let mut cmd = shell_command(command);
+                cmd.env("LOGY_EVENT", event.name())
+                    .env("LOGY_DEVICE", &device.summary.name);
+
+                match cmd.spawn() {
+                    Ok(mut child) => {
+                        tokio::spawn(async move {
+                            let _ = child.wait().await;
+                        });
+                    },
+                    Err(err) => eprintln!("Could not run command {}: {}", command, err),
+                }
+            },
+            Action::Notify {
+                summary,
+                body,
+            } => {
+                let mut notification = Notification::new();
+                notification.appname("logy").summary(&expand(summary));
+                if let Some(body) = body {
+                    notification.body(&expand(body));
+                }
+
+                tokio::task::spawn_blocking(move || {
+                    if let Err(err) = notification.show() {
+                        eprintln!("Could not show notification: {}", err);
+                    }
+                });
+            },
+        }
+    }
+}
+
+/// Creates a command that runs a command line using the system shell.
+fn shell_command(command: &str) -> tokio::process::Command {
+    #[cfg(windows)]
+    let mut cmd = {
+        let mut cmd = tokio::process::Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    };
+
+    #[cfg(not(windows))]
+    let mut cmd = {
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+
+    cmd.arg(command);
+    cmd
+}
@@ -0,0 +1,85 @@
+//! Follows system suspend and resume using systemd-logind.
+//!
+//! Receivers often come back from suspend with stale handles and devices
+//! reset their settings, so the daemon releases all channels before the
+//! system sleeps and rediscovers everything after it resumes. To get the
+//! chance to do so, it holds a delay inhibitor lock while the system is
+//! awake.
+
+use anyhow::Result;
+use futures_lite::StreamExt;
+use zbus::{Connection, proxy, zvariant::OwnedFd};
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
+
+/// Receives the sleep notifications of logind and manages the inhibitor lock
+/// delaying them.
+pub struct SleepMonitor {
+    proxy: ManagerProxy<'static>,
+    signals: PrepareForSleepStream,
+    inhibitor: Option<OwnedFd>,
+}
+
+impl SleepMonitor {
+    /// Connects to logind on the system bus and takes the inhibitor lock.
+    pub async fn connect() -> Result<Self> {
+        let conn = Connection::system().await?;
+        let proxy = ManagerProxy::new(&conn).await?;
+        let signals = proxy.receive_prepare_for_sleep().await?;
+
+        let mut monitor = Self {
+            proxy,
+            signals,
+            inhibitor: None,
+        };
+        monitor.inhibit().await?;
+
+        Ok(monitor)
+    }
+
+    /// Takes the inhibitor lock, if it is not held yet.
+    pub async fn inhibit(&mut self) -> Result<()> {
+        if self.inhibitor.is_none() {
+            self.inhibitor = Some(
+                self.proxy
+                    .inhibit(
+                        "sleep",
+                        "logy",
+                        "Releasing HID++ devices before suspend",
+                        "delay",
+                    )
+                    .await?,
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Releases the inhibitor lock, allowing the system to go to sleep.
+    pub fn release(&mut self) {
+        self.inhibitor = None;
+    }
+
+    /// Waits for the system to prepare for sleep or to resume.
+    ///
+    /// Returns `Some(true)` before the system goes to sleep, `Some(false)`
+    /// after it resumed and `None` if logind went away.
+    pub async fn next(&mut self) -> Option<bool> {
+        loop {
+            let signal = self.signals.next().await?;
+            if let Ok(args) = signal.args() {
+                return Some(args.start);
+            }
+        }
+    }
+}
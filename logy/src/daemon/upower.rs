@@ -0,0 +1,291 @@
+//! Exports device batteries using UPower's `org.freedesktop.UPower.Device`
+//! interface.
+//!
+//! UPower itself only reads the batteries the kernel exposes as power
+//! supplies, which excludes most devices connected through a receiver, and it
+//! offers no way for other services to add devices. The objects exported here
+//! only mirror the properties of UPower's device objects and are registered
+//! under logy's own bus name, not `org.freedesktop.UPower`. UPower and desktop
+//! environments therefore never see them; only clients explicitly pointed at
+//! logy's bus name can read the batteries through the familiar interface.
+
+use std::{
+    collections::HashMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use hidpp::feature::{
+    device_type_and_name::{DeviceType, DeviceTypeAndNameFeature},
+    unified_battery::{BatteryLevel, BatteryStatus},
+};
+use zbus::{Connection, interface, object_server::InterfaceRef, zvariant::OwnedObjectPath};
+
+use crate::{
+    battery::BatteryState,
+    devices::{DeviceSummary, DiscoveredDevice},
+};
+
+/// The path below which device objects are exported on logy's bus name,
+/// matching the layout of UPower.
+const DEVICES_PATH: &str = "/org/freedesktop/UPower/devices";
+
+/// Holds the UPower device objects exported on a D-Bus connection.
+pub struct UpowerBridge {
+    paths: HashMap<DeviceSummary, OwnedObjectPath>,
+}
+
+impl UpowerBridge {
+    pub fn new() -> Self {
+        Self {
+            paths: HashMap::new(),
+        }
+    }
+
+    /// Exports a device, if it is not exported yet.
+    pub async fn add_device(&mut self, conn: &Connection, device: &DiscoveredDevice) -> Result<()> {
+        if self.paths.contains_key(&device.summary) {
+            return Ok(());
+        }
+
+        let device_type = match device.device.get_feature::<DeviceTypeAndNameFeature>() {
+            Some(feature) => device_type(feature.get_device_type().await?),
+            None => UPOWER_TYPE_UNKNOWN,
+        };
+
+        let path =
+            OwnedObjectPath::try_from(format!("{}/logy_{}", DEVICES_PATH, self.paths.len()))?;
+        conn.object_server()
+            .at(&path, UpowerDevice {
+                summary: device.summary.clone(),
+                device_type,
+                present: true,
+                battery: None,
+                update_time: now(),
+            })
+            .await?;
+        self.paths.insert(device.summary.clone(), path);
+
+        Ok(())
+    }
+
+    /// Updates the battery state of an exported device.
+    pub async fn set_battery(
+        &self,
+        conn: &Connection,
+        device: &DeviceSummary,
+        state: BatteryState,
+    ) -> Result<()> {
+        let Some(iface) = self.interface(conn, device).await? else {
+            return Ok(());
+        };
+
+        {
+            let mut iface = iface.get_mut().await;
+            iface.battery = Some(state);
+            iface.update_time = now();
+        }
+
+        let emitter = iface.signal_emitter();
+        let iface = iface.get().await;
+        iface.percentage_changed(emitter).await?;
+        iface.state_changed(emitter).await?;
+        iface.battery_level_changed(emitter).await?;
+        iface.warning_level_changed(emitter).await?;
+        iface.update_time_changed(emitter).await?;
+
+        Ok(())
+    }
+
+    /// Updates whether an exported device is connected.
+    pub async fn set_online(
+        &self,
+        conn: &Connection,
+        device: &DeviceSummary,
+        online: bool,
+    ) -> Result<()> {
+        let Some(iface) = self.interface(conn, device).await? else {
+            return Ok(());
+        };
+
+        iface.get_mut().await.present = online;
+        iface
+            .get()
+            .await
+            .is_present_changed(iface.signal_emitter())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn interface(
+        &self,
+        conn: &Connection,
+        device: &DeviceSummary,
+    ) -> Result<Option<InterfaceRef<UpowerDevice>>> {
+        let Some(path) = self.paths.get(device) else {
+            return Ok(None);
+        };
+
+        Ok(Some(conn.object_server().interface(path).await?))
+    }
+}
+
+const UPOWER_TYPE_UNKNOWN: u32 = 0;
+const UPOWER_TYPE_MOUSE: u32 = 5;
+const UPOWER_TYPE_KEYBOARD: u32 = 6;
+const UPOWER_TYPE_GAMING_INPUT: u32 = 12;
+const UPOWER_TYPE_TOUCHPAD: u32 = 14;
+const UPOWER_TYPE_HEADSET: u32 = 17;
+const UPOWER_TYPE_SPEAKERS: u32 = 18;
+const UPOWER_TYPE_REMOTE_CONTROL: u32 = 22;
+const UPOWER_TYPE_CAMERA: u32 = 25;
+
+/// Maps a HID++ device type to the corresponding UPower device type.
+fn device_type(device_type: DeviceType) -> u32 {
+    match device_type {
+        DeviceType::Keyboard | DeviceType::Numpad => UPOWER_TYPE_KEYBOARD,
+        DeviceType::Mouse | DeviceType::Trackball => UPOWER_TYPE_MOUSE,
+        DeviceType::Trackpad => UPOWER_TYPE_TOUCHPAD,
+        DeviceType::RemoteControl | DeviceType::Presenter => UPOWER_TYPE_REMOTE_CONTROL,
+        DeviceType::Headset => UPOWER_TYPE_HEADSET,
+        DeviceType::Speaker => UPOWER_TYPE_SPEAKERS,
+        DeviceType::Webcam => UPOWER_TYPE_CAMERA,
+        DeviceType::SteeringWheel
+        | DeviceType::Joystick
+        | DeviceType::Gamepad
+        | DeviceType::CarSimPedals => UPOWER_TYPE_GAMING_INPUT,
+        _ => UPOWER_TYPE_UNKNOWN,
+    }
+}
+
+/// Returns the current time as a UNIX timestamp in seconds.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |x| x.as_secs())
+}
+
+/// Represents a device exported using UPower's device interface.
+struct UpowerDevice {
+    summary: DeviceSummary,
+    device_type: u32,
+    present: bool,
+    battery: Option<BatteryState>,
+    update_time: u64,
+}
+
+#[interface(name = "org.freedesktop.UPower.Device")]
+impl UpowerDevice {
+    #[zbus(property)]
+    fn native_path(&self) -> String {
+        match (self.summary.slot, self.summary.wpid) {
+            (Some(slot), Some(wpid)) => format!(
+                "logy-{:04x}-{:04x}-{}-{:04x}",
+                self.summary.vendor_id, self.summary.product_id, slot, wpid
+            ),
+            _ => format!(
+                "logy-{:04x}-{:04x}",
+                self.summary.vendor_id, self.summary.product_id
+            ),
+        }
+    }
+
+    #[zbus(property)]
+    fn vendor(&self) -> String {
+        "Logitech".to_string()
+    }
+
+    #[zbus(property)]
+    fn model(&self) -> String {
+        self.summary.name.clone()
+    }
+
+    #[zbus(property, name = "Type")]
+    fn device_type(&self) -> u32 {
+        self.device_type
+    }
+
+    /// Whether the device powers the system, which is never the case.
+    #[zbus(property)]
+    fn power_supply(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn is_present(&self) -> bool {
+        self.present
+    }
+
+    #[zbus(property)]
+    fn is_rechargeable(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn update_time(&self) -> u64 {
+        self.update_time
+    }
+
+    /// The battery percentage, or `0` if the device only reports coarse
+    /// levels, in which case `BatteryLevel` is set.
+    #[zbus(property)]
+    fn percentage(&self) -> f64 {
+        self.battery
+            .and_then(|state| state.percentage)
+            .map_or(0.0, f64::from)
+    }
+
+    /// The charging state: unknown (`0`), charging (`1`), discharging (`2`)
+    /// or fully charged (`4`).
+    #[zbus(property)]
+    fn state(&self) -> u32 {
+        let Some(state) = self.battery else {
+            return 0;
+        };
+
+        match state.status {
+            Some(BatteryStatus::Full) => 4,
+            Some(BatteryStatus::Discharging) => 2,
+            _ if state.charging => 1,
+            Some(_) => 0,
+            None => 2,
+        }
+    }
+
+    /// The coarse battery level for devices not reporting a percentage:
+    /// none (`1`), critical (`4`), low (`3`), normal (`6`) or full (`8`).
+    #[zbus(property)]
+    fn battery_level(&self) -> u32 {
+        let Some(state) = self.battery else {
+            return 0;
+        };
+        if state.percentage.is_some() {
+            return 1;
+        }
+
+        match state.level {
+            Some(BatteryLevel::Critical) => 4,
+            Some(BatteryLevel::Low) => 3,
+            Some(BatteryLevel::Good) => 6,
+            Some(BatteryLevel::Full) => 8,
+            _ => 0,
+        }
+    }
+
+    /// The warning level: none (`1`), low (`3`) or critical (`4`).
+    #[zbus(property)]
+    fn warning_level(&self) -> u32 {
+        let Some(state) = self.battery else {
+            return 1;
+        };
+
+        match (state.percentage, state.level) {
+            (Some(percentage), _) if percentage <= 5 => 4,
+            (Some(percentage), _) if percentage <= 10 => 3,
+            (None, Some(BatteryLevel::Critical)) => 4,
+            (None, Some(BatteryLevel::Low)) => 3,
+            _ => 1,
+        }
+    }
+}
@@ -0,0 +1,599 @@
+//! Discovers HID++ receivers and devices connected to the local machine.
+
+use std::{fmt, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{Result, anyhow, bail};
+use hidpp::{
+    async_hid::enumerate_hidpp,
+    channel::HidppChannel,
+    device::{Device, DeviceError},
+    feature::{
+        device_friendly_name::DeviceFriendlyNameFeature,
+        device_information::DeviceInformationFeature,
+        device_type_and_name::DeviceTypeAndNameFeature,
+        feature_set::FeatureInformation,
+    },
+    receiver::{
+        self,
+        PairedDeviceInfo,
+        Receiver,
+        ReceiverDevice,
+        bolt::{BoltPairingAction, BoltRegister},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+/// The device index used to address a device that is directly connected to
+/// the host via USB or Bluetooth, without a receiver in between.
+pub const DIRECT_DEVICE_INDEX: u8 = 0xff;
+
+/// How often to ping a device reported online by its receiver again if it
+/// still appears offline, as some devices take a moment to wake up.
+const PAIRED_DEVICE_RETRIES: u32 = 3;
+
+/// How long to wait between the pings of [`PAIRED_DEVICE_RETRIES`].
+const PAIRED_DEVICE_RETRY_DELAY: Duration = Duration::from_millis(300);
+
+/// Contains all receivers and online HID++2.0 devices found by [`discover`].
+pub struct Discovery {
+    pub receivers: Vec<DiscoveredReceiver>,
+    pub devices: Vec<DiscoveredDevice>,
+}
+
+/// Represents a receiver found by [`discover`].
+pub struct DiscoveredReceiver {
+    pub channel: Arc<HidppChannel>,
+    pub receiver: Receiver,
+}
+
+/// Represents an online HID++2.0 device found by [`discover`], with all of
+/// its features already enumerated.
+#[derive(Clone)]
+pub struct DiscoveredDevice {
+    /// The channel the device is reached through, which is shared with the
+    /// receiver for paired devices.
+    pub channel: Arc<HidppChannel>,
+
+    pub device: Device,
+
+    /// All features of the device, in the order of their feature index
+    /// starting at `1`.
+    pub features: Vec<FeatureInformation>,
+
+    pub summary: DeviceSummary,
+}
+
+/// Contains the information needed to identify a device in the output of a
+/// command.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+pub struct DeviceSummary {
+    pub name: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slot: Option<u8>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wpid: Option<u16>,
+}
+
+/// Discovers all receivers and online HID++2.0 devices, including devices
+/// paired to a receiver and devices connected directly to the host.
+///
+/// Devices that fail to initialize are reported on stderr and skipped, so a
+/// single misbehaving device does not hide all others.
+pub async fn discover() -> Result<Discovery> {
+    let channels: Vec<Arc<HidppChannel>> =
+        enumerate_hidpp().await?.into_iter().map(Arc::new).collect();
+
+    let mut discovery = Discovery {
+        receivers: Vec::new(),
+        devices: Vec::new(),
+    };
+
+    for channel in channels {
+        let Some(receiver) = receiver::detect(Arc::clone(&channel)) else {
+            match discover_direct_device(&channel).await {
+                Ok(Some(device)) => discovery.devices.push(device),
+                Ok(None) => (),
+                Err(err) => eprintln!(
+                    "Could not initialize device {:04x}:{:04x}: {}",
+                    channel.vendor_id, channel.product_id, err
+                ),
+            }
+            continue;
+        };
+
+        let mut paired_devices = match receiver.collect_paired_devices().await {
+            Ok(paired_devices) => paired_devices,
+            Err(err) => {
+                eprintln!(
+                    "Could not list the devices paired to {}: {}",
+                    receiver.name(),
+                    err
+                );
+                Vec::new()
+            },
+        };
+        paired_devices.sort_by_key(|x| x.slot);
+
+        for paired in paired_devices.into_iter().filter(|x| x.online) {
+            let slot = paired.slot;
+            match discover_paired_device(&channel, &receiver, paired).await {
+                Ok(Some(device)) => discovery.devices.push(device),
+                Ok(None) => (),
+                Err(err) => eprintln!("Could not initialize device in slot {}: {}", slot, err),
+            }
+        }
+
+        discovery.receivers.push(DiscoveredReceiver {
+            channel,
+            receiver,
+        });
+    }
+
+    Ok(discovery)
+}
+
+/// Initializes a device connected directly to the host.
+///
+/// Returns `Ok(None)` if the device is not a reachable HID++2.0 device.
+async fn discover_direct_device(channel: &Arc<HidppChannel>) -> Result<Option<DiscoveredDevice>> {
+    let Some((device, features)) = init_device(Arc::clone(channel), DIRECT_DEVICE_INDEX, 0).await?
+    else {
+        return Ok(None);
+    };
+
+    let name = match device.get_feature::<DeviceTypeAndNameFeature>() {
+        Some(feature) => feature.name().await?,
+        None => "Unknown device".to_string(),
+    };
+
+    Ok(Some(DiscoveredDevice {
+        channel: Arc::clone(channel),
+        summary: DeviceSummary {
+            name,
+            vendor_id: channel.vendor_id,
+            product_id: channel.product_id,
+            slot: None,
+            wpid: None,
+        },
+        device,
+        features,
+    }))
+}
+
+/// Initializes a device paired to a receiver.
+///
+/// Returns `Ok(None)` if the device is not a reachable HID++2.0 device.
+pub async fn discover_paired_device(
+    channel: &Arc<HidppChannel>,
+    receiver: &Receiver,
+    paired: PairedDeviceInfo,
+) -> Result<Option<DiscoveredDevice>> {
+    let name = receiver.get_device_name(&paired).await?;
+    let Some((device, features)) =
+        init_device(Arc::clone(channel), paired.slot, PAIRED_DEVICE_RETRIES).await?
+    else {
+        return Ok(None);
+    };
+
+    Ok(Some(DiscoveredDevice {
+        channel: Arc::clone(channel),
+        device,
+        features,
+        summary: DeviceSummary {
+            name,
+            vendor_id: channel.vendor_id,
+            product_id: channel.product_id,
+            slot: Some(paired.slot),
+            wpid: Some(paired.wpid),
+        },
+    }))
+}
+
+/// Initializes a HID++2.0 device and enumerates its features, retrying the
+/// given number of times while the device is offline.
+///
+/// Returns `Ok(None)` if there is no reachable HID++2.0 device at the given
+/// index.
+async fn init_device(
+    channel: Arc<HidppChannel>,
+    device_index: u8,
+    retries: u32,
+) -> Result<Option<(Device, Vec<FeatureInformation>)>> {
+    let mut device =
+        match Device::new_with_retries(channel, device_index, retries, PAIRED_DEVICE_RETRY_DELAY)
+            .await
+        {
+            Ok(device) => device,
+            Err(
+                DeviceError::DeviceNotFound
+                | DeviceError::DeviceOffline
+                | DeviceError::Unknown(_)
+                | DeviceError::UnsupportedProtocolVersion,
+            ) => {
+                return Ok(None);
+            },
+            Err(err) => return Err(err.into()),
+        };
+    let features = device.enumerate_features().await?.unwrap_or_default();
+
+    Ok(Some((device, features)))
+}
+
+/// Represents a device paired to a receiver, which does not have to be
+/// online.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+pub struct Pairing {
+    /// The name of the receiver.
+    pub receiver: String,
+
+    /// The index of the receiver in the list of receivers the pairing was
+    /// collected from.
+    pub receiver_index: usize,
+
+    pub slot: u8,
+    pub name: String,
+    pub wpid: u16,
+
+    /// The register write unpairing the device, if it is known for the
+    /// receiver.
+    #[serde(default)]
+    pub write: Option<RegisterWrite>,
+}
+
+/// Describes the HID++1.0 register write a receiver performs to unpair a
+/// device, for the output.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+pub struct RegisterWrite {
+    /// The address of the written register.
+    pub register: u8,
+
+    /// The name of the written register.
+    pub register_name: String,
+
+    /// The action written to the register.
+    pub action: u8,
+
+    /// The name of the action.
+    pub action_name: String,
+
+    /// The slot the action applies to.
+    pub slot: u8,
+}
+
+impl fmt::Display for RegisterWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} register ({:#04x}): {} ({:#04x}), slot {}",
+            self.register_name, self.register, self.action_name, self.action, self.slot
+        )
+    }
+}
+
+/// Collects the devices paired to all given receivers.
+pub async fn collect_pairings<'a>(
+    receivers: impl IntoIterator<Item = &'a DiscoveredReceiver>,
+) -> Result<Vec<Pairing>> {
+    let mut pairings = Vec::new();
+    for (receiver_i, receiver) in receivers.into_iter().enumerate() {
+        for paired in receiver.receiver.collect_paired_devices().await? {
+            pairings.push(Pairing {
+                receiver: receiver.receiver.name(),
+                receiver_index: receiver_i,
+                slot: paired.slot,
+                name: receiver.receiver.get_device_name(&paired).await?,
+                wpid: paired.wpid,
+                write: unpair_write(&receiver.receiver, paired.slot),
+            });
+        }
+    }
+
+    Ok(pairings)
+}
+
+/// Determines the register write unpairing the device in a slot of a
+/// receiver, if it is known for the receiver.
+fn unpair_write(receiver: &Receiver, slot: u8) -> Option<RegisterWrite> {
+    match receiver {
+        Receiver::Bolt(_) => Some(RegisterWrite {
+            register: BoltRegister::Pairing.into(),
+            register_name: format!("{:?}", BoltRegister::Pairing),
+            action: BoltPairingAction::Unpair.into(),
+            action_name: format!("{:?}", BoltPairingAction::Unpair),
+            slot,
+        }),
+        _ => None,
+    }
+}
+
+/// Selects devices by their name, serial number, unit ID, receiver slot or
+/// wireless product ID.
+///
+/// Selectors are parsed from strings like `name:MX Master`, `serial:1234ABCD`,
+/// `unit:0a1b2c3d`, `slot:2` or `wpid:b034`. A selector without a prefix
+/// matches any of these properties, where names match by a case-insensitive
+/// substring and everything else has to match exactly. If it is a valid slot,
+/// it only selects by slot, so that `2` does not also select every device with
+/// a `2` in its name.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum DeviceSelector {
+    Name(String),
+    Serial(String),
+    Slot(u8),
+    Wpid(u16),
+    Any(String),
+}
+
+impl FromStr for DeviceSelector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((prefix, value)) = s.split_once(':') else {
+            return Ok(match s.parse() {
+                Ok(slot) => Self::Slot(slot),
+                Err(_) => Self::Any(s.to_string()),
+            });
+        };
+
+        Ok(match prefix {
+            "name" => Self::Name(value.to_string()),
+            "serial" | "unit" => Self::Serial(value.to_string()),
+            "slot" => Self::Slot(
+                value
+                    .parse()
+                    .map_err(|_| anyhow!("invalid slot \"{}\"", value))?,
+            ),
+            "wpid" => Self::Wpid(
+                u16::from_str_radix(value.trim_start_matches("0x"), 16)
+                    .map_err(|_| anyhow!("invalid WPID \"{}\"", value))?,
+            ),
+            _ => Self::Any(s.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for DeviceSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "name:{}", name),
+            Self::Serial(serial) => write!(f, "serial:{}", serial),
+            Self::Slot(slot) => write!(f, "slot:{}", slot),
+            Self::Wpid(wpid) => write!(f, "wpid:{:04x}", wpid),
+            Self::Any(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Contains the properties of a device a [`DeviceSelector`] is matched
+/// against.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+pub struct SelectableDevice<'a> {
+    pub names: Vec<&'a str>,
+    pub serial_number: Option<&'a str>,
+    pub unit_id: Option<[u8; 4]>,
+    pub slot: Option<u8>,
+    pub wpid: Option<u16>,
+}
+
+impl DeviceSelector {
+    /// Whether the selector needs the serial number and unit ID of devices
+    /// to be matched.
+    pub fn needs_serial(&self) -> bool {
+        matches!(self, Self::Serial(_) | Self::Any(_))
+    }
+
+    /// Whether the selector needs all names of devices to be matched.
+    pub fn needs_names(&self) -> bool {
+        matches!(self, Self::Name(_) | Self::Any(_))
+    }
+
+    pub fn matches(&self, device: &SelectableDevice) -> bool {
+        match self {
+            Self::Name(name) => Self::matches_name(name, device),
+            Self::Serial(serial) => Self::matches_serial(serial, device),
+            Self::Slot(slot) => device.slot == Some(*slot),
+            Self::Wpid(wpid) => device.wpid == Some(*wpid),
+            Self::Any(value) => {
+                Self::matches_name(value, device)
+                    || Self::matches_serial(value, device)
+                    || device.slot.is_some_and(|slot| value == &slot.to_string())
+                    || device
+                        .wpid
+                        .is_some_and(|wpid| value.eq_ignore_ascii_case(&format!("{:04x}", wpid)))
+            },
+        }
+    }
+
+    fn matches_name(name: &str, device: &SelectableDevice) -> bool {
+        let name = name.to_lowercase();
+        device
+            .names
+            .iter()
+            .any(|x| x.to_lowercase().contains(&name))
+    }
+
+    fn matches_serial(serial: &str, device: &SelectableDevice) -> bool {
+        device
+            .serial_number
+            .is_some_and(|x| x.eq_ignore_ascii_case(serial))
+            || device
+                .unit_id
+                .is_some_and(|unit_id| serial.eq_ignore_ascii_case(&hex_string(&unit_id)))
+    }
+}
+
+impl Discovery {
+    /// Removes all devices not matching a selector, if one is given.
+    pub async fn select(&mut self, selector: Option<&DeviceSelector>) -> Result<()> {
+        let Some(selector) = selector else {
+            return Ok(());
+        };
+
+        let mut selected = Vec::new();
+        for device in self.devices.drain(..) {
+            if device.matches(selector).await? {
+                selected.push(device);
+            }
+        }
+        self.devices = selected;
+
+        Ok(())
+    }
+}
+
+impl Discovery {
+    /// Returns the only discovered device, failing if there is none or more
+    /// than one, which means a more specific device selector is needed.
+    pub fn into_single(self) -> Result<DiscoveredDevice> {
+        let mut devices = self.devices;
+        match devices.len() {
+            0 => bail!("no matching device was found"),
+            1 => Ok(devices.remove(0)),
+            _ => bail!(
+                "multiple devices match, select one using --device: {}",
+                devices
+                    .iter()
+                    .map(|x| x.summary.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl DiscoveredDevice {
+    /// Checks whether the device matches a selector, reading additional
+    /// properties from the device if the selector needs them.
+    pub async fn matches(&self, selector: &DeviceSelector) -> Result<bool> {
+        let mut names = vec![self.summary.name.clone()];
+        if selector.needs_names() {
+            if let Some(feature) = self.device.get_feature::<DeviceFriendlyNameFeature>() {
+                names.push(feature.get_whole_friendly_name().await?);
+            }
+            if self.summary.slot.is_some()
+                && let Some(feature) = self.device.get_feature::<DeviceTypeAndNameFeature>()
+            {
+                names.push(feature.name().await?);
+            }
+        }
+
+        let mut serial_number = None;
+        let mut unit_id = None;
+        if selector.needs_serial()
+            && let Some(feature) = self.device.get_feature::<DeviceInformationFeature>()
+        {
+            let info = feature.get_versioned_device_info().await?;
+            unit_id = info.unit_id();
+            if info.supports_serial_number() {
+                serial_number = Some(feature.get_serial_number().await?);
+            }
+        }
+
+        Ok(selector.matches(&SelectableDevice {
+            names: names.iter().map(String::as_str).collect(),
+            serial_number: serial_number.as_deref(),
+            unit_id,
+            slot: self.summary.slot,
+            wpid: self.summary.wpid,
+        }))
+    }
+}
+
+/// Formats bytes as a lowercase hexadecimal string without separators.
+pub fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device() -> SelectableDevice<'static> {
+        SelectableDevice {
+            names: vec!["MX Master 3S", "Work mouse 2"],
+            serial_number: Some("1234ABCD"),
+            unit_id: Some([0x0a, 0x1b, 0x2c, 0x3d]),
+            slot: Some(1),
+            wpid: Some(0xb034),
+        }
+    }
+
+    fn selector(s: &str) -> DeviceSelector {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            selector("name:MX Master"),
+            DeviceSelector::Name("MX Master".to_string())
+        );
+        assert_eq!(
+            selector("serial:1234ABCD"),
+            DeviceSelector::Serial("1234ABCD".to_string())
+        );
+        assert_eq!(
+            selector("unit:0a1b2c3d"),
+            DeviceSelector::Serial("0a1b2c3d".to_string())
+        );
+        assert_eq!(selector("slot:2"), DeviceSelector::Slot(2));
+        assert_eq!(selector("wpid:b034"), DeviceSelector::Wpid(0xb034));
+        assert_eq!(selector("wpid:0xb034"), DeviceSelector::Wpid(0xb034));
+        assert_eq!(selector("2"), DeviceSelector::Slot(2));
+        assert_eq!(selector("MX"), DeviceSelector::Any("MX".to_string()));
+        assert_eq!(
+            selector("other:value"),
+            DeviceSelector::Any("other:value".to_string())
+        );
+
+        assert!("slot:two".parse::<DeviceSelector>().is_err());
+        assert!("wpid:xyz".parse::<DeviceSelector>().is_err());
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for s in ["name:MX", "serial:1234ABCD", "slot:2", "wpid:b034", "MX"] {
+            assert_eq!(selector(s).to_string(), s);
+        }
+    }
+
+    #[test]
+    fn matches_properties() {
+        let device = device();
+
+        assert!(selector("name:master").matches(&device));
+        assert!(selector("name:work").matches(&device));
+        assert!(!selector("name:keyboard").matches(&device));
+        assert!(selector("serial:1234abcd").matches(&device));
+        assert!(selector("unit:0A1B2C3D").matches(&device));
+        assert!(!selector("serial:0000").matches(&device));
+        assert!(selector("slot:1").matches(&device));
+        assert!(!selector("slot:2").matches(&device));
+        assert!(selector("wpid:B034").matches(&device));
+        assert!(!selector("wpid:b035").matches(&device));
+    }
+
+    #[test]
+    fn matches_any_property() {
+        let device = device();
+
+        assert!(selector("master").matches(&device));
+        assert!(selector("1234abcd").matches(&device));
+        assert!(selector("0a1b2c3d").matches(&device));
+        assert!(selector("b034").matches(&device));
+        assert!(!selector("keyboard").matches(&device));
+    }
+
+    #[test]
+    fn plain_number_matches_slot_only() {
+        let device = device();
+
+        assert!(selector("1").matches(&device));
+        // The name "Work mouse 2" contains the number, but slot 2 is empty.
+        assert!(!selector("2").matches(&device));
+    }
+}
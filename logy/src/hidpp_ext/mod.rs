@@ -1,7 +0,0 @@
-//! Implements abstractions and extensions for the `hidpp` crate.
-//!
-//! Many of the features implemented in this module are planned to be
-//! implemented into `hidpp` itself once it is complete enough to decide on
-//! reasonable abstractions.
-
-pub mod receiver;
@@ -0,0 +1,104 @@
+//! Converts device configurations between logy profiles and other tools.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::config::{Action, Profile, Rule, Trigger};
+
+pub mod ratbag;
+pub mod solaar;
+
+/// Contains the profiles and rules imported from the configuration of
+/// another tool.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize)]
+pub struct Import {
+    pub profiles: BTreeMap<String, Profile>,
+    pub rules: Vec<Rule>,
+
+    /// Describes everything that was found but could not be imported.
+    pub warnings: Vec<String>,
+}
+
+impl Import {
+    /// Adds a profile, returning its name.
+    ///
+    /// The name is derived from the given text and made unique among the
+    /// imported profiles and the given existing ones.
+    pub fn add_profile(
+        &mut self,
+        name: &str,
+        profile: Profile,
+        existing: &BTreeMap<String, Profile>,
+    ) -> String {
+        let base = profile_name(name);
+        let mut name = base.clone();
+        let mut i = 2;
+        while self.profiles.contains_key(&name) || existing.contains_key(&name) {
+            name = format!("{}-{}", base, i);
+            i += 1;
+        }
+
+        self.profiles.insert(name.clone(), profile);
+        name
+    }
+
+    /// Adds a profile for a device together with a rule applying it whenever
+    /// the device connects.
+    pub fn add_device_profile(
+        &mut self,
+        device_name: &str,
+        profile: Profile,
+        existing: &BTreeMap<String, Profile>,
+    ) {
+        let name = self.add_profile(device_name, profile, existing);
+        self.rules.push(Rule {
+            on: Trigger::DeviceConnected,
+            device: Some(device_name.to_string()),
+            host: None,
+            actions: vec![Action::ApplyProfile {
+                profile: name,
+            }],
+        });
+    }
+
+    /// Serializes the imported profiles and rules as a fragment of the
+    /// configuration file, which can be appended to an existing one.
+    pub fn to_toml(&self) -> Result<String> {
+        #[derive(Serialize)]
+        struct Fragment<'a> {
+            profiles: &'a BTreeMap<String, Profile>,
+            daemon: DaemonFragment<'a>,
+        }
+
+        #[derive(Serialize)]
+        struct DaemonFragment<'a> {
+            rules: &'a [Rule],
+        }
+
+        Ok(toml::to_string(&Fragment {
+            profiles: &self.profiles,
+            daemon: DaemonFragment {
+                rules: &self.rules,
+            },
+        })?)
+    }
+}
+
+/// Derives a profile name from a device name, e.g. `mx-master-3s` from
+/// `MX Master 3S`.
+fn profile_name(device_name: &str) -> String {
+    let name = device_name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if name.is_empty() {
+        "device".to_string()
+    } else {
+        name
+    }
+}
@@ -0,0 +1,366 @@
+//! Imports and exports profiles in the representation used by
+//! [libratbag](https://github.com/libratbag/libratbag) and Piper.
+//!
+//! The document mirrors the objects and properties `ratbagd` exposes over
+//! D-Bus: a device with a list of profiles, each having a report rate, a list
+//! of resolutions and lists of buttons and LEDs.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Import;
+use crate::config::{DpiSettings, Profile};
+
+/// Represents a device with its onboard profiles.
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct RatbagDevice {
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    pub profiles: Vec<RatbagProfile>,
+}
+
+/// Represents a single profile of a [`RatbagDevice`].
+#[derive(Clone, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct RatbagProfile {
+    pub index: u32,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub is_active: bool,
+
+    /// The report rate in Hz.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_rate: Option<u32>,
+
+    #[serde(default)]
+    pub resolutions: Vec<RatbagResolution>,
+
+    /// The button mappings, which are kept as-is since logy can't represent
+    /// them yet.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buttons: Vec<serde_json::Value>,
+
+    /// The LED settings, which are kept as-is since logy can't represent them
+    /// yet.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leds: Vec<serde_json::Value>,
+}
+
+/// Represents a resolution step of a [`RatbagProfile`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Deserialize, Serialize)]
+pub struct RatbagResolution {
+    pub index: u32,
+
+    #[serde(default)]
+    pub is_active: bool,
+
+    #[serde(default)]
+    pub is_default: bool,
+
+    /// The horizontal and vertical resolution in DPI.
+    pub resolution: (u32, u32),
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Represents the contents of a ratbag document, which is either a single
+/// device or a list of them.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RatbagDocument {
+    Single(RatbagDevice),
+    Multiple(Vec<RatbagDevice>),
+}
+
+/// Imports the enabled profiles of all devices in a ratbag document.
+///
+/// The active profile of every device is applied whenever the device
+/// connects. Profile names are chosen to not collide with the existing
+/// profiles.
+pub fn import(content: &str, existing: &BTreeMap<String, Profile>) -> Result<Import> {
+    let devices = match serde_json::from_str(content)? {
+        RatbagDocument::Single(device) => vec![device],
+        RatbagDocument::Multiple(devices) => devices,
+    };
+
+    let mut import = Import::default();
+    for device in devices {
+        for ratbag_profile in device.profiles.iter().filter(|x| x.enabled) {
+            let label = match &ratbag_profile.name {
+                Some(name) => format!("{} {}", device.name, name),
+                None => format!("{} profile {}", device.name, ratbag_profile.index),
+            };
+
+            if ratbag_profile.report_rate.is_some() {
+                import.warnings.push(format!(
+                    "{}: skipped the report rate, which is not supported yet",
+                    label
+                ));
+            }
+            if !ratbag_profile.buttons.is_empty() {
+                import.warnings.push(format!(
+                    "{}: skipped {} button mapping(s), which are not supported yet",
+                    label,
+                    ratbag_profile.buttons.len()
+                ));
+            }
+            if !ratbag_profile.leds.is_empty() {
+                import.warnings.push(format!(
+                    "{}: skipped {} LED setting(s), which are not supported yet",
+                    label,
+                    ratbag_profile.leds.len()
+                ));
+            }
+
+            let resolution = ratbag_profile
+                .resolutions
+                .iter()
+                .find(|x| x.is_active)
+                .or_else(|| ratbag_profile.resolutions.iter().find(|x| x.is_default))
+                .or(ratbag_profile.resolutions.first());
+            let Some(resolution) = resolution else {
+                import
+                    .warnings
+                    .push(format!("{}: skipped, as it has no resolutions", label));
+                continue;
+            };
+
+            if ratbag_profile.resolutions.len() > 1 {
+                import.warnings.push(format!(
+                    "{}: only imported the active resolution of {} resolution steps",
+                    label,
+                    ratbag_profile.resolutions.len()
+                ));
+            }
+            let (x, y) = resolution.resolution;
+            if x != y {
+                import.warnings.push(format!(
+                    "{}: used the vertical resolution of {} DPI for both axes",
+                    label, y
+                ));
+            }
+            let Ok(dpi) = u16::try_from(y) else {
+                import.warnings.push(format!(
+                    "{}: skipped the invalid resolution {} DPI",
+                    label, y
+                ));
+                continue;
+            };
+
+            let profile = Profile {
+                dpi: Some(DpiSettings {
+                    dpi: Some(dpi),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+
+            if ratbag_profile.is_active {
+                import.add_device_profile(&device.name, profile, existing);
+            } else {
+                import.add_profile(&label, profile, existing);
+            }
+        }
+    }
+
+    Ok(import)
+}
+
+/// Exports profiles as the profiles of a single ratbag device.
+///
+/// Returns the device together with warnings about all settings that can't
+/// be represented.
+pub fn export(
+    device_name: &str,
+    profiles: &BTreeMap<String, Profile>,
+) -> (RatbagDevice, Vec<String>) {
+    let mut warnings = Vec::new();
+    let mut device = RatbagDevice {
+        name: device_name.to_string(),
+        model: None,
+        profiles: Vec::new(),
+    };
+
+    for (index, (name, profile)) in profiles.iter().enumerate() {
+        if profile.smartshift.is_some()
+            || profile.hires_wheel.is_some()
+            || profile.thumbwheel.is_some()
+        {
+            warnings.push(format!(
+                "{}: skipped the scroll wheel settings, which ratbag can't represent",
+                name
+            ));
+        }
+
+        let resolutions = profile
+            .dpi
+            .as_ref()
+            .and_then(|x| x.dpi)
+            .map(|dpi| RatbagResolution {
+                index: 0,
+                is_active: true,
+                is_default: true,
+                resolution: (u32::from(dpi), u32::from(dpi)),
+            })
+            .into_iter()
+            .collect();
+
+        device.profiles.push(RatbagProfile {
+            index: index as u32,
+            name: Some(name.clone()),
+            enabled: true,
+            is_active: index == 0,
+            report_rate: None,
+            resolutions,
+            buttons: Vec::new(),
+            leds: Vec::new(),
+        });
+    }
+
+    (device, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{HiResWheelSettings, Trigger};
+
+    fn dpi_profile(dpi: u16) -> Profile {
+        Profile {
+            dpi: Some(DpiSettings {
+                dpi: Some(dpi),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn import_profiles() {
+        let import = import(
+            r#"{
+                "name": "G502",
+                "profiles": [
+                    {
+                        "index": 0,
+                        "is_active": true,
+                        "report_rate": 1000,
+                        "resolutions": [
+                            { "index": 0, "resolution": [800, 800] },
+                            { "index": 1, "is_active": true, "resolution": [1600, 1600] }
+                        ]
+                    },
+                    {
+                        "index": 1,
+                        "name": "Gaming",
+                        "resolutions": [{ "index": 0, "resolution": [3200, 3200] }]
+                    },
+                    {
+                        "index": 2,
+                        "enabled": false,
+                        "resolutions": [{ "index": 0, "resolution": [400, 400] }]
+                    }
+                ]
+            }"#,
+            &BTreeMap::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            import.profiles,
+            BTreeMap::from([
+                ("g502".to_string(), dpi_profile(1600)),
+                ("g502-gaming".to_string(), dpi_profile(3200)),
+            ])
+        );
+        assert_eq!(import.rules.len(), 1);
+        assert_eq!(import.rules[0].on, Trigger::DeviceConnected);
+        assert_eq!(import.rules[0].device.as_deref(), Some("G502"));
+        assert_eq!(import.warnings, [
+            "G502 profile 0: skipped the report rate, which is not supported yet",
+            "G502 profile 0: only imported the active resolution of 2 resolution steps",
+        ]);
+    }
+
+    #[test]
+    fn import_invalid_resolutions() {
+        let import = import(
+            r#"[{
+                "name": "Mouse",
+                "profiles": [
+                    { "index": 0, "resolutions": [] },
+                    { "index": 1, "resolutions": [{ "index": 0, "resolution": [800, 70000] }] }
+                ]
+            }]"#,
+            &BTreeMap::new(),
+        )
+        .unwrap();
+
+        assert!(import.profiles.is_empty());
+        assert_eq!(import.warnings, [
+            "Mouse profile 0: skipped, as it has no resolutions",
+            "Mouse profile 1: used the vertical resolution of 70000 DPI for both axes",
+            "Mouse profile 1: skipped the invalid resolution 70000 DPI",
+        ]);
+    }
+
+    #[test]
+    fn export_profiles() {
+        let profiles = BTreeMap::from([
+            ("office".to_string(), dpi_profile(1000)),
+            ("scrolling".to_string(), Profile {
+                hires_wheel: Some(HiResWheelSettings {
+                    high_resolution: Some(true),
+                    inverted: None,
+                }),
+                ..Default::default()
+            }),
+        ]);
+
+        let (device, warnings) = export("MX Master 3S", &profiles);
+
+        assert_eq!(device.profiles.len(), 2);
+        assert!(device.profiles[0].is_active);
+        assert_eq!(device.profiles[0].resolutions[0].resolution, (1000, 1000));
+        assert!(device.profiles[1].resolutions.is_empty());
+        assert_eq!(warnings, [
+            "scrolling: skipped the scroll wheel settings, which ratbag can't represent"
+        ]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let profiles = BTreeMap::from([
+            ("a".to_string(), dpi_profile(800)),
+            ("b".to_string(), dpi_profile(2400)),
+        ]);
+
+        let (device, warnings) = export("Mouse", &profiles);
+        assert!(warnings.is_empty());
+
+        let content = serde_json::to_string(&device).unwrap();
+        let import = import(&content, &BTreeMap::new()).unwrap();
+
+        assert_eq!(
+            import.profiles,
+            BTreeMap::from([
+                ("mouse".to_string(), dpi_profile(800)),
+                ("mouse-b".to_string(), dpi_profile(2400)),
+            ])
+        );
+        assert!(import.warnings.is_empty());
+        assert_eq!(import.rules.len(), 1);
+    }
+}
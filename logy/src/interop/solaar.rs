@@ -0,0 +1,321 @@
+//! Imports device settings from a [Solaar](https://github.com/pwr-Solaar/Solaar)
+//! configuration.
+//!
+//! Solaar stores the settings of every device it has seen in `config.yaml`
+//! and user-defined rules in `rules.yaml`, both in the `solaar` directory of
+//! the platform's configuration directory.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_yaml::Value;
+
+use super::Import;
+use crate::config::{
+    DpiSettings,
+    HiResWheelSettings,
+    Profile,
+    RatchetMode,
+    SmartShiftSettings,
+    ThumbwheelSettings,
+};
+
+/// The value of Solaar's `smart-shift` setting representing permanent
+/// freespin mode.
+const SMART_SHIFT_FREESPIN: u64 = 1;
+
+/// The value of Solaar's `smart-shift` setting representing permanent ratchet
+/// mode.
+const SMART_SHIFT_RATCHET: u64 = 50;
+
+/// Returns the default Solaar configuration directory.
+pub fn default_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("solaar"))
+}
+
+/// Imports the device settings and rules from a Solaar configuration
+/// directory.
+///
+/// Profile names are chosen to not collide with the existing profiles.
+pub fn import(dir: &Path, existing: &BTreeMap<String, Profile>) -> Result<Import> {
+    let mut import = Import::default();
+
+    let config_path = dir.join("config.yaml");
+    let content = fs::read_to_string(&config_path)
+        .with_context(|| format!("could not read {}", config_path.display()))?;
+    let config: Value = serde_yaml::from_str(&content)
+        .with_context(|| format!("could not parse {}", config_path.display()))?;
+
+    // Current versions store a list starting with the Solaar version, older
+    // ones a mapping from device IDs to settings.
+    let entries: Vec<&Value> = match &config {
+        Value::Sequence(entries) => entries.iter().collect(),
+        Value::Mapping(entries) => entries.values().collect(),
+        _ => Vec::new(),
+    };
+    for settings in entries.into_iter().filter_map(Value::as_mapping) {
+        import_device(&mut import, settings, existing);
+    }
+
+    let rules_path = dir.join("rules.yaml");
+    match fs::read_to_string(&rules_path) {
+        Ok(content) => {
+            // Solaar declares a YAML version the parser does not accept,
+            // while the content itself is plain YAML.
+            let content = content
+                .lines()
+                .filter(|x| !x.starts_with("%YAML"))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            let mut count = 0;
+            for document in serde_yaml::Deserializer::from_str(&content) {
+                let value = Value::deserialize(document)
+                    .with_context(|| format!("could not parse {}", rules_path.display()))?;
+                // Every document is a single rule made of a list of conditions
+                // and actions.
+                if value.as_sequence().is_some_and(|x| !x.is_empty()) {
+                    count += 1;
+                }
+            }
+
+            if count > 0 {
+                import.warnings.push(format!(
+                    "skipped {} rule(s) from rules.yaml, as key, gesture and window conditions \
+                     have no logy equivalent",
+                    count
+                ));
+            }
+        },
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {},
+        Err(err) => {
+            return Err(err).with_context(|| format!("could not read {}", rules_path.display()));
+        },
+    }
+
+    Ok(import)
+}
+
+/// Imports the settings of a single device.
+fn import_device(
+    import: &mut Import,
+    settings: &serde_yaml::Mapping,
+    existing: &BTreeMap<String, Profile>,
+) {
+    let Some(name) = settings.get("_NAME").and_then(Value::as_str) else {
+        return;
+    };
+
+    let mut profile = Profile::default();
+    let mut imported = false;
+    for (key, value) in settings {
+        let Some(key) = key.as_str() else {
+            continue;
+        };
+        if key.starts_with('_') {
+            continue;
+        }
+
+        let recognized = match (key, value) {
+            ("smart-shift", Value::Number(threshold)) => {
+                let smartshift = profile
+                    .smartshift
+                    .get_or_insert_with(SmartShiftSettings::default);
+                match threshold.as_u64() {
+                    Some(SMART_SHIFT_FREESPIN) => smartshift.mode = Some(RatchetMode::Freespin),
+                    Some(SMART_SHIFT_RATCHET) => {
+                        smartshift.mode = Some(RatchetMode::Ratchet);
+                        smartshift.auto_disengage = Some(0xff);
+                    },
+                    Some(threshold) => {
+                        smartshift.mode = Some(RatchetMode::Ratchet);
+                        smartshift.auto_disengage = u8::try_from(threshold).ok();
+                    },
+                    None => {},
+                }
+                true
+            },
+            ("scroll-ratchet", Value::Number(mode)) => {
+                let mode = match mode.as_u64() {
+                    Some(1) => Some(RatchetMode::Freespin),
+                    Some(2) => Some(RatchetMode::Ratchet),
+                    _ => None,
+                };
+                if let Some(mode) = mode {
+                    profile
+                        .smartshift
+                        .get_or_insert_with(SmartShiftSettings::default)
+                        .mode = Some(mode);
+                }
+                mode.is_some()
+            },
+            ("hires-smooth-resolution", Value::Bool(high_resolution)) => {
+                profile
+                    .hires_wheel
+                    .get_or_insert_with(HiResWheelSettings::default)
+                    .high_resolution = Some(*high_resolution);
+                true
+            },
+            ("hires-smooth-invert", Value::Bool(inverted)) => {
+                profile
+                    .hires_wheel
+                    .get_or_insert_with(HiResWheelSettings::default)
+                    .inverted = Some(*inverted);
+                true
+            },
+            ("thumb-scroll-invert", Value::Bool(inverted)) => {
+                profile
+                    .thumbwheel
+                    .get_or_insert_with(ThumbwheelSettings::default)
+                    .inverted = Some(*inverted);
+                true
+            },
+            ("dpi", Value::Number(dpi)) => match dpi.as_u64().and_then(|x| u16::try_from(x).ok()) {
+                Some(dpi) => {
+                    profile.dpi = Some(DpiSettings {
+                        dpi: Some(dpi),
+                        ..Default::default()
+                    });
+                    true
+                },
+                None => false,
+            },
+            _ => false,
+        };
+
+        if recognized {
+            imported = true;
+        } else {
+            import
+                .warnings
+                .push(format!("{}: skipped unsupported setting \"{}\"", name, key));
+        }
+    }
+
+    if imported {
+        import.add_device_profile(name, profile, existing);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Action, Trigger};
+
+    fn import_yaml(yaml: &str, existing: &BTreeMap<String, Profile>) -> Import {
+        let settings: Value = serde_yaml::from_str(yaml).unwrap();
+        let mut import = Import::default();
+        import_device(&mut import, settings.as_mapping().unwrap(), existing);
+        import
+    }
+
+    #[test]
+    fn settings() {
+        let import = import_yaml(
+            "
+            _NAME: MX Master 3S
+            _modelId: B03400000000
+            smart-shift: 12
+            hires-smooth-resolution: true
+            hires-smooth-invert: false
+            thumb-scroll-invert: true
+            dpi: 1600
+            ",
+            &BTreeMap::new(),
+        );
+
+        assert_eq!(import.warnings, Vec::<String>::new());
+        assert_eq!(import.profiles["mx-master-3s"], Profile {
+            smartshift: Some(SmartShiftSettings {
+                mode: Some(RatchetMode::Ratchet),
+                auto_disengage: Some(12),
+            }),
+            hires_wheel: Some(HiResWheelSettings {
+                high_resolution: Some(true),
+                inverted: Some(false),
+            }),
+            thumbwheel: Some(ThumbwheelSettings {
+                inverted: Some(true),
+            }),
+            dpi: Some(DpiSettings {
+                dpi: Some(1600),
+                ..Default::default()
+            }),
+        });
+
+        let rule = &import.rules[0];
+        assert_eq!(rule.on, Trigger::DeviceConnected);
+        assert_eq!(rule.device.as_deref(), Some("MX Master 3S"));
+        assert_eq!(rule.actions, [Action::ApplyProfile {
+            profile: "mx-master-3s".to_string(),
+        }]);
+    }
+
+    #[test]
+    fn smart_shift_modes() {
+        let smartshift = |yaml: &str| {
+            import_yaml(yaml, &BTreeMap::new())
+                .profiles
+                .into_values()
+                .next()
+                .and_then(|x| x.smartshift)
+                .unwrap()
+        };
+
+        assert_eq!(
+            smartshift("{ _NAME: Mouse, smart-shift: 1 }").mode,
+            Some(RatchetMode::Freespin)
+        );
+        assert_eq!(
+            smartshift("{ _NAME: Mouse, smart-shift: 50 }"),
+            SmartShiftSettings {
+                mode: Some(RatchetMode::Ratchet),
+                auto_disengage: Some(0xff),
+            }
+        );
+        assert_eq!(
+            smartshift("{ _NAME: Mouse, scroll-ratchet: 1 }").mode,
+            Some(RatchetMode::Freespin)
+        );
+        assert_eq!(
+            smartshift("{ _NAME: Mouse, scroll-ratchet: 2 }").mode,
+            Some(RatchetMode::Ratchet)
+        );
+    }
+
+    #[test]
+    fn unsupported_settings() {
+        let import = import_yaml(
+            "{ _NAME: Keyboard, fn-swap: true, scroll-ratchet: 3 }",
+            &BTreeMap::new(),
+        );
+
+        assert!(import.profiles.is_empty());
+        assert!(import.rules.is_empty());
+        assert_eq!(import.warnings, [
+            "Keyboard: skipped unsupported setting \"fn-swap\"",
+            "Keyboard: skipped unsupported setting \"scroll-ratchet\"",
+        ]);
+    }
+
+    #[test]
+    fn unnamed_device() {
+        let import = import_yaml("{ dpi: 800 }", &BTreeMap::new());
+
+        assert_eq!(import, Import::default());
+    }
+
+    #[test]
+    fn unique_profile_names() {
+        let existing = BTreeMap::from([("mouse".to_string(), Profile::default())]);
+        let import = import_yaml("{ _NAME: Mouse, dpi: 800 }", &existing);
+
+        assert!(import.profiles.contains_key("mouse-2"));
+    }
+}
@@ -0,0 +1,303 @@
+//! Checks configuration files for problems before they are used.
+
+use std::ops::Range;
+
+use serde::Serialize;
+use toml_edit::{ImDocument, Item};
+
+use crate::config::{Action, Config, Trigger};
+
+/// The event kinds that can be published to MQTT.
+const MQTT_EVENTS: [&str; 3] = ["device_connected", "device_disconnected", "battery"];
+
+/// Represents how severe a [`Problem`] is.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The configuration can't be used.
+    Error,
+
+    /// The configuration can be used, but likely does not do what was
+    /// intended.
+    Warning,
+}
+
+/// Represents a problem found in a configuration file.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize)]
+pub struct Problem {
+    pub severity: Severity,
+
+    /// The dotted path of the offending key, like `daemon.rules[0].on`.
+    pub path: String,
+
+    /// The line the offending key is defined on, starting at `1`.
+    pub line: Option<usize>,
+
+    pub message: String,
+}
+
+/// Represents a segment of the path to a key.
+#[derive(Clone, Debug)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl From<&str> for Segment {
+    fn from(key: &str) -> Self {
+        Self::Key(key.to_string())
+    }
+}
+
+impl From<usize> for Segment {
+    fn from(index: usize) -> Self {
+        Self::Index(index)
+    }
+}
+
+/// Collects problems together with their location in the document.
+struct Linter<'a> {
+    content: &'a str,
+    document: Option<ImDocument<&'a str>>,
+    problems: Vec<Problem>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, severity: Severity, path: &[Segment], message: impl Into<String>) {
+        let line = self
+            .span(path)
+            .map(|span| line_of(self.content, span.start));
+
+        self.problems.push(Problem {
+            severity,
+            path: format_path(path),
+            line,
+            message: message.into(),
+        });
+    }
+
+    fn error(&mut self, path: &[Segment], message: impl Into<String>) {
+        self.report(Severity::Error, path, message);
+    }
+
+    fn warning(&mut self, path: &[Segment], message: impl Into<String>) {
+        self.report(Severity::Warning, path, message);
+    }
+
+    /// Finds the span of the value at a path, or of its closest parent that
+    /// is defined in the document.
+    fn span(&self, path: &[Segment]) -> Option<Range<usize>> {
+        let mut item = self.document.as_ref()?.as_item();
+        let mut span = None;
+
+        for segment in path {
+            let next = match segment {
+                Segment::Key(key) => item.get(key.as_str()),
+                Segment::Index(index) => item.get(*index),
+            };
+            let Some(next) = next else {
+                break;
+            };
+
+            item = next;
+            span = item_span(item).or(span);
+        }
+
+        span
+    }
+}
+
+fn item_span(item: &Item) -> Option<Range<usize>> {
+    match item {
+        Item::Value(value) => value.span(),
+        Item::Table(table) => table.span(),
+        Item::ArrayOfTables(array) => array.span(),
+        Item::None => None,
+    }
+}
+
+fn line_of(content: &str, offset: usize) -> usize {
+    content[..offset.min(content.len())].matches('\n').count() + 1
+}
+
+fn format_path(path: &[Segment]) -> String {
+    let mut formatted = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                if !formatted.is_empty() {
+                    formatted.push('.');
+                }
+                formatted.push_str(key);
+            },
+            Segment::Index(index) => formatted.push_str(&format!("[{}]", index)),
+        }
+    }
+
+    formatted
+}
+
+macro_rules! path {
+    ($($segment:expr),* $(,)?) => {
+        &[$(Segment::from($segment)),*]
+    };
+}
+
+/// Checks the contents of a configuration file.
+///
+/// The file is first checked against the schema of the configuration, which
+/// includes rejecting unknown keys. If it matches, the values are checked for
+/// problems the schema can't express, like out of range values and references
+/// to unknown profiles.
+pub fn lint(content: &str) -> Vec<Problem> {
+    let mut linter = Linter {
+        content,
+        document: ImDocument::parse(content).ok(),
+        problems: Vec::new(),
+    };
+
+    let config = match toml::from_str::<Config>(content) {
+        Ok(config) => config,
+        Err(err) => {
+            linter.problems.push(Problem {
+                severity: Severity::Error,
+                path: String::new(),
+                line: err.span().map(|span| line_of(content, span.start)),
+                message: err.message().to_string(),
+            });
+            return linter.problems;
+        },
+    };
+
+    lint_daemon(&mut linter, &config);
+    lint_profiles(&mut linter, &config);
+
+    linter.problems
+}
+
+fn lint_daemon(linter: &mut Linter, config: &Config) {
+    let daemon = &config.daemon;
+
+    for (i, &threshold) in daemon.battery.thresholds.iter().enumerate() {
+        if threshold > 100 {
+            linter.error(
+                path!("daemon", "battery", "thresholds", i),
+                format!("{}% is not a valid battery percentage", threshold),
+            );
+        }
+    }
+    if daemon.battery.hysteresis > 100 {
+        linter.error(
+            path!("daemon", "battery", "hysteresis"),
+            "the hysteresis can't exceed 100 percentage points",
+        );
+    }
+
+    for (i, rule) in daemon.rules.iter().enumerate() {
+        if let Trigger::BatteryBelow {
+            percentage,
+        } = rule.on
+            && percentage > 100
+        {
+            linter.error(
+                path!("daemon", "rules", i, "on", "percentage"),
+                format!("{}% is not a valid battery percentage", percentage),
+            );
+        }
+
+        if rule.device.as_ref().is_some_and(|x| x.trim().is_empty()) {
+            linter.warning(
+                path!("daemon", "rules", i, "device"),
+                "an empty device filter matches every device",
+            );
+        }
+
+        if rule.actions.is_empty() {
+            linter.warning(path!("daemon", "rules", i), "the rule has no actions");
+        }
+
+        for (j, action) in rule.actions.iter().enumerate() {
+            match action {
+                Action::ApplyProfile {
+                    profile,
+                } if !config.profiles.contains_key(profile) => {
+                    linter.error(
+                        path!("daemon", "rules", i, "actions", j, "profile"),
+                        format!("unknown profile \"{}\"", profile),
+                    );
+                },
+                Action::Command {
+                    command,
+                } if command.contains("{device}") => {
+                    linter.warning(
+                        path!("daemon", "rules", i, "actions", j, "command"),
+                        "{device} is not replaced in commands, use the LOGY_DEVICE environment \
+                         variable instead",
+                    );
+                },
+                _ => (),
+            }
+        }
+    }
+
+    if daemon.mqtt.port == 0 {
+        linter.error(path!("daemon", "mqtt", "port"), "port 0 is not valid");
+    }
+    for (i, event) in daemon.mqtt.events.iter().enumerate() {
+        if !MQTT_EVENTS.contains(&event.as_str()) {
+            linter.error(
+                path!("daemon", "mqtt", "events", i),
+                format!(
+                    "unknown event \"{}\", expected one of {}",
+                    event,
+                    MQTT_EVENTS.join(", ")
+                ),
+            );
+        }
+    }
+}
+
+fn lint_profiles(linter: &mut Linter, config: &Config) {
+    for (name, profile) in &config.profiles {
+        let name = name.as_str();
+
+        if profile.smartshift.is_none()
+            && profile.hires_wheel.is_none()
+            && profile.thumbwheel.is_none()
+            && profile.dpi.is_none()
+        {
+            linter.warning(path!("profiles", name), "the profile has no settings");
+        }
+
+        if let Some(settings) = &profile.smartshift
+            && settings.auto_disengage == Some(0)
+        {
+            linter.warning(
+                path!("profiles", name, "smartshift", "auto_disengage"),
+                "0 leaves the current threshold unchanged, use 255 to disable the automatic \
+                 disengage",
+            );
+        }
+
+        if let Some(settings) = &profile.dpi {
+            if settings.dpi == Some(0) {
+                linter.error(path!("profiles", name, "dpi", "dpi"), "the DPI can't be 0");
+            }
+
+            if let Some(preset) = &settings.preset {
+                if let Err(err) = preset.validate() {
+                    linter.error(path!("profiles", name, "dpi", "preset"), err.to_string());
+                }
+
+                for (i, step) in preset.steps.iter().enumerate() {
+                    if step.dpi == 0 {
+                        linter.error(
+                            path!("profiles", name, "dpi", "preset", "steps", i, "dpi"),
+                            "the DPI can't be 0",
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,15 @@
 use anyhow::Result;
 
-mod async_hid_impl;
+mod battery;
 mod cli;
-mod hidpp_ext;
+mod config;
+mod control;
+mod daemon;
+mod devices;
+mod interop;
+mod lint;
+mod profile;
+mod properties;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -0,0 +1,263 @@
+//! Applies configured profiles to devices.
+
+use std::{fmt, sync::Arc};
+
+use anyhow::{Result, bail};
+use hidpp::{
+    device::Device,
+    feature::{
+        CreatableFeature,
+        adjustable_dpi::AdjustableDpiFeature,
+        hires_wheel::{HiResWheelFeature, WheelEventTarget, WheelResolution},
+        smartshift::{SmartShiftFeature, WheelMode},
+        thumbwheel::{ThumbwheelFeature, ThumbwheelReportingMode},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Profile, RatchetMode};
+
+/// Represents a single write to a device performed when applying a profile.
+#[derive(Clone, Debug)]
+pub struct Operation {
+    pub info: OperationInfo,
+    action: Action,
+}
+
+/// Describes an [`Operation`] for the output, without the means to perform
+/// it.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Deserialize, Serialize)]
+pub struct OperationInfo {
+    /// The ID of the feature the operation uses.
+    pub feature_id: u16,
+
+    /// The name of the feature the operation uses.
+    pub feature: String,
+
+    /// The name of the feature function that is called.
+    pub function: String,
+
+    /// A human-readable summary of the arguments.
+    pub summary: String,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    RatchetControlMode {
+        mode: Option<WheelMode>,
+        auto_disengage: Option<u8>,
+    },
+    HiResWheelMode {
+        target: WheelEventTarget,
+        resolution: WheelResolution,
+        inverted: bool,
+    },
+    ThumbwheelReporting {
+        mode: ThumbwheelReportingMode,
+        inverted: bool,
+    },
+    SensorDpi {
+        dpi: u16,
+    },
+}
+
+impl Operation {
+    fn new<F: CreatableFeature>(
+        feature: &'static str,
+        function: &'static str,
+        summary: String,
+        action: Action,
+    ) -> Self {
+        Self {
+            info: OperationInfo {
+                feature_id: F::ID,
+                feature: feature.to_string(),
+                function: function.to_string(),
+                summary,
+            },
+            action,
+        }
+    }
+
+    /// Performs the operation on a device.
+    pub async fn execute(&self, device: &Device) -> Result<()> {
+        match self.action {
+            Action::RatchetControlMode {
+                mode,
+                auto_disengage,
+            } => {
+                required::<SmartShiftFeature>(device)?
+                    .set_ratchet_control_mode(mode, auto_disengage, None)
+                    .await?;
+            },
+            Action::HiResWheelMode {
+                target,
+                resolution,
+                inverted,
+            } => {
+                required::<HiResWheelFeature>(device)?
+                    .set_wheel_mode(target, resolution, inverted)
+                    .await?;
+            },
+            Action::ThumbwheelReporting {
+                mode,
+                inverted,
+            } => {
+                required::<ThumbwheelFeature>(device)?
+                    .set_thumbwheel_reporting(mode, inverted)
+                    .await?;
+            },
+            Action::SensorDpi {
+                dpi,
+            } => {
+                required::<AdjustableDpiFeature>(device)?
+                    .set_sensor_dpi(0, dpi)
+                    .await?;
+            },
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for OperationInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({:#06x}) {}: {}",
+            self.feature, self.feature_id, self.function, self.summary
+        )
+    }
+}
+
+fn required<F: CreatableFeature>(device: &Device) -> Result<Arc<F>> {
+    match device.get_feature::<F>() {
+        Some(feature) => Ok(feature),
+        None => bail!("the device does not support feature {:#06x}", F::ID),
+    }
+}
+
+/// Determines the operations needed to apply all settings of a profile that
+/// are supported by a device, without performing them.
+///
+/// The current state of the device is read to fill in settings the profile
+/// leaves unchanged.
+pub async fn plan(device: &Device, profile: &Profile) -> Result<Vec<Operation>> {
+    let mut operations = Vec::new();
+
+    if let Some(settings) = &profile.smartshift
+        && device.get_feature::<SmartShiftFeature>().is_some()
+    {
+        let mode = settings.mode.map(|mode| match mode {
+            RatchetMode::Freespin => WheelMode::Freespin,
+            RatchetMode::Ratchet => WheelMode::Ratchet,
+        });
+
+        operations.push(Operation::new::<SmartShiftFeature>(
+            "SmartShift",
+            "set_ratchet_control_mode",
+            format!(
+                "mode {}, auto-disengage {}",
+                optional(mode),
+                optional(settings.auto_disengage)
+            ),
+            Action::RatchetControlMode {
+                mode,
+                auto_disengage: settings.auto_disengage,
+            },
+        ));
+    }
+
+    if let Some(settings) = &profile.hires_wheel
+        && let Some(feature) = device.get_feature::<HiResWheelFeature>()
+    {
+        let mode = feature.get_wheel_mode().await?;
+        let resolution = match settings.high_resolution {
+            Some(true) => WheelResolution::High,
+            Some(false) => WheelResolution::Low,
+            None => mode.resolution,
+        };
+        let inverted = settings.inverted.unwrap_or(mode.inverted);
+
+        operations.push(Operation::new::<HiResWheelFeature>(
+            "HiResWheel",
+            "set_wheel_mode",
+            format!(
+                "target {:?}, resolution {:?}, inverted {}",
+                mode.target, resolution, inverted
+            ),
+            Action::HiResWheelMode {
+                target: mode.target,
+                resolution,
+                inverted,
+            },
+        ));
+    }
+
+    if let Some(settings) = &profile.thumbwheel
+        && let Some(feature) = device.get_feature::<ThumbwheelFeature>()
+    {
+        let status = feature.get_thumbwheel_status().await?;
+        let inverted = settings.inverted.unwrap_or(status.direction_inverted);
+
+        operations.push(Operation::new::<ThumbwheelFeature>(
+            "Thumbwheel",
+            "set_thumbwheel_reporting",
+            format!("mode {:?}, inverted {}", status.reporting_mode, inverted),
+            Action::ThumbwheelReporting {
+                mode: status.reporting_mode,
+                inverted,
+            },
+        ));
+    }
+
+    if let Some(settings) = &profile.dpi
+        && let Some(feature) = device.get_feature::<AdjustableDpiFeature>()
+    {
+        let list = feature.get_sensor_dpi_list(0).await?;
+        if let Some(preset) = &settings.preset {
+            preset.validate_for(&list)?;
+        }
+
+        let dpi = match (settings.dpi, &settings.preset) {
+            (Some(dpi), _) => {
+                if !list.contains(dpi) {
+                    bail!("the sensor does not support {} DPI", dpi);
+                }
+                Some(dpi)
+            },
+            (None, Some(preset)) => preset.default_step().map(|step| step.dpi),
+            (None, None) => None,
+        };
+
+        if let Some(dpi) = dpi {
+            operations.push(Operation::new::<AdjustableDpiFeature>(
+                "AdjustableDpi",
+                "set_sensor_dpi",
+                format!("sensor 0, {} DPI", dpi),
+                Action::SensorDpi {
+                    dpi,
+                },
+            ));
+        }
+    }
+
+    Ok(operations)
+}
+
+/// Applies all settings of a profile that are supported by a device.
+pub async fn apply(device: &Device, profile: &Profile) -> Result<()> {
+    for operation in plan(device, profile).await? {
+        operation.execute(device).await?;
+    }
+
+    Ok(())
+}
+
+/// Formats a setting the profile may leave unchanged.
+fn optional<T: fmt::Debug>(value: Option<T>) -> String {
+    match value {
+        Some(value) => format!("{:?}", value),
+        None => "unchanged".to_string(),
+    }
+}
@@ -0,0 +1,223 @@
+//! Retrieves general properties of devices, as shown by `logy probe` and
+//! `logy info`.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use hidpp::{
+    device::Device,
+    feature::{
+        battery_voltage::BatteryVoltageFeature,
+        device_friendly_name::DeviceFriendlyNameFeature,
+        device_information::{DeviceEntityType, DeviceInformationFeature},
+        device_type_and_name::{DeviceType, DeviceTypeAndNameFeature},
+        unified_battery::{BatteryLevel, BatteryStatus, UnifiedBatteryFeature},
+        wireless_signal_strength::WirelessSignalStrengthFeature,
+    },
+    protocol::ProtocolVersion,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::devices::SelectableDevice;
+
+/// Represents a device property that can be retrieved during a probe.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProbeField {
+    Type,
+    Name,
+    FriendlyName,
+    Battery,
+    Serial,
+    Firmware,
+    Protocol,
+    Signal,
+    Voltage,
+    Features,
+}
+
+impl ProbeField {
+    /// The properties retrieved if no fields are specified explicitly.
+    pub const DEFAULT: [Self; 5] = [
+        Self::Type,
+        Self::Name,
+        Self::FriendlyName,
+        Self::Battery,
+        Self::Serial,
+    ];
+}
+
+impl std::fmt::Display for ProbeField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.to_possible_value().unwrap().get_name().fmt(f)
+    }
+}
+
+/// Retrieves the requested properties of a device.
+pub async fn probe_properties(
+    mut device: Device,
+    fields: &[ProbeField],
+) -> Result<ProbedDeviceProperties> {
+    let mut properties = ProbedDeviceProperties::default();
+
+    let features = device.enumerate_features().await?;
+
+    if fields.contains(&ProbeField::Features) {
+        properties.feature_count = features.map(|features| features.len());
+    }
+
+    if fields.contains(&ProbeField::Protocol)
+        && let ProtocolVersion::V20 {
+            protocol_num,
+            target_sw,
+        } = device.protocol_version
+    {
+        properties.protocol_number.replace(protocol_num);
+        properties.target_software.replace(target_sw);
+    }
+
+    if let Some(feature) = device.get_feature::<DeviceTypeAndNameFeature>() {
+        if fields.contains(&ProbeField::Type) {
+            properties.kind.replace(feature.get_device_type().await?);
+        }
+        if fields.contains(&ProbeField::Name) {
+            properties
+                .full_name
+                .replace(feature.get_whole_device_name().await?);
+        }
+    }
+
+    if fields.contains(&ProbeField::FriendlyName)
+        && let Some(feature) = device.get_feature::<DeviceFriendlyNameFeature>()
+    {
+        let default_friendly_name = feature.get_whole_default_friendly_name().await?;
+        let friendly_name = feature.get_whole_friendly_name().await?;
+
+        if default_friendly_name != friendly_name {
+            properties.friendly_name.replace(friendly_name);
+        }
+    }
+
+    if fields.contains(&ProbeField::Battery)
+        && let Some(feature) = device.get_feature::<UnifiedBatteryFeature>()
+    {
+        let battery = feature.get_battery_info().await?;
+        properties
+            .battery_percentage
+            .replace(battery.charging_percentage);
+        properties.battery_level.replace(battery.level);
+        properties.battery_status.replace(battery.status);
+    }
+
+    if fields.contains(&ProbeField::Voltage)
+        && let Some(feature) = device.get_feature::<BatteryVoltageFeature>()
+    {
+        properties
+            .battery_voltage
+            .replace(feature.get_battery_voltage().await?.voltage);
+    }
+
+    if fields.contains(&ProbeField::Signal)
+        && let Some(feature) = device.get_feature::<WirelessSignalStrengthFeature>()
+    {
+        properties
+            .signal_strength
+            .replace(feature.get_signal_strength().await?);
+    }
+
+    if let Some(feature) = device.get_feature::<DeviceInformationFeature>() {
+        let info = feature.get_versioned_device_info().await?;
+        properties.unit_id = info.unit_id();
+
+        if fields.contains(&ProbeField::Serial) && info.supports_serial_number() {
+            properties
+                .serial_number
+                .replace(feature.get_serial_number().await?);
+        }
+
+        if fields.contains(&ProbeField::Firmware)
+            && let Some(fw) = feature
+                .get_all_fw_info()
+                .await?
+                .into_iter()
+                .find(|fw| fw.entity_type == DeviceEntityType::MainApplication)
+        {
+            properties
+                .firmware_version
+                .replace(fw.version().to_string());
+        }
+    }
+
+    Ok(properties)
+}
+
+/// Contains the properties retrieved from a device by [`probe_properties`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default, Deserialize, Serialize)]
+pub struct ProbedDeviceProperties {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<DeviceType>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_name: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub friendly_name: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery_percentage: Option<u8>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery_level: Option<BatteryLevel>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery_status: Option<BatteryStatus>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery_voltage: Option<u16>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal_strength: Option<u8>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub serial_number: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub firmware_version: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol_number: Option<u8>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_software: Option<u8>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub feature_count: Option<usize>,
+
+    /// Only used to match device selectors.
+    #[serde(skip)]
+    pub unit_id: Option<[u8; 4]>,
+}
+
+impl ProbedDeviceProperties {
+    /// Describes the device for matching it against a device selector.
+    pub fn selectable<'a>(
+        &'a self,
+        name: &'a str,
+        slot: Option<u8>,
+        wpid: Option<u16>,
+    ) -> SelectableDevice<'a> {
+        SelectableDevice {
+            names: [
+                Some(name),
+                self.full_name.as_deref(),
+                self.friendly_name.as_deref(),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            serial_number: self.serial_number.as_deref(),
+            unit_id: self.unit_id,
+            slot,
+            wpid,
+        }
+    }
+}